@@ -5,16 +5,31 @@ use std::{
 
 use crate::{Arg, DecodeError, FormatSliceElement, Table};
 use byteorder::{ReadBytesExt, LE};
-use defmt_parser::{get_max_bitfield_range, Fragment, Parameter, Type};
+use defmt_parser::{get_max_bitfield_range, Fragment, Level, Parameter, Type};
+
+/// Maximum nesting depth of format strings, to not overflow the stack on malformed data
+const MAX_DEPTH: usize = 64;
+
+/// Maximum number of elements of a format slice whose elements carry no data, e.g. `[(); N]`
+///
+/// Elements that carry data take up at least a byte each, so this only bounds the work done for
+/// malformed data.
+const MAX_EMPTY_ELEMENTS: usize = 1 << 16;
 
 pub(crate) struct Decoder<'t, 'b> {
     table: &'t Table,
     pub bytes: &'b [u8],
+    /// Nesting depth of `decode_format` calls
+    depth: usize,
 }
 
 impl<'t, 'b> Decoder<'t, 'b> {
     pub fn new(table: &'t Table, bytes: &'b [u8]) -> Self {
-        Self { table, bytes }
+        Self {
+            table,
+            bytes,
+            depth: 0,
+        }
     }
 
     /// Sort and deduplicate `params` so that they can be interpreted correctly during decoding
@@ -23,13 +38,62 @@ impl<'t, 'b> Decoder<'t, 'b> {
         merge_bitfields(params);
 
         // sort & dedup to ensure that format string args can be addressed by index too
-        params.sort_by(|a, b| a.index.cmp(&b.index));
+        params.sort_by_key(|param| param.index);
         params.dedup_by(|a, b| a.index == b.index);
     }
 
+    /// Reads an LEB128-encoded `u64` from `bytes`
+    pub fn read_leb64(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes.read_u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Malformed)
+    }
+
+    /// Reads a string index from `bytes`
+    pub fn read_index(&mut self) -> Result<usize, DecodeError> {
+        if self.table.leb128_indices() {
+            usize::try_from(self.read_leb64()?).map_err(|_| DecodeError::Malformed)
+        } else {
+            Ok(self.bytes.read_u16::<LE>()?.into())
+        }
+    }
+
+    /// Reads the sequence number that frames carry after the string index with the
+    /// `sequence-numbers` feature
+    pub fn read_sequence_number(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.bytes.read_u8()?)
+    }
+
+    /// Reads the log level that `defmt::log!` frames carry after the timestamp
+    pub fn read_level(&mut self) -> Result<Level, DecodeError> {
+        match self.bytes.read_u8()? {
+            0 => Ok(Level::Trace),
+            1 => Ok(Level::Debug),
+            2 => Ok(Level::Info),
+            3 => Ok(Level::Warn),
+            4 => Ok(Level::Error),
+            _ => Err(DecodeError::Malformed),
+        }
+    }
+
+    /// Reads a zigzag and LEB128 encoded signed integer that must fit into `T`
+    fn read_zigzag<T: TryFrom<i64> + Into<i128>>(&mut self) -> Result<i128, DecodeError> {
+        let x = self.read_leb64()?;
+        let x = (x >> 1) as i64 ^ -((x & 1) as i64);
+        T::try_from(x)
+            .map(Into::into)
+            .map_err(|_| DecodeError::Malformed)
+    }
+
     /// Gets a format string from `bytes` and `table`
     fn get_format(&mut self) -> Result<&'t str, DecodeError> {
-        let index = self.bytes.read_u16::<LE>()? as usize;
+        let index = self.read_index()?;
         let format = self
             .table
             .get_without_level(index)
@@ -72,6 +136,10 @@ impl<'t, 'b> Decoder<'t, 'b> {
         &mut self,
         num_elements: usize,
     ) -> Result<Vec<FormatSliceElement<'t>>, DecodeError> {
+        if num_elements > self.bytes.len().max(MAX_EMPTY_ELEMENTS) {
+            return Err(DecodeError::Malformed);
+        }
+
         let format = self.get_format()?;
         let is_enum = format.contains('|');
 
@@ -91,6 +159,16 @@ impl<'t, 'b> Decoder<'t, 'b> {
 
     /// Decodes arguments from the stream, according to `format`.
     pub fn decode_format(&mut self, format: &str) -> Result<Vec<Arg<'t>>, DecodeError> {
+        if self.depth == MAX_DEPTH {
+            return Err(DecodeError::Malformed);
+        }
+        self.depth += 1;
+        let args = self.decode_format_args(format);
+        self.depth -= 1;
+        args
+    }
+
+    fn decode_format_args(&mut self, format: &str) -> Result<Vec<Arg<'t>>, DecodeError> {
         let mut args = vec![]; // will contain the deserialized arguments on return
         let mut params = defmt_parser::parse(format, defmt_parser::ParserMode::ForwardsCompatible)
             .map_err(|_| DecodeError::Malformed)?
@@ -101,19 +179,56 @@ impl<'t, 'b> Decoder<'t, 'b> {
             })
             .collect::<Vec<_>>();
 
+        let (packed, packed_len) = if self.table.packed_args() {
+            defmt_parser::packed_args(&params)
+        } else {
+            (vec![], 0)
+        };
+        let mut packed_bytes = vec![0; packed_len];
+        for byte in &mut packed_bytes {
+            *byte = self.bytes.read_u8()?;
+        }
+
         self.prepare_params(&mut params);
 
         for param in &params {
+            if let Some(packed) = packed.iter().find(|packed| packed.index == param.index) {
+                let bits = (packed_bytes[packed.byte] >> packed.shift) & packed.mask();
+                args.push(match param.ty {
+                    Type::Bool => Arg::Bool(bits != 0),
+                    _ => Arg::Uxx(u128::from(bits) << packed.start),
+                });
+                continue;
+            }
+
             match &param.ty {
+                Type::I8 if self.table.zigzag_signed() => {
+                    args.push(Arg::Ixx(self.read_zigzag::<i8>()?))
+                }
+                Type::I16 if self.table.zigzag_signed() => {
+                    args.push(Arg::Ixx(self.read_zigzag::<i16>()?))
+                }
+                Type::I32 | Type::Isize if self.table.zigzag_signed() => {
+                    args.push(Arg::Ixx(self.read_zigzag::<i32>()?))
+                }
+                Type::I64 if self.table.zigzag_signed() => {
+                    args.push(Arg::Ixx(self.read_zigzag::<i64>()?))
+                }
                 Type::I8 => args.push(Arg::Ixx(self.bytes.read_i8()? as i128)),
                 Type::I16 => args.push(Arg::Ixx(self.bytes.read_i16::<LE>()? as i128)),
+                Type::I24 => args.push(Arg::Ixx(self.bytes.read_int::<LE>(3)? as i128)),
                 Type::I32 => args.push(Arg::Ixx(self.bytes.read_i32::<LE>()? as i128)),
+                Type::I48 => args.push(Arg::Ixx(self.bytes.read_int::<LE>(6)? as i128)),
+                Type::I56 => args.push(Arg::Ixx(self.bytes.read_int::<LE>(7)? as i128)),
                 Type::I64 => args.push(Arg::Ixx(self.bytes.read_i64::<LE>()? as i128)),
                 Type::I128 => args.push(Arg::Ixx(self.bytes.read_i128::<LE>()?)),
                 Type::Isize => args.push(Arg::Ixx(self.bytes.read_i32::<LE>()? as i128)),
                 Type::U8 => args.push(Arg::Uxx(self.bytes.read_u8()? as u128)),
                 Type::U16 => args.push(Arg::Uxx(self.bytes.read_u16::<LE>()? as u128)),
+                Type::U24 => args.push(Arg::Uxx(self.bytes.read_uint::<LE>(3)? as u128)),
                 Type::U32 => args.push(Arg::Uxx(self.bytes.read_u32::<LE>()? as u128)),
+                Type::U48 => args.push(Arg::Uxx(self.bytes.read_uint::<LE>(6)? as u128)),
+                Type::U56 => args.push(Arg::Uxx(self.bytes.read_uint::<LE>(7)? as u128)),
                 Type::U64 => args.push(Arg::Uxx(self.bytes.read_u64::<LE>()? as u128)),
                 Type::U128 => args.push(Arg::Uxx(self.bytes.read_u128::<LE>()?)),
                 Type::Usize => args.push(Arg::Uxx(self.bytes.read_u32::<LE>()? as u128)),
@@ -182,7 +297,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     args.push(Arg::Str(arg_str));
                 }
                 Type::IStr => {
-                    let str_index = self.bytes.read_u16::<LE>()? as usize;
+                    let str_index = self.read_index()?;
 
                     let string = self
                         .table
@@ -236,7 +351,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                 Type::FormatSequence => {
                     let mut seq_args = Vec::new();
                     loop {
-                        let index = self.bytes.read_u16::<LE>()? as usize;
+                        let index = self.read_index()?;
                         if index == 0 {
                             break;
                         }
@@ -263,7 +378,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
 
 /// Note that this will not change the Bitfield params in place, i.e. if `params` was sorted before
 /// a call to this function, it won't be afterwards.
-fn merge_bitfields(params: &mut Vec<Parameter>) {
+pub(crate) fn merge_bitfields(params: &mut Vec<Parameter>) {
     if params.is_empty() {
         return;
     }
@@ -298,12 +413,8 @@ fn merge_bitfields(params: &mut Vec<Parameter>) {
             let mut i = 0;
             while i != params.len() {
                 match &params[i].ty {
-                    Type::BitField(_) => {
-                        if params[i].index == index {
-                            params.remove(i);
-                        } else {
-                            i += 1; // we haven't removed a bitfield -> move i forward
-                        }
+                    Type::BitField(_) if params[i].index == index => {
+                        params.remove(i);
                     }
                     _ => {
                         i += 1; // we haven't removed a bitfield -> move i forward
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use crate::{Table, Tag};
+
+/// Interned strings of a [`Table`] that have the same, or almost the same, text
+///
+/// Every `defmt` log statement and `intern!` invocation gets its own string index, even if its
+/// string is identical to another one. Consolidating the call sites of a group, e.g. into a helper
+/// function, frees up string indices and, once there are fewer than 128 of them, shrinks every
+/// frame by a byte.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Duplicates {
+    /// Indices of the strings, in ascending order.
+    pub indices: Vec<usize>,
+    /// Whether all the strings have the same kind and text, as opposed to only similar text.
+    pub exact: bool,
+}
+
+impl Table {
+    /// Returns the groups of log statements and interned strings that have the same text,
+    /// ordered by the lowest index of each group.
+    ///
+    /// Two strings are near duplicates if they are equal after ignoring case, repeated whitespace
+    /// and trailing punctuation, and regardless of the log level. Format strings that are
+    /// generated by `defmt`, e.g. by `#[derive(Format)]`, are not included.
+    pub fn duplicates(&self) -> Vec<Duplicates> {
+        let mut groups = BTreeMap::<String, Vec<usize>>::new();
+        for (&index, entry) in &self.entries {
+            if is_call_site(&entry.string.tag) {
+                groups
+                    .entry(normalize(&entry.string.string))
+                    .or_default()
+                    .push(index);
+            }
+        }
+
+        let mut duplicates = groups
+            .into_values()
+            .filter(|indices| indices.len() > 1)
+            .map(|indices| {
+                let first = &self.entries[&indices[0]].string;
+                let exact = indices
+                    .iter()
+                    .all(|index| self.entries[index].string == *first);
+                Duplicates { indices, exact }
+            })
+            .collect::<Vec<_>>();
+        duplicates.sort_by_key(|duplicates| duplicates.indices[0]);
+        duplicates
+    }
+}
+
+/// Whether strings with tag `tag` are written by users, as opposed to generated by `defmt`.
+fn is_call_site(tag: &Tag) -> bool {
+    tag.to_level().is_some()
+        || matches!(
+            tag,
+            Tag::Println | Tag::Log | Tag::Dynamic | Tag::Str | Tag::ErrorCode | Tag::Write
+        )
+}
+
+fn normalize(string: &str) -> String {
+    string
+        .trim_end_matches(|c: char| c.is_ascii_punctuation() && c != '}')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, TableEntry};
+
+    fn table(entries: Vec<(Tag, &str)>) -> Table {
+        let entries = entries
+            .into_iter()
+            .map(|(tag, string)| TableEntry::new_without_symbol(tag, string.to_owned()));
+        Table::new(entries.enumerate().collect(), Encoding::Raw)
+    }
+
+    #[test]
+    fn groups() {
+        let table = table(vec![
+            (Tag::Info, "sensor ready"),
+            (Tag::Derived, "Foo {{ x: {=u8} }}"),
+            (Tag::Info, "sensor ready"),
+            (Tag::Warn, "x = {=u8}"),
+            (Tag::Str, "unique"),
+            (Tag::Derived, "Foo {{ x: {=u8} }}"),
+            (Tag::Error, "X  =  {=u8}!"),
+            (Tag::Info, "sensor ready"),
+        ]);
+
+        assert_eq!(
+            table.duplicates(),
+            [
+                Duplicates {
+                    indices: vec![0, 2, 7],
+                    exact: true,
+                },
+                Duplicates {
+                    indices: vec![3, 6],
+                    exact: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn same_text_different_kind() {
+        let table = table(vec![(Tag::Str, "done"), (Tag::Println, "done")]);
+
+        assert_eq!(
+            table.duplicates(),
+            [Duplicates {
+                indices: vec![0, 1],
+                exact: false,
+            }]
+        );
+    }
+}
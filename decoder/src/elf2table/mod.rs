@@ -3,6 +3,7 @@
 //! This is an implementation detail of [`probe-run`](https://github.com/knurling-rs/probe-run) and
 //! not meant to be consumed by other tools at the moment so all the API is unstable.
 
+mod obfuscate;
 mod symbol;
 
 use std::{
@@ -13,15 +14,25 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{BitflagsKey, StringEntry, Table, TableEntry, Tag, DEFMT_VERSIONS};
+use crate::{BitflagsKey, StringEntry, Table, TableEntry, Tag, DEFMT_VERSIONS, LATEST_VERSION};
 use anyhow::{anyhow, bail, ensure};
 use object::{Object, ObjectSection, ObjectSymbol};
 
-pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyhow::Error> {
+pub use self::obfuscate::{obfuscate, SideTable};
+
+pub fn parse_impl(
+    elf: &[u8],
+    check_version: bool,
+    side_table: Option<&SideTable>,
+) -> Result<Option<Table>, anyhow::Error> {
     let elf = object::File::parse(elf)?;
     // first pass to extract the `_defmt_version`
     let mut version = None;
     let mut encoding = None;
+    let mut sequence_numbers = false;
+    let mut reliable = false;
+    let mut encrypted = false;
+    let mut authenticated = false;
 
     // Note that we check for a quoted and unquoted version symbol, since LLD has a bug that
     // makes it keep the quotes from the linker script.
@@ -74,6 +85,20 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
             }
             encoding = Some(new_encoding);
         }
+
+        // like `_defmt_encoding_`, defined in Rust code with the `sequence-numbers` feature
+        if name == "_defmt_sequence_numbers_" {
+            sequence_numbers = true;
+        }
+        if name == "_defmt_reliable_" {
+            reliable = true;
+        }
+        if name == "_defmt_encrypted_" {
+            encrypted = true;
+        }
+        if name == "_defmt_authenticated_" {
+            authenticated = true;
+        }
     }
 
     // NOTE: We need to make sure to return `Ok(None)`, not `Err`, when defmt is not in use.
@@ -131,15 +156,22 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
         }
 
         if entry.section_index() == Some(defmt_section.index()) {
-            let sym = symbol::Symbol::demangle(name)?;
+            let original = match side_table.and_then(|table| table.get(name)) {
+                Some(original) => original,
+                None if name.starts_with(obfuscate::PREFIX) => {
+                    bail!("defmt symbol `{name}` is obfuscated and not in the side table")
+                }
+                None => name,
+            };
+            let sym = symbol::Symbol::demangle(original)?;
             match sym.tag() {
-                symbol::SymbolTag::Defmt(Tag::Timestamp) => {
+                symbol::SymbolTag::Defmt(tag @ (Tag::Timestamp | Tag::TimestampDelta)) => {
                     if timestamp.is_some() {
                         bail!("multiple timestamp format specifications found");
                     }
 
                     timestamp = Some(TableEntry::new(
-                        StringEntry::new(Tag::Timestamp, sym.data().to_string()),
+                        StringEntry::new(tag, sym.data().to_string()),
                         name.to_string(),
                     ));
                 }
@@ -221,9 +253,27 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
         timestamp,
         bitflags,
         encoding,
+        sequence_numbers,
+        reliable,
+        encrypted,
+        authenticated,
+        version: version.parse().unwrap_or(LATEST_VERSION),
     }))
 }
 
+/// Returns the number of interned strings of an ELF file, i.e. the number of string indices that
+/// are in use.
+///
+/// The number is read from the `__DEFMT_STRING_COUNT` symbol that the `defmt.x` linker script
+/// defines. Returns `None` if the ELF file was linked with an older version of the linker script.
+pub fn string_count(elf: &[u8]) -> Result<Option<u64>, anyhow::Error> {
+    let elf = object::File::parse(elf)?;
+    Ok(elf
+        .symbols()
+        .find(|entry| entry.name() == Ok("__DEFMT_STRING_COUNT"))
+        .map(|entry| entry.address()))
+}
+
 /// Checks if the version encoded in the symbol table is compatible with this version of the `decoder` crate
 fn check_version(version: &str) -> Result<(), String> {
     if !DEFMT_VERSIONS.contains(&version) {
@@ -0,0 +1,290 @@
+//! Removes the plaintext of the interned strings from ELF files.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{bail, ensure};
+use object::{CompressionFormat, Object, ObjectSection, ObjectSymbol};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the obfuscated symbol names.
+pub(super) const PREFIX: &str = "defmt.";
+
+/// Maps the obfuscated names of the `defmt` symbols of an ELF file to the original ones.
+///
+/// The side table is created by [`obfuscate`] and is needed to decode the logs of the obfuscated
+/// ELF file, with [`Table::parse_with_side_table`](crate::Table::parse_with_side_table). It
+/// contains all the interned strings, so it must be kept private.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct SideTable {
+    symbols: BTreeMap<String, String>,
+}
+
+impl SideTable {
+    /// Returns the original name of the symbol with the obfuscated name `obfuscated`.
+    pub fn get(&self, obfuscated: &str) -> Option<&str> {
+        self.symbols.get(obfuscated).map(String::as_str)
+    }
+}
+
+/// Replaces the names of the `defmt` symbols of an ELF file, which contain the interned strings,
+/// with hashes.
+///
+/// Returns the obfuscated ELF file and the [`SideTable`] that maps the hashes back to the
+/// original names. Only the symbol table and uncompressed debug info can contain the names; every
+/// copy of a symbol name is overwritten in place and padded with zeros, so the layout of the file
+/// doesn't change. Fails if a copy of a name is left in the obfuscated file.
+pub fn obfuscate(elf: &[u8]) -> Result<(Vec<u8>, SideTable), anyhow::Error> {
+    let names = defmt_symbols(elf)?;
+    let mut lengths = names.iter().map(|name| name.len()).collect::<Vec<_>>();
+    lengths.sort_unstable_by(|a, b| b.cmp(a));
+    lengths.dedup();
+
+    let mut obfuscated = elf.to_vec();
+    let mut side_table = SideTable::default();
+    let mut replaced = HashSet::new();
+    let mut pos = 0;
+    // NOTE copies of the names can be anywhere, e.g. in the string tables or inline in the debug
+    // info, so look for one at every `{`, which starts the JSON object of every name
+    while let Some(offset) = obfuscated[pos..].iter().position(|&b| b == b'{') {
+        pos += offset;
+        let Some(name) = name_at(&obfuscated[pos..], &names, &lengths) else {
+            pos += 1;
+            continue;
+        };
+
+        let hashed = format!("{PREFIX}{:016x}", fnv1a(name));
+        ensure!(
+            hashed.len() <= name.len(),
+            "defmt symbol `{name}` is too short to be obfuscated"
+        );
+        if let Some(other) = side_table.symbols.insert(hashed.clone(), name.to_string()) {
+            ensure!(
+                other == name,
+                "hash collision between defmt symbols `{other}` and `{name}`"
+            );
+        }
+
+        let dest = &mut obfuscated[pos..pos + name.len()];
+        dest.fill(0);
+        dest[..hashed.len()].copy_from_slice(hashed.as_bytes());
+        pos += name.len();
+        replaced.insert(name);
+    }
+
+    if let Some(name) = names.iter().find(|&&name| !replaced.contains(name)) {
+        bail!("could not find the name of defmt symbol `{name}` in the ELF file");
+    }
+    for pos in 0..obfuscated.len() {
+        if let Some(name) = name_at(&obfuscated[pos..], &names, &lengths) {
+            bail!("defmt symbol `{name}` is left in the obfuscated ELF file at offset {pos:#x}");
+        }
+    }
+
+    Ok((obfuscated, side_table))
+}
+
+/// Returns the longest of `names` that `data` starts with. `lengths` are the lengths of the names,
+/// longest first.
+fn name_at<'a>(data: &[u8], names: &HashSet<&'a str>, lengths: &[usize]) -> Option<&'a str> {
+    if data.first() != Some(&b'{') {
+        return None;
+    }
+    lengths.iter().find_map(|&len| {
+        let name = std::str::from_utf8(data.get(..len)?).ok()?;
+        names.get(name).copied()
+    })
+}
+
+/// Returns the names of the `defmt` symbols that contain interned strings.
+fn defmt_symbols(elf: &[u8]) -> Result<HashSet<&str>, anyhow::Error> {
+    let file = object::File::parse(elf)?;
+
+    for section in file.sections() {
+        let name = section.name().unwrap_or_default();
+        if name.starts_with(".debug") || name.starts_with(".zdebug") {
+            ensure!(
+                section.compressed_file_range()?.format == CompressionFormat::None,
+                "section `{name}` is compressed; strip or decompress the debug info first"
+            );
+        }
+    }
+
+    let Some(defmt_section) = file.section_by_name(".defmt") else {
+        bail!("`.defmt` section not found");
+    };
+
+    let mut names = HashSet::new();
+    for entry in file.symbols() {
+        if entry.section_index() != Some(defmt_section.index()) {
+            continue;
+        }
+        // like in `parse_impl`, only the JSON encoded symbols contain interned strings
+        match entry.name() {
+            Ok(name) if name.starts_with('{') => {
+                names.insert(name);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(names)
+}
+
+/// 64-bit FNV-1a hash, which is stable across platforms and Rust versions.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Table, Tag};
+
+    const INFO: &str = r#"{"package":"app","tag":"defmt_info","data":"secret {=u8}","disambiguator":"1","crate_name":"app"}"#;
+    const STR: &str = r#"{"package":"app","tag":"defmt_str","data":"classified","disambiguator":"2","crate_name":"app"}"#;
+
+    /// Returns a string table with `strings` and the offsets of the strings.
+    fn string_table<'a>(strings: impl IntoIterator<Item = &'a str>) -> (Vec<u8>, Vec<u32>) {
+        let mut table = vec![0];
+        let offsets = strings
+            .into_iter()
+            .map(|s| {
+                let offset = table.len() as u32;
+                table.extend(s.as_bytes());
+                table.push(0);
+                offset
+            })
+            .collect();
+        (table, offsets)
+    }
+
+    /// Returns a minimal 32-bit ELF file with a `.defmt` section containing symbols `names`, whose
+    /// first name also appears in the debug info.
+    fn elf(names: &[&str]) -> Vec<u8> {
+        const SPECIAL: [&str; 2] = ["_defmt_version_ = 5", "_defmt_encoding_ = raw"];
+
+        let (strtab, offsets) = string_table(SPECIAL.into_iter().chain(names.iter().copied()));
+        let mut symtab = vec![0; 16];
+        for (i, offset) in offsets.into_iter().enumerate() {
+            let (value, shndx) = match i.checked_sub(SPECIAL.len()) {
+                Some(index) => (index as u32 + 1, 1u16),
+                // absolute symbols
+                None => (0, 0xfff1),
+            };
+            symtab.extend(offset.to_le_bytes());
+            symtab.extend(value.to_le_bytes());
+            symtab.extend(1u32.to_le_bytes()); // size
+            symtab.extend([0x11, 0]); // global object, default visibility
+            symtab.extend(shndx.to_le_bytes());
+        }
+        let (debug_str, _) = string_table([names[0]]);
+        let (shstrtab, section_names) =
+            string_table([".defmt", ".debug_str", ".symtab", ".strtab", ".shstrtab"]);
+
+        // (type, link, info, entry size, data) of the sections after the null section
+        let sections: [(u32, u32, u32, u32, &[u8]); 5] = [
+            (1, 0, 0, 0, &[0; 16]),
+            (1, 0, 0, 0, &debug_str),
+            (2, 4, 1, 16, &symtab),
+            (3, 0, 0, 0, &strtab),
+            (3, 0, 0, 0, &shstrtab),
+        ];
+
+        let mut data = vec![];
+        let mut headers = vec![0; 40];
+        for ((ty, link, info, entsize, bytes), name) in sections.into_iter().zip(section_names) {
+            let offset = 52 + data.len() as u32;
+            data.extend(bytes);
+            data.resize(data.len().next_multiple_of(4), 0);
+            for field in [
+                name,
+                ty,
+                0,
+                0,
+                offset,
+                bytes.len() as u32,
+                link,
+                info,
+                4,
+                entsize,
+            ] {
+                headers.extend(field.to_le_bytes());
+            }
+        }
+
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1];
+        elf.resize(16, 0);
+        elf.extend(2u16.to_le_bytes()); // executable
+        elf.extend(40u16.to_le_bytes()); // ARM
+        elf.extend(1u32.to_le_bytes()); // version
+        elf.extend(0u32.to_le_bytes()); // entry point
+        elf.extend(0u32.to_le_bytes()); // program headers
+        elf.extend((52 + data.len() as u32).to_le_bytes()); // section headers
+        elf.extend(0u32.to_le_bytes()); // flags
+        for field in [52u16, 32, 0, 40, 6, 5] {
+            elf.extend(field.to_le_bytes());
+        }
+        elf.extend(data);
+        elf.extend(headers);
+        elf
+    }
+
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    }
+
+    #[test]
+    fn roundtrip() {
+        let elf = elf(&[INFO, STR]);
+        let table = Table::parse(&elf).unwrap().unwrap();
+
+        let (obfuscated, side_table) = obfuscate(&elf).unwrap();
+        assert_eq!(obfuscated.len(), elf.len());
+        assert!(!contains(&obfuscated, "secret"));
+        assert!(!contains(&obfuscated, "classified"));
+        assert_eq!(side_table.symbols.len(), 2);
+
+        // the side table survives serialization
+        let json = serde_json::to_string(&side_table).unwrap();
+        let side_table: SideTable = serde_json::from_str(&json).unwrap();
+
+        let deobfuscated = Table::parse_with_side_table(&obfuscated, &side_table)
+            .unwrap()
+            .unwrap();
+        for index in [1, 2] {
+            let (expected, actual) = (&table.entries[&index], &deobfuscated.entries[&index]);
+            assert_eq!(actual.string.string, expected.string.string);
+            assert_eq!(actual.string.tag, expected.string.tag);
+            assert_eq!(
+                side_table.get(&actual.raw_symbol),
+                Some(&*expected.raw_symbol)
+            );
+        }
+        assert_eq!(deobfuscated.entries[&2].string.tag, Tag::Str);
+    }
+
+    #[test]
+    fn inline_copy() {
+        // e.g. a `DW_FORM_string` attribute, which isn't in a string table
+        let mut elf = elf(&[INFO]);
+        elf.push(0x2a);
+        elf.extend(INFO.as_bytes());
+        elf.push(0);
+
+        let (obfuscated, _) = obfuscate(&elf).unwrap();
+        assert!(!contains(&obfuscated, "secret"));
+    }
+
+    #[test]
+    fn missing_side_table() {
+        let (obfuscated, _) = obfuscate(&elf(&[INFO])).unwrap();
+
+        assert!(Table::parse(&obfuscated).is_err());
+        assert!(Table::parse_with_side_table(&obfuscated, &SideTable::default()).is_err());
+    }
+}
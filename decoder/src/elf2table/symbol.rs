@@ -14,6 +14,7 @@ pub struct Symbol {
     /// * `defmt_prim` for primitive formatting strings that are placed at the start of the `.defmt`
     ///   section.
     /// * `defmt_fmt`, `defmt_str` for interned format strings and string literals.
+    /// * `defmt_error_code` for the messages of error codes.
     /// * `defmt_trace`, `defmt_debug`, `defmt_info`, `defmt_warn`, `defmt_error` for logging
     ///   messages used at the different log levels.
     /// * Anything starting with `defmt_` is reserved for use by defmt, other prefixes are free for
@@ -32,7 +33,7 @@ pub enum SymbolTag<'a> {
     Defmt(Tag),
 
     /// Non-`defmt_*` tag for custom tooling.
-    Custom(&'a str),
+    Custom(#[allow(dead_code)] &'a str),
 }
 
 impl Symbol {
@@ -48,9 +49,28 @@ impl Symbol {
             "defmt_bitflags" => SymbolTag::Defmt(Tag::Bitflags),
             "defmt_write" => SymbolTag::Defmt(Tag::Write),
             "defmt_timestamp" => SymbolTag::Defmt(Tag::Timestamp),
+            "defmt_timestamp_delta" => SymbolTag::Defmt(Tag::TimestampDelta),
             "defmt_bitflags_value" => SymbolTag::Defmt(Tag::BitflagsValue),
             "defmt_str" => SymbolTag::Defmt(Tag::Str),
+            "defmt_error_code" => SymbolTag::Defmt(Tag::ErrorCode),
             "defmt_println" => SymbolTag::Defmt(Tag::Println),
+            "defmt_log" => SymbolTag::Defmt(Tag::Log),
+            "defmt_heartbeat" => SymbolTag::Defmt(Tag::Heartbeat),
+            "defmt_event" => SymbolTag::Defmt(Tag::Event),
+            "defmt_test" => SymbolTag::Defmt(Tag::Test),
+            "defmt_version" => SymbolTag::Defmt(Tag::Version),
+            "defmt_wall_clock" => SymbolTag::Defmt(Tag::WallClock),
+            "defmt_clock_sync" => SymbolTag::Defmt(Tag::ClockSync),
+            "defmt_time_sync" => SymbolTag::Defmt(Tag::TimeSync),
+            "defmt_group_start" => SymbolTag::Defmt(Tag::GroupStart),
+            "defmt_group_end" => SymbolTag::Defmt(Tag::GroupEnd),
+            "defmt_chunk" => SymbolTag::Defmt(Tag::Chunk),
+            "defmt_counter" => SymbolTag::Defmt(Tag::Counter),
+            "defmt_gauge" => SymbolTag::Defmt(Tag::Gauge),
+            "defmt_stack" => SymbolTag::Defmt(Tag::Stack),
+            "defmt_self_check" => SymbolTag::Defmt(Tag::SelfCheck),
+            "defmt_continuation" => SymbolTag::Defmt(Tag::Continuation),
+            "defmt_dynamic" => SymbolTag::Defmt(Tag::Dynamic),
             "defmt_trace" => SymbolTag::Defmt(Tag::Trace),
             "defmt_debug" => SymbolTag::Defmt(Tag::Debug),
             "defmt_info" => SymbolTag::Defmt(Tag::Info),
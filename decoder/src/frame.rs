@@ -1,12 +1,19 @@
 use std::{
+    borrow::Cow,
+    collections::BTreeMap,
     convert::TryFrom,
     fmt::{self, Write as _},
     mem,
+    sync::Arc,
 };
 
-use crate::{Arg, BitflagsKey, Table};
+use crate::{
+    Arg, BitflagsKey, Chunk, Event, IsrSpan, Location, Metric, SelfCheckFailed, StackUsage, Table,
+    Tag, TestEvent,
+};
 use colored::Colorize;
 use defmt_parser::{DisplayHint, Fragment, Level, ParserMode, TimePrecision, Type};
+use serde_json::{json, Value};
 use time::{macros::format_description, OffsetDateTime};
 
 /// Used to convert a `i128` value into right target type in hex
@@ -17,7 +24,10 @@ impl std::fmt::LowerHex for I128Hex {
         match self.1 {
             Type::I8 => fmt::LowerHex::fmt(&(self.0 as i8), f),
             Type::I16 => fmt::LowerHex::fmt(&(self.0 as i16), f),
+            Type::I24 => fmt::LowerHex::fmt(&(self.0 as u32 & 0xff_ffff), f),
             Type::I32 => fmt::LowerHex::fmt(&(self.0 as i32), f),
+            Type::I48 => fmt::LowerHex::fmt(&(self.0 as u64 & 0xffff_ffff_ffff), f),
+            Type::I56 => fmt::LowerHex::fmt(&(self.0 as u64 & 0xff_ffff_ffff_ffff), f),
             Type::I64 => fmt::LowerHex::fmt(&(self.0 as i64), f),
             Type::I128 => fmt::LowerHex::fmt(&self.0, f),
             _ => panic!("Unsupported type '{:?}' found.", self.1),
@@ -30,7 +40,10 @@ impl std::fmt::UpperHex for I128Hex {
         match self.1 {
             Type::I8 => fmt::UpperHex::fmt(&(self.0 as i8), f),
             Type::I16 => fmt::UpperHex::fmt(&(self.0 as i16), f),
+            Type::I24 => fmt::UpperHex::fmt(&(self.0 as u32 & 0xff_ffff), f),
             Type::I32 => fmt::UpperHex::fmt(&(self.0 as i32), f),
+            Type::I48 => fmt::UpperHex::fmt(&(self.0 as u64 & 0xffff_ffff_ffff), f),
+            Type::I56 => fmt::UpperHex::fmt(&(self.0 as u64 & 0xff_ffff_ffff_ffff), f),
             Type::I64 => fmt::UpperHex::fmt(&(self.0 as i64), f),
             Type::I128 => fmt::UpperHex::fmt(&self.0, f),
             _ => panic!("Unsupported type '{:?}' found.", self.1),
@@ -46,9 +59,34 @@ pub struct Frame<'t> {
     index: u64,
     timestamp_format: Option<&'t str>,
     timestamp_args: Vec<Arg<'t>>,
-    // Format string
-    format: &'t str,
+    // Format string; only owned if it was transmitted in the frame, see `defmt::log_dynamic`
+    format: Cow<'t, str>,
     args: Vec<Arg<'t>>,
+    /// Wall-clock time in milliseconds since the Unix epoch, see [`Frame::wall_clock`].
+    pub(crate) wall_clock: Option<u64>,
+    /// Host time in milliseconds since the Unix epoch, see [`Frame::host_time`].
+    pub(crate) host_time: Option<u64>,
+    /// See [`Frame::sequence_number`].
+    pub(crate) sequence_number: Option<u8>,
+    /// Number of frames lost before this one, see [`Frame::lost_frames`].
+    pub(crate) lost_frames: Option<u8>,
+    /// See [`Frame::is_duplicate`].
+    pub(crate) duplicate: bool,
+    /// Result of verifying the MAC of this frame, see [`Frame::authentic`].
+    pub(crate) authentic: Option<bool>,
+    /// See [`Frame::missing_frames`].
+    pub(crate) missing_frames: Option<u64>,
+    /// See [`Frame::group`].
+    pub(crate) group: Option<u32>,
+    /// Offsets (in µs) of the clock domains reported by `defmt::clock_sync` so far, shared with
+    /// the stream's state and the other frames decoded since the latest report.
+    pub(crate) clock_offsets: Arc<BTreeMap<u16, i128>>,
+    /// Result of verifying a check frame, see [`Frame::self_check`].
+    pub(crate) self_check: Option<Result<(), SelfCheckFailed>>,
+    /// Data of the transfer completed by this chunk frame, see [`Frame::reassembled`].
+    pub(crate) reassembled: Option<Vec<u8>>,
+    /// The interrupt handler that this `isr_exit` frame returned from, see [`Frame::isr_span`].
+    pub(crate) isr_span: Option<IsrSpan>,
 }
 
 impl<'t> Frame<'t> {
@@ -58,7 +96,7 @@ impl<'t> Frame<'t> {
         index: u64,
         timestamp_format: Option<&'t str>,
         timestamp_args: Vec<Arg<'t>>,
-        format: &'t str,
+        format: impl Into<Cow<'t, str>>,
         args: Vec<Arg<'t>>,
     ) -> Self {
         Self {
@@ -67,8 +105,20 @@ impl<'t> Frame<'t> {
             index,
             timestamp_format,
             timestamp_args,
-            format,
+            format: format.into(),
             args,
+            wall_clock: None,
+            host_time: None,
+            sequence_number: None,
+            lost_frames: None,
+            duplicate: false,
+            authentic: None,
+            missing_frames: None,
+            group: None,
+            clock_offsets: Arc::default(),
+            self_check: None,
+            reassembled: None,
+            isr_span: None,
         }
     }
 
@@ -99,6 +149,373 @@ impl<'t> Frame<'t> {
         self.index
     }
 
+    /// Returns the wall-clock time of this frame, in milliseconds since the Unix epoch.
+    ///
+    /// This is only available after the target has reported the wall-clock time with
+    /// `defmt::wall_clock`, and if the timestamp is a single integer in microseconds, milliseconds
+    /// or ticks (e.g. `{=u64:us}`). The wall-clock time is derived from the monotonic timestamp.
+    pub fn wall_clock(&self) -> Option<u64> {
+        self.wall_clock
+    }
+
+    /// Returns the sequence number of this frame, with the `sequence-numbers` feature.
+    pub fn sequence_number(&self) -> Option<u8> {
+        self.sequence_number
+    }
+
+    /// Returns the number of frames that were lost between the previous frame and this one.
+    ///
+    /// Lost frames are detected with the sequence numbers of the `sequence-numbers` feature, which
+    /// wrap around after 255, so this is the number of lost frames modulo 256. Returns `None`
+    /// without that feature, and for the first frame of a stream. With the `reliable` feature,
+    /// lost frames may still arrive later, retransmitted, and this returns `None` for them.
+    pub fn lost_frames(&self) -> Option<u8> {
+        self.lost_frames
+    }
+
+    /// Returns `true` if this frame was already received, i.e. if it was retransmitted by firmware
+    /// with the `reliable` feature although the first transmission arrived.
+    ///
+    /// Duplicate frames must be acknowledged again, but not displayed.
+    pub fn is_duplicate(&self) -> bool {
+        self.duplicate
+    }
+
+    /// Returns whether the MAC of this frame is valid, for firmware with the `authentication`
+    /// feature.
+    ///
+    /// Returns `None` without that feature, or if the stream decoder has no [`Verify`](crate::Verify)
+    /// implementation to verify the MAC with. A frame whose counter doesn't advance past the one of
+    /// the previous authentic frame of its stream, i.e. a replayed or reordered frame, is not
+    /// authentic either. Frames that are not authentic are still decoded, so the tampered logs can
+    /// be inspected, but must not be trusted.
+    pub fn authentic(&self) -> Option<bool> {
+        self.authentic
+    }
+
+    /// Returns the number of frames of this frame's stream that are missing before it, i.e. that
+    /// were removed or lost, for firmware with the `authentication` feature.
+    ///
+    /// Returns `None` unless this frame is [`authentic`](Self::authentic), and for the first
+    /// authentic frame of a stream.
+    pub fn missing_frames(&self) -> Option<u64> {
+        self.missing_frames
+    }
+
+    /// Returns the host's wall-clock time at the timestamp of this frame, in milliseconds since the
+    /// Unix epoch.
+    ///
+    /// This is only available after the target has emitted a time sync frame with
+    /// `defmt::time_sync`, if the data was passed to the decoder with
+    /// [`StreamDecoder::received_at`](crate::StreamDecoder::received_at), and if the timestamp is
+    /// a single integer in microseconds, milliseconds or ticks. The drift between the target's
+    /// clock and the host's is corrected once there are several time sync frames.
+    pub fn host_time(&self) -> Option<u64> {
+        self.host_time
+    }
+
+    /// Returns the clock domain of this frame's timestamp: the value of its `{=u16:domain}`
+    /// parameter, which may have any unsigned integer type, or `0` if it has none.
+    pub fn clock_domain(&self) -> u16 {
+        self.timestamp_params()
+            .into_iter()
+            .flatten()
+            .find(|(hint, _)| *hint == Some(DisplayHint::ClockDomain))
+            .map_or(0, |(_, domain)| domain as u16)
+    }
+
+    /// Returns the timestamp of this frame, in microseconds, in the clock domain `domain`.
+    ///
+    /// Cores or channels with their own time sources report how their clocks relate to each other
+    /// with `defmt::clock_sync`, and select the clock domain of their timestamps with a
+    /// `{=u16:domain}` parameter in `timestamp!`. Domain `0` is the clock of the timestamps without
+    /// such a parameter. This returns `None` if the clock domain of this frame or `domain` has not
+    /// been synchronized yet, or if the timestamp is not an integer in microseconds, milliseconds
+    /// or ticks.
+    pub fn domain_timestamp(&self, domain: u16) -> Option<u128> {
+        u128::try_from(self.base_timestamp_micros()? + self.clock_offset(domain)?).ok()
+    }
+
+    /// Returns the timestamp in microseconds in clock domain `0`.
+    pub(crate) fn base_timestamp_micros(&self) -> Option<i128> {
+        Some(self.timestamp_micros()? as i128 - self.clock_offset(self.clock_domain())?)
+    }
+
+    fn clock_offset(&self, domain: u16) -> Option<i128> {
+        match domain {
+            0 => Some(0),
+            _ => self.clock_offsets.get(&domain).copied(),
+        }
+    }
+
+    /// Returns the hints and values of the timestamp's parameters, if they are all integers.
+    fn timestamp_params(&self) -> Option<Vec<(Option<DisplayHint>, u128)>> {
+        let format = self.timestamp_format?;
+        let params = defmt_parser::parse(format, ParserMode::ForwardsCompatible).ok()?;
+        params
+            .into_iter()
+            .filter_map(|fragment| match fragment {
+                Fragment::Parameter(param) => Some(param),
+                Fragment::Literal(_) => None,
+            })
+            .map(|param| match self.timestamp_args.get(param.index)? {
+                Arg::Uxx(x) => Some((param.hint, *x)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the monotonic timestamp in microseconds, in the frame's clock domain, if the
+    /// timestamp format allows it.
+    pub(crate) fn timestamp_micros(&self) -> Option<u128> {
+        let mut params = self
+            .timestamp_params()?
+            .into_iter()
+            .filter(|(hint, _)| *hint != Some(DisplayHint::ClockDomain));
+        let (Some((hint, x)), None) = (params.next(), params.next()) else {
+            return None;
+        };
+
+        match hint {
+            Some(DisplayHint::Seconds(TimePrecision::Micros)) => Some(x),
+            Some(DisplayHint::Seconds(TimePrecision::Millis)) => x.checked_mul(1_000),
+            Some(DisplayHint::Ticks(hz)) => Some(x.checked_mul(1_000_000)? / u128::from(hz)),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is a heartbeat frame emitted by `defmt::heartbeat`.
+    ///
+    /// Heartbeat frames carry a timestamp but no log message.
+    pub fn is_heartbeat(&self) -> bool {
+        self.table.is_heartbeat(self.index as usize)
+    }
+
+    /// Returns `true` if this is a version frame emitted by `defmt::announce_version`.
+    ///
+    /// Version frames whose version differs from the ELF file's are rejected while decoding.
+    pub fn is_version(&self) -> bool {
+        self.table.is_version(self.index as usize)
+    }
+
+    /// Returns `true` if this is a time sync frame emitted by `defmt::time_sync`.
+    pub fn is_time_sync(&self) -> bool {
+        self.table.is_time_sync(self.index as usize)
+    }
+
+    /// Returns `true` if this frame starts the statements of a `defmt::group!`.
+    pub fn is_group_start(&self) -> bool {
+        self.table.is_group_start(self.index as usize)
+    }
+
+    /// Returns `true` if this frame ends the statements of a `defmt::group!`.
+    pub fn is_group_end(&self) -> bool {
+        self.table.is_group_end(self.index as usize)
+    }
+
+    /// Returns the ID of the `defmt::group!` this frame was logged in, if any, including the frames
+    /// that start and end the group.
+    ///
+    /// The statements of a group are never interleaved with the frames of other execution
+    /// contexts. The decoder numbers the groups in the order it received them.
+    pub fn group(&self) -> Option<u32> {
+        self.group
+    }
+
+    /// Returns `true` if this is a check frame emitted with the `self-check` feature.
+    pub fn is_self_check(&self) -> bool {
+        self.table.is_self_check(self.index as usize)
+    }
+
+    /// Returns the result of verifying this check frame against the data received since the
+    /// previous one.
+    ///
+    /// Returns `None` if this is not a check frame, or if it was not verified because it was
+    /// decoded on its own, with [`Table::decode`]. If a [`StreamDecoder`](crate::StreamDecoder)
+    /// attached to an already running target, the first check fails.
+    pub fn self_check(&self) -> Option<Result<(), SelfCheckFailed>> {
+        self.self_check
+    }
+
+    /// Returns the timeline event if this is an event frame emitted by `defmt::event`.
+    ///
+    /// Event frames are displayed like `println!` messages, e.g. `isr_enter=3` or
+    /// `event=adc_done`.
+    pub fn event(&self) -> Option<Event<'_>> {
+        if !self.table.is_event(self.index as usize) {
+            return None;
+        }
+
+        let id = match self.args.first() {
+            Some(Arg::Uxx(id)) => u16::try_from(*id).ok(),
+            _ => None,
+        };
+        let (kind, value) = self.format.split_once('=').unwrap_or((&self.format, ""));
+        match (kind, id) {
+            ("event", None) => Some(Event::Named(value)),
+            ("task_switch", Some(id)) => Some(Event::TaskSwitch(id)),
+            ("isr_enter", Some(id)) => Some(Event::IsrEnter(id)),
+            ("isr_exit", None) => Some(Event::IsrExit),
+            ("marker", Some(id)) => Some(Event::Marker(id)),
+            _ => None,
+        }
+    }
+
+    /// Returns the interrupt handler that returned, if this is an `isr_exit` event frame.
+    ///
+    /// The `isr_enter` and `isr_exit` frames are matched up by a
+    /// [`StreamDecoder`](crate::StreamDecoder). Returns `None` for an `isr_exit` frame without a
+    /// matching `isr_enter` frame, or if the frame is decoded on its own with [`Table::decode`].
+    pub fn isr_span(&self) -> Option<IsrSpan> {
+        self.isr_span
+    }
+
+    /// Returns the metric sample if this is a metric frame emitted by `defmt::counter!` or
+    /// `defmt::gauge!`.
+    ///
+    /// Metric frames are displayed like `println!` messages, e.g. `rx_packets += 1` or
+    /// `temperature = 21`.
+    pub fn metric(&self) -> Option<Metric<'_>> {
+        match (self.table.metric_tag(self.index as usize)?, &*self.args) {
+            (Tag::Counter, []) => Some(Metric::Counter {
+                name: self.format.strip_suffix(" += 1")?,
+                delta: 1,
+            }),
+            (Tag::Counter, [Arg::Uxx(delta)]) => Some(Metric::Counter {
+                name: self.format.strip_suffix(" += {=u32}")?,
+                delta: u32::try_from(*delta).ok()?,
+            }),
+            (Tag::Gauge, [Arg::Ixx(value)]) => Some(Metric::Gauge {
+                name: self.format.strip_suffix(" = {=i32}")?,
+                value: i32::try_from(*value).ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the stack usage if this is a stack frame emitted by `defmt::stack::Stack::report`.
+    pub fn stack_usage(&self) -> Option<StackUsage> {
+        if !self.table.is_stack(self.index as usize) {
+            return None;
+        }
+
+        match &*self.args {
+            [Arg::Uxx(used), Arg::Uxx(size)] => Some(StackUsage {
+                used: u32::try_from(*used).ok()?,
+                size: u32::try_from(*size).ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the chunk if this is a chunk frame emitted by `defmt::chunked`.
+    pub fn chunk(&self) -> Option<Chunk<'_>> {
+        if !self.table.is_chunk(self.index as usize) {
+            return None;
+        }
+
+        match &*self.args {
+            [Arg::IStr(label), Arg::Uxx(id), Arg::Uxx(offset), Arg::Uxx(total), Arg::Slice(data)] => {
+                Some(Chunk {
+                    label,
+                    id: u16::try_from(*id).ok()?,
+                    offset: u32::try_from(*offset).ok()?,
+                    total: u32::try_from(*total).ok()?,
+                    data,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the whole buffer sent with `defmt::chunked`, if this is the last chunk frame of the
+    /// transfer.
+    ///
+    /// The chunks are reassembled by a [`StreamDecoder`](crate::StreamDecoder). Returns `None` if
+    /// any chunk of the transfer was lost, or if a transfer consisting of more than one chunk is
+    /// decoded frame by frame with [`Table::decode`].
+    pub fn reassembled(&self) -> Option<&[u8]> {
+        self.reassembled.as_deref()
+    }
+
+    /// Returns the test harness event if this is a test frame emitted by `defmt::harness`.
+    pub fn test_event(&self) -> Option<TestEvent<'t>> {
+        if !self.table.is_test(self.index as usize) {
+            return None;
+        }
+
+        let number = |arg: &Arg| match arg {
+            Arg::Uxx(x) => u16::try_from(*x).ok(),
+            _ => None,
+        };
+        match (&*self.format, &*self.args) {
+            ("({=u16}/{=u16}) running `{=istr}`...", [n, c, Arg::IStr(name)]) => {
+                Some(TestEvent::Started {
+                    number: number(n)?,
+                    count: number(c)?,
+                    name,
+                })
+            }
+            ("({=u16}/{=u16}) ignoring `{=istr}`...", [n, c, Arg::IStr(name)]) => {
+                Some(TestEvent::Ignored {
+                    number: number(n)?,
+                    count: number(c)?,
+                    name,
+                })
+            }
+            ("`{=istr}` passed", [Arg::IStr(name)]) => Some(TestEvent::Passed(name)),
+            ("`{=istr}` failed", [Arg::IStr(name)]) => Some(TestEvent::Failed(name)),
+            ("test result: {=u16} passed; {=u16} failed; {=u16} ignored", [p, f, i]) => {
+                Some(TestEvent::Finished {
+                    passed: number(p)?,
+                    failed: number(f)?,
+                    ignored: number(i)?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns this frame as a JSON object, for log ingestion pipelines.
+    ///
+    /// The object has the fields `timestamp` (formatted like [`Frame::display_timestamp`]),
+    /// `wall_clock` (see [`Frame::wall_clock`]), `level`, `module`, `file`, `line` (from
+    /// `location`), `message` (formatted like [`Frame::display_message`]) and `args`. Missing
+    /// fields are `null`.
+    ///
+    /// `args` holds the top-level arguments of the message in order: booleans and numbers as JSON
+    /// numbers, ignoring display hints, strings and characters as strings, byte slices as arrays
+    /// and nested `Format` values as their formatted string. Floats that aren't finite are `null`.
+    pub fn to_json(&self, location: Option<&Location>) -> Value {
+        json!({
+            "timestamp": self.display_timestamp().map(|ts| ts.to_string()),
+            "wall_clock": self.wall_clock,
+            "level": self.level.map(Level::as_str),
+            "module": location.map(|loc| &loc.module),
+            "file": location.map(|loc| loc.file.display().to_string()),
+            "line": location.map(|loc| loc.line),
+            "message": self.display_message().to_string(),
+            "args": self.args.iter().map(|arg| self.arg_to_json(arg)).collect::<Vec<_>>(),
+        })
+    }
+
+    fn arg_to_json(&self, arg: &Arg) -> Value {
+        match arg {
+            Arg::Bool(x) => Value::Bool(*x),
+            Arg::F32(x) => json!(x),
+            Arg::F64(x) => json!(x),
+            Arg::Uxx(x) => serde_json::Number::from_u128(*x).map_or(Value::Null, Value::Number),
+            Arg::Ixx(x) => serde_json::Number::from_i128(*x).map_or(Value::Null, Value::Number),
+            Arg::Str(x) | Arg::Preformatted(x) => Value::String(x.clone()),
+            Arg::IStr(x) => Value::String(x.to_string()),
+            Arg::Char(x) => Value::String(x.to_string()),
+            Arg::Slice(x) => json!(x),
+            Arg::Format { .. } | Arg::FormatSlice { .. } | Arg::FormatSequence { .. } => {
+                Value::String(self.format_args("{=?}", std::slice::from_ref(arg), None))
+            }
+        }
+    }
+
     fn format_args(&self, format: &str, args: &[Arg], parent_hint: Option<&DisplayHint>) -> String {
         self.format_args_real(format, args, parent_hint).unwrap() // cannot fail, we only write to a `String`
     }
@@ -145,6 +562,14 @@ impl<'t> Frame<'t> {
                                     }
                                 }
                                 _ => match hint {
+                                    Some(DisplayHint::NicheOption) => match *x {
+                                        0 => buf.push_str("None"),
+                                        x => {
+                                            buf.push_str("Some(");
+                                            self.format_u128(x, parent_hint, &mut buf)?;
+                                            buf.push(')');
+                                        }
+                                    },
                                     Some(DisplayHint::ISO8601(precision)) => {
                                         self.format_iso8601(*x as u64, precision, &mut buf)?
                                     }
@@ -155,24 +580,40 @@ impl<'t> Frame<'t> {
                                 },
                             }
                         }
-                        Arg::Ixx(x) => self.format_i128(*x, param.ty, hint, &mut buf)?,
-                        Arg::Str(x) | Arg::Preformatted(x) => self.format_str(x, hint, &mut buf)?,
+                        Arg::Ixx(x) => match hint {
+                            Some(DisplayHint::NicheOption) => match *x {
+                                0 => buf.push_str("None"),
+                                x => {
+                                    buf.push_str("Some(");
+                                    self.format_i128(x, param.ty, parent_hint, &mut buf)?;
+                                    buf.push(')');
+                                }
+                            },
+                            _ => self.format_i128(*x, param.ty, hint, &mut buf)?,
+                        },
+                        Arg::Str(x) => self.format_str(x, hint, &mut buf)?,
+                        // already formatted on the target, e.g. by `Debug2Format`
+                        Arg::Preformatted(x) => buf.push_str(x),
                         Arg::IStr(x) => self.format_str(x, hint, &mut buf)?,
                         Arg::Format { format, args } => match parent_hint {
-                            Some(DisplayHint::Ascii) => {
+                            Some(DisplayHint::Ascii | DisplayHint::Hexdump) => {
                                 buf.push_str(&self.format_args(format, args, parent_hint));
                             }
                             _ => buf.push_str(&self.format_args(format, args, hint)),
                         },
                         Arg::FormatSequence { args } => {
                             for arg in args {
-                                buf.push_str(&self.format_args("{=?}", &[arg.clone()], hint))
+                                buf.push_str(&self.format_args(
+                                    "{=?}",
+                                    std::slice::from_ref(arg),
+                                    hint,
+                                ))
                             }
                         }
                         Arg::FormatSlice { elements } => {
                             match hint {
-                                // Filter Ascii Hints, which contains u8 byte slices
-                                Some(DisplayHint::Ascii)
+                                // Filter Ascii and Hexdump Hints, which contains u8 byte slices
+                                Some(DisplayHint::Ascii | DisplayHint::Hexdump)
                                     if elements.iter().filter(|e| e.format == "{=u8}").count()
                                         != 0 =>
                                 {
@@ -207,7 +648,10 @@ impl<'t> Frame<'t> {
                             }
                         }
                         Arg::Slice(x) => self.format_bytes(x, hint, &mut buf)?,
-                        Arg::Char(c) => write!(buf, "{c}")?,
+                        Arg::Char(c) => match hint {
+                            Some(DisplayHint::Debug) => write!(buf, "{c:?}")?,
+                            _ => write!(buf, "{c}")?,
+                        },
                     }
                 }
             }
@@ -250,6 +694,12 @@ impl<'t> Frame<'t> {
                 let millis = x % 1_000;
                 write!(buf, "{seconds}.{millis:03}")?;
             }
+            Some(DisplayHint::Ticks(hz)) => {
+                let hz = u128::from(*hz);
+                let seconds = x / hz;
+                let micros = x % hz * 1_000_000 / hz;
+                write!(buf, "{seconds}.{micros:06}")?;
+            }
             Some(DisplayHint::Time(TimePrecision::Micros)) => {
                 self.format_time(x, &TimePrecision::Micros, buf)?;
             }
@@ -368,6 +818,31 @@ impl<'t> Frame<'t> {
                 }
                 buf.push('\"');
             }
+            Some(DisplayHint::Hexdump) => {
+                // one line per 16 bytes, like `hexdump -C`:
+                // 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 00 01 02  |Hello, world!...|
+                for (line, chunk) in bytes.chunks(16).enumerate() {
+                    write!(buf, "\n{:08x} ", line * 16)?;
+                    for column in 0..16 {
+                        if column == 8 {
+                            buf.push(' ');
+                        }
+                        match chunk.get(column) {
+                            Some(byte) => write!(buf, " {byte:02x}")?,
+                            None => buf.push_str("   "),
+                        }
+                    }
+                    buf.push_str("  |");
+                    for byte in chunk {
+                        if byte.is_ascii_graphic() || *byte == b' ' {
+                            buf.push(*byte as char);
+                        } else {
+                            buf.push('.');
+                        }
+                    }
+                    buf.push('|');
+                }
+            }
             Some(DisplayHint::Hexadecimal { .. }) | Some(DisplayHint::Binary { .. }) => {
                 // `core::write!` doesn't quite produce the output we want, for example
                 // `write!("{:#04x?}", bytes)` produces a multi-line output
@@ -500,7 +975,7 @@ impl fmt::Display for DisplayMessage<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let args = self
             .frame
-            .format_args(self.frame.format, &self.frame.args, None);
+            .format_args(&self.frame.format, &self.frame.args, None);
         f.write_str(&args)
     }
 }
@@ -551,7 +1026,7 @@ impl fmt::Display for DisplayFrame<'_> {
 
         let args = self
             .frame
-            .format_args(self.frame.format, &self.frame.args, None);
+            .format_args(&self.frame.format, &self.frame.args, None);
 
         write!(f, "{timestamp}{level}{args}")
     }
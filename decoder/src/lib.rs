@@ -10,32 +10,41 @@
 #![cfg_attr(docsrs, doc(cfg(unstable)))]
 #![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
 
-pub const DEFMT_VERSIONS: &[&str] = &["3", "4"];
+pub const DEFMT_VERSIONS: &[&str] = &["3", "4", "5"];
 // To avoid a breaking change, still provide `DEFMT_VERSION`.
 #[deprecated = "Please use DEFMT_VERSIONS instead"]
-pub const DEFMT_VERSION: &str = DEFMT_VERSIONS[1];
+pub const DEFMT_VERSION: &str = DEFMT_VERSIONS[DEFMT_VERSIONS.len() - 1];
+
+/// Wire format version of tables whose version is unknown, e.g. because it was not checked.
+const LATEST_VERSION: u32 = 5;
 
 mod decoder;
+mod duplicates;
 mod elf2table;
 mod frame;
 pub mod log;
+mod size;
 mod stream;
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, VecDeque},
     error::Error,
-    fmt, io,
+    fmt, io, mem,
+    rc::Rc,
     str::FromStr,
+    sync::Arc,
 };
 
-use byteorder::{ReadBytesExt, LE};
 use defmt_parser::Level;
 
 use crate::{decoder::Decoder, elf2table::parse_impl};
 
 pub use crate::{
-    elf2table::{Location, Locations},
+    duplicates::Duplicates,
+    elf2table::{obfuscate, string_count, Location, Locations, SideTable},
     frame::Frame,
+    size::EncodedSize,
     stream::StreamDecoder,
 };
 
@@ -52,13 +61,54 @@ pub enum Tag {
     Write,
     /// An interned string, for use with `{=istr}`.
     Str,
+    /// The message of an error code created by `defmt::error_code!`.
+    ErrorCode,
     /// Defines the global timestamp format.
     Timestamp,
+    /// Defines the global timestamp format of a timestamp encoded as LEB128 delta to the previous
+    /// frame's timestamp.
+    TimestampDelta,
 
     /// `static` containing a possible value of a bitflags type.
     BitflagsValue,
     /// Format string created by `defmt::println!`.
     Println,
+    /// Format string created by `defmt::log!`, whose log frames carry the log level.
+    Log,
+    /// Format string of the frames emitted by `defmt::heartbeat`.
+    Heartbeat,
+    /// Format string of the timeline event frames emitted by `defmt::event`.
+    Event,
+    /// Format string of the test frames emitted by `defmt::harness`.
+    Test,
+    /// Format string of the frames emitted by `defmt::announce_version`.
+    Version,
+    /// Format string of the frames emitted by `defmt::wall_clock`.
+    WallClock,
+    /// Format string of the frames emitted by `defmt::clock_sync`.
+    ClockSync,
+    /// Format string of the frames emitted by `defmt::time_sync`.
+    TimeSync,
+    /// Format string of the frames that start the statements of a `defmt::group!`.
+    GroupStart,
+    /// Format string of the frames that end the statements of a `defmt::group!`.
+    GroupEnd,
+    /// Format string of the chunk frames emitted by `defmt::chunked`.
+    Chunk,
+    /// Format string of the metric frames emitted by `defmt::counter!`.
+    Counter,
+    /// Format string of the metric frames emitted by `defmt::gauge!`.
+    Gauge,
+    /// Format string of the frames emitted by `defmt::stack::Stack::report`.
+    Stack,
+    /// Format string of the check frames emitted with the `self-check` feature.
+    SelfCheck,
+    /// Format string of the continuation frames of long frames, emitted when `defmt` is built
+    /// with `DEFMT_MAX_FRAME_SIZE`.
+    Continuation,
+    /// Format string of the frames emitted by `defmt::log_dynamic`, which carry the log level and
+    /// their actual format string.
+    Dynamic,
 
     Trace,
     Debug,
@@ -80,6 +130,92 @@ impl Tag {
     }
 }
 
+/// A timeline event recorded by one of the `defmt::event` functions or `defmt::event!`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event<'t> {
+    /// The scheduler switched to the task with the given ID.
+    TaskSwitch(u16),
+    /// The interrupt handler with the given ID was entered.
+    IsrEnter(u16),
+    /// The most recently entered interrupt handler returned.
+    IsrExit,
+    /// A user-defined marker with the given ID.
+    Marker(u16),
+    /// A user-defined marker with the given name, recorded by `defmt::event!`.
+    Named(&'t str),
+}
+
+/// A test harness event recorded by one of the `defmt::harness` functions
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TestEvent<'t> {
+    /// The test `name`, the `number`th of `count` tests, is about to run.
+    Started {
+        number: u16,
+        count: u16,
+        name: &'t str,
+    },
+    /// The test `name`, the `number`th of `count` tests, is skipped.
+    Ignored {
+        number: u16,
+        count: u16,
+        name: &'t str,
+    },
+    /// The test with the given name passed.
+    Passed(&'t str),
+    /// The test with the given name failed.
+    Failed(&'t str),
+    /// The test run is finished.
+    Finished {
+        passed: u16,
+        failed: u16,
+        ignored: u16,
+    },
+}
+
+/// A metric sample recorded by `defmt::counter!` or `defmt::gauge!`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Metric<'t> {
+    /// The counter `name` was incremented by `delta`.
+    Counter { name: &'t str, delta: u32 },
+    /// The gauge `name` was set to `value`.
+    Gauge { name: &'t str, value: i32 },
+}
+
+/// A chunk of a buffer sent with `defmt::chunked`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Chunk<'a> {
+    /// The label passed to `defmt::chunked`.
+    pub label: &'a str,
+    /// ID of the transfer the chunk belongs to.
+    pub id: u16,
+    /// Offset of the chunk in the buffer.
+    pub offset: u32,
+    /// Length of the whole buffer.
+    pub total: u32,
+    pub data: &'a [u8],
+}
+
+/// The stack usage reported by `defmt::stack::Stack::report`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StackUsage {
+    /// High-water mark of the stack, in bytes.
+    pub used: u32,
+    /// Size of the stack, in bytes.
+    pub size: u32,
+}
+
+/// An interrupt handler that returned, matched up from its `isr_enter` and `isr_exit` event frames
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IsrSpan {
+    /// ID of the interrupt handler.
+    pub irq: u16,
+    /// Number of handlers the handler was nested in; `0` if it interrupted thread mode.
+    pub depth: usize,
+    /// Time between entering and returning from the handler, in µs, if the timestamps can be
+    /// converted to µs.
+    pub duration_micros: Option<u128>,
+}
+
 /// Entry in [`Table`] combining a format string with its raw symbol
 #[derive(Debug, Eq, PartialEq)]
 pub struct TableEntry {
@@ -128,7 +264,11 @@ struct BitflagsKey {
 #[non_exhaustive]
 pub enum Encoding {
     Raw,
+    /// `raw` with periodic resynchronization markers
+    RawSync,
     Rzcobs,
+    Lzss,
+    LengthPrefixed,
 }
 
 impl FromStr for Encoding {
@@ -137,7 +277,10 @@ impl FromStr for Encoding {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "raw" => Ok(Encoding::Raw),
+            "raw-sync" => Ok(Encoding::RawSync),
             "rzcobs" => Ok(Encoding::Rzcobs),
+            "lzss" => Ok(Encoding::Lzss),
+            "length-prefixed" => Ok(Encoding::LengthPrefixed),
             _ => anyhow::bail!("Unknown defmt encoding '{}' specified. This is a bug.", s),
         }
     }
@@ -147,7 +290,10 @@ impl Encoding {
     pub const fn can_recover(&self) -> bool {
         match self {
             Encoding::Raw => false,
+            Encoding::RawSync => true,
             Encoding::Rzcobs => true,
+            Encoding::Lzss => false,
+            Encoding::LengthPrefixed => true,
         }
     }
 }
@@ -159,27 +305,107 @@ pub struct Table {
     entries: BTreeMap<usize, TableEntry>,
     bitflags: HashMap<BitflagsKey, Vec<(String, u128)>>,
     encoding: Encoding,
+    /// Whether the frames carry a sequence number, see `defmt`'s `sequence-numbers` feature.
+    sequence_numbers: bool,
+    /// Whether the target expects acks, see `defmt`'s `reliable` feature.
+    reliable: bool,
+    /// Whether the frames are encrypted, see `defmt`'s `encryption` feature.
+    encrypted: bool,
+    /// Whether the frames carry a MAC, see `defmt`'s `authentication` feature.
+    authenticated: bool,
+    /// Wire format version of the firmware.
+    version: u32,
 }
 
 impl Table {
+    /// Creates a table from a string table, e.g. one that was not read from an ELF file.
+    ///
+    /// The table uses the latest wire format version and has no timestamp, use
+    /// [`set_timestamp_entry`](Self::set_timestamp_entry) if the frames contain one.
+    pub fn new(entries: BTreeMap<usize, TableEntry>, encoding: Encoding) -> Self {
+        Self {
+            timestamp: None,
+            entries,
+            bitflags: HashMap::new(),
+            encoding,
+            sequence_numbers: false,
+            reliable: false,
+            encrypted: false,
+            authenticated: false,
+            version: LATEST_VERSION,
+        }
+    }
+
     /// Parses an ELF file and returns the decoded `defmt` table.
     ///
     /// This function returns `None` if the ELF file contains no `.defmt` section.
     pub fn parse(elf: &[u8]) -> Result<Option<Table>, anyhow::Error> {
-        parse_impl(elf, true)
+        parse_impl(elf, true, None)
     }
 
     /// Like `parse`, but does not verify that the defmt version in the firmware matches the host.
     ///
     /// CAUTION: This is meant for defmt/probe-run development only and can result in reading garbage data.
     pub fn parse_ignore_version(elf: &[u8]) -> Result<Option<Table>, anyhow::Error> {
-        parse_impl(elf, false)
+        parse_impl(elf, false, None)
+    }
+
+    /// Like `parse`, but for an ELF file whose strings were removed by [`obfuscate`].
+    ///
+    /// `side_table` is the side table that was created together with the obfuscated ELF file.
+    pub fn parse_with_side_table(
+        elf: &[u8],
+        side_table: &SideTable,
+    ) -> Result<Option<Table>, anyhow::Error> {
+        parse_impl(elf, true, Some(side_table))
     }
 
     pub fn set_timestamp_entry(&mut self, timestamp: TableEntry) {
         self.timestamp = Some(timestamp);
     }
 
+    /// Sets whether the frames carry a sequence number after the string index, i.e. whether the
+    /// firmware was built with `defmt`'s `sequence-numbers` feature.
+    pub fn set_sequence_numbers(&mut self, sequence_numbers: bool) {
+        self.sequence_numbers = sequence_numbers;
+    }
+
+    /// Sets whether the target retransmits frames until they are acknowledged, i.e. whether the
+    /// firmware was built with `defmt`'s `reliable` feature. This implies sequence numbers.
+    pub fn set_reliable(&mut self, reliable: bool) {
+        self.reliable = reliable;
+        self.sequence_numbers |= reliable;
+    }
+
+    /// Returns `true` if the target expects an [`ack`] for every frame it sends.
+    pub fn reliable(&self) -> bool {
+        self.reliable
+    }
+
+    /// Sets whether the frames are encrypted, i.e. whether the firmware was built with `defmt`'s
+    /// `encryption` feature.
+    pub fn set_encrypted(&mut self, encrypted: bool) {
+        self.encrypted = encrypted;
+    }
+
+    /// Returns `true` if the frames are encrypted, and can only be decoded by a stream decoder
+    /// created with [`new_decrypting_stream_decoder`](Self::new_decrypting_stream_decoder).
+    pub fn encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// Sets whether the frames carry a MAC, i.e. whether the firmware was built with `defmt`'s
+    /// `authentication` feature.
+    pub fn set_authenticated(&mut self, authenticated: bool) {
+        self.authenticated = authenticated;
+    }
+
+    /// Returns `true` if the frames carry a MAC, which a stream decoder created with
+    /// [`new_stream_decoder_with_keys`](Self::new_stream_decoder_with_keys) verifies.
+    pub fn authenticated(&self) -> bool {
+        self.authenticated
+    }
+
     fn _get(&self, index: usize) -> Result<(Option<Level>, &str), ()> {
         let entry = self.entries.get(&index).ok_or(())?;
         Ok((entry.string.tag.to_level(), &entry.string.string))
@@ -198,9 +424,115 @@ impl Table {
         }
     }
 
+    fn is_runtime_level(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| matches!(entry.string.tag, Tag::Log | Tag::Dynamic))
+    }
+
+    fn is_dynamic(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Dynamic)
+    }
+
+    fn is_heartbeat(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Heartbeat)
+    }
+
+    fn is_version(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Version)
+    }
+
+    fn is_wall_clock(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::WallClock)
+    }
+
+    fn is_clock_sync(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::ClockSync)
+    }
+
+    fn is_time_sync(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::TimeSync)
+    }
+
+    fn is_group_start(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::GroupStart)
+    }
+
+    fn is_group_end(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::GroupEnd)
+    }
+
+    fn is_chunk(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Chunk)
+    }
+
+    fn is_stack(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Stack)
+    }
+
+    fn is_continuation(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Continuation)
+    }
+
+    /// Whether the firmware splits long frames into continuation frames.
+    fn splits_frames(&self) -> bool {
+        self.entries
+            .values()
+            .any(|entry| entry.string.tag == Tag::Continuation)
+    }
+
+    fn metric_tag(&self, index: usize) -> Option<&Tag> {
+        self.entries
+            .get(&index)
+            .map(|entry| &entry.string.tag)
+            .filter(|tag| matches!(tag, Tag::Counter | Tag::Gauge))
+    }
+
+    fn is_self_check(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::SelfCheck)
+    }
+
+    fn is_event(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Event)
+    }
+
+    fn is_test(&self, index: usize) -> bool {
+        self.entries
+            .get(&index)
+            .is_some_and(|entry| entry.string.tag == Tag::Test)
+    }
+
     pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
         self.entries.iter().filter_map(move |(idx, entry)| {
-            if entry.string.tag.to_level().is_some() || entry.string.tag == Tag::Println {
+            if entry.string.tag.to_level().is_some()
+                || matches!(entry.string.tag, Tag::Println | Tag::Log | Tag::Dynamic)
+            {
                 Some(*idx)
             } else {
                 None
@@ -217,6 +549,23 @@ impl Table {
         self.entries.values().map(|s| &*s.raw_symbol)
     }
 
+    /// Iterates over the error codes created by `defmt::error_code!` and their messages, ordered
+    /// by code
+    pub fn error_codes(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.string.tag == Tag::ErrorCode)
+            .map(|(&code, entry)| (code, &*entry.string.string))
+    }
+
+    /// Returns the message of the error code `code`, or `None` if the firmware has no such code.
+    pub fn error_message(&self, code: usize) -> Option<&str> {
+        self.entries
+            .get(&code)
+            .filter(|entry| entry.string.tag == Tag::ErrorCode)
+            .map(|entry| &*entry.string.string)
+    }
+
     pub fn get_locations(&self, elf: &[u8]) -> Result<Locations, anyhow::Error> {
         elf2table::get_locations(elf, self)
     }
@@ -226,30 +575,289 @@ impl Table {
     /// * `bytes`
     ///   * contains the data sent by the device that logs.
     ///   * contains the [log string index, timestamp, optional fmt string args]
+    ///
+    /// Delta timestamps are reconstructed relative to zero, and the check frames of the
+    /// `self-check` feature are not verified. Use a [`StreamDecoder`] to decode consecutive
+    /// frames.
     pub fn decode<'t>(
         &'t self,
-        mut bytes: &[u8],
+        bytes: &[u8],
     ) -> Result<(Frame<'t>, /* consumed: */ usize), DecodeError> {
-        let len = bytes.len();
-        let index = bytes.read_u16::<LE>()? as u64;
+        let mut state = StreamState {
+            check: None,
+            ..StreamState::default()
+        };
+        self.decode_with_state(bytes, &mut state)
+    }
+
+    /// Decodes a complete frame of a framed encoding, like `decode_with_state`, and reassembles
+    /// the frames that were split into continuation frames.
+    ///
+    /// Returns `None` if the rest of the frame follows in a continuation frame.
+    pub(crate) fn decode_framed<'t>(
+        &'t self,
+        mut frame: Vec<u8>,
+        state: &mut StreamState,
+    ) -> Option<Result<(Frame<'t>, /* unconsumed: */ usize), DecodeError>> {
+        let mut authentic = None;
+        let mut missing = None;
+        if self.authenticated {
+            // the MAC covers the encrypted data, so it's verified first
+            let Some(verified) = self.verify(&frame, state.keys.verifier.as_deref()) else {
+                return Some(Err(DecodeError::Malformed));
+            };
+            let Verified {
+                stream_id, counter, ..
+            } = verified;
+            frame = verified.data;
+            authentic = verified.authentic;
+
+            // only the counters of authentic frames can be trusted
+            if authentic == Some(true) {
+                match state.counters.get(&stream_id) {
+                    // replayed, or reordered
+                    Some(&previous) if counter <= previous => authentic = Some(false),
+                    previous => {
+                        missing = previous.map(|previous| counter - previous - 1);
+                        state.counters.insert(stream_id, counter);
+                    }
+                }
+            }
+        }
+
+        if self.encrypted {
+            let decrypted = state
+                .keys
+                .cipher
+                .as_ref()
+                .and_then(|cipher| self.decrypt(&frame, &**cipher));
+            match decrypted {
+                Some(decrypted) => frame = decrypted,
+                None => return Some(Err(DecodeError::Malformed)),
+            }
+        }
+
+        let mut continued = false;
+        let mut decoder = Decoder::new(self, &frame);
+        if let Ok(index) = decoder.read_index() {
+            if self.is_continuation(index) {
+                continued = true;
+                let header = frame.len() - decoder.bytes.len();
+                // a frame split while another one was being split continues the inner one first
+                let Some((mut first, first_authentic)) = state.partial.pop() else {
+                    return Some(Err(DecodeError::Malformed));
+                };
+                first.extend_from_slice(&frame[header..]);
+                frame = first;
+                // a split frame is only authentic if all of its parts are
+                authentic = first_authentic.zip(authentic).map(|(a, b)| a && b);
+            }
+        }
+        if !continued {
+            // a group ends with the frame it was written to, even if its end frame was lost
+            state.group = None;
+            state.group_rest = None;
+        }
+
+        let mut decoded = self.decode_in_frame(frame, authentic, state);
+        if let Some(Ok((frame, _))) = &mut decoded {
+            frame.missing_frames = missing;
+        }
+        decoded
+    }
+
+    /// Decodes the rest of a frame that holds the statements of a `defmt::group!`, if the previous
+    /// call of `decode_framed` or this one left any.
+    ///
+    /// Returns `None` if there's no rest, or if it continues in a continuation frame.
+    pub(crate) fn decode_group_rest<'t>(
+        &'t self,
+        state: &mut StreamState,
+    ) -> Option<Result<Frame<'t>, DecodeError>> {
+        let (rest, authentic) = state.group_rest.take()?;
+        match self.decode_in_frame(rest, authentic, state)? {
+            Ok((frame, _unconsumed)) => Some(Ok(frame)),
+            Err(DecodeError::UnexpectedEof) => Some(Err(DecodeError::Malformed)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Decodes a log frame from the start of `frame`, which holds the data of a frame of a framed
+    /// encoding. The rest of the statements of a `defmt::group!` is kept for `decode_group_rest`.
+    fn decode_in_frame<'t>(
+        &'t self,
+        frame: Vec<u8>,
+        authentic: Option<bool>,
+        state: &mut StreamState,
+    ) -> Option<Result<(Frame<'t>, /* unconsumed: */ usize), DecodeError>> {
+        match self.decode_with_state(&frame, state) {
+            Ok((mut decoded, consumed)) => {
+                decoded.authentic = authentic;
+                // the statements of a group share a frame
+                if decoded.group.is_some() && consumed < frame.len() {
+                    state.group_rest = Some((frame[consumed..].to_vec(), authentic));
+                    return Some(Ok((decoded, 0)));
+                }
+                Some(Ok((decoded, frame.len() - consumed)))
+            }
+            Err(DecodeError::UnexpectedEof) if self.splits_frames() => {
+                if state.partial.len() == MAX_PARTIAL_FRAMES {
+                    // the continuation of the oldest one was lost
+                    state.partial.remove(0);
+                }
+                state.partial.push((frame, authentic));
+                None
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Strips the header and MAC from a frame of firmware with the `authentication` feature, and
+    /// verifies the MAC with `verifier`, if any. Returns `None` if the frame is malformed.
+    fn verify(&self, frame: &[u8], verifier: Option<&dyn Verify>) -> Option<Verified> {
+        let mut decoder = Decoder::new(self, frame);
+        let stream_id = u32::try_from(decoder.read_leb64().ok()?).ok()?;
+        let counter = decoder.read_leb64().ok()?;
+        let (data, mac) = decoder
+            .bytes
+            .split_at(decoder.bytes.len().checked_sub(MAC_LEN)?);
+
+        // must match `defmt::encoding::Authenticated`
+        let mut header = [0; 12];
+        header[..4].copy_from_slice(&stream_id.to_le_bytes());
+        header[4..].copy_from_slice(&counter.to_le_bytes());
+        let mac = mac.try_into().ok()?;
+        let authentic = verifier.map(|verifier| verifier.verify(&header, data, mac));
+        Some(Verified {
+            data: data.to_vec(),
+            authentic,
+            stream_id,
+            counter,
+        })
+    }
 
+    /// Decrypts a frame of firmware with the `encryption` feature. Returns `None` if it is
+    /// malformed or not authentic.
+    fn decrypt(&self, frame: &[u8], cipher: &dyn Decrypt) -> Option<Vec<u8>> {
+        let mut decoder = Decoder::new(self, frame);
+        let stream_id = u32::try_from(decoder.read_leb64().ok()?).ok()?;
+        let counter = decoder.read_leb64().ok()?;
+        let (data, tag) = decoder
+            .bytes
+            .split_at(decoder.bytes.len().checked_sub(TAG_LEN)?);
+
+        // must match `defmt::encoding::Encrypted`
+        let mut nonce = [0; 12];
+        nonce[..4].copy_from_slice(&stream_id.to_le_bytes());
+        nonce[4..].copy_from_slice(&counter.to_le_bytes());
+        let mut data = data.to_vec();
+        cipher
+            .decrypt(&nonce, &mut data, tag.try_into().ok()?)
+            .then_some(data)
+    }
+
+    /// Like `decode`, but takes into account the `state` left behind by the frames decoded before
+    /// this one. `state` is only updated if the frame was decoded successfully.
+    pub(crate) fn decode_with_state<'t>(
+        &'t self,
+        bytes: &[u8],
+        state: &mut StreamState,
+    ) -> Result<(Frame<'t>, /* consumed: */ usize), DecodeError> {
+        let len = bytes.len();
         let mut decoder = Decoder::new(self, bytes);
+        let index = decoder.read_index()? as u64;
+        let mut next_state = state.clone();
+
+        let mut sequence_number = None;
+        let mut lost_frames = None;
+        let mut duplicate = false;
+        if self.sequence_numbers {
+            let number = decoder.read_sequence_number()?;
+            sequence_number = Some(number);
+            duplicate = self.reliable && state.received.contains(&number);
+            let gap = state
+                .sequence_number
+                .map(|previous| number.wrapping_sub(previous).wrapping_sub(1));
+            // a retransmitted frame arrives after the frames that followed it
+            let late = self.reliable && gap.is_some_and(|gap| gap >= 128);
+            if !duplicate && !late {
+                lost_frames = gap;
+                next_state.sequence_number = Some(number);
+            }
+            if self.reliable && !duplicate {
+                if next_state.received.len() == MAX_RECEIVED {
+                    next_state.received.pop_front();
+                }
+                next_state.received.push_back(number);
+            }
+        }
 
         let mut timestamp_format = None;
         let mut timestamp_args = Vec::new();
         if let Some(entry) = self.timestamp.as_ref() {
             let format = &entry.string.string;
             timestamp_format = Some(&**format);
-            timestamp_args = decoder.decode_format(format)?;
+            timestamp_args = if entry.string.tag == Tag::TimestampDelta {
+                let delta = decoder.read_leb64()?;
+                next_state.previous_timestamp = state.previous_timestamp.wrapping_add(delta);
+                vec![Arg::Uxx(next_state.previous_timestamp.into())]
+            } else {
+                decoder.decode_format(format)?
+            };
         }
 
-        let (level, format) = self
+        let (mut level, format) = self
             .get_with_level(index as usize)
             .map_err(|_| DecodeError::Malformed)?;
+        if self.is_runtime_level(index as usize) {
+            level = Some(decoder.read_level()?);
+        }
+
+        let mut format = Cow::Borrowed(format);
+        let mut args = decoder.decode_format(&format)?;
+        if self.is_dynamic(index as usize) {
+            // the actual format string is transmitted in the frame, followed by its arguments
+            let Some(Arg::Str(dynamic)) = args.pop() else {
+                return Err(DecodeError::Malformed);
+            };
+            args = decoder.decode_format(&dynamic)?;
+            format = Cow::Owned(dynamic);
+        }
+        if let Some(&Arg::Uxx(found)) = args.first() {
+            if self.is_version(index as usize) && found != self.version.into() {
+                return Err(DecodeError::VersionMismatch {
+                    expected: self.version,
+                    found: u32::try_from(found).unwrap_or(u32::MAX),
+                });
+            }
+        }
+        let reported_wall_clock = match args.first() {
+            Some(&Arg::Uxx(unix_ms)) if self.is_wall_clock(index as usize) => Some(unix_ms),
+            _ => None,
+        };
+        let reported_check = match &*args {
+            [Arg::Uxx(crc), Arg::Uxx(frames)] if self.is_self_check(index as usize) => {
+                Some((*crc as u32, *frames as u32))
+            }
+            _ => None,
+        };
+        let reported_clock_sync = match &*args {
+            [Arg::Uxx(domain), Arg::Uxx(micros)] if self.is_clock_sync(index as usize) => {
+                Some((*domain as u16, *micros))
+            }
+            _ => None,
+        };
 
-        let args = decoder.decode_format(format)?;
+        let reported_chunk = match &*args {
+            [Arg::IStr(_), Arg::Uxx(id), Arg::Uxx(offset), Arg::Uxx(total), Arg::Slice(data)]
+                if self.is_chunk(index as usize) =>
+            {
+                Some((*id as u16, *offset as usize, *total as usize, data.clone()))
+            }
+            _ => None,
+        };
 
-        let frame = Frame::new(
+        let mut frame = Frame::new(
             self,
             level,
             index,
@@ -259,14 +867,138 @@ impl Table {
             args,
         );
 
+        let now = frame.timestamp_micros();
+        if let (Some(unix_ms), Some(micros)) = (reported_wall_clock, now) {
+            next_state.wall_clock = Some(WallClockAnchor { unix_ms, micros });
+        }
+        frame.clock_offsets = next_state.clock_offsets.clone();
+        if let (Some((domain, micros)), Some(base)) =
+            (reported_clock_sync, frame.base_timestamp_micros())
+        {
+            // the frames decoded so far keep the offsets they were decoded with
+            Arc::make_mut(&mut next_state.clock_offsets).insert(domain, micros as i128 - base);
+            frame.clock_offsets = next_state.clock_offsets.clone();
+        }
+        if let (true, Some(now), Some(received)) =
+            (self.is_time_sync(index as usize), now, state.received_at)
+        {
+            next_state.host_clock.sync(now, received);
+        }
+        match frame.event() {
+            Some(Event::IsrEnter(irq)) => next_state.isrs.push((irq, now)),
+            Some(Event::IsrExit) => {
+                // an exit without enter, e.g. after attaching to a running target, has no span
+                if let Some((irq, entered)) = next_state.isrs.pop() {
+                    frame.isr_span = Some(IsrSpan {
+                        irq,
+                        depth: next_state.isrs.len(),
+                        duration_micros: now
+                            .zip(entered)
+                            .and_then(|(now, entered)| now.checked_sub(entered)),
+                    });
+                }
+            }
+            _ => {}
+        }
+        if let Some((id, offset, total, data)) = reported_chunk {
+            if offset == 0 {
+                next_state.transfers.insert(id, Vec::new());
+            }
+            // a transfer with a missing chunk can't be reassembled
+            match next_state.transfers.remove(&id) {
+                Some(mut buffer) if buffer.len() == offset => {
+                    buffer.extend(data);
+                    if buffer.len() >= total {
+                        frame.reassembled = Some(buffer);
+                    } else {
+                        next_state.transfers.insert(id, buffer);
+                    }
+                }
+                _ => {}
+            }
+        }
+        frame.wall_clock = next_state
+            .wall_clock
+            .zip(now)
+            .and_then(|(anchor, now)| anchor.at(now));
+        frame.host_time = now.and_then(|now| next_state.host_clock.at(now));
+        frame.sequence_number = sequence_number;
+        frame.lost_frames = lost_frames;
+        frame.duplicate = duplicate;
+        if self.is_group_start(index as usize) {
+            next_state.group = Some(next_state.groups);
+            next_state.groups = next_state.groups.wrapping_add(1);
+        }
+        frame.group = next_state.group;
+        if self.is_group_end(index as usize) {
+            next_state.group = None;
+        }
+        // the frame was already received, nothing changed
+        if duplicate {
+            return Ok((frame, len - decoder.bytes.len()));
+        }
+
         let consumed = len - decoder.bytes.len();
+        if let Some(check) = &mut next_state.check {
+            if let Some((crc, sent)) = reported_check {
+                let received = mem::take(check);
+                frame.self_check = Some(if (crc, sent) == (received.crc, received.frames) {
+                    Ok(())
+                } else {
+                    Err(SelfCheckFailed {
+                        sent,
+                        received: received.frames,
+                    })
+                });
+            } else {
+                check.crc = crc32(check.crc, &bytes[..consumed]);
+                // like on the target, the statements of a group are a single frame
+                if frame.group.is_none() || self.is_group_end(index as usize) {
+                    check.frames += 1;
+                }
+            }
+        }
+        *state = next_state;
         Ok((frame, consumed))
     }
 
     pub fn new_stream_decoder(&self) -> Box<dyn StreamDecoder + '_> {
         match self.encoding {
             Encoding::Raw => Box::new(stream::Raw::new(self)),
+            Encoding::RawSync => Box::new(stream::RawSync::new(self)),
             Encoding::Rzcobs => Box::new(stream::Rzcobs::new(self)),
+            Encoding::Lzss => Box::new(stream::Lzss::new(self)),
+            Encoding::LengthPrefixed => Box::new(stream::LengthPrefixed::new(self)),
+        }
+    }
+
+    /// Like [`new_stream_decoder`](Self::new_stream_decoder), for firmware with `defmt`'s
+    /// `encryption` feature, whose frames are decrypted with `cipher`.
+    ///
+    /// Frames that can't be decrypted are malformed. Only the framed encodings support encryption.
+    /// This is a shorthand for [`new_stream_decoder_with_keys`](Self::new_stream_decoder_with_keys).
+    pub fn new_decrypting_stream_decoder(
+        &self,
+        cipher: impl Decrypt + 'static,
+    ) -> Box<dyn StreamDecoder + '_> {
+        self.new_stream_decoder_with_keys(Keys::default().with_cipher(cipher))
+    }
+
+    /// Like [`new_stream_decoder`](Self::new_stream_decoder), for firmware with `defmt`'s
+    /// `encryption` and/or `authentication` features, whose frames are decrypted and verified
+    /// with `keys`.
+    ///
+    /// See [`Frame::authentic`] for the result of verifying a frame. Only the framed encodings
+    /// support encryption and authentication.
+    pub fn new_stream_decoder_with_keys(&self, keys: Keys) -> Box<dyn StreamDecoder + '_> {
+        let state = StreamState {
+            keys,
+            ..StreamState::default()
+        };
+        match self.encoding {
+            Encoding::Rzcobs => Box::new(stream::Rzcobs::with_state(self, state)),
+            Encoding::LengthPrefixed => Box::new(stream::LengthPrefixed::with_state(self, state)),
+            _ => self.new_stream_decoder(),
         }
     }
 
@@ -274,11 +1006,334 @@ impl Table {
         self.encoding
     }
 
+    /// Signed integers are zigzag and LEB128 encoded since wire format version 5.
+    fn zigzag_signed(&self) -> bool {
+        self.version >= 5
+    }
+
+    /// Sub-byte arguments are packed into shared bytes since wire format version 5, see
+    /// [`defmt_parser::packed_args`].
+    fn packed_args(&self) -> bool {
+        self.version >= 5
+    }
+
+    /// String indices are LEB128 encoded since wire format version 5.
+    fn leb128_indices(&self) -> bool {
+        self.version >= 5
+    }
+
     pub fn has_timestamp(&self) -> bool {
         self.timestamp.is_some()
     }
 }
 
+/// Decodes a single frame from the start of `bytes`, like [`Table::decode`].
+///
+/// Returns the frame and the number of bytes it took up. This never panics, whatever `bytes`
+/// contains, and its resource usage is bounded by the length of `bytes`, so it is suitable as the
+/// entry point of a fuzzing harness:
+///
+/// ``` ignore
+/// fuzz_target!(|data: &[u8]| {
+///     let _ = defmt_decoder::decode_frame(data, &TABLE);
+/// });
+/// ```
+pub fn decode_frame<'t>(
+    bytes: &[u8],
+    table: &'t Table,
+) -> Result<(Frame<'t>, /* consumed: */ usize), DecodeError> {
+    table.decode(bytes)
+}
+
+/// Decrypts the frames of firmware built with `defmt`'s `encryption` feature.
+///
+/// Implement this with the AEAD and key the firmware's `Encrypt` implementation uses, e.g.
+/// ChaCha20-Poly1305.
+pub trait Decrypt {
+    /// Decrypts `data` in place with the 96-bit `nonce`, and verifies it with the authentication
+    /// tag `tag`. Returns `false` if `data` is not authentic.
+    fn decrypt(&self, nonce: &[u8; 12], data: &mut [u8], tag: &[u8; 16]) -> bool;
+}
+
+/// Length of the authentication tag of encrypted frames
+const TAG_LEN: usize = 16;
+
+/// Verifies the MACs of the frames of firmware built with `defmt`'s `authentication` feature.
+///
+/// Implement this with the MAC algorithm and key the firmware's `Authenticate` implementation
+/// uses, e.g. HMAC-SHA256.
+pub trait Verify {
+    /// Returns `true` if `mac` is the MAC of `header` followed by `data`, truncated to 8 bytes.
+    ///
+    /// `header` is the stream ID (`u32`, little endian) followed by the counter of the frame (`u64`,
+    /// little endian). Compare the MACs in constant time.
+    fn verify(&self, header: &[u8; 12], data: &[u8], mac: &[u8; 8]) -> bool;
+}
+
+/// A frame of firmware with the `authentication` feature, without its header and MAC
+struct Verified {
+    data: Vec<u8>,
+    /// Whether the MAC is valid, `None` if it wasn't verified
+    authentic: Option<bool>,
+    stream_id: u32,
+    counter: u64,
+}
+
+/// Length of the MAC of authenticated frames
+const MAC_LEN: usize = 8;
+
+/// The keys a stream decoder decrypts and verifies the frames with, see
+/// [`Table::new_stream_decoder_with_keys`].
+#[derive(Clone, Default)]
+pub struct Keys {
+    cipher: Option<Rc<dyn Decrypt>>,
+    verifier: Option<Rc<dyn Verify>>,
+}
+
+impl Keys {
+    /// Decrypts the frames with `cipher`, for firmware with the `encryption` feature.
+    pub fn with_cipher(mut self, cipher: impl Decrypt + 'static) -> Self {
+        self.cipher = Some(Rc::new(cipher));
+        self
+    }
+
+    /// Verifies the MACs of the frames with `verifier`, for firmware with the `authentication`
+    /// feature.
+    pub fn with_verifier(mut self, verifier: impl Verify + 'static) -> Self {
+        self.verifier = Some(Rc::new(verifier));
+        self
+    }
+}
+
+impl fmt::Debug for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keys")
+            .field("cipher", &self.cipher.is_some())
+            .field("verifier", &self.verifier.is_some())
+            .finish()
+    }
+}
+
+/// Returns the ack message for the frame with sequence number `sequence_number`.
+///
+/// Firmware built with the `reliable` feature retransmits every frame until the host sends this
+/// message back, see [`Frame::sequence_number`]. Retransmitted frames must be acknowledged again,
+/// since the first ack may have been lost. Must match `defmt::reliable`.
+pub fn ack(sequence_number: u8) -> [u8; 3] {
+    [0xac, sequence_number, !sequence_number]
+}
+
+/// A control message for firmware built with `defmt`'s `control` feature, which passes the data it
+/// receives from the host to `defmt::control::poll_control`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Control {
+    /// Skips the log statements below this level.
+    SetLevel(Level),
+    /// Flushes the logger.
+    Flush,
+    /// Emits a time sync frame, see [`Frame::is_time_sync`].
+    TimeSync,
+    /// Skips the log statements below `level` in the module with index `module`, or makes the
+    /// module follow the level of [`SetLevel`](Self::SetLevel) again if `level` is `None`. Needs
+    /// `defmt`'s `module-filter` feature. See [`module_index`].
+    SetModuleLevel { module: u8, level: Option<Level> },
+}
+
+impl Control {
+    /// Returns the message to send to the target. Must match `defmt::control`.
+    pub fn to_bytes(self) -> [u8; 3] {
+        let (command, argument) = match self {
+            Control::SetLevel(level) => (0xc1, level as u8),
+            Control::Flush => (0xc2, 0),
+            Control::TimeSync => (0xc3, 0),
+            Control::SetModuleLevel { module, level } => {
+                (0xd0 + level.map_or(5, |level| level as u8), module)
+            }
+        };
+        [command, argument, !argument]
+    }
+}
+
+/// Number of entries of the per-module level table. Must match `defmt`.
+const MODULES: u32 = 64;
+
+/// Returns the index of the module with path `module`, e.g. `"app::radio"`, in the per-module level
+/// table of `defmt`'s `module-filter` feature, for [`Control::SetModuleLevel`].
+///
+/// Module paths are hashed into a table of 64 entries, so modules whose paths collide share their
+/// level. Must match `defmt::export::module_index`.
+pub fn module_index(module: &str) -> u8 {
+    let hash = module.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    (hash % MODULES) as u8
+}
+
+/// State that a [`StreamDecoder`] carries over from one frame to the next
+#[derive(Clone, Debug)]
+pub(crate) struct StreamState {
+    /// Absolute timestamp of the previous frame, for delta timestamps.
+    previous_timestamp: u64,
+    /// The most recent wall-clock time reported by the target.
+    wall_clock: Option<WallClockAnchor>,
+    /// Offsets (in µs) between the clock domains reported by the target and clock domain `0`,
+    /// shared with the frames decoded since the latest report.
+    clock_offsets: Arc<BTreeMap<u16, i128>>,
+    /// The data received so far of the transfers sent by `defmt::chunked`, by transfer ID.
+    transfers: BTreeMap<u16, Vec<u8>>,
+    /// The interrupt handlers that were entered but haven't returned yet, innermost last, with
+    /// the time (in µs) they were entered.
+    isrs: Vec<(u16, Option<u128>)>,
+    /// The frames received since the last check frame, `None` if check frames aren't verified.
+    check: Option<CheckState>,
+    /// The frames whose rest follows in continuation frames, innermost last, and whether their
+    /// parts so far are authentic.
+    partial: Vec<(Vec<u8>, Option<bool>)>,
+    /// The host time (in µs since the Unix epoch) the latest data was received at, if known.
+    received_at: Option<u128>,
+    /// The time sync frames received so far.
+    host_clock: HostClock,
+    /// Sequence number of the previous frame, with the `sequence-numbers` feature.
+    sequence_number: Option<u8>,
+    /// Sequence numbers of the frames received most recently, to recognize retransmitted frames
+    /// with the `reliable` feature.
+    received: VecDeque<u8>,
+    /// Decrypts and verifies the frames, with the `encryption` and `authentication` features.
+    keys: Keys,
+    /// The counter of the latest authentic frame of each stream ID, with the `authentication`
+    /// feature.
+    counters: BTreeMap<u32, u64>,
+    /// ID of the `defmt::group!` whose statements are being decoded, if any.
+    group: Option<u32>,
+    /// Number of groups decoded so far, the ID of the next one.
+    groups: u32,
+    /// The rest of the frame holding the statements of a group, and whether it's authentic.
+    group_rest: Option<(Vec<u8>, Option<bool>)>,
+}
+
+/// Number of sequence numbers kept to recognize retransmitted frames; half of all sequence numbers,
+/// so the oldest ones are forgotten before they are reused
+const MAX_RECEIVED: usize = 128;
+
+/// Maximum number of frames waiting for their continuation frames
+const MAX_PARTIAL_FRAMES: usize = 8;
+
+impl Default for StreamState {
+    fn default() -> Self {
+        Self {
+            previous_timestamp: 0,
+            wall_clock: None,
+            clock_offsets: Arc::default(),
+            transfers: BTreeMap::new(),
+            isrs: Vec::new(),
+            check: Some(CheckState::default()),
+            partial: Vec::new(),
+            received_at: None,
+            host_clock: HostClock::default(),
+            sequence_number: None,
+            received: VecDeque::new(),
+            keys: Keys::default(),
+            counters: BTreeMap::new(),
+            group: None,
+            groups: 0,
+            group_rest: None,
+        }
+    }
+}
+
+/// CRC-32 and number of the frames received since the last check frame
+#[derive(Clone, Copy, Debug, Default)]
+struct CheckState {
+    crc: u32,
+    frames: u32,
+}
+
+/// Must match `crc32` in `defmt`.
+fn crc32(mut crc: u32, bytes: &[u8]) -> u32 {
+    crc = !crc;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & 0u32.wrapping_sub(crc & 1));
+        }
+    }
+    !crc
+}
+
+/// Wall-clock time reported by `defmt::wall_clock`, and the monotonic timestamp at that moment
+#[derive(Clone, Copy, Debug)]
+struct WallClockAnchor {
+    unix_ms: u128,
+    micros: u128,
+}
+
+impl WallClockAnchor {
+    /// Returns the wall-clock time (in ms) at the monotonic timestamp `micros`.
+    fn at(self, micros: u128) -> Option<u64> {
+        let elapsed_ms = (micros as i128 - self.micros as i128).div_euclid(1_000);
+        u64::try_from(self.unix_ms as i128 + elapsed_ms).ok()
+    }
+}
+
+/// Maximum number of time sync frames the host time is derived from
+const MAX_TIME_SYNCS: usize = 16;
+
+/// Monotonic timestamps (in µs) of the frames emitted by `defmt::time_sync`, and the host time (in
+/// µs since the Unix epoch) they were received at
+#[derive(Clone, Debug, Default)]
+struct HostClock {
+    syncs: VecDeque<(u128, u128)>,
+}
+
+impl HostClock {
+    fn sync(&mut self, micros: u128, received_at: u128) {
+        // the timestamps start over when the target resets, which invalidates the earlier syncs
+        if self.syncs.back().is_some_and(|&(last, _)| micros < last) {
+            self.syncs.clear();
+        }
+        if self.syncs.len() == MAX_TIME_SYNCS {
+            self.syncs.pop_front();
+        }
+        self.syncs.push_back((micros, received_at));
+    }
+
+    /// Returns the host time (in ms) at the monotonic timestamp `micros`.
+    ///
+    /// The host time is a least-squares fit of the time syncs, so both the offset and the drift
+    /// of the target's clock are corrected, and the latency of the transport averages out.
+    fn at(&self, micros: u128) -> Option<u64> {
+        let &(first_micros, first_received) = self.syncs.front()?;
+        // relative to the first sync, which keeps the values small enough for `f64`
+        let points = self.syncs.iter().map(|&(micros, received)| {
+            (
+                (micros - first_micros) as f64,
+                (received as i128 - first_received as i128) as f64,
+            )
+        });
+        let n = self.syncs.len() as f64;
+        let (sum_x, sum_y) = points
+            .clone()
+            .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let (mean_x, mean_y) = (sum_x / n, sum_y / n);
+        let (covariance, variance) = points.fold((0.0, 0.0), |(c, v), (x, y)| {
+            (
+                c + (x - mean_x) * (y - mean_y),
+                v + (x - mean_x) * (x - mean_x),
+            )
+        });
+        // a single sync doesn't tell the drift, assume there is none
+        let rate = if variance > 0.0 {
+            covariance / variance
+        } else {
+            1.0
+        };
+
+        let x = (micros as i128 - first_micros as i128) as f64;
+        let received = first_received as i128 + (mean_y + (x - mean_x) * rate).round() as i128;
+        u64::try_from(received.div_euclid(1_000)).ok()
+    }
+}
+
 // NOTE follows `parser::Type`
 #[derive(Debug, Clone, PartialEq)]
 enum Arg<'t> {
@@ -323,11 +1378,18 @@ struct FormatSliceElement<'t> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum DecodeError {
     /// More data is needed to decode the next frame.
     UnexpectedEof,
 
     Malformed,
+
+    /// The stream announced a wire format version that differs from the one in the ELF file.
+    VersionMismatch {
+        expected: u32,
+        found: u32,
+    },
 }
 
 impl From<io::Error> for DecodeError {
@@ -345,23 +1407,52 @@ impl fmt::Display for DecodeError {
         match self {
             DecodeError::UnexpectedEof => f.write_str("unexpected end of stream"),
             DecodeError::Malformed => f.write_str("malformed data"),
+            DecodeError::VersionMismatch { expected, found } => write!(
+                f,
+                "defmt wire format version mismatch: stream is using {found}, ELF file is using {expected}"
+            ),
+
         }
     }
 }
 
 impl Error for DecodeError {}
 
+/// A check frame of the `self-check` feature reported different data than was received since the
+/// previous check frame, i.e. frames were lost or corrupted in transit
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SelfCheckFailed {
+    /// The number of frames the target sent
+    pub sent: u32,
+    /// The number of frames that were decoded
+    pub received: u32,
+}
+
+impl fmt::Display for SelfCheckFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { sent, received } = self;
+        if sent == received {
+            write!(
+                f,
+                "self-check failed: the data of the last {sent} frames was corrupted in transit"
+            )
+        } else {
+            write!(
+                f,
+                "self-check failed: {sent} frames were sent since the last check, but {received} were received"
+            )
+        }
+    }
+}
+
+impl Error for SelfCheckFailed {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn test_table(entries: impl IntoIterator<Item = TableEntry>) -> Table {
-        Table {
-            timestamp: None,
-            entries: entries.into_iter().enumerate().collect(),
-            bitflags: Default::default(),
-            encoding: Encoding::Raw,
-        }
+        Table::new(entries.into_iter().enumerate().collect(), Encoding::Raw)
     }
 
     fn test_table_with_timestamp(
@@ -376,6 +1467,11 @@ mod tests {
             entries: entries.into_iter().enumerate().collect(),
             bitflags: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            reliable: false,
+            encrypted: false,
+            authenticated: false,
+            version: LATEST_VERSION,
         }
     }
 
@@ -399,6 +1495,11 @@ mod tests {
             )),
             bitflags: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            reliable: false,
+            encrypted: false,
+            authenticated: false,
+            version: LATEST_VERSION,
         };
 
         let frame = table.decode(bytes).unwrap().0;
@@ -406,15 +1507,32 @@ mod tests {
     }
 
     #[test]
-    fn decode() {
-        let entries = vec![
+    fn error_codes() {
+        let table = test_table([
+            TableEntry::new_without_symbol(Tag::ErrorCode, "timeout".to_owned()),
+            TableEntry::new_without_symbol(Tag::Str, "not an error code".to_owned()),
+            TableEntry::new_without_symbol(Tag::ErrorCode, "overrun".to_owned()),
+        ]);
+
+        assert_eq!(
+            table.error_codes().collect::<Vec<_>>(),
+            [(0, "timeout"), (2, "overrun")]
+        );
+        assert_eq!(table.error_message(2), Some("overrun"));
+        assert_eq!(table.error_message(1), None);
+        assert_eq!(table.error_message(3), None);
+    }
+
+    #[test]
+    fn decode() {
+        let entries = vec![
             TableEntry::new_without_symbol(Tag::Info, "Hello, world!".to_owned()),
             TableEntry::new_without_symbol(Tag::Debug, "The answer is {=u8}!".to_owned()),
         ];
 
         let table = test_table(entries);
 
-        let bytes = [0, 0];
+        let bytes = [0];
         //     index ^
 
         assert_eq!(
@@ -434,7 +1552,7 @@ mod tests {
         );
 
         let bytes = [
-            1, 0,  // index
+            1,  // index
             42, // argument
         ];
 
@@ -457,6 +1575,82 @@ mod tests {
         // TODO Format ({:?})
     }
 
+    #[test]
+    fn zigzag_integers() {
+        const FMT: &str = "Hello, {=i8} {=i16} {=i32} {=i64} {=isize} {=i8}!";
+
+        let entries = vec![TableEntry::new_without_symbol(Tag::Info, FMT.to_owned())];
+
+        let table = test_table(entries);
+
+        let bytes = [
+            0, // index
+            1, // i8
+            0xff, 0xff, 0x03, // i16
+            0xfe, 0xff, 0xff, 0xff, 0x0f, // i32
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // i64
+            0x04, // isize
+            0xff, 0x03, // i8, out of range
+        ];
+
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+
+        let bytes = &bytes[..bytes.len() - 2];
+        let frame = table.decode(&[bytes, &[0xff, 0x01]].concat()).unwrap().0;
+        assert_eq!(
+            frame.display_message().to_string(),
+            format!("Hello, -1 {} {} {} 2 -128!", i16::MIN, i32::MAX, i64::MIN)
+        );
+    }
+
+    #[test]
+    fn leb128_indices() {
+        let entries = (0..=300).map(|i| TableEntry::new_without_symbol(Tag::Info, i.to_string()));
+        let table = test_table(entries);
+
+        let bytes = [
+            0xac, 0x02, // index
+        ];
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(frame.index(), 300);
+        assert_eq!(frame.display_message().to_string(), "300");
+        assert_eq!(consumed, bytes.len());
+
+        let bytes = [
+            0xad, 0x02, // index, out of range
+        ];
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn odd_width_integers() {
+        const FMT: &str = "{=u24} {=u48} {=u56} {=i24} {=i48:x} {=i56}";
+
+        let entries = vec![TableEntry::new_without_symbol(Tag::Info, FMT.to_owned())];
+
+        let table = test_table(entries);
+
+        let bytes = [
+            0, // index
+            0x56, 0x34, 0x12, // u24
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // u48
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, // u56
+            0xfe, 0xff, 0xff, // i24
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // i48
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, // i56
+        ];
+
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(
+            frame.display_message().to_string(),
+            format!(
+                "1193046 281474976710655 36028797018963969 -2 ffffffffffff {}",
+                -(1i64 << 55)
+            )
+        );
+    }
+
     #[test]
     fn all_integers() {
         const FMT: &str =
@@ -464,7 +1658,9 @@ mod tests {
 
         let entries = vec![TableEntry::new_without_symbol(Tag::Info, FMT.to_owned())];
 
-        let table = test_table(entries);
+        // fixed-width signed integers and string indices
+        let mut table = test_table(entries);
+        table.version = 4;
 
         let bytes = [
             0, 0,  // index
@@ -493,16 +1689,16 @@ mod tests {
                     vec![],
                     FMT,
                     vec![
-                        Arg::Uxx(42),                      // u8
-                        Arg::Uxx(u16::max_value().into()), // u16
-                        Arg::Uxx(u32::max_value().into()), // u32
-                        Arg::Uxx(u64::max_value().into()), // u64
-                        Arg::Uxx(u128::max_value()),       // u128
-                        Arg::Ixx(-1),                      // i8
-                        Arg::Ixx(-1),                      // i16
-                        Arg::Ixx(-1),                      // i32
-                        Arg::Ixx(-1),                      // i64
-                        Arg::Ixx(-1),                      // i128
+                        Arg::Uxx(42),              // u8
+                        Arg::Uxx(u16::MAX.into()), // u16
+                        Arg::Uxx(u32::MAX.into()), // u32
+                        Arg::Uxx(u64::MAX.into()), // u64
+                        Arg::Uxx(u128::MAX),       // u128
+                        Arg::Ixx(-1),              // i8
+                        Arg::Ixx(-1),              // i16
+                        Arg::Ixx(-1),              // i32
+                        Arg::Ixx(-1),              // i64
+                        Arg::Ixx(-1),              // i128
                     ],
                 ),
                 bytes.len(),
@@ -522,7 +1718,7 @@ mod tests {
 
         let table = test_table(entries);
         let bytes = [
-            0, 0,  // index
+            0,  // index
             42, // argument
         ];
 
@@ -543,7 +1739,7 @@ mod tests {
         );
 
         let bytes = [
-            1, 0,  // index
+            1,  // index
             42, // u8
             0xff, 0xff, // u16
         ];
@@ -575,8 +1771,8 @@ mod tests {
         let table = test_table(entries);
 
         let bytes = [
-            0, 0, // index
-            1, 0,  // index of the struct
+            0,  // index
+            1,  // index of the struct
             42, // Foo.x
         ];
 
@@ -612,13 +1808,13 @@ mod tests {
         let table = test_table(entries);
 
         let bytes = [
-            0, 0, // index
-            1, 0, // index of Foo
-            2, 0,  // index of Bar
+            0,  // index
+            1,  // index of Foo
+            2,  // index of Bar
             42, // bar.x
-            3, 0,  // index of State
+            3,  // index of State
             23, // State variable
-            0, 0, // terminator
+            0,  // terminator
         ];
 
         assert_eq!(
@@ -663,9 +1859,9 @@ mod tests {
         let table = test_table_with_timestamp(entries, "{=u8:us}");
 
         let bytes = [
-            0, 0, // index
-            2, // timestamp
-            1, 0,  // index of the struct
+            0,  // index
+            2,  // timestamp
+            1,  // index of the struct
             42, // Foo.x
         ];
 
@@ -676,15 +1872,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn json() {
+        let entries = vec![
+            TableEntry::new_without_symbol(
+                Tag::Warn,
+                "{=u8:x} {=i16} {=bool} {=str} {=?} {=[u8]} {=[?]}".to_owned(),
+            ),
+            TableEntry::new_without_symbol(Tag::Derived, "Foo {{ x: {=u8} }}".to_owned()),
+        ];
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0,  // index
+            2,  // timestamp
+            42, // u8
+            3,  // i16
+            1,  // bool
+            2, 0, 0, 0, b'h', b'i', // str
+            1, 7, // Foo { x: 7 }
+            2, 0, 0, 0, 1, 2, // [u8]
+            1, 0, 0, 0, 1, 8, // [Foo]
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        let location = Location {
+            file: "src/main.rs".into(),
+            line: 12,
+            module: "app::net".to_owned(),
+        };
+
+        assert_eq!(
+            frame.to_json(Some(&location)),
+            serde_json::json!({
+                "timestamp": "0.000002",
+                "wall_clock": null,
+                "level": "warn",
+                "module": "app::net",
+                "file": "src/main.rs",
+                "line": 12,
+                "message": "2a -2 true hi Foo { x: 7 } [1, 2] [Foo { x: 8 }]",
+                "args": [42, -2, true, "hi", "Foo { x: 7 }", [1, 2], "[Foo { x: 8 }]"],
+            })
+        );
+
+        let json = frame.to_json(None);
+        assert_eq!(json["module"], serde_json::Value::Null);
+        assert_eq!(json["line"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn timestamp_width() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "x={=u8}".to_owned(),
+        )];
+
+        let table = test_table_with_timestamp(entries, "{=u32:us}");
+
+        let bytes = [
+            0, // index
+            1, 0, 0, 1,  // timestamp
+            42, // x
+        ];
+
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.display(false).to_string(), "16.777217 INFO x=42");
+
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "x={=u8}".to_owned(),
+        )];
+
+        let table = test_table(entries);
+
+        let bytes = [
+            0,  // index
+            42, // x
+        ];
+
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.display(false).to_string(), "INFO x=42");
+    }
+
+    #[test]
+    fn formatted_timestamp() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello".to_owned()),
+            TableEntry::new_without_symbol(Tag::Write, "{=u8}.{=u16:03}s".to_owned()),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u8}.{=u16:03}");
+
+        let bytes = [
+            0,  // index
+            12, // seconds
+            34, 0, // milliseconds
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display(false).to_string(), "12.034 INFO Hello");
+
+        let table = test_table_with_timestamp(table.entries.into_values(), "{}");
+
+        let bytes = [
+            0,  // index
+            1,  // index of the timestamp's `write!` format string
+            12, // seconds
+            34, 0, // milliseconds
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display(false).to_string(), "12.034s INFO Hello");
+    }
+
     #[test]
     fn display_i16_with_hex_hint() {
         // defmt::info!("x: {=i16:#x},y: {=i16:#x},z: {=i16:#x}", -1_i16, -100_i16, -1000_i16);
         let bytes = [
-            0,
-            0,           // index
-            2,           // timestamp
-            0b1111_1111, // the logged i16 value -1
-            0b1111_1111,
+            0, // index
+            2, // timestamp
+            1, // the logged i16 value -1, zigzag encoded
         ];
 
         decode_and_expect(
@@ -704,9 +2013,9 @@ mod tests {
         let table = test_table_with_timestamp(entries, "{=u8:us}");
 
         let bytes = [
-            0, 0, // index
-            2, // timestamp
-            1, 0,  // index of the struct
+            0,  // index
+            2,  // timestamp
+            1,  // index of the struct
             42, // value
         ];
 
@@ -727,9 +2036,9 @@ mod tests {
         let table = test_table_with_timestamp(entries, "{=u8:us}");
 
         let bytes = [
-            0, 0, // index
-            2, // timestamp
-            1, 0,  // index of the struct
+            0,  // index
+            2,  // timestamp
+            1,  // index of the struct
             42, // value
         ];
 
@@ -750,9 +2059,9 @@ mod tests {
         let table = test_table_with_timestamp(entries, "{=u8:us}");
 
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
-            1, 0, // index into the struct
+            1, // index into the struct
             5, 0, 0, 0, // length of the string
             b'H', b'e', b'l', b'l', b'o', // string "Hello"
         ];
@@ -775,13 +2084,13 @@ mod tests {
         let table = test_table_with_timestamp(entries, "{=u8:us}");
 
         let bytes = [
-            3, 0, // frame index
+            3, // frame index
             2, // timestamp value of type `u8`
             1, 0, 0, 0, // number of elements in `FormatSlice`
-            2, 0, // index to `Data` struct
-            1, 0, // Format index to table entry: `{=[?]}`
-            2, 0, 0, 0, // inner FormatSlice, number of elements in `name` field
-            0, 0,   // Format index to table entry: `{=u8}`
+            2, // index to `Data` struct
+            1, // Format index to table entry: `{=[?]}`
+            2, 0, 0, 0,   // inner FormatSlice, number of elements in `name` field
+            0,   // Format index to table entry: `{=u8}`
             72,  // "H"
             105, // "i"
         ];
@@ -792,10 +2101,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display_hexdump() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Println,
+            "rx:{=[u8]:hexdump}".to_owned(),
+        )];
+
+        let table = test_table(entries);
+
+        let mut bytes = vec![
+            0, // index
+            20, 0, 0, 0, // length of the slice
+        ];
+        bytes.extend(b"Hello, world!\n\x00\x01\x02\x7f\xffabc");
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display_message().to_string(),
+            "rx:\n\
+             00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|\n\
+             00000010  02 7f ff 61                                       |...a|",
+        );
+    }
+
+    #[test]
+    fn display_ticks_timestamp() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello".to_owned(),
+        )];
+
+        let table = test_table_with_timestamp(entries, "{=u32:ticks@32768}");
+
+        let bytes = [
+            0, // index
+            0x00, 0xc0, 0, 0, // timestamp = 1.5 * 32768
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display(false).to_string(), "1.500000 INFO Hello");
+    }
+
     #[test]
     fn display_iso8601_timestamp() {
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
             36, 188, 151, 238, 120, 1, 0, 0, // unix timestamp in bytes: 1618910624804
         ];
@@ -810,7 +2161,7 @@ mod tests {
     #[test]
     fn bools_simple() {
         let bytes = [
-            0, 0,          // index
+            0,          // index
             2,          // timestamp
             true as u8, // the logged bool value
         ];
@@ -818,10 +2169,62 @@ mod tests {
         decode_and_expect("my bool={=bool}", &bytes, "0.000002 INFO my bool=true");
     }
 
+    #[test]
+    fn malformed_frames() {
+        let table = test_table([
+            TableEntry::new_without_symbol(Tag::Info, "{=?}".to_string()),
+            TableEntry::new_without_symbol(Tag::Info, "{=[?]}".to_string()),
+            TableEntry::new_without_symbol(Tag::Dynamic, "{=str}".to_string()),
+            TableEntry::new_without_symbol(Tag::Derived, "x".to_string()),
+        ]);
+
+        // format nested in itself: fails instead of overflowing the stack
+        let bytes = vec![0; 100_000];
+        assert_eq!(
+            decode_frame(&bytes, &table).unwrap_err(),
+            DecodeError::Malformed
+        );
+
+        // huge slice of elements without data: fails instead of allocating it
+        let mut bytes = vec![1];
+        bytes.extend(u32::MAX.to_le_bytes());
+        bytes.push(3);
+        assert_eq!(
+            decode_frame(&bytes, &table).unwrap_err(),
+            DecodeError::Malformed
+        );
+        // ... but a reasonable number is fine
+        let bytes = [1, 3, 0, 0, 0, 3];
+        assert!(decode_frame(&bytes, &table).is_ok());
+
+        // truncated bitfield in a runtime format string
+        let mut bytes = vec![2, 2, 5, 0, 0, 0]; // index, level, format length
+        bytes.extend(b"{0=1}");
+        assert_eq!(
+            decode_frame(&bytes, &table).unwrap_err(),
+            DecodeError::Malformed
+        );
+    }
+
+    #[test]
+    fn bools_packed() {
+        let bytes = [
+            0,      // index
+            2,      // timestamp
+            0b0101, // the 3 bools and the low bit of the bitfield, packed
+            42,     // u8
+        ];
+
+        decode_and_expect(
+            "{=bool} {=bool} {=u8} {=bool} {=0..1}",
+            &bytes,
+            "0.000002 INFO true false 42 true 0",
+        );
+    }
+
     #[test]
     fn bitfields() {
         let bytes = [
-            0,
             0,           // index
             2,           // timestamp
             0b1110_0101, // u8
@@ -836,7 +2239,6 @@ mod tests {
     #[test]
     fn bitfields_reverse_order() {
         let bytes = [
-            0,
             0,           // index
             2,           // timestamp
             0b1101_0010, // u8
@@ -851,11 +2253,10 @@ mod tests {
     #[test]
     fn bitfields_different_indices() {
         let bytes = [
-            0,
-            0,           // index
-            2,           // timestamp
-            0b1111_0000, // u8
-            0b1110_0101, // u8
+            0,       // index
+            2,       // timestamp
+            0b10000, // packed bitfield
+            0b11100, // packed bitfield, doesn't fit in the first byte
         ];
         decode_and_expect(
             "#0: {0=0..5:b}, #1: {1=3..8:b}",
@@ -867,7 +2268,6 @@ mod tests {
     #[test]
     fn bitfields_u16() {
         let bytes = [
-            0,
             0, // index
             2, // timestamp
             0b1111_0000,
@@ -879,12 +2279,10 @@ mod tests {
     #[test]
     fn bitfields_mixed_types() {
         let bytes = [
-            0,
-            0, // index
-            2, // timestamp
-            0b1111_0000,
-            0b1110_0101, // u16
-            0b1111_0001, // u8
+            0,       // index
+            2,       // timestamp
+            0b01011, // packed u16 bitfield
+            0b10001, // packed u8 bitfield
         ];
         decode_and_expect(
             "#0: {0=7..12:b}, #1: {1=0..5:b}",
@@ -896,13 +2294,11 @@ mod tests {
     #[test]
     fn bitfields_mixed() {
         let bytes = [
-            0,
-            0, // index
-            2, // timestamp
-            0b1111_0000,
-            0b1110_0101, // u16 bitfields
-            42,          // u8
-            0b1111_0001, // u8 bitfields
+            0,       // index
+            2,       // timestamp
+            0b01011, // packed u16 bitfield
+            0b10001, // packed u8 bitfield
+            42,      // u8
         ];
         decode_and_expect(
             "#0: {0=7..12:b}, #1: {1=u8}, #2: {2=0..5:b}",
@@ -914,7 +2310,6 @@ mod tests {
     #[test]
     fn bitfields_across_boundaries() {
         let bytes = [
-            0,
             0, // index
             2, // timestamp
             0b1101_0010,
@@ -930,7 +2325,6 @@ mod tests {
     #[test]
     fn bitfields_across_boundaries_diff_indices() {
         let bytes = [
-            0,
             0, // index
             2, // timestamp
             0b1101_0010,
@@ -947,7 +2341,6 @@ mod tests {
     #[test]
     fn bitfields_truncated_front() {
         let bytes = [
-            0,
             0,           // index
             2,           // timestamp
             0b0110_0011, // truncated(!) u16
@@ -962,7 +2355,6 @@ mod tests {
     #[test]
     fn bitfields_non_truncated_u32() {
         let bytes = [
-            0,
             0,           // index
             2,           // timestamp
             0b0110_0011, // -
@@ -980,7 +2372,6 @@ mod tests {
     #[test]
     fn bitfields_u128() {
         let bytes = [
-            0,
             0,           // index
             2,           // timestamp
             0b1110_0101, // 120..127
@@ -1006,7 +2397,7 @@ mod tests {
     #[test]
     fn slice() {
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
             2, 0, 0, 0, // length of the slice
             23, 42, // slice content
@@ -1017,7 +2408,7 @@ mod tests {
     #[test]
     fn slice_with_trailing_args() {
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
             2, 0, 0, 0, // length of the slice
             23, 42, // slice content
@@ -1031,10 +2422,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn interned_string() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "error: {=istr}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Str, "disk full".to_owned()),
+        ];
+
+        let table = test_table(entries);
+        let bytes = [
+            0, // index
+            1, // index of the interned string
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display_message().to_string(), "error: disk full");
+
+        let bytes = [
+            0, // index
+            0, // index of a log message, not an interned string
+        ];
+
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+    }
+
     #[test]
     fn string_hello_world() {
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
             5, 0, 0, 0, // length of the string
             b'W', b'o', b'r', b'l', b'd',
@@ -1046,7 +2461,7 @@ mod tests {
     #[test]
     fn string_with_trailing_data() {
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
             5, 0, 0, 0, // length of the string
             b'W', b'o', b'r', b'l', b'd', 125, // trailing data
@@ -1062,7 +2477,7 @@ mod tests {
     #[test]
     fn char_data() {
         let bytes = [
-            0, 0, // index
+            0, // index
             2, // timestamp
             0x61, 0x00, 0x00, 0x00, // char 'a'
             0x9C, 0xF4, 0x01, 0x00, // Purple heart emoji
@@ -1075,6 +2490,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn char_debug() {
+        let bytes = [
+            0, // index
+            2, // timestamp
+            0x27, 0x00, 0x00, 0x00, // char '\''
+        ];
+
+        decode_and_expect("c={=char:?}", &bytes, "0.000002 INFO c='\\''");
+    }
+
     #[test]
     fn option() {
         let mut entries = BTreeMap::new();
@@ -1099,14 +2525,19 @@ mod tests {
             )),
             bitflags: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            reliable: false,
+            encrypted: false,
+            authenticated: false,
+            version: LATEST_VERSION,
         };
 
         let bytes = [
-            4, 0, // string index (INFO)
-            0, // timestamp
-            3, 0, // string index (enum)
-            1, // Some discriminant
-            2, 0,  // string index (u8)
+            4,  // string index (INFO)
+            0,  // timestamp
+            3,  // string index (enum)
+            1,  // Some discriminant
+            2,  // string index (u8)
             42, // Some.0
         ];
 
@@ -1114,13 +2545,1288 @@ mod tests {
         assert_eq!(frame.display(false).to_string(), "0.000000 INFO x=Some(42)");
 
         let bytes = [
-            4, 0, // string index (INFO)
+            4, // string index (INFO)
             1, // timestamp
-            3, 0, // string index (enum)
+            3, // string index (enum)
             0, // None discriminant
         ];
 
         let frame = table.decode(&bytes).unwrap().0;
         assert_eq!(frame.display(false).to_string(), "0.000001 INFO x=None");
     }
+
+    #[test]
+    fn option_non_zero() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "x={=?} y={=?:x}".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::Derived,
+                "{=u8:__internal_niche_option}".to_owned(),
+            ),
+            TableEntry::new_without_symbol(
+                Tag::Derived,
+                "{=i8:__internal_niche_option}".to_owned(),
+            ),
+        ];
+
+        let table = test_table(entries);
+
+        let bytes = [
+            0,  // index
+            1,  // string index (Option<NonZeroU8>)
+            0,  // None
+            2,  // string index (Option<NonZeroI8>)
+            83, // Some(-42), zigzag encoded
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display_message().to_string(), "x=None y=Some(d6)");
+
+        let bytes = [
+            0,  // index
+            1,  // string index (Option<NonZeroU8>)
+            42, // Some(42)
+            2,  // string index (Option<NonZeroI8>)
+            0,  // None
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.display_message().to_string(), "x=Some(42) y=None");
+    }
+
+    #[test]
+    fn heartbeat() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello, world!".to_owned()),
+            TableEntry::new_without_symbol(Tag::Heartbeat, "heartbeat".to_owned()),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            1,  // index
+            42, // timestamp
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert!(frame.is_heartbeat());
+        assert_eq!(frame.level(), None);
+        assert_eq!(frame.display_timestamp().unwrap().to_string(), "0.000042");
+
+        let bytes = [
+            0,  // index
+            42, // timestamp
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert!(!frame.is_heartbeat());
+    }
+
+    #[test]
+    fn runtime_level() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Log,
+            "The answer is {=u8}".to_owned(),
+        )];
+
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0,  // index
+            42, // timestamp
+            3,  // level
+            42, // u8
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.level(), Some(Level::Warn));
+        assert_eq!(frame.display_message().to_string(), "The answer is 42");
+        assert_eq!(table.indices().collect::<Vec<_>>(), [0]);
+
+        let bytes = [
+            0,  // index
+            42, // timestamp
+            5,  // level, out of range
+            42, // u8
+        ];
+
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn dynamic() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Dynamic,
+            "{=str}".to_owned(),
+        )];
+
+        let table = test_table(entries);
+
+        let bytes = [
+            0, // index
+            2, // level
+            9, 0, 0, 0, // length of the format string
+            b'x', b'=', b'{', b'=', b'u', b'8', b':', b'x', b'}', // format string
+            42,   // u8
+        ];
+
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.level(), Some(Level::Info));
+        assert_eq!(frame.display_message().to_string(), "x=2a");
+        assert_eq!(table.indices().collect::<Vec<_>>(), [0]);
+
+        let bytes = [
+            0, // index
+            2, // level
+            1, 0, 0, 0,    // length of the format string
+            b'{', // format string, invalid
+        ];
+
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn version() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Version,
+            "wire format version {=u8}".to_owned(),
+        )];
+
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0,  // index
+            42, // timestamp
+            5,  // version
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert!(frame.is_version());
+
+        let bytes = [
+            0,  // index
+            42, // timestamp
+            6,  // version
+        ];
+
+        assert_eq!(
+            table.decode(&bytes),
+            Err(DecodeError::VersionMismatch {
+                expected: 5,
+                found: 6
+            })
+        );
+    }
+
+    #[test]
+    fn isr_span() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Event, "isr_enter={=u16}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Event, "isr_exit".to_owned()),
+        ];
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[
+            1,  // isr_exit, without isr_enter
+            5,  // timestamp
+            0,  // isr_enter
+            10, // timestamp
+            3, 0,  // irq
+            0,  // isr_enter
+            12, // timestamp
+            7, 0,  // irq
+            1,  // isr_exit
+            15, // timestamp
+            1,  // isr_exit
+            20, // timestamp
+        ]);
+
+        assert_eq!(stream_decoder.decode().unwrap().isr_span(), None);
+        assert_eq!(stream_decoder.decode().unwrap().isr_span(), None);
+        assert_eq!(stream_decoder.decode().unwrap().isr_span(), None);
+        assert_eq!(
+            stream_decoder.decode().unwrap().isr_span(),
+            Some(IsrSpan {
+                irq: 7,
+                depth: 1,
+                duration_micros: Some(3),
+            })
+        );
+        assert_eq!(
+            stream_decoder.decode().unwrap().isr_span(),
+            Some(IsrSpan {
+                irq: 3,
+                depth: 0,
+                duration_micros: Some(10),
+            })
+        );
+    }
+
+    #[test]
+    fn event() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Event, "isr_enter={=u16}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Event, "isr_exit".to_owned()),
+            TableEntry::new_without_symbol(Tag::Println, "isr_exit".to_owned()),
+            TableEntry::new_without_symbol(Tag::Event, "event=adc_done".to_owned()),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0,  // index
+            42, // timestamp
+            3, 0, // irq
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.event(), Some(Event::IsrEnter(3)));
+        assert_eq!(frame.display(false).to_string(), "0.000042 isr_enter=3");
+
+        let bytes = [
+            1,  // index
+            43, // timestamp
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.event(), Some(Event::IsrExit));
+
+        let bytes = [
+            2,  // index
+            44, // timestamp
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.event(), None);
+
+        let bytes = [
+            3,  // index
+            45, // timestamp
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.event(), Some(Event::Named("adc_done")));
+        assert_eq!(frame.display(false).to_string(), "0.000045 event=adc_done");
+    }
+
+    #[test]
+    fn test_event() {
+        let entries = vec![
+            TableEntry::new_without_symbol(
+                Tag::Test,
+                "({=u16}/{=u16}) running `{=istr}`...".to_owned(),
+            ),
+            TableEntry::new_without_symbol(Tag::Test, "`{=istr}` failed".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::Test,
+                "test result: {=u16} passed; {=u16} failed; {=u16} ignored".to_owned(),
+            ),
+            TableEntry::new_without_symbol(Tag::Str, "assert_true".to_owned()),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0, // index
+            1, // timestamp
+            1, 0, // number
+            8, 0, // count
+            3, // name
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.test_event(),
+            Some(TestEvent::Started {
+                number: 1,
+                count: 8,
+                name: "assert_true"
+            })
+        );
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000001 (1/8) running `assert_true`..."
+        );
+
+        let bytes = [
+            1, // index
+            2, // timestamp
+            3, // name
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.test_event(), Some(TestEvent::Failed("assert_true")));
+
+        let bytes = [
+            2, // index
+            3, // timestamp
+            7, 0, // passed
+            0, 0, // failed
+            1, 0, // ignored
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.test_event(),
+            Some(TestEvent::Finished {
+                passed: 7,
+                failed: 0,
+                ignored: 1
+            })
+        );
+    }
+
+    #[test]
+    fn self_check() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "x={=u8}".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::SelfCheck,
+                "self-check: crc {=u32:#010x} over {=u16} frames".to_owned(),
+            ),
+        ];
+        let table = test_table(entries);
+
+        let crc = crc32(0, &[0, 42]).to_le_bytes();
+        let mut bytes = vec![
+            0, 42, // x=42
+            1, crc[0], crc[1], crc[2], crc[3], 1, 0, // check frame
+            0, 43, // x=43
+            1, crc[0], crc[1], crc[2], crc[3], 1, 0, // check frame, with the CRC of x=42
+        ];
+        let mut decoder = table.new_stream_decoder();
+        decoder.received(&bytes);
+
+        assert_eq!(decoder.decode().unwrap().self_check(), None);
+        let frame = decoder.decode().unwrap();
+        assert!(frame.is_self_check());
+        assert_eq!(frame.self_check(), Some(Ok(())));
+        decoder.decode().unwrap();
+        assert_eq!(
+            decoder.decode().unwrap().self_check(),
+            Some(Err(SelfCheckFailed {
+                sent: 1,
+                received: 1
+            }))
+        );
+
+        // not verified on its own
+        bytes.drain(..2);
+        assert_eq!(table.decode(&bytes).unwrap().0.self_check(), None);
+    }
+
+    #[test]
+    fn delta_timestamp() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.set_timestamp_entry(TableEntry::new_without_symbol(
+            Tag::TimestampDelta,
+            "{=u64:us}".to_owned(),
+        ));
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[
+            0, // index
+            0xe8, 0x07, // timestamp delta = 1000
+        ]);
+        stream_decoder.received(&[0]); // index
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display(false).to_string(), "0.001000 INFO Hello");
+
+        stream_decoder.received(&[
+            5, // timestamp delta = 5
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display(false).to_string(), "0.001005 INFO Hello");
+    }
+
+    #[test]
+    fn raw_sync_stream() {
+        const SYNC: [u8; 8] = [0xd3, 0xfe, 0x6d, 0x74, 0x5e, 0xc1, 0xa7, 0x0b];
+
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8} {=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::RawSync;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        // attached mid-frame
+        stream_decoder.received(&[
+            1, 2, // end of a frame
+            0, 3, 4, // frame before the next marker
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+        stream_decoder.received(&SYNC[..5]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+        stream_decoder.received(&SYNC[5..]);
+        stream_decoder.received(&[
+            0, 5, 6, // index, u8, u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "5 6");
+
+        // a lost byte results in a malformed frame, the next one after a marker is decoded again
+        stream_decoder.received(&[
+            7, 8, // frame with a missing index
+            0, 9, 10, // frame before the next marker
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+        stream_decoder.received(&SYNC);
+        stream_decoder.received(&[
+            0, 11, 12, // index, u8, u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "11 12");
+    }
+
+    #[test]
+    fn length_prefixed_stream() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8} {=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::LengthPrefixed;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[
+            3, // length
+            0, 1, // index, u8
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+        stream_decoder.received(&[
+            2, // u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "1 2");
+
+        stream_decoder.received(&[
+            0, // length, frame dropped by the encoder
+            4, // length
+            0, 3, 4, 5, // index, u8, u8, trailing data
+            3, // length
+            0, 6, 7, // index, u8, u8
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "6 7");
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+    }
+
+    #[test]
+    fn split_frames() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "{=u8} {=u8} {=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Continuation, "(continued)".to_owned()),
+            TableEntry::new_without_symbol(Tag::Info, "{=u8}".to_owned()),
+        ];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::LengthPrefixed;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[
+            2, // length
+            0, 1, // index, u8
+            2, // length
+            1, 2, // continuation index, u8
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+        stream_decoder.received(&[
+            2, // length
+            2, 3, // index, u8 of a frame that interrupted the split frame
+            2, // length
+            1, 4, // continuation index, u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "3");
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "1 2 4");
+
+        // a continuation frame without a split frame
+        stream_decoder.received(&[
+            2, // length
+            1, 5, // continuation index, u8
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn lzss_stream() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8} {=u8} {=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Lzss;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[
+            0x03, // 4 literals
+            0, 1, 2, 3, // index, u8, u8, u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "1 2 3");
+
+        stream_decoder.received(&[
+            0x81, // match of 4 bytes
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+        stream_decoder.received(&[
+            3, // 4 bytes back
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "1 2 3");
+
+        stream_decoder.received(&[
+            0x80, // match of 3 bytes
+            0xff, // 256 bytes back, before the start of the stream
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn lzss_window_wraps() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8} {=u8} {=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Lzss;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        // 70 frames of 4 bytes, more than the window holds
+        for i in 0..70 {
+            stream_decoder.received(&[0x03, 0, i, i, i]);
+            let frame = stream_decoder.decode().unwrap();
+            assert_eq!(frame.display_message().to_string(), format!("{i} {i} {i}"));
+        }
+
+        stream_decoder.received(&[
+            0x81, // match of 4 bytes
+            0xff, // 256 bytes back, the start of the 7th frame
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "6 6 6");
+    }
+
+    #[test]
+    fn lzss_reset() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8} {=u8} {=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Lzss;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[0x03, 0, 1, 2, 3]);
+        assert!(stream_decoder.decode().is_ok());
+
+        stream_decoder.received(&[
+            0x7f, // reset, the target dropped a frame
+            0x80, // match of 3 bytes
+            3,    // 4 bytes back, before the reset
+        ]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn wall_clock() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::WallClock,
+                "wall clock: {=u64:iso8601ms}".to_owned(),
+            ),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u16:ms}");
+        let mut stream_decoder = table.new_stream_decoder();
+
+        stream_decoder.received(&[
+            0, // index
+            10, 0, // timestamp
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.wall_clock(), None);
+
+        stream_decoder.received(&[
+            1, // index
+            20, 0, // timestamp
+            36, 188, 151, 238, 120, 1, 0, 0, // unix time in ms: 1618910624804
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.wall_clock(), Some(1618910624804));
+        assert_eq!(
+            frame.display_message().to_string(),
+            "wall clock: 2021-04-20T09:23:44.804Z"
+        );
+
+        stream_decoder.received(&[
+            0, // index
+            0xe8, 0x03, // timestamp
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.wall_clock(), Some(1618910624804 + 980));
+    }
+
+    #[test]
+    fn metric() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Counter, "rx_packets += 1".to_owned()),
+            TableEntry::new_without_symbol(Tag::Counter, "rx_bytes += {=u32}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Gauge, "temperature = {=i32}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Println, "rx_packets += 1".to_owned()),
+        ];
+
+        let table = test_table(entries);
+
+        let frame = table.decode(&[0]).unwrap().0;
+        assert_eq!(
+            frame.metric(),
+            Some(Metric::Counter {
+                name: "rx_packets",
+                delta: 1
+            })
+        );
+        assert_eq!(frame.display(false).to_string(), "rx_packets += 1");
+
+        let frame = table.decode(&[1, 64, 0, 0, 0]).unwrap().0;
+        assert_eq!(
+            frame.metric(),
+            Some(Metric::Counter {
+                name: "rx_bytes",
+                delta: 64
+            })
+        );
+
+        let bytes = [
+            2, // index
+            5, // value, zigzag encoded
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.metric(),
+            Some(Metric::Gauge {
+                name: "temperature",
+                value: -3
+            })
+        );
+
+        let frame = table.decode(&[3]).unwrap().0;
+        assert_eq!(frame.metric(), None);
+    }
+
+    #[test]
+    fn stack_usage() {
+        let entries = vec![
+            TableEntry::new_without_symbol(
+                Tag::Info,
+                "stack: {=u32} of {=u32} bytes used".to_owned(),
+            ),
+            TableEntry::new_without_symbol(
+                Tag::Stack,
+                "stack: {=u32} of {=u32} bytes used".to_owned(),
+            ),
+        ];
+        let table = test_table(entries);
+
+        let bytes = [
+            1, // index
+            0, 6, 0, 0, // used
+            0, 32, 0, 0, // size
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.stack_usage(),
+            Some(StackUsage {
+                used: 1536,
+                size: 8192,
+            })
+        );
+        assert_eq!(
+            frame.display_message().to_string(),
+            "stack: 1536 of 8192 bytes used"
+        );
+
+        let bytes = [
+            0, // index
+            0, 6, 0, 0, // used
+            0, 32, 0, 0, // size
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.stack_usage(), None);
+    }
+
+    #[test]
+    fn chunked() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Str, "capture".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::Chunk,
+                "{=istr} #{=u16} @{=u32}/{=u32}: {=[u8]:02x}".to_owned(),
+            ),
+        ];
+        let table = test_table(entries);
+
+        let chunk = |id: u8, offset: u8, data: &[u8]| {
+            let mut bytes = vec![
+                1, // index
+                0, // label
+                id,
+                0, // transfer ID
+                offset,
+                0,
+                0,
+                0, // offset
+                3,
+                0,
+                0,
+                0, // total
+                data.len() as u8,
+                0,
+                0,
+                0, // data.len()
+            ];
+            bytes.extend(data);
+            bytes
+        };
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&chunk(7, 0, &[1, 2]));
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(
+            frame.chunk(),
+            Some(Chunk {
+                label: "capture",
+                id: 7,
+                offset: 0,
+                total: 3,
+                data: &[1, 2],
+            })
+        );
+        assert_eq!(frame.reassembled(), None);
+        assert_eq!(
+            frame.display_message().to_string(),
+            "capture #7 @0/3: [01, 02]"
+        );
+
+        // another transfer in between doesn't interfere
+        stream_decoder.received(&chunk(8, 0, &[4, 5]));
+        assert_eq!(stream_decoder.decode().unwrap().reassembled(), None);
+
+        stream_decoder.received(&chunk(7, 2, &[3]));
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.reassembled(), Some(&[1, 2, 3][..]));
+
+        // transfer 8 lost a chunk
+        stream_decoder.received(&chunk(8, 3, &[]));
+        let frame = stream_decoder.decode().unwrap();
+        assert!(frame.chunk().is_some());
+        assert_eq!(frame.reassembled(), None);
+    }
+
+    #[test]
+    fn clock_sync() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::ClockSync,
+                "clock sync: domain {=u16} at {=u64:us}".to_owned(),
+            ),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u16:us}");
+        let mut stream_decoder = table.new_stream_decoder();
+
+        stream_decoder.received(&[
+            1, // index
+            100, 0, // timestamp
+            1, 0, // domain
+            0x10, 0x27, 0, 0, 0, 0, 0, 0, // domain time: 10_000
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.domain_timestamp(1), Some(10_000));
+
+        stream_decoder.received(&[
+            0, // index
+            150, 0, // timestamp
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.domain_timestamp(1), Some(10_050));
+        assert_eq!(frame.domain_timestamp(2), None);
+    }
+
+    #[test]
+    fn clock_domains() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::ClockSync,
+                "clock sync: domain {=u16} at {=u64:us}".to_owned(),
+            ),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u16:domain}/{=u16:us}");
+        let mut stream_decoder = table.new_stream_decoder();
+
+        stream_decoder.received(&[
+            1, // index
+            0, 0, // clock domain
+            100, 0, // timestamp
+            1, 0, // domain
+            0x10, 0x27, 0, 0, 0, 0, 0, 0, // domain time: 10_000
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.clock_domain(), 0);
+        assert_eq!(frame.domain_timestamp(1), Some(10_000));
+
+        // a statement timestamped in domain 1
+        stream_decoder.received(&[
+            0, // index
+            1, 0, // clock domain
+            0x42, 0x27, // timestamp: 10_050
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.clock_domain(), 1);
+        assert_eq!(frame.domain_timestamp(0), Some(150));
+        assert_eq!(frame.domain_timestamp(1), Some(10_050));
+        assert_eq!(frame.display_timestamp().unwrap().to_string(), "1/0.010050");
+
+        // domain 2 has not been synchronized
+        stream_decoder.received(&[
+            0, // index
+            2, 0, // clock domain
+            100, 0, // timestamp
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.domain_timestamp(0), None);
+    }
+
+    #[test]
+    fn clock_domain_of_any_unsigned_width() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::ClockSync,
+                "clock sync: domain {=u16} at {=u64:us}".to_owned(),
+            ),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u8:domain}/{=u16:us}");
+        let mut stream_decoder = table.new_stream_decoder();
+
+        stream_decoder.received(&[
+            1, // index
+            0, // clock domain
+            100, 0, // timestamp
+            1, 0, // domain
+            0x10, 0x27, 0, 0, 0, 0, 0, 0, // domain time: 10_000
+        ]);
+        stream_decoder.decode().unwrap();
+
+        stream_decoder.received(&[
+            0, // index
+            1, // clock domain
+            0x42, 0x27, // timestamp: 10_050
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.clock_domain(), 1);
+        assert_eq!(frame.domain_timestamp(0), Some(150));
+        assert_eq!(frame.display_timestamp().unwrap().to_string(), "1/0.010050");
+    }
+
+    #[test]
+    fn time_sync() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "Hello".to_owned()),
+            TableEntry::new_without_symbol(Tag::TimeSync, "time sync".to_owned()),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u32:us}");
+        let mut stream_decoder = table.new_stream_decoder();
+        const HOST: u128 = 1_700_000_000_000_000;
+
+        stream_decoder.received_at(
+            &[
+                0, // index
+                0x40, 0x42, 0x0f, 0, // timestamp: 1_000_000
+            ],
+            HOST,
+        );
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.host_time(), None);
+
+        stream_decoder.received_at(
+            &[
+                1, // index
+                0x40, 0x42, 0x0f, 0, // timestamp: 1_000_000
+            ],
+            HOST + 5_000,
+        );
+        let frame = stream_decoder.decode().unwrap();
+        assert!(frame.is_time_sync());
+        assert_eq!(frame.host_time(), Some(1_700_000_000_005));
+
+        // the target's clock is 0.1% slow
+        stream_decoder.received_at(
+            &[
+                1, // index
+                0x80, 0x84, 0x1e, 0, // timestamp: 2_000_000
+            ],
+            HOST + 1_006_000,
+        );
+        stream_decoder.decode().unwrap();
+
+        stream_decoder.received(&[
+            0, // index
+            0xc0, 0xc6, 0x2d, 0, // timestamp: 3_000_000
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.host_time(), Some(1_700_000_002_007));
+    }
+
+    #[test]
+    fn lost_frames() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.set_sequence_numbers(true);
+        let mut stream_decoder = table.new_stream_decoder();
+
+        stream_decoder.received(&[
+            0,   // index
+            254, // sequence number
+            1,   // u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.lost_frames(), None);
+        assert_eq!(frame.display_message().to_string(), "1");
+
+        stream_decoder.received(&[
+            0,   // index
+            255, // sequence number
+            2,   // u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.lost_frames(), Some(0));
+
+        // the sequence numbers wrap around
+        stream_decoder.received(&[
+            0, // index
+            2, // sequence number
+            3, // u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.lost_frames(), Some(2));
+    }
+
+    #[test]
+    fn retransmitted_frames() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.set_reliable(true);
+        table.encoding = Encoding::LengthPrefixed;
+        let mut stream_decoder = table.new_stream_decoder();
+
+        stream_decoder.received(&[
+            3, // length
+            0, 0, 1, // index, sequence number, u8
+            3, // length
+            0, 2, 3, // index, sequence number, u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.sequence_number(), Some(0));
+        assert!(!frame.is_duplicate());
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.lost_frames(), Some(1));
+
+        // the lost frame arrives late, the others again
+        stream_decoder.received(&[
+            3, // length
+            0, 1, 2, // index, sequence number, u8
+            3, // length
+            0, 2, 3, // index, sequence number, u8
+            3, // length
+            0, 3, 4, // index, sequence number, u8
+        ]);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "2");
+        assert_eq!(frame.lost_frames(), None);
+        assert!(!frame.is_duplicate());
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.sequence_number(), Some(2));
+        assert!(frame.is_duplicate());
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.lost_frames(), Some(0));
+
+        assert_eq!(ack(3), [0xac, 3, 0xfc]);
+    }
+
+    #[test]
+    fn groups() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "{=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::GroupStart, "group start".to_owned()),
+            TableEntry::new_without_symbol(Tag::GroupEnd, "group end".to_owned()),
+        ];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::LengthPrefixed;
+
+        #[rustfmt::skip]
+        let frames = [
+            6, // length
+            1, // group start
+            0, 1, // index, u8
+            0, 2, // index, u8
+            2, // group end
+            2, // length
+            0, 3, // index, u8
+        ];
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&frames);
+        let frame = stream_decoder.decode().unwrap();
+        assert!(frame.is_group_start());
+        assert_eq!(frame.group(), Some(0));
+        for expected in ["1", "2"] {
+            let frame = stream_decoder.decode().unwrap();
+            assert_eq!(frame.display_message().to_string(), expected);
+            assert_eq!(frame.group(), Some(0));
+        }
+        let frame = stream_decoder.decode().unwrap();
+        assert!(frame.is_group_end());
+        assert_eq!(frame.group(), Some(0));
+
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "3");
+        assert_eq!(frame.group(), None);
+    }
+
+    #[test]
+    fn self_check_groups() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "{=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::GroupStart, "group start".to_owned()),
+            TableEntry::new_without_symbol(Tag::GroupEnd, "group end".to_owned()),
+            TableEntry::new_without_symbol(
+                Tag::SelfCheck,
+                "self-check: crc {=u32:#010x} over {=u16} frames".to_owned(),
+            ),
+        ];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::LengthPrefixed;
+
+        // a group is a single frame on the target
+        let crc = crc32(0, &[1, 0, 1, 0, 2, 2, 0, 3]).to_le_bytes();
+        #[rustfmt::skip]
+        let frames = [
+            6, // length
+            1, // group start
+            0, 1, // index, u8
+            0, 2, // index, u8
+            2, // group end
+            2, // length
+            0, 3, // index, u8
+            7, // length
+            3, crc[0], crc[1], crc[2], crc[3], 2, 0, // check frame over 2 frames
+        ];
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&frames);
+        for _ in 0..5 {
+            assert_eq!(stream_decoder.decode().unwrap().self_check(), None);
+        }
+        assert_eq!(stream_decoder.decode().unwrap().self_check(), Some(Ok(())));
+    }
+
+    #[test]
+    fn control_messages() {
+        assert_eq!(Control::SetLevel(Level::Warn).to_bytes(), [0xc1, 3, 0xfc]);
+        assert_eq!(Control::Flush.to_bytes(), [0xc2, 0, 0xff]);
+        assert_eq!(Control::TimeSync.to_bytes(), [0xc3, 0, 0xff]);
+
+        let module = module_index("app::radio");
+        assert!(u32::from(module) < MODULES);
+        let set_level = Control::SetModuleLevel {
+            module,
+            level: Some(Level::Trace),
+        };
+        assert_eq!(set_level.to_bytes(), [0xd0, module, !module]);
+        let reset = Control::SetModuleLevel {
+            module,
+            level: None,
+        };
+        assert_eq!(reset.to_bytes(), [0xd5, module, !module]);
+    }
+
+    /// Not a cipher: flips the bits of the data, and expects the nonce as the tag
+    struct Flip;
+
+    impl Decrypt for Flip {
+        fn decrypt(&self, nonce: &[u8; 12], data: &mut [u8], tag: &[u8; 16]) -> bool {
+            data.iter_mut().for_each(|byte| *byte = !*byte);
+            tag[..12] == nonce[..]
+        }
+    }
+
+    #[test]
+    fn encrypted_frames() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.set_encrypted(true);
+        table.encoding = Encoding::LengthPrefixed;
+
+        #[rustfmt::skip]
+        let frames = [
+            20, // length
+            1, 2, // stream ID, counter
+            0xff, 0xfa, // encrypted index, u8
+            1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // tag
+            20, // length
+            1, 3, // stream ID, counter
+            0xff, 0xf9, // encrypted index, u8
+            1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // tag of another frame
+        ];
+
+        let mut stream_decoder = table.new_decrypting_stream_decoder(Flip);
+        stream_decoder.received(&frames);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "5");
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+
+        // without the key
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&frames);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+    }
+
+    /// Not a MAC: the sum of the header and data bytes
+    struct Sum;
+
+    impl Verify for Sum {
+        fn verify(&self, header: &[u8; 12], data: &[u8], mac: &[u8; 8]) -> bool {
+            let sum = header
+                .iter()
+                .chain(data)
+                .map(|&byte| u64::from(byte))
+                .sum::<u64>();
+            sum.to_le_bytes() == *mac
+        }
+    }
+
+    #[test]
+    fn authenticated_frames() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.set_authenticated(true);
+        table.encoding = Encoding::LengthPrefixed;
+
+        #[rustfmt::skip]
+        let frames = [
+            12, // length
+            1, 0, // stream ID, counter
+            0, 5, // index, u8
+            6, 0, 0, 0, 0, 0, 0, 0, // MAC
+            12, // length
+            1, 1, // stream ID, counter
+            0, 6, // index, tampered u8
+            7, 0, 0, 0, 0, 0, 0, 0, // MAC
+        ];
+
+        let mut stream_decoder =
+            table.new_stream_decoder_with_keys(Keys::default().with_verifier(Sum));
+        stream_decoder.received(&frames);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "5");
+        assert_eq!(frame.authentic(), Some(true));
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "6");
+        assert_eq!(frame.authentic(), Some(false));
+
+        // without the key, the MAC is only stripped
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&frames);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "5");
+        assert_eq!(frame.authentic(), None);
+    }
+
+    #[test]
+    fn authenticated_counters() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.set_authenticated(true);
+        table.encoding = Encoding::LengthPrefixed;
+
+        #[rustfmt::skip]
+        let frames = [
+            12, // length
+            1, 0, // stream ID, counter
+            0, 5, // index, u8
+            6, 0, 0, 0, 0, 0, 0, 0, // MAC
+            12, // length
+            1, 3, // stream ID, counter: 2 frames were removed
+            0, 6, // index, u8
+            10, 0, 0, 0, 0, 0, 0, 0, // MAC
+            12, // length
+            1, 0, // stream ID, counter: the first frame, replayed
+            0, 5, // index, u8
+            6, 0, 0, 0, 0, 0, 0, 0, // MAC
+            12, // length
+            2, 0, // stream ID, counter: another stream
+            0, 7, // index, u8
+            9, 0, 0, 0, 0, 0, 0, 0, // MAC
+        ];
+
+        let mut stream_decoder =
+            table.new_stream_decoder_with_keys(Keys::default().with_verifier(Sum));
+        stream_decoder.received(&frames);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.authentic(), Some(true));
+        assert_eq!(frame.missing_frames(), None);
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.authentic(), Some(true));
+        assert_eq!(frame.missing_frames(), Some(2));
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display_message().to_string(), "5");
+        assert_eq!(frame.authentic(), Some(false));
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.authentic(), Some(true));
+        assert_eq!(frame.missing_frames(), None);
+    }
 }
@@ -0,0 +1,245 @@
+use std::ops::Add;
+
+use defmt_parser::{Fragment, ParserMode, Type};
+
+use crate::{decoder::merge_bitfields, Table, Tag};
+
+/// Encoded size of the log frames of a log statement, in bytes
+///
+/// The sizes cover the frame data, i.e. the string index, timestamp, level and arguments, before
+/// the frame is encoded with the stream's [`Encoding`](crate::Encoding).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncodedSize {
+    /// The size of a frame whose LEB128 and zigzag encoded integers take up a single byte and whose
+    /// strings, slices and `Debug`/`Display` arguments are empty.
+    ///
+    /// This is also the lower bound, except for nested `Format` values, whose data is unknown and
+    /// not included.
+    pub typical: usize,
+    /// The size of the largest possible frame, or `None` if the frame has arguments of unbounded
+    /// size: strings, slices, nested `Format` values and `Debug`/`Display` arguments.
+    pub worst_case: Option<usize>,
+}
+
+impl EncodedSize {
+    const fn fixed(size: usize) -> Self {
+        Self {
+            typical: size,
+            worst_case: Some(size),
+        }
+    }
+
+    const fn unbounded(typical: usize) -> Self {
+        Self {
+            typical,
+            worst_case: None,
+        }
+    }
+}
+
+impl Add for EncodedSize {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            typical: self.typical + rhs.typical,
+            worst_case: self.worst_case.zip(rhs.worst_case).map(|(a, b)| a + b),
+        }
+    }
+}
+
+impl Table {
+    /// Returns the encoded size of the log frames whose format string has index `index`.
+    ///
+    /// Returns `None` if the table has no such format string, or if it is malformed.
+    pub fn encoded_size(&self, index: usize) -> Option<EncodedSize> {
+        let entry = self.entries.get(&index)?;
+
+        let mut size = EncodedSize::fixed(self.index_size(index));
+        if self.sequence_numbers {
+            size = size + EncodedSize::fixed(1);
+        }
+        if let Some(timestamp) = &self.timestamp {
+            size = size
+                + match timestamp.string.tag {
+                    Tag::TimestampDelta => EncodedSize {
+                        typical: 1,
+                        worst_case: Some(leb128_size(u64::MAX)),
+                    },
+                    _ => self.args_size(&timestamp.string.string)?,
+                };
+        }
+        if self.is_runtime_level(index) {
+            size = size + EncodedSize::fixed(1);
+        }
+
+        Some(size + self.args_size(&entry.string.string)?)
+    }
+
+    /// Returns the encoded size of every log statement, ordered by index.
+    pub fn encoded_sizes(&self) -> impl Iterator<Item = (usize, EncodedSize)> + '_ {
+        self.indices()
+            .filter_map(|index| Some((index, self.encoded_size(index)?)))
+    }
+
+    /// Returns the size of string index `index`.
+    fn index_size(&self, index: usize) -> usize {
+        if self.leb128_indices() {
+            leb128_size(index as u64)
+        } else {
+            2
+        }
+    }
+
+    /// Returns the size of an arbitrary string index, i.e. of the largest one in the table.
+    fn any_index_size(&self) -> usize {
+        let max = self.entries.keys().next_back().copied().unwrap_or_default();
+        self.index_size(max)
+    }
+
+    /// Returns the size of the arguments of `format`.
+    fn args_size(&self, format: &str) -> Option<EncodedSize> {
+        let mut params = defmt_parser::parse(format, ParserMode::ForwardsCompatible)
+            .ok()?
+            .into_iter()
+            .filter_map(|frag| match frag {
+                Fragment::Parameter(param) => Some(param),
+                Fragment::Literal(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let (packed, packed_len) = if self.packed_args() {
+            defmt_parser::packed_args(&params)
+        } else {
+            (vec![], 0)
+        };
+
+        // like `Decoder::prepare_params`
+        merge_bitfields(&mut params);
+        params.sort_by_key(|param| param.index);
+        params.dedup_by(|a, b| a.index == b.index);
+
+        let mut size = EncodedSize::fixed(packed_len);
+        for param in &params {
+            if packed.iter().any(|packed| packed.index == param.index) {
+                continue;
+            }
+
+            let zigzag = |bytes: usize| EncodedSize {
+                typical: 1,
+                worst_case: Some(leb128_size(u64::MAX >> (64 - 8 * bytes))),
+            };
+            size = size
+                + match &param.ty {
+                    Type::I8 if self.zigzag_signed() => zigzag(1),
+                    Type::I16 if self.zigzag_signed() => zigzag(2),
+                    Type::I32 | Type::Isize if self.zigzag_signed() => zigzag(4),
+                    Type::I64 if self.zigzag_signed() => zigzag(8),
+                    Type::I8 | Type::U8 | Type::Bool => EncodedSize::fixed(1),
+                    Type::I16 | Type::U16 => EncodedSize::fixed(2),
+                    Type::I24 | Type::U24 => EncodedSize::fixed(3),
+                    Type::I32 | Type::U32 | Type::Isize | Type::Usize => EncodedSize::fixed(4),
+                    Type::F32 | Type::Char => EncodedSize::fixed(4),
+                    Type::I48 | Type::U48 => EncodedSize::fixed(6),
+                    Type::I56 | Type::U56 => EncodedSize::fixed(7),
+                    Type::I64 | Type::U64 | Type::F64 => EncodedSize::fixed(8),
+                    Type::I128 | Type::U128 => EncodedSize::fixed(16),
+                    Type::BitField(range) => {
+                        let bytes = (range.end - 1) / 8 - range.start / 8 + 1;
+                        EncodedSize::fixed(usize::from(bytes).next_power_of_two())
+                    }
+                    Type::U8Array(len) => EncodedSize::fixed(*len),
+                    Type::IStr => EncodedSize::fixed(self.any_index_size()),
+                    // length, then the data
+                    Type::Str | Type::U8Slice => EncodedSize::unbounded(4),
+                    Type::FormatSlice => EncodedSize::unbounded(4 + self.any_index_size()),
+                    // string index, then the data
+                    Type::Format | Type::FormatArray(_) => {
+                        EncodedSize::unbounded(self.any_index_size())
+                    }
+                    // zero terminated sequence of string indices and data
+                    Type::FormatSequence => EncodedSize::unbounded(1),
+                    // `0xff` terminated UTF-8
+                    Type::Debug | Type::Display => EncodedSize::unbounded(1),
+                };
+        }
+
+        Some(size)
+    }
+}
+
+fn leb128_size(x: u64) -> usize {
+    (64 - x.leading_zeros() as usize).max(1).div_ceil(7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, TableEntry};
+
+    fn table(timestamp: Option<TableEntry>, entries: Vec<TableEntry>) -> Table {
+        let mut table = Table::new(entries.into_iter().enumerate().collect(), Encoding::Raw);
+        table.timestamp = timestamp;
+        table
+    }
+
+    #[test]
+    fn leb128() {
+        assert_eq!(leb128_size(0), 1);
+        assert_eq!(leb128_size(127), 1);
+        assert_eq!(leb128_size(128), 2);
+        assert_eq!(leb128_size(u64::MAX), 10);
+    }
+
+    #[test]
+    fn fixed_size() {
+        let table = table(
+            Some(TableEntry::new_without_symbol(
+                Tag::Timestamp,
+                "{=u32:us}".to_owned(),
+            )),
+            vec![
+                TableEntry::new_without_symbol(Tag::Info, "{=u8} {=f64} {=[u8; 3]}".to_owned()),
+                TableEntry::new_without_symbol(Tag::Log, "{=bool} {=bool} {=i16}".to_owned()),
+                TableEntry::new_without_symbol(Tag::Info, "{0=0..4} {0=8..12}".to_owned()),
+            ],
+        );
+
+        // index, timestamp, arguments
+        assert_eq!(table.encoded_size(0), Some(EncodedSize::fixed(1 + 4 + 12)));
+        // the level, the packed bools and the zigzag encoded `i16`
+        assert_eq!(
+            table.encoded_size(1),
+            Some(EncodedSize {
+                typical: 1 + 4 + 1 + 1 + 1,
+                worst_case: Some(1 + 4 + 1 + 1 + 3),
+            })
+        );
+        // one bitfield spanning two bytes
+        assert_eq!(table.encoded_size(2), Some(EncodedSize::fixed(1 + 4 + 2)));
+        assert_eq!(table.encoded_size(3), None);
+    }
+
+    #[test]
+    fn unbounded() {
+        let table = table(
+            Some(TableEntry::new_without_symbol(
+                Tag::TimestampDelta,
+                "{=u64:us}".to_owned(),
+            )),
+            vec![
+                TableEntry::new_without_symbol(Tag::Warn, "{=str} {=?}".to_owned()),
+                TableEntry::new_without_symbol(Tag::Derived, "Foo".to_owned()),
+            ],
+        );
+
+        assert_eq!(
+            table.encoded_size(0),
+            Some(EncodedSize::unbounded(1 + 1 + 4 + 1))
+        );
+        assert_eq!(
+            table.encoded_sizes().collect::<Vec<_>>(),
+            [(0, EncodedSize::unbounded(7))]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use super::StreamDecoder;
+use crate::{DecodeError, Frame, StreamState, Table};
+
+pub struct LengthPrefixed<'a> {
+    table: &'a Table,
+    state: StreamState,
+    raw: Vec<u8>,
+}
+
+impl<'a> LengthPrefixed<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self::with_state(table, StreamState::default())
+    }
+
+    pub(crate) fn with_state(table: &'a Table, state: StreamState) -> Self {
+        Self {
+            table,
+            state,
+            raw: Vec::new(),
+        }
+    }
+}
+
+/// Reads the LEB128 length prefix at the start of `raw`.
+///
+/// Returns the frame length and the length of the prefix, or `None` if the prefix is incomplete.
+fn read_prefix(raw: &[u8]) -> Option<Result<(usize, usize), DecodeError>> {
+    let mut len = 0;
+    for (i, byte) in raw.iter().enumerate() {
+        if i >= 5 {
+            return Some(Err(DecodeError::Malformed));
+        }
+        len |= usize::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(Ok((len, i + 1)));
+        }
+    }
+    None
+}
+
+impl<'a> StreamDecoder for LengthPrefixed<'a> {
+    fn received(&mut self, data: &[u8]) {
+        self.raw.extend_from_slice(data);
+    }
+
+    fn received_at(&mut self, data: &[u8], unix_micros: u128) {
+        self.state.received_at = Some(unix_micros);
+        self.received(data);
+    }
+
+    fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
+        // the statements of a `group!` share a frame
+        if let Some(decoded) = self.table.decode_group_rest(&mut self.state) {
+            return decoded;
+        }
+
+        loop {
+            let (len, prefix) = match read_prefix(&self.raw) {
+                Some(Ok(prefix)) => prefix,
+                Some(Err(e)) => {
+                    // the stream can't be trusted anymore, start over with the next data
+                    self.raw.clear();
+                    return Err(e);
+                }
+                None => return Err(DecodeError::UnexpectedEof),
+            };
+            if self.raw.len() < prefix + len {
+                return Err(DecodeError::UnexpectedEof);
+            }
+
+            // Even if decoding fails, pop the frame off so we don't get stuck.
+            let frame: Vec<u8> = self.raw.drain(..prefix + len).skip(prefix).collect();
+
+            // empty frames replace frames that were too long for the encoder
+            if frame.is_empty() {
+                return Err(DecodeError::Malformed);
+            }
+
+            // the rest of a split frame follows in the next frame
+            let Some(decoded) = self.table.decode_framed(frame, &mut self.state) else {
+                continue;
+            };
+            return match decoded {
+                Ok((frame, 0)) => Ok(frame),
+                Ok(_) => Err(DecodeError::Malformed),
+                Err(DecodeError::UnexpectedEof) => Err(DecodeError::Malformed),
+                Err(DecodeError::Malformed) => Err(DecodeError::Malformed),
+                Err(e @ DecodeError::VersionMismatch { .. }) => Err(e),
+            };
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::StreamDecoder;
+use crate::{DecodeError, Frame, StreamState, Table};
+
+/// Size of the sliding window of the `lzss` encoding
+const WINDOW: usize = 256;
+const MIN_MATCH: usize = 3;
+/// Token that empties the window, sent after the target dropped a frame
+const RESET: u8 = 0x7f;
+
+pub struct Lzss<'a> {
+    table: &'a Table,
+    state: StreamState,
+    /// Compressed data that doesn't form a complete token yet
+    compressed: Vec<u8>,
+    /// Decompressed data that hasn't been decoded yet
+    data: Vec<u8>,
+    /// The last `WINDOW` decompressed bytes
+    window: Window,
+}
+
+impl<'a> Lzss<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            state: StreamState::default(),
+            compressed: Vec::new(),
+            data: Vec::new(),
+            window: Window {
+                bytes: [0; WINDOW],
+                head: 0,
+                filled: 0,
+            },
+        }
+    }
+
+    /// Decompresses all complete tokens in `compressed`
+    fn decompress(&mut self) -> Result<(), DecodeError> {
+        let mut pos = 0;
+        while let Some(&token) = self.compressed.get(pos) {
+            if token == RESET {
+                self.window.clear();
+                pos += 1;
+            } else if token & 0x80 == 0 {
+                let len = usize::from(token) + 1;
+                let Some(literals) = self.compressed.get(pos + 1..pos + 1 + len) else {
+                    break;
+                };
+                for &byte in literals {
+                    self.window.push(byte);
+                    self.data.push(byte);
+                }
+                pos += 1 + len;
+            } else {
+                let Some(&offset) = self.compressed.get(pos + 1) else {
+                    break;
+                };
+                let len = usize::from(token & 0x7f) + MIN_MATCH;
+                let distance = usize::from(offset) + 1;
+                if distance > self.window.filled {
+                    return Err(DecodeError::Malformed);
+                }
+                for _ in 0..len {
+                    let byte = self.window.back(distance);
+                    self.window.push(byte);
+                    self.data.push(byte);
+                }
+                pos += 2;
+            }
+        }
+        self.compressed.drain(..pos);
+        Ok(())
+    }
+}
+
+/// Ring buffer of the decompressed bytes, indexed by `head` like the encoder's window
+struct Window {
+    bytes: [u8; WINDOW],
+    head: u8,
+    filled: usize,
+}
+
+impl Window {
+    fn push(&mut self, byte: u8) {
+        self.bytes[usize::from(self.head)] = byte;
+        self.head = self.head.wrapping_add(1);
+        self.filled = (self.filled + 1).min(WINDOW);
+    }
+
+    fn clear(&mut self) {
+        self.head = 0;
+        self.filled = 0;
+    }
+
+    /// Returns the byte `distance` bytes back, `1..=WINDOW`
+    fn back(&self, distance: usize) -> u8 {
+        self.bytes[(usize::from(self.head) + WINDOW - distance) % WINDOW]
+    }
+}
+
+impl<'a> StreamDecoder for Lzss<'a> {
+    fn received(&mut self, data: &[u8]) {
+        self.compressed.extend_from_slice(data);
+    }
+
+    fn received_at(&mut self, data: &[u8], unix_micros: u128) {
+        self.state.received_at = Some(unix_micros);
+        self.received(data);
+    }
+
+    fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
+        self.decompress()?;
+        match self.table.decode_with_state(&self.data, &mut self.state) {
+            Ok((frame, consumed)) => {
+                self.data.drain(0..consumed);
+                Ok(frame)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -1,7 +1,13 @@
+mod length_prefixed;
+mod lzss;
 mod raw;
+mod raw_sync;
 mod rzcobs;
 
+pub use length_prefixed::LengthPrefixed;
+pub use lzss::Lzss;
 pub use raw::Raw;
+pub use raw_sync::RawSync;
 pub use rzcobs::Rzcobs;
 
 use crate::{DecodeError, Frame};
@@ -11,5 +17,15 @@ pub trait StreamDecoder {
     /// internally, and makes decoded frames available through [`decode`](StreamDecoder::decode).
     fn received(&mut self, data: &[u8]);
 
+    /// Like [`received`](StreamDecoder::received), for data that was received at `unix_micros`,
+    /// the host's wall-clock time in microseconds since the Unix epoch.
+    ///
+    /// The time sync frames in `data` are paired with this time, see
+    /// [`Frame::host_time`](crate::Frame::host_time).
+    fn received_at(&mut self, data: &[u8], unix_micros: u128) {
+        let _ = unix_micros;
+        self.received(data);
+    }
+
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError>;
 }
@@ -1,8 +1,9 @@
 use super::StreamDecoder;
-use crate::{DecodeError, Frame, Table};
+use crate::{DecodeError, Frame, StreamState, Table};
 
 pub struct Raw<'a> {
     table: &'a Table,
+    state: StreamState,
     data: Vec<u8>,
 }
 
@@ -10,6 +11,7 @@ impl<'a> Raw<'a> {
     pub fn new(table: &'a Table) -> Self {
         Self {
             table,
+            state: StreamState::default(),
             data: Vec::new(),
         }
     }
@@ -20,8 +22,13 @@ impl<'a> StreamDecoder for Raw<'a> {
         self.data.extend_from_slice(data);
     }
 
+    fn received_at(&mut self, data: &[u8], unix_micros: u128) {
+        self.state.received_at = Some(unix_micros);
+        self.received(data);
+    }
+
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
-        match self.table.decode(&self.data) {
+        match self.table.decode_with_state(&self.data, &mut self.state) {
             Ok((frame, consumed)) => {
                 self.data.drain(0..consumed);
                 Ok(frame)
@@ -0,0 +1,89 @@
+use super::StreamDecoder;
+use crate::{DecodeError, Frame, StreamState, Table};
+
+/// Resynchronization marker of the `raw-sync` encoding.
+///
+/// Must match `SYNC_MARKER` in `defmt`.
+const SYNC_MARKER: [u8; 8] = [0xd3, 0xfe, 0x6d, 0x74, 0x5e, 0xc1, 0xa7, 0x0b];
+
+/// Decoder for the `raw` encoding with periodic resynchronization markers.
+///
+/// Data is discarded until the first marker, and again after a decoding error.
+pub struct RawSync<'a> {
+    table: &'a Table,
+    state: StreamState,
+    data: Vec<u8>,
+    /// Whether `data` starts at a frame boundary
+    synced: bool,
+}
+
+impl<'a> RawSync<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            state: StreamState::default(),
+            data: Vec::new(),
+            synced: false,
+        }
+    }
+
+    /// Drops data up to and including the next marker. Returns `false` if there is none yet.
+    fn resync(&mut self) -> bool {
+        match self
+            .data
+            .windows(SYNC_MARKER.len())
+            .position(|window| window == SYNC_MARKER)
+        {
+            Some(pos) => {
+                self.data.drain(..pos + SYNC_MARKER.len());
+                true
+            }
+            None => {
+                // keep what may be the start of a marker
+                let keep = SYNC_MARKER.len() - 1;
+                self.data.drain(..self.data.len().saturating_sub(keep));
+                false
+            }
+        }
+    }
+}
+
+impl<'a> StreamDecoder for RawSync<'a> {
+    fn received(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    fn received_at(&mut self, data: &[u8], unix_micros: u128) {
+        self.state.received_at = Some(unix_micros);
+        self.received(data);
+    }
+
+    fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
+        if !self.synced {
+            if !self.resync() {
+                return Err(DecodeError::UnexpectedEof);
+            }
+            self.synced = true;
+        }
+
+        while self.data.starts_with(&SYNC_MARKER) {
+            self.data.drain(..SYNC_MARKER.len());
+        }
+        if SYNC_MARKER.starts_with(&self.data) {
+            // no data, or the start of a marker
+            return Err(DecodeError::UnexpectedEof);
+        }
+
+        match self.table.decode_with_state(&self.data, &mut self.state) {
+            Ok((frame, consumed)) => {
+                self.data.drain(0..consumed);
+                Ok(frame)
+            }
+            Err(DecodeError::Malformed) => {
+                self.synced = false;
+                Err(DecodeError::Malformed)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
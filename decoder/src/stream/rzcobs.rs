@@ -1,5 +1,5 @@
 use super::StreamDecoder;
-use crate::{DecodeError, Frame, Table};
+use crate::{DecodeError, Frame, StreamState, Table};
 
 /// Decode a full message.
 ///
@@ -41,13 +41,19 @@ fn rzcobs_decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
 
 pub struct Rzcobs<'a> {
     table: &'a Table,
+    state: StreamState,
     raw: Vec<u8>,
 }
 
 impl<'a> Rzcobs<'a> {
     pub fn new(table: &'a Table) -> Self {
+        Self::with_state(table, StreamState::default())
+    }
+
+    pub(crate) fn with_state(table: &'a Table, state: StreamState) -> Self {
         Self {
             table,
+            state,
             raw: Vec::new(),
         }
     }
@@ -65,31 +71,48 @@ impl<'a> StreamDecoder for Rzcobs<'a> {
         self.raw.extend_from_slice(data);
     }
 
+    fn received_at(&mut self, data: &[u8], unix_micros: u128) {
+        self.state.received_at = Some(unix_micros);
+        self.received(data);
+    }
+
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
-        // Find frame separator. If not found, we don't have enough data yet.
-        let zero = self
-            .raw
-            .iter()
-            .position(|&x| x == 0)
-            .ok_or(DecodeError::UnexpectedEof)?;
+        // the statements of a `group!` share a frame
+        if let Some(decoded) = self.table.decode_group_rest(&mut self.state) {
+            return decoded;
+        }
 
-        let frame = rzcobs_decode(&self.raw[..zero]);
+        loop {
+            // Find frame separator. If not found, we don't have enough data yet.
+            let zero = self
+                .raw
+                .iter()
+                .position(|&x| x == 0)
+                .ok_or(DecodeError::UnexpectedEof)?;
 
-        // Even if it failed, pop the data off so we don't get stuck.
-        // Pop off the frame + 1 or more separator zero-bytes
-        if let Some(nonzero) = self.raw[zero..].iter().position(|&x| x != 0) {
-            self.raw.drain(0..zero + nonzero);
-        } else {
-            self.raw.clear();
-        }
+            let frame = rzcobs_decode(&self.raw[..zero]);
+
+            // Even if it failed, pop the data off so we don't get stuck.
+            // Pop off the frame + 1 or more separator zero-bytes
+            if let Some(nonzero) = self.raw[zero..].iter().position(|&x| x != 0) {
+                self.raw.drain(0..zero + nonzero);
+            } else {
+                self.raw.clear();
+            }
 
-        assert!(self.raw.is_empty() || self.raw[0] != 0);
+            assert!(self.raw.is_empty() || self.raw[0] != 0);
 
-        let frame: Vec<u8> = frame?;
-        match self.table.decode(&frame) {
-            Ok((frame, _consumed)) => Ok(frame),
-            Err(DecodeError::UnexpectedEof) => Err(DecodeError::Malformed),
-            Err(DecodeError::Malformed) => Err(DecodeError::Malformed),
+            let frame: Vec<u8> = frame?;
+            // the rest of a split frame follows in the next frame
+            let Some(decoded) = self.table.decode_framed(frame, &mut self.state) else {
+                continue;
+            };
+            return match decoded {
+                Ok((frame, _unconsumed)) => Ok(frame),
+                Err(DecodeError::UnexpectedEof) => Err(DecodeError::Malformed),
+                Err(DecodeError::Malformed) => Err(DecodeError::Malformed),
+                Err(e @ DecodeError::VersionMismatch { .. }) => Err(e),
+            };
         }
     }
 }
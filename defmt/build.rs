@@ -4,8 +4,91 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Put the linker script somewhere the linker can find it
     let out = &PathBuf::from(env::var("OUT_DIR")?);
     let linker_script = fs::read_to_string("defmt.x.in")?;
+    // the string indices are the addresses of the `.defmt` symbols, so they must fit in a pointer
+    // of the target and in the `u32` the decoder expects
+    let pointer_width = env::var("CARGO_CFG_TARGET_POINTER_WIDTH")?.parse::<u32>()?;
+    let max_index = u32::MAX >> (32 - pointer_width.min(32));
+    let linker_script = linker_script.replace("$MAX_INDEX", &max_index.to_string());
     fs::write(out.join("defmt.x"), linker_script)?;
     println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rustc-check-cfg=cfg(no_cas, c_variadic, defmt_sync, defmt_max_frame_size)");
+
+    // Number of frames between two resynchronization markers of the `raw` encoding
+    println!("cargo:rerun-if-env-changed=DEFMT_SYNC_INTERVAL");
+    if let Ok(interval) = env::var("DEFMT_SYNC_INTERVAL") {
+        let interval = match interval.parse::<u16>() {
+            Ok(interval) if interval != 0 => interval,
+            _ => return Err(format!(
+                "DEFMT_SYNC_INTERVAL must be a number of frames between 1 and 65535, found `{interval}`"
+            )
+            .into()),
+        };
+        if env::var_os("CARGO_FEATURE_ENCODING_RAW").is_none() {
+            return Err("DEFMT_SYNC_INTERVAL requires the `encoding-raw` feature".into());
+        }
+        fs::write(
+            out.join("sync_interval.rs"),
+            format!("const SYNC_INTERVAL: u16 = {interval};\n"),
+        )?;
+        println!("cargo:rustc-cfg=defmt_sync");
+    }
+
+    // Size in bytes above which a log frame is split into continuation frames
+    println!("cargo:rerun-if-env-changed=DEFMT_MAX_FRAME_SIZE");
+    if let Ok(size) = env::var("DEFMT_MAX_FRAME_SIZE") {
+        let size = match size.parse::<u16>() {
+            Ok(size) if size >= 16 => size,
+            _ => return Err(format!(
+                "DEFMT_MAX_FRAME_SIZE must be a number of bytes between 16 and 65535, found `{size}`"
+            )
+            .into()),
+        };
+        if env::var_os("CARGO_FEATURE_ENCODING_RAW").is_some()
+            || env::var_os("CARGO_FEATURE_ENCODING_LZSS").is_some()
+        {
+            return Err("DEFMT_MAX_FRAME_SIZE requires a framed encoding, \
+                        `encoding-rzcobs` or `encoding-length-prefixed`"
+                .into());
+        }
+        fs::write(
+            out.join("max_frame_size.rs"),
+            format!("const MAX_FRAME_SIZE: usize = {size};\n"),
+        )?;
+        println!("cargo:rustc-cfg=defmt_max_frame_size");
+    }
+
+    // Retransmitted frames can only be told apart in a framed encoding, and continuation frames
+    // have no sequence number of their own
+    if env::var_os("CARGO_FEATURE_RELIABLE").is_some() {
+        if env::var_os("CARGO_FEATURE_ENCODING_RAW").is_some()
+            || env::var_os("CARGO_FEATURE_ENCODING_LZSS").is_some()
+        {
+            return Err("the `reliable` feature requires a framed encoding, \
+                        `encoding-rzcobs` or `encoding-length-prefixed`"
+                .into());
+        }
+        if env::var_os("DEFMT_MAX_FRAME_SIZE").is_some() {
+            return Err(
+                "the `reliable` feature can't be combined with DEFMT_MAX_FRAME_SIZE".into(),
+            );
+        }
+    }
+
+    // The length of an encrypted or authenticated frame is only known from its framing
+    for feature in ["encryption", "authentication"] {
+        let enabled = env::var_os(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_some();
+        if enabled
+            && (env::var_os("CARGO_FEATURE_ENCODING_RAW").is_some()
+                || env::var_os("CARGO_FEATURE_ENCODING_LZSS").is_some())
+        {
+            return Err(format!(
+                "the `{feature}` feature requires a framed encoding, \
+                 `encoding-rzcobs` or `encoding-length-prefixed`"
+            )
+            .into());
+        }
+    }
+
     let target = env::var("TARGET")?;
 
     // `"atomic-cas": false` in `--print target-spec-json`
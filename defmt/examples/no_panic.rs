@@ -0,0 +1,60 @@
+//! Checks that log statements can't panic: in optimized builds, this example fails to link if the
+//! encoding of the log statement in `main` contains a panic branch.
+//!
+//! `cargo build --release --example no_panic`
+
+use core::hint::black_box;
+
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {}
+    unsafe fn flush() {}
+    unsafe fn release() {}
+    unsafe fn write(bytes: &[u8]) {
+        black_box(bytes);
+    }
+}
+
+defmt::timestamp!("{=u32:us}", black_box(0));
+
+/// Calls a function that doesn't exist when dropped, which only links if the drop, i.e. unwinding
+/// out of the log statement, was optimized out.
+struct NoPanic;
+
+impl Drop for NoPanic {
+    fn drop(&mut self) {
+        // unoptimized builds keep the bounds checks that can be proven to never fail
+        #[cfg(not(debug_assertions))]
+        {
+            extern "Rust" {
+                fn log_statement_may_panic() -> !;
+            }
+            unsafe { log_statement_may_panic() }
+        }
+    }
+}
+
+#[derive(defmt::Format)]
+struct Point {
+    x: i32,
+    y: Option<u64>,
+}
+
+fn main() {
+    let guard = NoPanic;
+    defmt::info!(
+        "{=u8} {=u24} {=u32} {=i64} {=str} {=[u8]} {} {} {}",
+        black_box(1u8),
+        black_box(0x12_3456u32),
+        black_box(2u32),
+        black_box(-3i64),
+        black_box("four"),
+        black_box(&[5u8][..]),
+        black_box(Point { x: 6, y: Some(7) }),
+        black_box([8u16; 3]),
+        black_box(&[9i32, 10][..]),
+    );
+    core::mem::forget(guard);
+}
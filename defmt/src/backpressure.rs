@@ -0,0 +1,66 @@
+//! Backpressure of the global logger.
+//!
+//! When the transport can't keep up, a global logger either drops log frames or blocks the
+//! firmware until there is room. Neither should go unnoticed: the logger reports the congestion
+//! with [`set_congested`], and the application can poll [`is_congested`] or install a [hook] to
+//! reduce its log verbosity until the congestion is over.
+//!
+//! [hook]: set_hook
+
+use core::{
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+static CONGESTED: AtomicBool = AtomicBool::new(false);
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Reports whether the global logger is congested.
+///
+/// To be called by `Logger` implementations, e.g. with `true` when a frame had to be dropped or
+/// the buffer of the transport is almost full, and with `false` once it has drained. Calls the
+/// [hook](set_hook) if the state changed.
+pub fn set_congested(congested: bool) {
+    // NOTE no read-modify-write needed; a preempting context restores the state before returning
+    // to us, and a missed transition is reported by the next call
+    if CONGESTED.load(Ordering::Relaxed) == congested {
+        return;
+    }
+    CONGESTED.store(congested, Ordering::Relaxed);
+
+    let hook = HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // safety: only `fn(bool)` pointers are stored in `HOOK`, see `set_hook`
+        let hook = unsafe { core::mem::transmute::<*mut (), fn(bool)>(hook) };
+        hook(congested)
+    }
+}
+
+/// Returns `true` if the global logger reported that it is congested.
+pub fn is_congested() -> bool {
+    CONGESTED.load(Ordering::Relaxed)
+}
+
+/// Installs a function that is called whenever the global logger becomes congested (with `true`)
+/// or the congestion is over (with `false`).
+///
+/// The hook is called from within the global logger, possibly while a log frame is written, so it
+/// must not log itself; it should e.g. only raise or lower a log level that the application checks
+/// before logging.
+///
+/// # Examples
+///
+/// ```
+/// use core::sync::atomic::{AtomicBool, Ordering};
+///
+/// static VERBOSE: AtomicBool = AtomicBool::new(true);
+///
+/// defmt::backpressure::set_hook(|congested| VERBOSE.store(!congested, Ordering::Relaxed));
+///
+/// if VERBOSE.load(Ordering::Relaxed) {
+///     defmt::println!("details..");
+/// }
+/// ```
+pub fn set_hook(hook: fn(congested: bool)) {
+    HOOK.store(hook as *mut (), Ordering::Release);
+}
@@ -0,0 +1,79 @@
+//! Control of the logging by the host, enabled by the `control` feature.
+//!
+//! Over a link that can carry data from the host to the target, like an RTT down channel or the RX
+//! line of a UART, the host can send control messages that change the logging while the firmware
+//! runs. Pass all the data received from the host to [`poll_control`]:
+//!
+//! ``` ignore
+//! // e.g. in the UART interrupt handler
+//! defmt::control::poll_control(&data);
+//! ```
+//!
+//! Every control message is 3 bytes long: a command byte, an argument, and the complement of the
+//! argument. Data that isn't a control message is ignored, so the same link can carry other
+//! messages, like the acks of the `reliable` feature. The commands are:
+//!
+//! | command        | argument | effect                                                       |
+//! |----------------|----------|--------------------------------------------------------------|
+//! | `0xc1`         | level    | [`set_max_level`], with the level as `u8` (`0` is `Trace`)   |
+//! | `0xc2`         | `0`      | [`flush`](crate::flush)es the logger                         |
+//! | `0xc3`         | `0`      | emits a time sync frame, see [`time_sync`](crate::time_sync) |
+//! | `0xd0` + level | module   | [`set_module_max_level`], with the module's index            |
+//! | `0xd5`         | module   | [`set_module_max_level`] to `None`                           |
+//!
+//! The module commands need the `module-filter` feature, and are ignored without it.
+//! `defmt-decoder` encodes these messages with `Control::to_bytes`.
+//!
+//! [`set_max_level`]: crate::set_max_level
+//! [`set_module_max_level`]: crate::set_module_max_level
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use crate::Level;
+
+const SET_LEVEL: u8 = 0xc1;
+const FLUSH: u8 = 0xc2;
+const TIME_SYNC: u8 = 0xc3;
+/// Plus the level as `u8`, or `5` to make the module follow the global level
+#[cfg(feature = "module-filter")]
+const SET_MODULE_LEVEL: u8 = 0xd0;
+
+/// The last two bytes received from the host, to find the control messages in
+static RECEIVED: AtomicU16 = AtomicU16::new(0);
+
+/// Processes data received from the host, which contains its control messages.
+///
+/// Messages may be split across calls, but the data must be passed in the order it was received,
+/// from a single execution context. The flush and time sync commands acquire the global logger,
+/// like a log statement, so don't call this while the logger is acquired.
+pub fn poll_control(data: &[u8]) {
+    // NOTE no compare-and-swap needed, there's a single execution context receiving data
+    let [mut command, mut argument] = RECEIVED.load(Ordering::Relaxed).to_be_bytes();
+    for &byte in data {
+        if byte == !argument && run(command, argument) {
+            [command, argument] = [0, 0];
+        } else {
+            [command, argument] = [argument, byte];
+        }
+    }
+    RECEIVED.store(u16::from_be_bytes([command, argument]), Ordering::Relaxed);
+}
+
+/// Runs a command. Returns `false` if it isn't one.
+fn run(command: u8, argument: u8) -> bool {
+    match (command, argument) {
+        (SET_LEVEL, level) => match Level::from_u8(level) {
+            Some(level) => crate::set_max_level(level),
+            None => return false,
+        },
+        (FLUSH, 0) => crate::flush(),
+        (TIME_SYNC, 0) => crate::time_sync(),
+        #[cfg(feature = "module-filter")]
+        (SET_MODULE_LEVEL..=0xd5, module) if usize::from(module) < crate::level::MODULES => {
+            let level = Level::from_u8(command - SET_MODULE_LEVEL);
+            crate::level::set_module_index_max_level(module, level);
+        }
+        _ => return false,
+    }
+    true
+}
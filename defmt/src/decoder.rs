@@ -0,0 +1,96 @@
+//! Host-side decoding of the wire format, enabled by the `std` feature.
+//!
+//! This re-exports the parts of [`defmt-decoder`] that turn the bytes sent by the target back into
+//! structured [`Frame`]s, so host tools and tests don't have to reimplement the wire format. The
+//! string table is usually read from the firmware's ELF file with [`read_table`] or [`Table::parse`],
+//! but it can also be built by hand with [`Table::new`]:
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//!
+//! use defmt::decoder::{Encoding, StringEntry, Table, TableEntry, Tag};
+//!
+//! let mut entries = BTreeMap::new();
+//! let string = StringEntry::new(Tag::Info, "x={=u8}".to_string());
+//! entries.insert(0, TableEntry::new(string, "x".to_string()));
+//! let table = Table::new(entries, Encoding::Raw);
+//!
+//! let (frame, consumed) = table.decode(&[0, 42]).unwrap();
+//! assert_eq!(frame.display_message().to_string(), "x=42");
+//! assert_eq!(consumed, 2);
+//! ```
+//!
+//! Like `defmt-decoder` itself, this API is unstable.
+//!
+//! [`defmt-decoder`]: https://docs.rs/defmt-decoder
+
+use std::{boxed::Box, error::Error, fs, path::Path};
+
+pub use defmt_decoder::{
+    decode_frame, DecodeError, Encoding, Frame, Location, Locations, StreamDecoder, StringEntry,
+    Table, TableEntry, Tag,
+};
+
+/// Reads the string table out of the `.defmt` linker sections of the ELF file at `path`.
+///
+/// Returns `Ok(None)` if the file has no `.defmt` section, i.e. the firmware doesn't use defmt, and
+/// an error if the file can't be read or parsed, or was built with an incompatible defmt version.
+pub fn read_table(path: impl AsRef<Path>) -> Result<Option<Table>, Box<dyn Error + Send + Sync>> {
+    let elf = fs::read(path)?;
+    Ok(Table::parse(&elf)?)
+}
+
+/// Returns the table of the strings interned by this program so far, with the `host-logger`
+/// feature.
+///
+/// Strings are interned the first time they are used, so this decodes all log frames written
+/// before the call.
+#[cfg(all(
+    feature = "host-logger",
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+pub fn host_table() -> Table {
+    crate::export::host_table()
+}
+
+/// Returns the number of strings interned by this program so far, with the `host-logger` feature.
+///
+/// The strings have the indices `1..=count`, see [`host_string`].
+#[cfg(all(
+    feature = "host-logger",
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+pub fn host_string_count() -> usize {
+    crate::export::host_string_count()
+}
+
+/// Returns the string interned by this program with index `index`, and its tag (e.g. `"info"`),
+/// with the `host-logger` feature.
+///
+/// A logger that sends the frames to another process sends the strings along with them, so the
+/// receiver can build the string table with [`host_table_entry`].
+#[cfg(all(
+    feature = "host-logger",
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+pub fn host_string(index: usize) -> Option<(&'static str, &'static str)> {
+    crate::export::host_string(index)
+}
+
+/// Returns the table entry of a `string` with `tag` that was interned at runtime, with the
+/// `host-logger` feature, e.g. by another process that sent it along with its log frames.
+pub fn host_table_entry(string: &str, tag: &str) -> TableEntry {
+    crate::export::mocked_entry(string, tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_table_errors() {
+        assert!(read_table("does/not/exist").is_err());
+        // not an ELF file
+        assert!(read_table(file!()).is_err());
+    }
+}
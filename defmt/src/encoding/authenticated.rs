@@ -0,0 +1,136 @@
+// With the `authentication` feature, every frame is followed by its MAC, truncated to 8 bytes,
+// before it's passed to the framed encoding:
+//   <stream ID: LEB128> <counter: LEB128> <frame data> <MAC: 8 bytes>
+//
+// The MAC covers the stream ID (`u32`, little endian), the counter (`u64`, little endian) and the
+// frame data, so frames can't be modified unnoticed. The MAC is computed while the frame is written,
+// so unlike encryption this needs no buffer.
+//
+// The decoder tracks the counter of every stream ID, and detects replayed, reordered and removed
+// frames as long as no stream ID is used twice: the counter starts at 0 on every boot.
+
+use super::{leb128, Encode};
+
+/// Computes the MAC of log frames, e.g. HMAC-SHA256 or CMAC-AES, with a key provisioned on the
+/// device.
+///
+/// `defmt` doesn't depend on a particular cryptography crate; implement this trait with the MAC of
+/// your choice. The host verifies the MACs with the same algorithm and key, see `Verify` in
+/// `defmt-decoder`.
+pub trait Authenticate {
+    /// Starts computing the MAC of a frame, whose `header` is the stream ID (`u32`, little endian)
+    /// followed by the counter (`u64`, little endian).
+    fn start(&mut self, header: &[u8; 12]);
+
+    /// Adds `data` of the frame to the MAC.
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the MAC of the frame, truncated to 8 bytes.
+    fn finish(&mut self) -> [u8; 8];
+}
+
+/// Appends a MAC, computed with `A`, to the frames encoded by `E`.
+///
+/// `E` must be a framed encoding, like [`Rzcobs`](super::Rzcobs) or
+/// [`LengthPrefixed`](super::LengthPrefixed), typically [`Encoder`](super::Encoder). To encrypt
+/// the frames as well, wrap this in [`Encrypted`](super::Encrypted) (with the `encryption`
+/// feature), so the MAC covers the encrypted data.
+pub struct Authenticated<E, A> {
+    inner: E,
+    mac: A,
+    stream_id: u32,
+    counter: u64,
+}
+
+impl<E: Encode, A: Authenticate> Authenticated<E, A> {
+    /// Creates an encoder that authenticates the frames of `inner` with `mac`.
+    ///
+    /// `stream_id` tells the frames of several `Authenticated` that use the same key apart, e.g.
+    /// those of different execution contexts. The counter of the frames starts at 0, so to detect
+    /// replayed frames, `stream_id` must be unique for every boot as well, e.g. derived from a boot
+    /// counter kept in flash.
+    pub const fn new(inner: E, mac: A, stream_id: u32) -> Self {
+        Self {
+            inner,
+            mac,
+            stream_id,
+            counter: 0,
+        }
+    }
+}
+
+impl<E: Encode, A: Authenticate> Encode for Authenticated<E, A> {
+    fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        let mut header = [0; 12];
+        header[..4].copy_from_slice(&self.stream_id.to_le_bytes());
+        header[4..].copy_from_slice(&self.counter.to_le_bytes());
+        self.mac.start(&header);
+
+        let mut encoded = [0; 15];
+        let mut len = leb128(u64::from(self.stream_id), &mut encoded);
+        len += leb128(self.counter, &mut encoded[len..]);
+        self.counter += 1;
+
+        self.inner.start_frame(&mut write);
+        self.inner.write(&encoded[..len], &mut write);
+    }
+
+    fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        let mac = self.mac.finish();
+        self.inner.write(&mac, &mut write);
+        self.inner.end_frame(&mut write);
+    }
+
+    fn write(&mut self, data: &[u8], write: impl FnMut(&[u8])) {
+        self.mac.update(data);
+        self.inner.write(data, write);
+    }
+
+    fn frame_dropped(&mut self) {
+        self.inner.frame_dropped()
+    }
+}
+
+#[cfg(feature = "unstable-test")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::LengthPrefixed;
+
+    /// Not a MAC: the sum of the header and data bytes
+    #[derive(Default)]
+    struct Sum(u64);
+
+    impl Authenticate for Sum {
+        fn start(&mut self, header: &[u8; 12]) {
+            self.0 = header.iter().map(|&byte| u64::from(byte)).sum();
+        }
+
+        fn update(&mut self, data: &[u8]) {
+            self.0 += data.iter().map(|&byte| u64::from(byte)).sum::<u64>();
+        }
+
+        fn finish(&mut self) -> [u8; 8] {
+            self.0.to_le_bytes()
+        }
+    }
+
+    #[test]
+    fn frames() {
+        let mut res = Vec::<u8>::new();
+        let mut e = Authenticated::new(LengthPrefixed::<256>::new(), Sum::default(), 7);
+        for frame in [&[1u8, 2][..], &[3]] {
+            e.start_frame(|data| res.extend(data));
+            e.write(frame, |data| res.extend(data));
+            e.end_frame(|data| res.extend(data));
+        }
+
+        #[rustfmt::skip]
+        let expected = [
+            // length, stream ID, counter, data, MAC
+            12, 7, 0, 1, 2, 10, 0, 0, 0, 0, 0, 0, 0,
+            11, 7, 1, 3, 11, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(res, expected);
+    }
+}
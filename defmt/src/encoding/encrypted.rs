@@ -0,0 +1,143 @@
+// With the `encryption` feature, the data of every frame is encrypted with an AEAD before it's
+// passed to the framed encoding:
+//   <stream ID: LEB128> <counter: LEB128> <encrypted frame data> <authentication tag: 16 bytes>
+//
+// The nonce is the stream ID (`u32`, little endian) followed by the counter (`u64`, little
+// endian). The frame is buffered, since the tag can only be computed once the frame is complete.
+// Frames longer than the buffer are dropped.
+
+use super::{leb128, Encode};
+
+/// Encrypts log frames with an AEAD, e.g. ChaCha20-Poly1305.
+///
+/// `defmt` doesn't depend on a particular cryptography crate; implement this trait with the AEAD of
+/// your choice. The host decrypts the frames with the same algorithm and key, see `Decrypt` in
+/// `defmt-decoder`.
+pub trait Encrypt {
+    /// Encrypts `data` in place with the 96-bit `nonce`, and returns the authentication tag.
+    fn encrypt(&mut self, nonce: &[u8; 12], data: &mut [u8]) -> [u8; 16];
+}
+
+/// Encrypts the frames encoded by `E` with the cipher `C`, in frames of up to `SIZE` bytes.
+///
+/// `E` must be a framed encoding, like [`Rzcobs`](super::Rzcobs) or
+/// [`LengthPrefixed`](super::LengthPrefixed), typically [`Encoder`](super::Encoder).
+pub struct Encrypted<E, C, const SIZE: usize> {
+    inner: E,
+    cipher: C,
+    stream_id: u32,
+    counter: u64,
+    buf: [u8; SIZE],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<E: Encode, C: Encrypt, const SIZE: usize> Encrypted<E, C, SIZE> {
+    /// Creates an encoder that encrypts the frames of `inner` with `cipher`.
+    ///
+    /// A nonce must never be used twice with the same key, so `stream_id` must be unique for every
+    /// `Encrypted` that uses the key, across resets as well: e.g. derive it from a boot counter
+    /// kept in flash, and the execution context the encoder is used by.
+    pub const fn new(inner: E, cipher: C, stream_id: u32) -> Self {
+        Self {
+            inner,
+            cipher,
+            stream_id,
+            counter: 0,
+            buf: [0; SIZE],
+            len: 0,
+            overflowed: false,
+        }
+    }
+}
+
+impl<E: Encode, C: Encrypt, const SIZE: usize> Encode for Encrypted<E, C, SIZE> {
+    fn start_frame(&mut self, _write: impl FnMut(&[u8])) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+
+    fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        if self.overflowed {
+            #[cfg(feature = "stats")]
+            crate::stats::frame_dropped();
+            return;
+        }
+
+        let mut nonce = [0; 12];
+        nonce[..4].copy_from_slice(&self.stream_id.to_le_bytes());
+        nonce[4..].copy_from_slice(&self.counter.to_le_bytes());
+        let data = &mut self.buf[..self.len];
+        let tag = self.cipher.encrypt(&nonce, data);
+
+        let mut header = [0; 15];
+        let mut len = leb128(u64::from(self.stream_id), &mut header);
+        len += leb128(self.counter, &mut header[len..]);
+        self.counter += 1;
+
+        self.inner.start_frame(&mut write);
+        self.inner.write(&header[..len], &mut write);
+        self.inner.write(data, &mut write);
+        self.inner.write(&tag, &mut write);
+        self.inner.end_frame(&mut write);
+    }
+
+    fn write(&mut self, data: &[u8], _write: impl FnMut(&[u8])) {
+        match self.buf.get_mut(self.len..self.len + data.len()) {
+            Some(buf) if !self.overflowed => {
+                buf.copy_from_slice(data);
+                self.len += data.len();
+            }
+            _ => self.overflowed = true,
+        }
+    }
+
+    fn frame_dropped(&mut self) {
+        self.inner.frame_dropped()
+    }
+}
+
+#[cfg(feature = "unstable-test")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::LengthPrefixed;
+
+    /// Not a cipher: flips the bits of the data, and returns the nonce twice as the tag
+    struct Flip;
+
+    impl Encrypt for Flip {
+        fn encrypt(&mut self, nonce: &[u8; 12], data: &mut [u8]) -> [u8; 16] {
+            data.iter_mut().for_each(|byte| *byte = !*byte);
+            let mut tag = [0; 16];
+            tag[..4].copy_from_slice(&nonce[..4]);
+            tag[4..].copy_from_slice(&nonce[..12]);
+            tag
+        }
+    }
+
+    fn encode(frames: &[&[u8]]) -> Vec<u8> {
+        let mut res = Vec::new();
+        let mut e = Encrypted::<_, _, 4>::new(LengthPrefixed::<256>::new(), Flip, 0x80);
+        for frame in frames {
+            e.start_frame(|data| res.extend(data));
+            e.write(frame, |data| res.extend(data));
+            e.end_frame(|data| res.extend(data));
+        }
+        res
+    }
+
+    #[test]
+    fn frames() {
+        #[rustfmt::skip]
+        let expected = [
+            // length, stream ID, counter, encrypted data
+            21, 0x80, 0x01, 0, 0xfe, 0xfd,
+            // tag
+            0x80, 0, 0, 0, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            21, 0x80, 0x01, 1, 0xfc, 0xfb,
+            0x80, 0, 0, 0, 0x80, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(encode(&[&[1, 2], &[0; 5], &[3, 4]]), expected);
+    }
+}
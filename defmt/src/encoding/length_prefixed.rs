@@ -0,0 +1,120 @@
+// Every frame is preceded by its length in bytes, as LEB128:
+//   <len: LEB128> <frame data: len bytes>
+//
+// The length has to be known before the frame data is written, so the frame is buffered. Frames
+// longer than the buffer are dropped and replaced by an empty frame, which the decoder reports as
+// malformed.
+//
+// The buffer of `Encoder` holds 256 bytes, or, with `DEFMT_MAX_FRAME_SIZE`, the longest frame the
+// splitting lets through, including what `Encrypted` and `Authenticated` add to it.
+
+use super::Encode;
+
+#[cfg(all(feature = "encoding-length-prefixed", defmt_max_frame_size))]
+include!(concat!(env!("OUT_DIR"), "/max_frame_size.rs"));
+
+/// Size of the buffer of the `length-prefixed` [`Encoder`](super::Encoder).
+#[cfg(all(feature = "encoding-length-prefixed", not(defmt_max_frame_size)))]
+pub(super) const ENCODER_SIZE: usize = 256;
+#[cfg(all(feature = "encoding-length-prefixed", defmt_max_frame_size))]
+pub(super) const ENCODER_SIZE: usize = MAX_FRAME_SIZE
+    // stream ID and counter, as LEB128, and the authentication tag of `Encrypted`
+    + if cfg!(feature = "encryption") { 5 + 10 + 16 } else { 0 }
+    // stream ID and counter, as LEB128, and the MAC of `Authenticated`
+    + if cfg!(feature = "authentication") { 5 + 10 + 8 } else { 0 };
+
+/// The `length-prefixed` encoding: every frame is preceded by its length.
+///
+/// Frames are buffered in `SIZE` bytes; longer frames are dropped and replaced by an empty frame.
+pub struct LengthPrefixed<const SIZE: usize = 256> {
+    buf: [u8; SIZE],
+    len: usize,
+    overflowed: bool,
+}
+
+#[allow(clippy::new_without_default)]
+impl<const SIZE: usize> LengthPrefixed<SIZE> {
+    /// Creates a new encoder.
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; SIZE],
+            len: 0,
+            overflowed: false,
+        }
+    }
+}
+
+impl<const SIZE: usize> Encode for LengthPrefixed<SIZE> {
+    fn start_frame(&mut self, _write: impl FnMut(&[u8])) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+
+    fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        #[cfg(feature = "stats")]
+        if self.overflowed {
+            crate::stats::frame_dropped();
+        }
+        let len = if self.overflowed { 0 } else { self.len };
+
+        let mut prefix = [0u8; 5];
+        let mut x = len;
+        let mut i = 0;
+        loop {
+            prefix[i] = (x & 0x7f) as u8;
+            x >>= 7;
+            if x == 0 {
+                break;
+            }
+            prefix[i] |= 0x80;
+            i += 1;
+        }
+
+        write(&prefix[..=i]);
+        write(&self.buf[..len]);
+    }
+
+    fn write(&mut self, data: &[u8], _write: impl FnMut(&[u8])) {
+        match self.buf.get_mut(self.len..self.len + data.len()) {
+            Some(buf) if !self.overflowed => {
+                buf.copy_from_slice(data);
+                self.len += data.len();
+            }
+            _ => self.overflowed = true,
+        }
+    }
+}
+
+#[cfg(feature = "unstable-test")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<const SIZE: usize>(frames: &[&[u8]]) -> Vec<u8> {
+        let mut res = Vec::new();
+        let mut e = LengthPrefixed::<SIZE>::new();
+        for frame in frames {
+            e.start_frame(|data| res.extend(data));
+            e.write(frame, |data| res.extend(data));
+            e.end_frame(|data| res.extend(data));
+        }
+        res
+    }
+
+    #[test]
+    fn frames() {
+        assert_eq!(encode::<256>(&[&[]]), [0]);
+        assert_eq!(encode::<256>(&[&[1, 2], &[3]]), [2, 1, 2, 1, 3]);
+
+        let long = [7; 200];
+        let mut expected = vec![0xc8, 0x01];
+        expected.extend(long);
+        assert_eq!(encode::<256>(&[&long]), expected);
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(encode::<256>(&[&[0; 257], &[1]]), [0, 1, 1]);
+        assert_eq!(encode::<4>(&[&[1, 2, 3, 4], &[5; 5]]), [4, 1, 2, 3, 4, 0]);
+    }
+}
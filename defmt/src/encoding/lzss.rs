@@ -0,0 +1,215 @@
+// LZSS compression with a 256-byte sliding window, in the spirit of heatshrink. All tokens are
+// byte-aligned so the encoder can flush at the end of every frame:
+//   0nnnnnnn            => output the n+1 literal bytes that follow, n < 0x7f
+//   01111111            => start over with an empty window
+//   1lllllll oooooooo   => output l+3 bytes, copied from o+1 bytes back in the output
+//
+// The window spans frame boundaries, so repeated log messages compress well, but the decoder has
+// to receive the whole stream from the start. There is no framing. When the transport drops a
+// frame, the encoder starts over and emits the reset token at the start of the next frame.
+
+use super::Encode;
+
+const WINDOW: usize = 256;
+const MIN_MATCH: usize = 3;
+// the format allows longer runs and matches; shorter ones keep the encoder state small
+const MAX_MATCH: usize = 16;
+const MAX_LITERALS: usize = 16;
+const RESET: u8 = 0x7f;
+
+/// The `lzss` encoding: frames are compressed with a 256-byte sliding window.
+pub struct Lzss {
+    /// Bytes emitted so far, ring buffer indexed by `head`
+    window: [u8; WINDOW],
+    head: u8,
+    filled: u16,
+    /// Bytes that have not been compressed yet
+    lookahead: [u8; MAX_MATCH],
+    lookahead_len: u8,
+    /// Literal bytes waiting for their run to be emitted
+    literals: [u8; MAX_LITERALS],
+    literals_len: u8,
+    /// Whether the next frame must start with the reset token
+    reset: bool,
+}
+
+#[allow(clippy::new_without_default)]
+impl Lzss {
+    /// Creates a new encoder.
+    pub const fn new() -> Self {
+        Self {
+            window: [0; WINDOW],
+            head: 0,
+            filled: 0,
+            lookahead: [0; MAX_MATCH],
+            lookahead_len: 0,
+            literals: [0; MAX_LITERALS],
+            literals_len: 0,
+            reset: false,
+        }
+    }
+}
+
+impl Encode for Lzss {
+    fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        if self.reset {
+            write(&[RESET]);
+            self.reset = false;
+        }
+    }
+
+    fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        while self.lookahead_len != 0 {
+            self.compress_one(&mut write);
+        }
+        self.flush_literals(&mut write);
+    }
+
+    fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
+        for &byte in data {
+            self.lookahead[usize::from(self.lookahead_len)] = byte;
+            self.lookahead_len += 1;
+            if usize::from(self.lookahead_len) == MAX_MATCH {
+                self.compress_one(&mut write);
+            }
+        }
+    }
+
+    fn frame_dropped(&mut self) {
+        // the frame was flushed by `end_frame`, so only the window is left
+        self.head = 0;
+        self.filled = 0;
+        self.reset = true;
+    }
+}
+
+impl Lzss {
+    /// Emits the longest match at the start of the lookahead, or moves its first byte to the
+    /// pending literals if there's no match.
+    fn compress_one(&mut self, write: &mut impl FnMut(&[u8])) {
+        let (distance, len) = self.longest_match();
+
+        let consumed = if len >= MIN_MATCH {
+            self.flush_literals(write);
+            write(&[0x80 | (len - MIN_MATCH) as u8, (distance - 1) as u8]);
+            len
+        } else {
+            self.literals[usize::from(self.literals_len)] = self.lookahead[0];
+            self.literals_len += 1;
+            if usize::from(self.literals_len) == MAX_LITERALS {
+                self.flush_literals(write);
+            }
+            1
+        };
+
+        for i in 0..consumed {
+            self.push_window(self.lookahead[i]);
+        }
+        self.lookahead.copy_within(consumed.., 0);
+        self.lookahead_len -= consumed as u8;
+    }
+
+    /// Returns the distance and length of the longest match of the lookahead in the window.
+    ///
+    /// A match may extend into the lookahead itself, i.e. its length may exceed its distance.
+    fn longest_match(&self) -> (usize, usize) {
+        let lookahead = &self.lookahead[..usize::from(self.lookahead_len)];
+        let mut best = (0, 0);
+
+        for distance in 1..=usize::from(self.filled) {
+            let len = lookahead
+                .iter()
+                .enumerate()
+                .take_while(|&(i, byte)| {
+                    let source = if i < distance {
+                        self.window[usize::from(self.head).wrapping_sub(distance - i) % WINDOW]
+                    } else {
+                        lookahead[i - distance]
+                    };
+                    source == *byte
+                })
+                .count();
+
+            if len > best.1 {
+                best = (distance, len);
+                if len == lookahead.len() {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    fn push_window(&mut self, byte: u8) {
+        self.window[usize::from(self.head)] = byte;
+        self.head = self.head.wrapping_add(1);
+        if usize::from(self.filled) < WINDOW {
+            self.filled += 1;
+        }
+    }
+
+    fn flush_literals(&mut self, write: &mut impl FnMut(&[u8])) {
+        if self.literals_len != 0 {
+            write(&[self.literals_len - 1]);
+            write(&self.literals[..usize::from(self.literals_len)]);
+            self.literals_len = 0;
+        }
+    }
+}
+
+#[cfg(feature = "unstable-test")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(frames: &[&[u8]]) -> Vec<u8> {
+        let mut res = Vec::new();
+        let mut e = Lzss::new();
+        for frame in frames {
+            e.start_frame(|data| res.extend(data));
+            e.write(frame, |data| res.extend(data));
+            e.end_frame(|data| res.extend(data));
+        }
+        res
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(encode(&[&[]]), [0u8; 0]);
+        assert_eq!(encode(&[&[1, 2]]), [0x01, 1, 2]);
+        assert_eq!(encode(&[&[1], &[2]]), [0x00, 1, 0x00, 2]);
+    }
+
+    #[test]
+    fn matches() {
+        // repeated frame
+        assert_eq!(
+            encode(&[&[1, 2, 3, 4], &[1, 2, 3, 4]]),
+            [0x03, 1, 2, 3, 4, 0x81, 3],
+        );
+        // run overlapping the lookahead
+        assert_eq!(encode(&[&[7; 10]]), [0x00, 7, 0x86, 0]);
+        // match longer than `MAX_MATCH`
+        assert_eq!(encode(&[&[0; 20]]), [0x00, 0, 0x8d, 0, 0x80, 0],);
+    }
+
+    #[test]
+    fn dropped_frame() {
+        let mut res = Vec::new();
+        let mut e = Lzss::new();
+        for dropped in [false, true, false] {
+            let mut frame: Vec<u8> = Vec::new();
+            e.start_frame(|data| frame.extend(data));
+            e.write(&[1, 2, 3, 4], |data| frame.extend(data));
+            e.end_frame(|data| frame.extend(data));
+            if dropped {
+                e.frame_dropped();
+            } else {
+                res.extend(frame);
+            }
+        }
+        // the last frame can't refer to the dropped one
+        assert_eq!(res, [0x03, 1, 2, 3, 4, RESET, 0x03, 1, 2, 3, 4]);
+    }
+}
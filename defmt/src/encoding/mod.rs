@@ -1,9 +1,90 @@
-#[cfg(all(feature = "encoding-raw", feature = "encoding-rzcobs"))]
+//! Encodings of the log frames for sending them over the wire.
+//!
+//! [`Encoder`] uses the encoding selected via the `encoding-*` Cargo feature; all encodings are
+//! available as types implementing [`Encode`] as well.
+
+#[cfg(any(
+    all(feature = "encoding-raw", feature = "encoding-rzcobs"),
+    all(feature = "encoding-raw", feature = "encoding-lzss"),
+    all(feature = "encoding-raw", feature = "encoding-length-prefixed"),
+    all(feature = "encoding-rzcobs", feature = "encoding-lzss"),
+    all(feature = "encoding-rzcobs", feature = "encoding-length-prefixed"),
+    all(feature = "encoding-lzss", feature = "encoding-length-prefixed"),
+))]
 compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
 
-#[cfg_attr(feature = "encoding-raw", path = "raw.rs")]
-#[cfg_attr(not(feature = "encoding-raw"), path = "rzcobs.rs")]
-mod inner;
+#[cfg(feature = "authentication")]
+mod authenticated;
+#[cfg(feature = "encryption")]
+mod encrypted;
+mod length_prefixed;
+mod lzss;
+mod raw;
+mod rzcobs;
+
+#[cfg(feature = "authentication")]
+pub use self::authenticated::{Authenticate, Authenticated};
+#[cfg(feature = "encryption")]
+pub use self::encrypted::{Encrypt, Encrypted};
+pub use self::{length_prefixed::LengthPrefixed, lzss::Lzss, raw::Raw, rzcobs::Rzcobs};
+
+#[cfg(feature = "encoding-raw")]
+type Selected = Raw;
+#[cfg(feature = "encoding-lzss")]
+type Selected = Lzss;
+#[cfg(feature = "encoding-length-prefixed")]
+type Selected = LengthPrefixed<{ length_prefixed::ENCODER_SIZE }>;
+#[cfg(not(any(
+    feature = "encoding-raw",
+    feature = "encoding-lzss",
+    feature = "encoding-length-prefixed"
+)))]
+type Selected = Rzcobs;
+
+/// Writes `x` as LEB128 to the start of `buf`, and returns the number of bytes written.
+#[cfg(any(feature = "authentication", feature = "encryption"))]
+fn leb128(mut x: u64, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    for byte in buf {
+        *byte = (x & 0x7f) as u8;
+        x >>= 7;
+        len += 1;
+        if x == 0 {
+            break;
+        }
+        *byte |= 0x80;
+    }
+    len
+}
+
+/// Encodes raw defmt frames for sending over the wire.
+///
+/// This is implemented by every encoding, so a new wire framing only needs to implement this trait,
+/// and generic code can work with any of them. Most `Logger` implementations should use [`Encoder`],
+/// which uses the encoding selected via the `encoding-*` Cargo feature.
+///
+/// The host tooling learns which encoding to decode from that Cargo feature as well. A `Logger`
+/// that uses a different encoding than the selected one produces data the host tooling can't
+/// decode, unless it is decoded with the right encoding by other means.
+///
+/// See [`Encoder`] for the rules the methods must be called with.
+pub trait Encode {
+    /// Starts encoding a log frame.
+    fn start_frame(&mut self, write: impl FnMut(&[u8]));
+
+    /// Finishes encoding a log frame.
+    fn end_frame(&mut self, write: impl FnMut(&[u8]));
+
+    /// Writes part of the data of a log frame.
+    fn write(&mut self, data: &[u8], write: impl FnMut(&[u8]));
+
+    /// Notes that the transport dropped the whole frame that was encoded last, e.g. because its
+    /// buffer was full.
+    ///
+    /// Encodings that keep state across frames start over, so that the following frames can be
+    /// decoded without the dropped one.
+    fn frame_dropped(&mut self) {}
+}
 
 // This wrapper struct is to avoid copypasting the public docs in all the impls.
 
@@ -19,14 +100,14 @@ mod inner;
 /// Encodings may perform two functions:
 ///
 /// - Framing: Adds extra data to allow the encoder to know when each frame starts
-/// and ends in the stream. Unframed log frames already contain enough information for
-/// the decoder to know when they end, so framing is optional. However, without framing
-/// the decoder must receive all bytes intact or it may "lose sync". With framing, it can
-/// recover from missing/corrupted data, and can start decoding from the "middle" of an
-/// already-running stream.
+///   and ends in the stream. Unframed log frames already contain enough information for
+///   the decoder to know when they end, so framing is optional. However, without framing
+///   the decoder must receive all bytes intact or it may "lose sync". With framing, it can
+///   recover from missing/corrupted data, and can start decoding from the "middle" of an
+///   already-running stream.
 /// - Compression: The frame data has rather low entropy (for example, it contains many
-/// zero bytes due to encoding all integers in fixed with, and will likely contain many
-/// repetitions). Compression can decrease the on-the-wire required bandwidth.
+///   zero bytes due to encoding all integers in fixed with, and will likely contain many
+///   repetitions). Compression can decrease the on-the-wire required bandwidth.
 ///
 /// defmt provides the `Encoder` separately instead of feeding already-encoded bytes
 /// to the `Logger` because `Logger` implementations may decide to allow
@@ -34,14 +115,15 @@ mod inner;
 /// priority levels. In this case, the Logger implementation needs to create one
 /// Encoder for each such context.
 pub struct Encoder {
-    inner: inner::Encoder,
+    inner: Selected,
 }
 
+#[allow(clippy::new_without_default)]
 impl Encoder {
     /// Create a new `Encoder`.
     pub const fn new() -> Self {
         Self {
-            inner: inner::Encoder::new(),
+            inner: Selected::new(),
         }
     }
 
@@ -83,4 +165,34 @@ impl Encoder {
     pub fn write(&mut self, data: &[u8], write: impl FnMut(&[u8])) {
         self.inner.write(data, write)
     }
+
+    /// Notes that the transport dropped the whole frame that was encoded last.
+    ///
+    /// `Logger` impls that buffer the encoded data and drop frames that don't fit must call this
+    /// after `end_frame` for every dropped frame. Dropping only part of a frame corrupts the
+    /// stream.
+    ///
+    /// The `lzss` encoding compresses frames with a window that spans frame boundaries; it starts
+    /// over with an empty window, and tells the decoder to do so in the next frame.
+    pub fn frame_dropped(&mut self) {
+        self.inner.frame_dropped()
+    }
+}
+
+impl Encode for Encoder {
+    fn start_frame(&mut self, write: impl FnMut(&[u8])) {
+        self.inner.start_frame(write)
+    }
+
+    fn end_frame(&mut self, write: impl FnMut(&[u8])) {
+        self.inner.end_frame(write)
+    }
+
+    fn write(&mut self, data: &[u8], write: impl FnMut(&[u8])) {
+        self.inner.write(data, write)
+    }
+
+    fn frame_dropped(&mut self) {
+        self.inner.frame_dropped()
+    }
 }
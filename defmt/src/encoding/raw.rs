@@ -1,17 +1,51 @@
-pub(crate) struct Encoder {
+// With `DEFMT_SYNC_INTERVAL=n` set at build time, a resynchronization marker is written before the
+// first frame and then before every n-th frame, so a decoder that attached mid-run or lost bytes
+// can find the next frame boundary.
+
+use super::Encode;
+
+#[cfg(defmt_sync)]
+include!(concat!(env!("OUT_DIR"), "/sync_interval.rs"));
+
+/// Must match `SYNC_MARKER` in `defmt-decoder`.
+#[cfg(defmt_sync)]
+const SYNC_MARKER: [u8; 8] = [0xd3, 0xfe, 0x6d, 0x74, 0x5e, 0xc1, 0xa7, 0x0b];
+
+/// The `raw` encoding: frames are sent as-is.
+pub struct Raw {
+    /// Frames started since the last resynchronization marker
+    #[cfg(defmt_sync)]
+    frames: u16,
     _private: (),
 }
 
-impl Encoder {
-    pub(crate) const fn new() -> Self {
-        Self { _private: () }
+#[allow(clippy::new_without_default)]
+impl Raw {
+    /// Creates a new encoder.
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(defmt_sync)]
+            frames: 0,
+            _private: (),
+        }
     }
+}
+
+impl Encode for Raw {
+    #[cfg(not(defmt_sync))]
+    fn start_frame(&mut self, _write: impl FnMut(&[u8])) {}
 
-    pub(crate) fn start_frame(&mut self, _write: impl FnMut(&[u8])) {}
+    #[cfg(defmt_sync)]
+    fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        if self.frames == 0 {
+            write(&SYNC_MARKER);
+        }
+        self.frames = (self.frames + 1) % SYNC_INTERVAL;
+    }
 
-    pub(crate) fn end_frame(&mut self, _write: impl FnMut(&[u8])) {}
+    fn end_frame(&mut self, _write: impl FnMut(&[u8])) {}
 
-    pub(crate) fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
+    fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
         write(data)
     }
 }
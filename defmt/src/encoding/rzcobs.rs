@@ -9,13 +9,18 @@
 //   1nnnnnnn => output n+7 bytes from stream, output 0x00
 //   11111111 => output 134 bytes from stream
 
-pub(crate) struct Encoder {
+use super::Encode;
+
+/// The `rzcobs` encoding: frames are separated by zero bytes and zero-compressed.
+pub struct Rzcobs {
     run: u8,
     zeros: u8,
     started: bool,
 }
 
-impl Encoder {
+#[allow(clippy::new_without_default)]
+impl Rzcobs {
+    /// Creates a new encoder.
     pub const fn new() -> Self {
         Self {
             run: 0,
@@ -23,8 +28,10 @@ impl Encoder {
             started: false,
         }
     }
+}
 
-    pub fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
+impl Encode for Rzcobs {
+    fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
         let mut write_byte = move |b: u8| write(&[b]);
         if !self.started {
             self.started = true;
@@ -36,7 +43,7 @@ impl Encoder {
         }
     }
 
-    pub fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+    fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
         let mut write_byte = move |b: u8| write(&[b]);
 
         // Finish writing the previous symbol if needed.
@@ -53,7 +60,7 @@ impl Encoder {
         self.zeros = 0;
     }
 
-    pub fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
+    fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
         let mut write_byte = move |b: u8| write(&[b]);
 
         for &byte in data {
@@ -254,7 +261,7 @@ mod tests {
         for (dec, enc) in tests {
             let mut res: Vec<u8> = Vec::new();
 
-            let mut e = Encoder::new();
+            let mut e = Rzcobs::new();
             e.started = true; // simulate that this is not the first frame.
 
             e.start_frame(|data| res.extend(data));
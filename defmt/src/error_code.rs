@@ -0,0 +1,29 @@
+use crate::Str;
+
+/// A compact error code tied to an interned message, created via [`error_code!`].
+///
+/// The code is the string index of the message, so it's small and, like the message, it costs no
+/// space in the firmware besides the `u32`. Firmware can return it over a protocol or store it,
+/// and host tooling expands it to the message with the `defmt` table of the ELF file, e.g. with
+/// `defmt-print -e <elf> error-codes`. The codes are only valid for the ELF file they were built
+/// into.
+///
+/// Logging an `ErrorCode` with `{}` prints its message.
+///
+/// [`error_code!`]: macro.error_code.html
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErrorCode {
+    pub(crate) message: Str,
+}
+
+impl ErrorCode {
+    /// Returns the error code.
+    pub fn code(&self) -> u32 {
+        self.message.as_index()
+    }
+
+    /// Returns the interned message.
+    pub fn message(&self) -> Str {
+        self.message
+    }
+}
@@ -0,0 +1,83 @@
+//! Timeline events for tracing the execution of the firmware.
+//!
+//! Each function in this module emits one *event frame*. Event frames use the same transport and
+//! header (string index plus timestamp) as log frames, but the decoder recognizes them and exposes
+//! them as structured [events] so host tooling can reconstruct a timeline of task switches,
+//! interrupts and user-defined markers, similar to SEGGER SystemView.
+//!
+//! Identifiers (task, interrupt and marker IDs) are opaque to defmt; they only have to be unique
+//! within their own category. Markers can also be named, with the [`event!`](crate::event!) macro.
+//!
+//! [events]: https://docs.rs/defmt-decoder/*/defmt_decoder/enum.Event.html
+
+use crate as defmt;
+use crate::{export, Str};
+
+fn emit(kind: &Str, id: Option<u16>) {
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(kind);
+    if let Some(id) = id {
+        export::u16(&id);
+    }
+}
+
+/// Records that the scheduler switched to the task identified by `task`.
+pub fn task_switch(task: u16) {
+    emit(
+        &defmt_macros::intern_tagged!("event", "task_switch={=u16}"),
+        Some(task),
+    )
+}
+
+/// Records that the interrupt handler identified by `irq` was entered.
+pub fn isr_enter(irq: u16) {
+    emit(
+        &defmt_macros::intern_tagged!("event", "isr_enter={=u16}"),
+        Some(irq),
+    )
+}
+
+/// Records that the most recently entered interrupt handler returned.
+pub fn isr_exit() {
+    emit(&defmt_macros::intern_tagged!("event", "isr_exit"), None)
+}
+
+/// Records that the interrupt handler identified by `irq` was entered, and that it returned when
+/// the returned guard is dropped.
+///
+/// This keeps the `isr_enter` and `isr_exit` frames paired on every return path, so the decoder
+/// can match them up to measure the time spent in each handler and how deeply handlers nest.
+///
+/// # Examples
+///
+/// ```
+/// fn uart0() {
+///     let _isr = defmt::event::isr(5);
+///     // handle the interrupt ..
+/// } // `isr_exit` is recorded here
+/// ```
+pub fn isr(irq: u16) -> IsrGuard {
+    isr_enter(irq);
+    IsrGuard { _private: () }
+}
+
+/// Records [`isr_exit`] when dropped, see [`isr`].
+#[must_use = "`isr_exit` is recorded as soon as the guard is dropped"]
+pub struct IsrGuard {
+    _private: (),
+}
+
+impl Drop for IsrGuard {
+    fn drop(&mut self) {
+        isr_exit()
+    }
+}
+
+/// Records a user-defined marker identified by `id`.
+pub fn marker(id: u16) {
+    emit(
+        &defmt_macros::intern_tagged!("event", "marker={=u16}"),
+        Some(id),
+    )
+}
@@ -0,0 +1,102 @@
+//! Self-check of the transport, enabled by the `self-check` feature.
+//!
+//! The CRC-32 of the data of all frames is accumulated, and every `INTERVAL` frames a check frame
+//! reports it, together with the number of frames it covers. Check frames themselves are not
+//! covered. The decoder computes the same CRC over the frames it received and reports a mismatch.
+//!
+//! The check frame covers all the frames that precede it on the wire: the CRC is taken while the
+//! check frame holds the global logger, so a frame of an execution context that preempted the
+//! release of the last frame is covered too.
+
+use core::cell::Cell;
+
+/// Number of frames covered by each check frame
+const INTERVAL: u16 = 64;
+
+/// Must match `crc32` in `defmt-decoder`.
+pub(crate) fn crc32(mut crc: u32, bytes: &[u8]) -> u32 {
+    crc = !crc;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & 0u32.wrapping_sub(crc & 1));
+        }
+    }
+    !crc
+}
+
+struct Check {
+    crc: Cell<u32>,
+    frames: Cell<u16>,
+    /// Set while the check frame is written, which is not covered by the CRC.
+    reporting: Cell<bool>,
+}
+
+// safety: only accessed while the global logger is acquired
+unsafe impl Sync for Check {}
+
+static CHECK: Check = Check {
+    crc: Cell::new(0),
+    frames: Cell::new(0),
+    reporting: Cell::new(false),
+};
+
+/// Adds `bytes` to the CRC. Must be called while the global logger is acquired.
+pub(super) fn write(bytes: &[u8]) {
+    if !CHECK.reporting.get() {
+        CHECK.crc.set(crc32(CHECK.crc.get(), bytes));
+    }
+}
+
+/// Counts a completed frame. Must be called while the global logger is acquired, before it is
+/// released.
+///
+/// Returns `true` if a check frame is due, see [`report`].
+pub(super) fn end_frame() -> bool {
+    let frames = CHECK.frames.get().saturating_add(1);
+    CHECK.frames.set(frames);
+    frames >= INTERVAL
+}
+
+/// Emits a check frame over the frames counted so far, and starts over. Must be called while the
+/// global logger is *not* acquired.
+///
+/// If another execution context emitted the check frame first, this one covers no frames.
+#[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
+#[inline(never)]
+pub(super) fn report() {
+    use crate as defmt;
+
+    // safety: will be released a few lines further down
+    unsafe { super::acquire() };
+    #[cfg(defmt_max_frame_size)]
+    let outer = super::split::start_frame();
+    // NOTE taken while the logger is acquired, so no frame can come between the covered frames and
+    // the check frame
+    let crc = CHECK.crc.replace(0);
+    let frames = CHECK.frames.replace(0);
+    CHECK.reporting.set(true);
+    super::header(&defmt_macros::intern_tagged!(
+        "self_check",
+        "self-check: crc {=u32:#010x} over {=u16} frames"
+    ));
+    super::u32(&crc);
+    super::u16(&frames);
+    CHECK.reporting.set(false);
+    #[cfg(defmt_max_frame_size)]
+    super::split::end_frame(outer);
+    // safety: acquire() was called a few lines above
+    unsafe { super::release_logger() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        // the CRC can be computed piecewise
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+    }
+}
@@ -0,0 +1,312 @@
+use core::cell::UnsafeCell;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{GuardedLogger, Write};
+
+/// Storage for the guard of a `#[global_logger(guarded)]` logger.
+///
+/// The guard returned by `GuardedLogger::acquire` lives here between the `_defmt_acquire` and
+/// `_defmt_release` calls. Acquisitions nest like the execution contexts that make them, so the
+/// slot tracks the nesting depth of the current context and the depth whose acquisition got the
+/// guard. Only that context writes; the writes of the contexts nested in it, or of those it
+/// preempted, are discarded.
+///
+/// With the `std` feature every thread is its own execution context and keeps its own depth.
+/// Without it the depth is shared, which is only correct on a single core where contexts
+/// preempt each other in a stack-like fashion: the innermost context is then always the one that
+/// is currently running.
+pub struct GuardSlot<L: GuardedLogger> {
+    guard: UnsafeCell<Option<L::Guard>>,
+    #[cfg(not(feature = "std"))]
+    depth: AtomicUsize,
+    #[cfg(not(feature = "std"))]
+    owner: AtomicUsize,
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static DEPTH: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    static OWNER: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+// safety: `guard` is only accessed by the execution context that owns the logger, which may be a
+// different thread than the one that owned it before
+unsafe impl<L: GuardedLogger> Sync for GuardSlot<L> where L::Guard: Send {}
+
+#[allow(clippy::new_without_default)]
+impl<L: GuardedLogger> GuardSlot<L> {
+    pub const fn new() -> Self {
+        Self {
+            guard: UnsafeCell::new(None),
+            #[cfg(not(feature = "std"))]
+            depth: AtomicUsize::new(0),
+            #[cfg(not(feature = "std"))]
+            owner: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn acquire(&self) {
+        let depth = self.depth() + 1;
+        self.set_depth(depth);
+        if let Some(guard) = L::acquire() {
+            // safety: we own the logger in the current execution context
+            let slot = unsafe { &mut *self.guard.get() };
+            if slot.is_some() {
+                panic!("defmt logger taken reentrantly")
+            }
+            *slot = Some(guard);
+            self.set_owner(depth);
+        }
+    }
+
+    /// # Safety
+    /// Must follow an earlier call to `acquire()` in the same execution context
+    pub unsafe fn release(&self) {
+        let depth = self.depth();
+        if self.owner() == depth {
+            let guard = (*self.guard.get()).take();
+            // a contract violation in the caller; not checked in release builds to keep
+            // `release` small
+            debug_assert!(
+                guard.is_some(),
+                "defmt logger released without being acquired"
+            );
+            self.set_owner(0);
+            drop(guard)
+        }
+        self.set_depth(depth.wrapping_sub(1));
+    }
+
+    /// # Safety
+    /// Must be called between `acquire()` and `release()`
+    pub unsafe fn acquired(&self) -> bool {
+        self.owns_guard() && (*self.guard.get()).is_some()
+    }
+
+    /// # Safety
+    /// Must be called between `acquire()` and `release()`
+    pub unsafe fn write(&self, bytes: &[u8]) {
+        self.with_guard(|guard| guard.write(bytes))
+    }
+
+    /// # Safety
+    /// Must be called between `acquire()` and `release()`
+    pub unsafe fn flush(&self) {
+        self.with_guard(|guard| guard.flush())
+    }
+
+    unsafe fn with_guard(&self, f: impl FnOnce(&mut L::Guard)) {
+        if !self.owns_guard() {
+            return;
+        }
+        if let Some(guard) = (*self.guard.get()).as_mut() {
+            f(guard)
+        }
+    }
+
+    /// Whether the guard was acquired by the current execution context, rather than by one it
+    /// preempted or one nested in it
+    fn owns_guard(&self) -> bool {
+        self.owner() == self.depth()
+    }
+
+    #[cfg(feature = "std")]
+    fn depth(&self) -> usize {
+        DEPTH.with(|depth| depth.get())
+    }
+
+    #[cfg(feature = "std")]
+    fn set_depth(&self, depth: usize) {
+        DEPTH.with(|cell| cell.set(depth))
+    }
+
+    #[cfg(feature = "std")]
+    fn owner(&self) -> usize {
+        OWNER.with(|owner| owner.get())
+    }
+
+    #[cfg(feature = "std")]
+    fn set_owner(&self, depth: usize) {
+        OWNER.with(|owner| owner.set(depth))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    // NOTE no read-modify-write needed; a preempting context restores the depth before returning
+    // to us
+    #[cfg(not(feature = "std"))]
+    fn set_depth(&self, depth: usize) {
+        self.depth.store(depth, Ordering::Relaxed)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn owner(&self) -> usize {
+        self.owner.load(Ordering::Relaxed)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn set_owner(&self, depth: usize) {
+        self.owner.store(depth, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Logger;
+
+    impl GuardedLogger for Logger {
+        type Guard = Guard;
+
+        fn acquire() -> Option<Guard> {
+            Some(Guard)
+        }
+    }
+
+    struct Guard;
+
+    impl Write for Guard {
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    #[test]
+    fn acquire_release() {
+        let slot = GuardSlot::<Logger>::new();
+        for _ in 0..2 {
+            slot.acquire();
+            unsafe {
+                assert!(slot.acquired());
+                slot.release();
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic = "defmt logger taken reentrantly"]
+    fn double_acquire() {
+        let slot = GuardSlot::<Logger>::new();
+        slot.acquire();
+        slot.acquire();
+    }
+
+    #[test]
+    #[should_panic = "defmt logger released without being acquired"]
+    fn release_without_acquire() {
+        let slot = GuardSlot::<Logger>::new();
+        slot.acquire();
+        unsafe {
+            slot.release();
+            slot.release();
+        }
+    }
+
+    #[test]
+    fn preempted_by_acquiring_context() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static ACQUIRES: AtomicUsize = AtomicUsize::new(0);
+        static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+        static RELEASES: AtomicUsize = AtomicUsize::new(0);
+
+        struct BusyLogger;
+
+        impl GuardedLogger for BusyLogger {
+            type Guard = CountingGuard;
+
+            // the first context finds the logger taken, the one preempting it gets it
+            fn acquire() -> Option<CountingGuard> {
+                match ACQUIRES.fetch_add(1, Ordering::Relaxed) {
+                    1 => Some(CountingGuard),
+                    _ => None,
+                }
+            }
+        }
+
+        struct CountingGuard;
+
+        impl Write for CountingGuard {
+            fn write(&mut self, bytes: &[u8]) {
+                WRITTEN.fetch_add(bytes.len(), Ordering::Relaxed);
+            }
+        }
+
+        impl Drop for CountingGuard {
+            fn drop(&mut self) {
+                RELEASES.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let slot = GuardSlot::<BusyLogger>::new();
+        slot.acquire();
+        unsafe {
+            assert!(!slot.acquired());
+
+            // preempting context
+            slot.acquire();
+            assert!(slot.acquired());
+            slot.write(&[1, 2]);
+            slot.release();
+            assert_eq!(RELEASES.load(Ordering::Relaxed), 1);
+
+            // back in the first context, whose writes are still discarded
+            assert!(!slot.acquired());
+            slot.write(&[3]);
+            slot.release();
+        }
+        assert_eq!(WRITTEN.load(Ordering::Relaxed), 2);
+        assert_eq!(RELEASES.load(Ordering::Relaxed), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn other_thread_skipped() {
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        static TAKEN: AtomicBool = AtomicBool::new(false);
+
+        struct ExclusiveLogger;
+
+        impl GuardedLogger for ExclusiveLogger {
+            type Guard = ExclusiveGuard;
+
+            fn acquire() -> Option<ExclusiveGuard> {
+                (!TAKEN.swap(true, Ordering::Acquire)).then_some(ExclusiveGuard)
+            }
+        }
+
+        struct ExclusiveGuard;
+
+        impl Write for ExclusiveGuard {
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
+
+        impl Drop for ExclusiveGuard {
+            fn drop(&mut self) {
+                TAKEN.store(false, Ordering::Release);
+            }
+        }
+
+        static SLOT: GuardSlot<ExclusiveLogger> = GuardSlot::new();
+
+        SLOT.acquire();
+        std::thread::spawn(|| {
+            SLOT.acquire();
+            unsafe {
+                assert!(!SLOT.acquired());
+                SLOT.release();
+            }
+        })
+        .join()
+        .unwrap();
+        unsafe {
+            assert!(SLOT.acquired());
+            SLOT.release();
+        }
+        assert!(!TAKEN.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,77 @@
+//! String interning for the `host-logger` feature.
+//!
+//! Strings are interned at runtime, the first time they are used, and shared by all threads.
+
+use std::{collections::BTreeMap, sync::Mutex, vec::Vec};
+
+struct Strings {
+    /// Index of each interned string and tag
+    indices: BTreeMap<(&'static str, &'static str), u32>,
+    /// Interned strings and tags; the string with index `i` is at position `i - 1`
+    strings: Vec<(&'static str, &'static str)>,
+}
+
+static STRINGS: Mutex<Strings> = Mutex::new(Strings {
+    indices: BTreeMap::new(),
+    strings: Vec::new(),
+});
+
+/// Returns the index of `string`, interning it if it's new.
+pub(crate) fn fetch_add_string_index(string: &'static str, tag: &'static str) -> u32 {
+    let mut guard = STRINGS.lock().unwrap_or_else(|e| e.into_inner());
+    let Strings { indices, strings } = &mut *guard;
+    *indices.entry((string, tag)).or_insert_with(|| {
+        strings.push((string, tag));
+        // index 0 is reserved, e.g. as the terminator of format sequences
+        strings.len() as u32
+    })
+}
+
+/// Returns the number of strings interned so far; their indices are `1..=count`.
+pub(crate) fn string_count() -> usize {
+    STRINGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .strings
+        .len()
+}
+
+/// Returns the string with index `index` and its tag.
+pub(crate) fn string(index: usize) -> Option<(&'static str, &'static str)> {
+    let guard = STRINGS.lock().unwrap_or_else(|e| e.into_inner());
+    guard.strings.get(index.checked_sub(1)?).copied()
+}
+
+/// Returns a table of the strings interned so far.
+pub(crate) fn table() -> defmt_decoder::Table {
+    use defmt_decoder::Table;
+
+    let guard = STRINGS.lock().unwrap_or_else(|e| e.into_inner());
+    let entries = guard
+        .strings
+        .iter()
+        .enumerate()
+        .map(|(i, &(string, tag))| (i + 1, super::mocked_entry(string, tag)))
+        .collect();
+    let mut table = Table::new(entries, encoding());
+    table.set_sequence_numbers(cfg!(feature = "sequence-numbers"));
+    table
+}
+
+/// Returns the encoding selected with the `encoding-*` features, like `_defmt_encoding_` does on
+/// the target.
+fn encoding() -> defmt_decoder::Encoding {
+    use defmt_decoder::Encoding;
+
+    if cfg!(all(feature = "encoding-raw", defmt_sync)) {
+        Encoding::RawSync
+    } else if cfg!(feature = "encoding-raw") {
+        Encoding::Raw
+    } else if cfg!(feature = "encoding-lzss") {
+        Encoding::Lzss
+    } else if cfg!(feature = "encoding-length-prefixed") {
+        Encoding::LengthPrefixed
+    } else {
+        Encoding::Rzcobs
+    }
+}
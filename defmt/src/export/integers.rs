@@ -3,20 +3,64 @@ use super::*;
 macro_rules! write_to_le_bytes {
     ($($s:ident),*) => {
         $(/// Implementation detail
+        #[cfg_attr(feature = "outline-encoders", inline(never))]
         pub fn $s(b: &$s) {
             write(&b.to_le_bytes())
         })*
     };
 }
 
-write_to_le_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+write_to_le_bytes!(u8, u16, u32, u64, u128, i128);
+
+macro_rules! write_zigzag_leb128 {
+    ($($s:ident: $wide:ident as $unsigned:ident => $leb:ident;)*) => {
+        $(/// Implementation detail
+        #[cfg_attr(feature = "outline-encoders", inline(never))]
+        pub fn $s(b: &$s) {
+            let b = $wide::from(*b);
+            $leb(&(((b << 1) ^ (b >> ($wide::BITS - 1))) as $unsigned))
+        })*
+    };
+}
+
+// Small values of either sign are common, so signed integers are zigzag encoded (0, -1, 1, -2, ...
+// become 0, 1, 2, 3, ...) and then compressed with LEB128.
+write_zigzag_leb128! {
+    i8: i32 as u32 => leb32;
+    i16: i32 as u32 => leb32;
+    i32: i32 as u32 => leb32;
+    i64: i64 as u64 => leb64;
+}
+
+macro_rules! write_truncated_le_bytes {
+    ($($s:ident: $ty:ty, $bytes:literal;)*) => {
+        $(/// Implementation detail
+        #[cfg_attr(feature = "outline-encoders", inline(never))]
+        pub fn $s(b: &$ty) {
+            write(&b.to_le_bytes()[..$bytes])
+        })*
+    };
+}
+
+// Values of odd-width hardware registers are sent without padding them to the next power of two;
+// bits beyond the width are discarded.
+write_truncated_le_bytes! {
+    u24: u32, 3;
+    u48: u64, 6;
+    u56: u64, 7;
+    i24: i32, 3;
+    i48: i64, 6;
+    i56: i64, 7;
+}
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn usize(b: &usize) {
     write(&(*b as u32).to_le_bytes())
 }
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn isize(b: &isize) {
-    write(&(*b as i32).to_le_bytes())
+    i32(&(*b as i32))
 }
@@ -1,11 +1,51 @@
+#[cfg(any(feature = "self-check", test))]
+#[cfg_attr(
+    not(all(
+        feature = "self-check",
+        not(any(feature = "unstable-test", feature = "host-println"))
+    )),
+    allow(dead_code)
+)]
+mod check;
+mod guarded;
+#[cfg(all(
+    feature = "host-logger",
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+mod host;
 mod integers;
+#[cfg(all(
+    defmt_max_frame_size,
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+mod split;
+#[cfg(feature = "unstable-test")]
+mod tokens;
 mod traits;
 
 use core::fmt::Write as _;
 
 use crate::{Format, Formatter, Str};
 
-pub use self::integers::*;
+#[cfg(all(
+    feature = "host-logger",
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+pub(crate) use self::host::fetch_add_string_index;
+#[cfg(all(
+    feature = "host-logger",
+    not(any(feature = "unstable-test", feature = "host-println"))
+))]
+pub(crate) use self::host::{
+    string as host_string, string_count as host_string_count, table as host_table,
+};
+#[cfg(feature = "unstable-test")]
+pub use self::tokens::{fetch_tokens, Token};
+pub use self::{
+    guarded::GuardSlot,
+    integers::*,
+    traits::{UnwrapError, UnwrapErrorDebug, UnwrapErrorFormat},
+};
 pub use bitflags::bitflags;
 
 pub trait UnsignedInt {}
@@ -15,38 +55,109 @@ impl UnsignedInt for u32 {}
 impl UnsignedInt for u64 {}
 impl UnsignedInt for u128 {}
 
-#[cfg(feature = "unstable-test")]
+#[cfg(any(feature = "unstable-test", feature = "host-println"))]
 thread_local! {
-    static I: core::sync::atomic::AtomicU16 = core::sync::atomic::AtomicU16::new(0);
-    static BYTES: core::cell::RefCell<Vec<u8>> = core::cell::RefCell::new(Vec::new());
+    static I: core::sync::atomic::AtomicU8 = const { core::sync::atomic::AtomicU8::new(0) };
+    static BYTES: core::cell::RefCell<Vec<u8>> = const { core::cell::RefCell::new(Vec::new()) };
+    static STRINGS: core::cell::RefCell<std::collections::BTreeMap<u8, (&'static str, &'static str)>> =
+        const { core::cell::RefCell::new(std::collections::BTreeMap::new()) };
 }
 
 /// For testing purposes
 #[cfg(feature = "unstable-test")]
-pub fn fetch_string_index() -> u16 {
+pub fn fetch_string_index() -> u8 {
     I.with(|i| i.load(core::sync::atomic::Ordering::Relaxed))
 }
 
 /// For testing purposes
-#[cfg(feature = "unstable-test")]
-pub fn fetch_add_string_index() -> u16 {
-    I.with(|i| i.fetch_add(1, core::sync::atomic::Ordering::Relaxed))
+///
+/// Mocks string interning: returns a new index for `string` and records it, together with its
+/// `tag`, so that the data written on the host can be decoded.
+#[cfg(any(feature = "unstable-test", feature = "host-println"))]
+pub fn fetch_add_string_index(string: &'static str, tag: &'static str) -> u8 {
+    // NOTE the index is kept at 7 bits so that its LEB128 encoding is a single byte
+    let index = I.with(|i| {
+        let index = i.load(core::sync::atomic::Ordering::Relaxed);
+        i.store(
+            index.wrapping_add(1) & 0x7f,
+            core::sync::atomic::Ordering::Relaxed,
+        );
+        index
+    });
+    STRINGS.with(|s| s.borrow_mut().insert(index, (string, tag)));
+    index
+}
+
+/// Returns a table of the strings interned so far, to decode the data written on the host.
+#[cfg(all(
+    feature = "std",
+    any(feature = "unstable-test", feature = "host-println")
+))]
+pub(crate) fn mocked_table() -> defmt_decoder::Table {
+    use defmt_decoder::{Encoding, Table};
+
+    let entries = STRINGS.with(|s| {
+        s.borrow()
+            .iter()
+            .map(|(&index, &(string, tag))| (usize::from(index), mocked_entry(string, tag)))
+            .collect()
+    });
+    let mut table = Table::new(entries, Encoding::Raw);
+    table.set_sequence_numbers(cfg!(feature = "sequence-numbers"));
+    table
+}
+
+/// Returns the table entry of a string interned on the host.
+#[cfg(feature = "std")]
+pub(crate) fn mocked_entry(string: &str, tag: &str) -> defmt_decoder::TableEntry {
+    use defmt_decoder::{StringEntry, TableEntry, Tag};
+    use std::string::ToString;
+
+    let tag = match tag {
+        "trace" => Tag::Trace,
+        "debug" => Tag::Debug,
+        "info" => Tag::Info,
+        "warn" => Tag::Warn,
+        "error" => Tag::Error,
+        "println" => Tag::Println,
+        "log" => Tag::Log,
+        "dynamic" => Tag::Dynamic,
+        "test" => Tag::Test,
+        "error_code" => Tag::ErrorCode,
+        "chunk" => Tag::Chunk,
+        "counter" => Tag::Counter,
+        "gauge" => Tag::Gauge,
+        "stack" => Tag::Stack,
+        "time_sync" => Tag::TimeSync,
+        "group_start" => Tag::GroupStart,
+        "group_end" => Tag::GroupEnd,
+        "continuation" => Tag::Continuation,
+        // only the tags of frames matter when decoding
+        _ => Tag::Derived,
+    };
+    let string = StringEntry::new(tag, string.to_string());
+    TableEntry::new(string, "<mocked>".to_string())
 }
 
 /// Get and clear the logged bytes
-#[cfg(feature = "unstable-test")]
+///
+/// With the `sequence-numbers` feature this also restarts the sequence numbers, so the first frame
+/// logged afterwards has sequence number 0.
+#[cfg(any(feature = "unstable-test", feature = "testing"))]
 pub fn fetch_bytes() -> Vec<u8> {
+    #[cfg(feature = "sequence-numbers")]
+    with_sequence_number(|next| next.store(0, core::sync::atomic::Ordering::Relaxed));
     BYTES.with(|b| core::mem::take(&mut *b.borrow_mut()))
 }
 
 /// Only to be used by the defmt macros
 /// Safety: must be paired with a later call to release()
-#[cfg(feature = "unstable-test")]
+#[cfg(any(feature = "unstable-test", feature = "host-println"))]
 pub unsafe fn acquire() {}
 
 /// Only to be used by the defmt macros
 /// Safety: must be paired with a later call to release()
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
 #[inline(always)]
 pub unsafe fn acquire() {
     extern "Rust" {
@@ -58,38 +169,194 @@ pub unsafe fn acquire() {
 /// Only to be used by the defmt macros
 /// Safety: must follow an earlier call to acquire()
 #[cfg(feature = "unstable-test")]
-pub unsafe fn release() {}
+pub unsafe fn release() {
+    #[cfg(feature = "stats")]
+    crate::stats::end_frame();
+}
+
+// Whether the log frames of the current thread are kept for `fetch_bytes` instead of being printed,
+// see `testing::capture_logs`
+#[cfg(all(feature = "testing", not(feature = "unstable-test")))]
+thread_local! {
+    static CAPTURING: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+/// Runs `f`, keeping the bytes of the log frames it emits for `fetch_bytes`.
+#[cfg(any(feature = "testing", all(feature = "unstable-test", feature = "std")))]
+pub(crate) fn capture(f: impl FnOnce()) {
+    // the frames are never printed when testing `defmt` itself
+    #[cfg(not(feature = "unstable-test"))]
+    CAPTURING.with(|capturing| capturing.set(true));
+    f();
+    #[cfg(not(feature = "unstable-test"))]
+    CAPTURING.with(|capturing| capturing.set(false));
+}
 
 /// Only to be used by the defmt macros
 /// Safety: must follow an earlier call to acquire()
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(all(feature = "host-println", not(feature = "unstable-test")))]
+pub unsafe fn release() {
+    #[cfg(feature = "stats")]
+    crate::stats::end_frame();
+    #[cfg(feature = "testing")]
+    if CAPTURING.with(|capturing| capturing.get()) {
+        return;
+    }
+    let bytes = BYTES.with(|b| core::mem::take(&mut *b.borrow_mut()));
+    // the frame is incomplete if a `Format` impl panicked
+    if std::thread::panicking() {
+        return;
+    }
+    match mocked_table().decode(&bytes) {
+        Ok((frame, _)) => std::eprintln!("{}", frame.display(false)),
+        Err(e) => std::eprintln!("(defmt: could not decode log frame: {e})"),
+    }
+}
+
+/// Only to be used by the defmt macros
+/// Safety: must follow an earlier call to acquire()
+#[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
 #[inline(always)]
 pub unsafe fn release() {
+    #[cfg(feature = "stats")]
+    crate::stats::end_frame();
+    #[cfg(feature = "self-check")]
+    let check_due = check::end_frame();
+    release_logger();
+    #[cfg(feature = "self-check")]
+    if check_due {
+        check::report();
+    }
+}
+
+// What the current thread is writing. Only tracked on the host, where execution contexts are
+// threads: on the target, a log statement in an interrupt handler legitimately preempts the log
+// frame of the interrupted context.
+#[cfg(all(any(feature = "std", feature = "unstable-test"), debug_assertions))]
+std::thread_local! {
+    static WRITING: core::cell::Cell<Writing> = const { core::cell::Cell::new(Writing::Nothing) };
+}
+
+#[cfg(all(any(feature = "std", feature = "unstable-test"), debug_assertions))]
+#[derive(Clone, Copy)]
+enum Writing {
+    Nothing,
+    Frame,
+    Group,
+}
+
+/// Releases the global logger when it is dropped, see [`acquire_guard`].
+pub struct ReleaseGuard {
+    /// Length of the frame this one interrupted, see `split::start_frame`
+    #[cfg(all(
+        defmt_max_frame_size,
+        not(any(feature = "unstable-test", feature = "host-println"))
+    ))]
+    outer_frame_len: usize,
+    _private: (),
+}
+
+impl Drop for ReleaseGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        // safety: the guard is only created by `acquire_guard`, which called acquire()
+        unsafe { release() }
+        #[cfg(all(
+            defmt_max_frame_size,
+            not(any(feature = "unstable-test", feature = "host-println"))
+        ))]
+        split::end_frame(self.outer_frame_len);
+        #[cfg(all(any(feature = "std", feature = "unstable-test"), debug_assertions))]
+        WRITING.set(Writing::Nothing);
+    }
+}
+
+/// Only to be used by the defmt macros
+///
+/// Acquires the global logger and returns a guard that releases it, even if a `Format` impl or
+/// the timestamp function panics while the log frame is written. Without it, unwinding would
+/// leave the logger acquired, so every later log statement would fail.
+///
+/// In debug builds on the host, panics if the current thread is already writing a log frame.
+///
+/// Safety: same as acquire(); the guard must be dropped in the same execution context
+#[inline(always)]
+pub unsafe fn acquire_guard() -> ReleaseGuard {
+    // a nested log statement would corrupt the frame or deadlock, depending on the logger
+    #[cfg(all(any(feature = "std", feature = "unstable-test"), debug_assertions))]
+    match WRITING.replace(Writing::Frame) {
+        Writing::Nothing => {}
+        Writing::Frame => panic!(
+            "defmt log statement used while writing another log frame, e.g. in a `Format` impl; \
+             use `defmt::write!` to format values in `Format` impls"
+        ),
+        Writing::Group => panic!(
+            "defmt log statement used while writing a `group!`, e.g. in the arguments of one of \
+             its statements; evaluate them before the group"
+        ),
+    }
+    acquire();
+    ReleaseGuard {
+        #[cfg(all(
+            defmt_max_frame_size,
+            not(any(feature = "unstable-test", feature = "host-println"))
+        ))]
+        outer_frame_len: split::start_frame(),
+        _private: (),
+    }
+}
+
+/// Safety: must follow an earlier call to acquire()
+#[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
+#[inline(always)]
+unsafe fn release_logger() {
     extern "Rust" {
         fn _defmt_release();
     }
     _defmt_release()
 }
 
-#[cfg(feature = "unstable-test")]
+#[cfg(any(feature = "unstable-test", feature = "host-println"))]
 pub fn write(bytes: &[u8]) {
+    #[cfg(feature = "stats")]
+    crate::stats::write(bytes.len());
     BYTES.with(|b| b.borrow_mut().extend(bytes))
 }
 
-#[cfg(not(feature = "unstable-test"))]
-#[inline(always)]
+#[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
+#[cfg_attr(not(feature = "outline-encoders"), inline(always))]
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn write(bytes: &[u8]) {
     extern "Rust" {
         fn _defmt_write(bytes: &[u8]);
     }
-    unsafe { _defmt_write(bytes) }
+    #[cfg(feature = "self-check")]
+    check::write(bytes);
+    #[cfg(feature = "stats")]
+    crate::stats::write(bytes.len());
+    #[cfg(defmt_max_frame_size)]
+    split::write(bytes, |bytes| unsafe { _defmt_write(bytes) });
+    #[cfg(not(defmt_max_frame_size))]
+    unsafe {
+        _defmt_write(bytes)
+    }
 }
 
 /// For testing purposes
-#[cfg(feature = "unstable-test")]
+///
+/// Timestamps are not supported on the host.
+#[cfg(any(
+    feature = "unstable-test",
+    feature = "host-println",
+    feature = "host-logger"
+))]
 pub fn timestamp(_fmt: crate::Formatter<'_>) {}
 
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(not(any(
+    feature = "unstable-test",
+    feature = "host-println",
+    feature = "host-logger"
+)))]
 #[inline(always)]
 pub fn timestamp(fmt: crate::Formatter<'_>) {
     extern "Rust" {
@@ -98,11 +365,133 @@ pub fn timestamp(fmt: crate::Formatter<'_>) {
     unsafe { _defmt_timestamp(fmt) }
 }
 
+/// Timestamp of the previous log frame, for `timestamp!(delta, ..)`.
+struct PreviousTimestamp(core::cell::Cell<u64>);
+
+// safety: only accessed from `_defmt_timestamp`, i.e. while the global logger is acquired
+unsafe impl Sync for PreviousTimestamp {}
+
+static PREVIOUS_TIMESTAMP: PreviousTimestamp = PreviousTimestamp(core::cell::Cell::new(0));
+
+/// Only to be used by `timestamp!(delta, ..)`
+///
+/// Writes the difference to the previous timestamp as LEB128.
+pub fn timestamp_delta(now: u64) {
+    let previous = PREVIOUS_TIMESTAMP.0.replace(now);
+    leb64(&now.wrapping_sub(previous));
+}
+
+/// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
+pub fn leb32(x: &u32) {
+    // values below 128, like most lengths, string indices and zigzag encoded integers, are by far
+    // the most common, so skip the loop for them
+    if *x < 0x80 {
+        return write(&[*x as u8]);
+    }
+
+    let mut buf = [0u8; 5];
+    let mut x = *x;
+    let mut len = 0;
+    // NOTE no indexing, so there are no bounds checks that could panic
+    for byte in &mut buf {
+        *byte = (x & 0x7f) as u8;
+        x >>= 7;
+        len += 1;
+        if x == 0 {
+            break;
+        }
+        *byte |= 0x80;
+    }
+    write(buf.get(..len).unwrap_or(&buf))
+}
+
+/// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
+pub fn leb64(x: &u64) {
+    // see `leb32`
+    if *x < 0x80 {
+        return write(&[*x as u8]);
+    }
+
+    let mut buf = [0u8; 10];
+    let mut x = *x;
+    let mut len = 0;
+    // NOTE no indexing, so there are no bounds checks that could panic
+    for byte in &mut buf {
+        *byte = (x & 0x7f) as u8;
+        x >>= 7;
+        len += 1;
+        if x == 0 {
+            break;
+        }
+        *byte |= 0x80;
+    }
+    write(buf.get(..len).unwrap_or(&buf))
+}
+
+/// For testing purposes
+#[cfg(any(feature = "unstable-test", feature = "host-println"))]
+pub fn acquired() -> bool {
+    true
+}
+
+/// Returns `true` if the data written by the current execution context reaches the logger.
+///
+/// This is `false` between `acquire()` and `release()` if a guarded logger could not be acquired.
+#[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
+#[inline(always)]
+pub fn acquired() -> bool {
+    extern "Rust" {
+        fn _defmt_acquired() -> bool;
+    }
+    unsafe { _defmt_acquired() }
+}
+
 /// Returns the interned string at `address`.
-pub fn make_istr(address: u16) -> Str {
+#[cfg(not(any(
+    feature = "unstable-test",
+    feature = "host-println",
+    feature = "host-logger"
+)))]
+pub const fn make_istr(address: &'static u8) -> Str {
     Str { address }
 }
 
+/// For testing purposes
+///
+/// Returns the interned string `string`; it gets its index when it is written.
+#[cfg(any(
+    feature = "unstable-test",
+    feature = "host-println",
+    feature = "host-logger"
+))]
+pub const fn make_istr(string: &'static str, tag: &'static str) -> Str {
+    Str {
+        string: (string, tag),
+    }
+}
+
+/// Returns the error code whose message is `message`.
+pub const fn make_error_code(message: Str) -> crate::ErrorCode {
+    crate::ErrorCode { message }
+}
+
+/// Emits an event frame that consists of its header only.
+pub fn event(format: &Str) {
+    // safety: released when the guard is dropped
+    let _guard = unsafe { acquire_guard() };
+    header(format);
+}
+
+/// Emits a metric frame with format string `format`, whose data is written by `write_value`.
+pub fn metric(format: &Str, write_value: impl FnOnce()) {
+    // safety: released when the guard is dropped
+    let _guard = unsafe { acquire_guard() };
+    header(format);
+    write_value();
+}
+
 /// Create a Formatter.
 pub fn make_formatter<'a>() -> Formatter<'a> {
     Formatter {
@@ -119,12 +508,20 @@ pub fn into_result<T: traits::IntoResult>(x: T) -> Result<T::Ok, T::Error> {
 }
 
 /// For testing purposes
-#[cfg(feature = "unstable-test")]
+#[cfg(any(
+    feature = "unstable-test",
+    feature = "host-println",
+    feature = "host-logger"
+))]
 pub fn panic() -> ! {
     panic!()
 }
 
-#[cfg(not(feature = "unstable-test"))]
+#[cfg(not(any(
+    feature = "unstable-test",
+    feature = "host-println",
+    feature = "host-logger"
+)))]
 #[inline(always)]
 pub fn panic() -> ! {
     extern "Rust" {
@@ -143,37 +540,65 @@ pub fn fmt<T: Format + ?Sized>(f: &T) {
 pub fn fmt_slice<T: Format>(values: &[T]) {
     usize(&values.len());
     istr(&T::_format_tag());
-    for value in values {
-        value._format_data();
+    T::_format_slice_data(values);
+}
+
+/// Types whose in-memory representation on little-endian targets is their wire format.
+///
+/// # Safety
+///
+/// Implementors must not contain padding bytes and must be encoded as their little-endian bytes.
+pub(crate) unsafe trait LeBytes: Format + Sized {}
+
+/// Writes all `values` with a single `write` call on little-endian targets, instead of one call
+/// per value.
+#[inline]
+pub(crate) fn le_bytes<T: LeBytes>(values: &[T]) {
+    if cfg!(target_endian = "little") {
+        // safety: `T: LeBytes` has no padding bytes, so all `size_of_val(values)` bytes are
+        // initialized
+        write(unsafe {
+            core::slice::from_raw_parts(values.as_ptr().cast(), core::mem::size_of_val(values))
+        })
+    } else {
+        for value in values {
+            value._format_data();
+        }
     }
 }
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn f32(b: &f32) {
     write(&f32::to_bits(*b).to_le_bytes())
 }
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn f64(b: &f64) {
     write(&f64::to_bits(*b).to_le_bytes())
 }
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn char(b: &char) {
     write(&(*b as u32).to_le_bytes())
 }
 
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn str(s: &str) {
     usize(&s.len());
     write(s.as_bytes());
 }
 
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn slice(s: &[u8]) {
     usize(&s.len());
     write(s);
 }
 
 // NOTE: This is passed `&[u8; N]` – it's just coerced to a slice.
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn u8_array(a: &[u8]) {
     write(a);
 }
@@ -181,17 +606,17 @@ pub fn u8_array(a: &[u8]) {
 // NOTE: This is passed `&[u8; N]` – it's just coerced to a slice.
 pub fn fmt_array<T: Format>(a: &[T]) {
     istr(&T::_format_tag());
-    for value in a {
-        value._format_data();
-    }
+    T::_format_slice_data(a);
 }
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn istr(s: &Str) {
-    write(&s.address.to_le_bytes())
+    leb32(&s.as_index())
 }
 
 /// Implementation detail
+#[cfg_attr(feature = "outline-encoders", inline(never))]
 pub fn bool(b: &bool) {
     u8(&(*b as u8));
 }
@@ -208,10 +633,93 @@ pub fn display(val: &dyn core::fmt::Display) {
     write(&[0xff]);
 }
 
+/// Implementation detail
+#[cfg(feature = "ufmt")]
+pub fn udebug<T: ufmt::uDebug + ?Sized>(val: &T) {
+    ufmt::uDebug::fmt(val, &mut ufmt::Formatter::new(&mut FmtWrite)).ok();
+    write(&[0xff]);
+}
+
+/// Implementation detail
+#[cfg(feature = "ufmt")]
+pub fn udisplay<T: ufmt::uDisplay + ?Sized>(val: &T) {
+    ufmt::uDisplay::fmt(val, &mut ufmt::Formatter::new(&mut FmtWrite)).ok();
+    write(&[0xff]);
+}
+
 #[inline(never)]
 pub fn header(s: &Str) {
     istr(s);
-    timestamp(make_formatter());
+    // don't pay for reading the timestamp if the frame is discarded anyway
+    if acquired() {
+        #[cfg(feature = "sequence-numbers")]
+        u8(&next_sequence_number());
+        timestamp(make_formatter());
+    }
+}
+
+pub use crate::level::module_index;
+
+/// Returns `true` if log statements of `level` are logged in the module with index `module`, see
+/// `defmt::set_max_level` and `defmt::set_module_max_level`.
+#[inline(always)]
+pub fn level_enabled(level: crate::Level, module: u8) -> bool {
+    crate::level::enabled(level, module)
+}
+
+/// Only to be used by the `group!` macro, after acquiring the global logger for the group
+pub fn group_start() {
+    use crate as defmt;
+
+    header(&defmt_macros::intern_tagged!("group_start", "group start"));
+    // the arguments of the group's statements are evaluated while the logger is held
+    #[cfg(all(any(feature = "std", feature = "unstable-test"), debug_assertions))]
+    WRITING.set(Writing::Group);
+}
+
+/// Only to be used by the `group!` macro, before releasing the global logger
+pub fn group_end() {
+    use crate as defmt;
+
+    header(&defmt_macros::intern_tagged!("group_end", "group end"));
+}
+
+/// Sequence number of the next log frame, with the `sequence-numbers` feature.
+#[cfg(all(feature = "sequence-numbers", not(feature = "unstable-test")))]
+static SEQUENCE_NUMBER: core::sync::atomic::AtomicU8 = core::sync::atomic::AtomicU8::new(0);
+
+// NOTE thread local when testing, like the mocked string index, so that unit tests running in
+// parallel see their own sequence numbers
+#[cfg(all(feature = "sequence-numbers", feature = "unstable-test"))]
+thread_local! {
+    static SEQUENCE_NUMBER: core::sync::atomic::AtomicU8 = const { core::sync::atomic::AtomicU8::new(0) };
+}
+
+#[cfg(feature = "sequence-numbers")]
+fn with_sequence_number<R>(f: impl FnOnce(&core::sync::atomic::AtomicU8) -> R) -> R {
+    #[cfg(not(feature = "unstable-test"))]
+    return f(&SEQUENCE_NUMBER);
+    #[cfg(feature = "unstable-test")]
+    return SEQUENCE_NUMBER.with(f);
+}
+
+/// Returns the sequence number of the current log frame. Must be called while the global logger is
+/// acquired, so no CAS is needed.
+#[cfg(feature = "sequence-numbers")]
+fn next_sequence_number() -> u8 {
+    use core::sync::atomic::Ordering;
+
+    with_sequence_number(|next| {
+        let sequence_number = next.load(Ordering::Relaxed);
+        next.store(sequence_number.wrapping_add(1), Ordering::Relaxed);
+        sequence_number
+    })
+}
+
+/// Returns the sequence number of the most recent log frame.
+#[cfg(feature = "reliable")]
+pub(crate) fn sequence_number() -> u8 {
+    with_sequence_number(|next| next.load(core::sync::atomic::Ordering::Relaxed)).wrapping_sub(1)
 }
 
 struct FmtWrite;
@@ -222,3 +730,13 @@ impl core::fmt::Write for FmtWrite {
         Ok(())
     }
 }
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uWrite for FmtWrite {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        write(s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+//! Splitting of long log frames, enabled by setting `DEFMT_MAX_FRAME_SIZE` at build time.
+//!
+//! Once a frame reaches `MAX_FRAME_SIZE` bytes, the global logger is released and acquired again,
+//! and the rest of the data is written to *continuation frames*, which start with the index of the
+//! `continuation` string instead of a log message. The decoder appends their data to the frame it
+//! could not decode on its own.
+
+use core::cell::Cell;
+
+include!(concat!(env!("OUT_DIR"), "/max_frame_size.rs"));
+
+/// Number of bytes written to the current frame.
+struct FrameLen(Cell<usize>);
+
+// safety: only accessed while the global logger is acquired, or by the execution context that
+// acquired it
+unsafe impl Sync for FrameLen {}
+
+static FRAME_LEN: FrameLen = FrameLen(Cell::new(0));
+
+/// Starts a new frame. Returns the length of the frame it interrupted, for `end_frame`.
+pub(super) fn start_frame() -> usize {
+    FRAME_LEN.0.replace(0)
+}
+
+/// Resumes the frame that was interrupted when `start_frame` returned `outer`.
+pub(super) fn end_frame(outer: usize) {
+    FRAME_LEN.0.set(outer);
+}
+
+/// Writes `bytes` with `write`, in continuation frames once the current frame is full.
+#[inline(always)]
+pub(super) fn write(mut bytes: &[u8], write: impl Fn(&[u8])) {
+    loop {
+        let len = FRAME_LEN.0.get();
+        let room = MAX_FRAME_SIZE.saturating_sub(len);
+        if bytes.len() <= room {
+            FRAME_LEN.0.set(len + bytes.len());
+            return write(bytes);
+        }
+
+        let (head, tail) = bytes.split_at(room);
+        write(head);
+        bytes = tail;
+        continue_frame(&write);
+    }
+}
+
+/// Ends the current frame and starts a continuation frame.
+#[inline(never)]
+fn continue_frame(write: &impl Fn(&[u8])) {
+    use crate as defmt;
+
+    // a logger that could not be acquired discards the data anyway
+    if !super::acquired() {
+        FRAME_LEN.0.set(0);
+        return;
+    }
+
+    // safety: the global logger is acquired, and acquired again right away
+    unsafe {
+        super::release_logger();
+        super::acquire();
+    }
+    let index = defmt_macros::intern_tagged!("continuation", "(continued)").as_index();
+    // the header is not part of the frame's data, so it bypasses the self-check and the stats
+    let mut buf = [0u8; 5];
+    let header = leb128(index, &mut buf);
+    write(header);
+    FRAME_LEN.0.set(header.len());
+}
+
+/// Encodes `x` as LEB128, like `leb32`, into `buf`.
+fn leb128(mut x: u32, buf: &mut [u8; 5]) -> &[u8] {
+    let mut len = 0;
+    // NOTE no indexing, so there are no bounds checks that could panic
+    for byte in buf.iter_mut() {
+        *byte = (x & 0x7f) as u8;
+        x >>= 7;
+        len += 1;
+        if x == 0 {
+            break;
+        }
+        *byte |= 0x80;
+    }
+    buf.get(..len).unwrap_or(buf)
+}
@@ -0,0 +1,133 @@
+use super::fetch_bytes;
+
+/// For testing purposes
+///
+/// A typed piece of the data written by the encoding functions in this module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    /// Interned string index, LEB128 encoded (`istr`, `header`)
+    Index(u64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    /// Unsigned integer, LEB128 encoded (`leb64`)
+    Leb(u64),
+    /// Signed integer, zigzag and LEB128 encoded (`i8`..`i64`)
+    Signed(i64),
+    /// Length-prefixed string (`str`)
+    Str(String),
+    /// `0xff` terminated string (`debug`, `display`)
+    Fmt(String),
+    /// Raw bytes, e.g. arrays
+    Bytes(Vec<u8>),
+}
+
+/// For testing purposes
+///
+/// Fetches the bytes written so far (like [`fetch_bytes`]) and splits them into tokens of the
+/// same kinds as `expected`, so that tests can compare the two:
+///
+/// ```
+/// # use defmt::export::{fetch_string_index, fetch_tokens, Token};
+/// let index = fetch_string_index();
+/// defmt::write!(defmt::export::make_formatter(), "{=u8} {=str}", 42, "hi");
+///
+/// let expected = [Token::Index(index.into()), Token::U8(42), Token::Str("hi".into())];
+/// assert_eq!(fetch_tokens(&expected), expected);
+/// ```
+///
+/// [`Token::Bytes`] consumes as many bytes as the expected token holds. If the data doesn't match
+/// the expected kinds, or there's more data than expected, the remaining bytes are returned as a
+/// final [`Token::Bytes`].
+pub fn fetch_tokens(expected: &[Token]) -> Vec<Token> {
+    let bytes = fetch_bytes();
+    let mut bytes = &bytes[..];
+    let mut tokens = Vec::new();
+
+    for kind in expected {
+        match read(kind, bytes) {
+            Some((token, rest)) => {
+                tokens.push(token);
+                bytes = rest;
+            }
+            None => break,
+        }
+    }
+
+    if !bytes.is_empty() {
+        tokens.push(Token::Bytes(bytes.to_vec()));
+    }
+    tokens
+}
+
+/// Reads a token of the same kind as `kind`, returns `None` if `bytes` doesn't hold one.
+fn read<'b>(kind: &Token, bytes: &'b [u8]) -> Option<(Token, &'b [u8])> {
+    Some(match kind {
+        Token::Index(_) => {
+            let (x, rest) = leb64(bytes)?;
+            (Token::Index(x), rest)
+        }
+        Token::U8(_) => {
+            let (x, rest) = split::<1>(bytes)?;
+            (Token::U8(u8::from_le_bytes(x)), rest)
+        }
+        Token::U16(_) => {
+            let (x, rest) = split::<2>(bytes)?;
+            (Token::U16(u16::from_le_bytes(x)), rest)
+        }
+        Token::U32(_) => {
+            let (x, rest) = split::<4>(bytes)?;
+            (Token::U32(u32::from_le_bytes(x)), rest)
+        }
+        Token::U64(_) => {
+            let (x, rest) = split::<8>(bytes)?;
+            (Token::U64(u64::from_le_bytes(x)), rest)
+        }
+        Token::Leb(_) => {
+            let (x, rest) = leb64(bytes)?;
+            (Token::Leb(x), rest)
+        }
+        Token::Signed(_) => {
+            let (x, rest) = leb64(bytes)?;
+            (Token::Signed((x >> 1) as i64 ^ -((x & 1) as i64)), rest)
+        }
+        Token::Str(_) => {
+            let (len, rest) = split::<4>(bytes)?;
+            let len = u32::from_le_bytes(len) as usize;
+            if rest.len() < len {
+                return None;
+            }
+            let (s, rest) = rest.split_at(len);
+            (Token::Str(String::from_utf8(s.to_vec()).ok()?), rest)
+        }
+        Token::Fmt(_) => {
+            let end = bytes.iter().position(|&b| b == 0xff)?;
+            let s = String::from_utf8(bytes[..end].to_vec()).ok()?;
+            (Token::Fmt(s), &bytes[end + 1..])
+        }
+        Token::Bytes(expected) => {
+            if bytes.len() < expected.len() {
+                return None;
+            }
+            let (x, rest) = bytes.split_at(expected.len());
+            (Token::Bytes(x.to_vec()), rest)
+        }
+    })
+}
+
+fn split<const N: usize>(bytes: &[u8]) -> Option<([u8; N], &[u8])> {
+    let x = bytes.get(..N)?.try_into().ok()?;
+    Some((x, &bytes[N..]))
+}
+
+fn leb64(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut x = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        x |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((x, &bytes[i + 1..]));
+        }
+    }
+    None
+}
@@ -1,6 +1,8 @@
 #[allow(unused_imports)]
 use crate as defmt;
-use crate::{Format, Formatter, Str};
+use core::fmt;
+
+use crate::{Debug2Format, Format, Formatter, Str};
 
 pub trait Truncate<U> {
     fn truncate(self) -> U;
@@ -80,3 +82,36 @@ impl<T, E> IntoResult for Result<T, E> {
         self
     }
 }
+
+/// The error of a failed `unwrap!`, formatted with its `Format` impl if it has one and with its
+/// `core::fmt::Debug` impl otherwise
+///
+/// This relies on method resolution trying the receiver types in order: `(&&UnwrapError(&e))
+/// .format_unwrap_error()` matches the [`UnwrapErrorFormat`] impl on `&&UnwrapError` if `E:
+/// Format`, and the [`UnwrapErrorDebug`] impl on `&UnwrapError` after a dereference otherwise.
+///
+/// # Call sites
+/// * [`defmt::unwrap!`]
+pub struct UnwrapError<'a, E: ?Sized>(pub &'a E);
+
+pub trait UnwrapErrorFormat<'a, E: ?Sized> {
+    fn format_unwrap_error(self) -> &'a E;
+}
+
+impl<'a, E: Format + ?Sized> UnwrapErrorFormat<'a, E> for &&UnwrapError<'a, E> {
+    #[inline]
+    fn format_unwrap_error(self) -> &'a E {
+        self.0
+    }
+}
+
+pub trait UnwrapErrorDebug<'a, E: fmt::Debug + ?Sized> {
+    fn format_unwrap_error(self) -> Debug2Format<'a, E>;
+}
+
+impl<'a, E: fmt::Debug + ?Sized> UnwrapErrorDebug<'a, E> for &UnwrapError<'a, E> {
+    #[inline]
+    fn format_unwrap_error(self) -> Debug2Format<'a, E> {
+        Debug2Format(self.0)
+    }
+}
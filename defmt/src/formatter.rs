@@ -1,16 +1,167 @@
-use core::marker::PhantomData;
+use core::{fmt, marker::PhantomData};
+
+#[allow(unused_imports)]
+use crate as defmt;
+use crate::export;
 
 /// Handle to a defmt logger.
+///
+/// `Formatter` is zero-sized: the data of the log statement is written straight to the
+/// [`#[global_logger]`](crate::global_logger), whose functions are resolved at link time, so there
+/// is no writer pointer to pass around and no dynamic dispatch per argument. See the `inline`
+/// argument of `#[global_logger]` to also avoid the function call.
 #[derive(Copy, Clone)]
 pub struct Formatter<'a> {
     pub(crate) _phantom: PhantomData<&'a ()>,
 }
 
+impl<'a> Formatter<'a> {
+    /// Returns a [`core::fmt::Write`] implementor that writes into this `Formatter`.
+    ///
+    /// Everything written through the returned [`FmtWriter`] is transmitted as a single runtime
+    /// string, like [`Display2Format`] does. This is meant to ease migrating code that uses
+    /// `core::write!`; prefer `defmt::write!` whenever possible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::fmt::Write as _;
+    /// use defmt::{Format, Formatter};
+    ///
+    /// struct Legacy(u32);
+    ///
+    /// impl Format for Legacy {
+    ///     fn format(&self, fmt: Formatter) {
+    ///         let mut writer = fmt.fmt_writer();
+    ///         core::write!(writer, "Legacy({})", self.0).ok();
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Display2Format`]: crate::Display2Format
+    pub fn fmt_writer(self) -> FmtWriter<'a> {
+        export::istr(&defmt_macros::internp!("{=__internal_Display}"));
+        FmtWriter { _fmt: self }
+    }
+}
+
+/// A [`core::fmt::Write`] adapter for a [`Formatter`], created by [`Formatter::fmt_writer`].
+///
+/// The string is terminated when the `FmtWriter` is dropped.
+pub struct FmtWriter<'a> {
+    _fmt: Formatter<'a>,
+}
+
+impl fmt::Write for FmtWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        export::write(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl Drop for FmtWriter<'_> {
+    fn drop(&mut self) {
+        export::write(&[0xff]);
+    }
+}
+
 /// An interned string created via [`intern!`].
 ///
+/// `Str`s can be created in `const` and `static` initializers, e.g. to build a lookup table of
+/// messages. They can be compared, hashed and ordered, so they can also be used as keys of maps,
+/// and [`as_index`](Self::as_index) returns the string index, e.g. to send it over another channel
+/// than the logs.
+///
 /// [`intern!`]: macro.intern.html
 #[derive(Clone, Copy)]
 pub struct Str {
-    /// 16-bit address
-    pub(crate) address: u16,
+    /// The symbol of the string; its address is the string index
+    #[cfg(not(any(
+        feature = "unstable-test",
+        feature = "host-println",
+        feature = "host-logger"
+    )))]
+    pub(crate) address: &'static u8,
+    /// The string and its tag, which are interned when the `Str` is written
+    #[cfg(any(
+        feature = "unstable-test",
+        feature = "host-println",
+        feature = "host-logger"
+    ))]
+    pub(crate) string: (&'static str, &'static str),
+}
+
+impl Str {
+    /// Returns the string index, which is how the string is sent over the wire.
+    ///
+    /// The host can look up the string with the index in the `defmt` table of the ELF file. On the
+    /// host, i.e. with the `host-println` or `host-logger` features, strings are interned at
+    /// runtime, so this interns the string if it wasn't interned yet; with `host-println`, every
+    /// call returns a new index.
+    pub fn as_index(&self) -> u32 {
+        // NOTE the linker script makes sure that the addresses fit into a `u32`
+        #[cfg(not(any(
+            feature = "unstable-test",
+            feature = "host-println",
+            feature = "host-logger"
+        )))]
+        let index = self.address as *const u8 as usize as u32;
+        #[cfg(any(
+            feature = "unstable-test",
+            feature = "host-println",
+            feature = "host-logger"
+        ))]
+        // `host-logger` interns into a `u32` already, the mocked interning into a `u8`
+        #[allow(clippy::useless_conversion)]
+        let index = u32::from(export::fetch_add_string_index(self.string.0, self.string.1));
+        index
+    }
+
+    /// The key by which `Str`s are compared: the string index, or the string and its tag if the
+    /// strings are interned at runtime, to not intern them just to compare them.
+    #[cfg(not(any(
+        feature = "unstable-test",
+        feature = "host-println",
+        feature = "host-logger"
+    )))]
+    fn key(&self) -> *const u8 {
+        self.address
+    }
+
+    #[cfg(any(
+        feature = "unstable-test",
+        feature = "host-println",
+        feature = "host-logger"
+    ))]
+    fn key(&self) -> (&'static str, &'static str) {
+        self.string
+    }
+}
+
+impl PartialEq for Str {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Str {}
+
+impl PartialOrd for Str {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `Str`s are ordered by their string index on the target. On the host the order is unspecified,
+/// but consistent.
+impl Ord for Str {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl core::hash::Hash for Str {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
 }
@@ -0,0 +1,77 @@
+//! Well-known frames for on-target test harnesses, like `defmt-test`.
+//!
+//! Each function in this module emits one *test frame*. Test frames are displayed like
+//! `println!` messages, but the decoder also exposes them as structured [test events], so host
+//! tooling can tell which tests passed or failed and when the test run is finished, without
+//! parsing log messages.
+//!
+//! Test names are interned strings, see [`intern!`](crate::intern).
+//!
+//! [test events]: https://docs.rs/defmt-decoder/*/defmt_decoder/enum.TestEvent.html
+
+use crate as defmt;
+use crate::{export, Str};
+
+fn emit(kind: &Str, numbers: &[u16], name: Option<Str>) {
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(kind);
+    for number in numbers {
+        export::u16(number);
+    }
+    if let Some(name) = name {
+        export::istr(&name);
+    }
+}
+
+/// Reports that test `name`, the `number`th of `count` tests, is about to run.
+///
+/// Displayed as e.g. ``(1/8) running `assert_true`...``.
+pub fn test_started(number: u16, count: u16, name: Str) {
+    emit(
+        &defmt_macros::intern_tagged!("test", "({=u16}/{=u16}) running `{=istr}`..."),
+        &[number, count],
+        Some(name),
+    )
+}
+
+/// Reports that test `name`, the `number`th of `count` tests, is skipped.
+pub fn test_ignored(number: u16, count: u16, name: Str) {
+    emit(
+        &defmt_macros::intern_tagged!("test", "({=u16}/{=u16}) ignoring `{=istr}`..."),
+        &[number, count],
+        Some(name),
+    )
+}
+
+/// Reports that test `name` passed.
+pub fn test_passed(name: Str) {
+    emit(
+        &defmt_macros::intern_tagged!("test", "`{=istr}` passed"),
+        &[],
+        Some(name),
+    )
+}
+
+/// Reports that test `name` failed.
+///
+/// Call this before panicking, if a failing test aborts the test run.
+pub fn test_failed(name: Str) {
+    emit(
+        &defmt_macros::intern_tagged!("test", "`{=istr}` failed"),
+        &[],
+        Some(name),
+    )
+}
+
+/// Reports that the test run is finished.
+pub fn finished(passed: u16, failed: u16, ignored: u16) {
+    emit(
+        &defmt_macros::intern_tagged!(
+            "test",
+            "test result: {=u16} passed; {=u16} failed; {=u16} ignored"
+        ),
+        &[passed, failed, ignored],
+        None,
+    )
+}
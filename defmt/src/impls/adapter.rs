@@ -73,3 +73,169 @@ impl<T: fmt::Display + ?Sized> Format for Display2Format<'_, T> {
         export::display(&self.0);
     }
 }
+
+/// An "adapter" type to feed `serde::Serialize` values into defmt macros, which expect
+/// `defmt::Format` values.
+///
+/// The value is rendered on-device into a `Debug`-like text representation, e.g.
+/// `Point { x: 1, y: None }`, so this is even less efficient than [`Debug2Format`]. It is meant
+/// for prototyping with types that have no `Format` implementation yet. Requires the `serde`
+/// feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[derive(serde::Serialize)]
+/// # struct Config { baud_rate: u32 }
+/// # let config = Config { baud_rate: 115_200 };
+/// #
+/// defmt::info!("{}", defmt::Serde2Format(&config));
+/// //                                      ˆˆˆˆˆˆ
+/// //                                      must implement `serde::Serialize`
+/// ```
+///
+/// Note that any provided defmt display hints will be ignored.
+#[cfg(feature = "serde")]
+pub struct Serde2Format<'a, T: serde::Serialize + ?Sized>(pub &'a T);
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + ?Sized> Format for Serde2Format<'_, T> {
+    default_format!();
+
+    fn _format_tag() -> Str {
+        defmt_macros::internp!("{=__internal_Debug}")
+    }
+
+    fn _format_data(&self) {
+        export::debug(&super::serde_::SerdeDebug(self.0));
+    }
+}
+
+/// An "adapter" type to feed `ufmt::uDebug` values into defmt macros, which expect
+/// `defmt::Format` values.
+///
+/// Like [`Debug2Format`], this disables compression and formats the value on-device, with the
+/// `ufmt` code. It eases the migration from `ufmt`-based logging. Requires the `ufmt` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[derive(ufmt::derive::uDebug)]
+/// # struct Pair { x: u32, y: u32 }
+/// # let pair = Pair { x: 1, y: 2 };
+/// #
+/// defmt::info!("{:?}", defmt::UDebug2Format(&pair));
+/// //                                         ˆˆˆˆ
+/// //                                         must implement `ufmt::uDebug`
+/// ```
+///
+/// Note that any provided defmt display hints will be ignored.
+#[cfg(feature = "ufmt")]
+pub struct UDebug2Format<'a, T: ufmt::uDebug + ?Sized>(pub &'a T);
+
+#[cfg(feature = "ufmt")]
+impl<T: ufmt::uDebug + ?Sized> Format for UDebug2Format<'_, T> {
+    default_format!();
+
+    fn _format_tag() -> Str {
+        defmt_macros::internp!("{=__internal_Debug}")
+    }
+
+    fn _format_data(&self) {
+        export::udebug(self.0);
+    }
+}
+
+/// An "adapter" type to feed `ufmt::uDisplay` values into defmt macros, which expect
+/// `defmt::Format` values.
+///
+/// Like [`Display2Format`], this disables compression and formats the value on-device, with the
+/// `ufmt` code. It eases the migration from `ufmt`-based logging. Requires the `ufmt` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # struct Celsius(u32);
+/// #
+/// # impl ufmt::uDisplay for Celsius {
+/// #     fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+/// #     where
+/// #         W: ufmt::uWrite + ?Sized,
+/// #     {
+/// #         ufmt::uDisplay::fmt(&self.0, f)?;
+/// #         f.write_str(" C")
+/// #     }
+/// # }
+/// # let temperature = Celsius(21);
+/// #
+/// defmt::info!("{}", defmt::UDisplay2Format(&temperature));
+/// //                                         ˆˆˆˆˆˆˆˆˆˆˆ
+/// //                                         must implement `ufmt::uDisplay`
+/// ```
+///
+/// Note that any provided defmt display hints will be ignored.
+#[cfg(feature = "ufmt")]
+pub struct UDisplay2Format<'a, T: ufmt::uDisplay + ?Sized>(pub &'a T);
+
+#[cfg(feature = "ufmt")]
+impl<T: ufmt::uDisplay + ?Sized> Format for UDisplay2Format<'_, T> {
+    default_format!();
+
+    fn _format_tag() -> Str {
+        defmt_macros::internp!("{=__internal_Display}")
+    }
+
+    fn _format_data(&self) {
+        export::udisplay(self.0);
+    }
+}
+
+/// An "adapter" type that formats the items of an iterator, separated by a string.
+///
+/// Created by [`join`](crate::join).
+#[derive(Clone, Copy)]
+pub struct Join<I> {
+    items: I,
+    separator: &'static str,
+}
+
+/// Formats the items of `items`, separated by `separator`.
+///
+/// `items` is iterated every time the returned value is formatted, so it must be cheap to clone,
+/// like a reference to a collection or an iterator adapter over one. Unlike a slice, which is
+/// shown as `[a, b]`, the items are shown without brackets.
+///
+/// # Examples
+///
+/// ```rust
+/// let neighbors = [3u8, 7, 12];
+/// defmt::info!("neighbors: {}", defmt::join(&neighbors, ", "));
+/// // -> INFO neighbors: 3, 7, 12
+///
+/// let queue = [("rx", 1u8), ("tx", 2)];
+/// defmt::info!("tasks: {}", defmt::join(queue.iter().map(|(name, _)| name), " -> "));
+/// // -> INFO tasks: rx -> tx
+/// ```
+pub fn join<I>(items: I, separator: &'static str) -> Join<I>
+where
+    I: IntoIterator + Clone,
+    I::Item: Format,
+{
+    Join { items, separator }
+}
+
+impl<I> Format for Join<I>
+where
+    I: IntoIterator + Clone,
+    I::Item: Format,
+{
+    fn format(&self, fmt: Formatter) {
+        let mut items = self.items.clone().into_iter();
+        if let Some(first) = items.next() {
+            crate::write!(fmt, "{}", first);
+        }
+        for item in items {
+            crate::write!(fmt, "{=str}{}", self.separator, item);
+        }
+    }
+}
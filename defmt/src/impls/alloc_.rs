@@ -4,14 +4,14 @@ impl<T> Format for alloc::boxed::Box<T>
 where
     T: ?Sized + Format,
 {
-    delegate_format!(T, self, &*self);
+    delegate_format!(T, self, &**self);
 }
 
 impl<T> Format for alloc::rc::Rc<T>
 where
     T: ?Sized + Format,
 {
-    delegate_format!(T, self, &*self);
+    delegate_format!(T, self, &**self);
 }
 
 #[cfg(not(no_cas))]
@@ -19,7 +19,7 @@ impl<T> Format for alloc::sync::Arc<T>
 where
     T: ?Sized + Format,
 {
-    delegate_format!(T, self, &*self);
+    delegate_format!(T, self, &**self);
 }
 
 impl<T> Format for alloc::vec::Vec<T>
@@ -12,6 +12,7 @@ mod cell;
 mod net;
 mod num;
 mod ops;
+mod panic;
 mod ptr;
 mod slice;
 
@@ -26,19 +27,12 @@ where
 
     #[inline]
     fn _format_tag() -> Str {
-        internp!("None|Some({=?})")
+        T::_option_format_tag()
     }
 
     #[inline]
     fn _format_data(&self) {
-        match self {
-            None => export::u8(&0),
-            Some(x) => {
-                export::u8(&1);
-                export::istr(&T::_format_tag());
-                x._format_data()
-            }
-        }
+        T::_format_option_data(self)
     }
 }
 
@@ -97,6 +91,22 @@ impl Format for core::convert::Infallible {
     }
 }
 
+/// Formats the arguments on-device, like [`Display2Format`](crate::Display2Format), so code that
+/// produces `format_args!` values, like a `log::Log` implementation, can forward them to defmt.
+impl Format for core::fmt::Arguments<'_> {
+    default_format!();
+
+    #[inline]
+    fn _format_tag() -> Str {
+        internp!("{=__internal_Display}")
+    }
+
+    #[inline]
+    fn _format_data(&self) {
+        export::display(self)
+    }
+}
+
 impl Format for core::time::Duration {
     fn format(&self, fmt: Formatter) {
         crate::write!(
@@ -3,27 +3,38 @@ use core::num;
 use super::*;
 
 macro_rules! non_zero {
-    ($type:ty, $hint:literal) => {
+    ($type:ty, $hint:literal, $option_hint:literal) => {
         impl Format for $type {
             fn format(&self, fmt: Formatter) {
                 crate::write!(fmt, $hint, self.get());
             }
+
+            // `None` is sent as 0, so `Option<Self>` takes no more space than `Self`
+            #[inline]
+            fn _option_format_tag() -> Str {
+                internp!($option_hint)
+            }
+
+            #[inline]
+            fn _format_option_data(value: &Option<Self>) {
+                value.map_or(0, Self::get)._format_data()
+            }
         }
     };
 }
 
-non_zero! {num::NonZeroI8, "{=i8}"}
-non_zero! {num::NonZeroI16, "{=i16}"}
-non_zero! {num::NonZeroI32, "{=i32}"}
-non_zero! {num::NonZeroI64, "{=i64}"}
-non_zero! {num::NonZeroI128, "{=i128}"}
-non_zero! {num::NonZeroIsize, "{=isize}"}
-non_zero! {num::NonZeroU8, "{=u8}"}
-non_zero! {num::NonZeroU16, "{=u16}"}
-non_zero! {num::NonZeroU32, "{=u32}"}
-non_zero! {num::NonZeroU64, "{=u64}"}
-non_zero! {num::NonZeroU128, "{=u128}"}
-non_zero! {num::NonZeroUsize, "{=usize}"}
+non_zero! {num::NonZeroI8, "{=i8}", "{=i8:__internal_niche_option}"}
+non_zero! {num::NonZeroI16, "{=i16}", "{=i16:__internal_niche_option}"}
+non_zero! {num::NonZeroI32, "{=i32}", "{=i32:__internal_niche_option}"}
+non_zero! {num::NonZeroI64, "{=i64}", "{=i64:__internal_niche_option}"}
+non_zero! {num::NonZeroI128, "{=i128}", "{=i128:__internal_niche_option}"}
+non_zero! {num::NonZeroIsize, "{=isize}", "{=isize:__internal_niche_option}"}
+non_zero! {num::NonZeroU8, "{=u8}", "{=u8:__internal_niche_option}"}
+non_zero! {num::NonZeroU16, "{=u16}", "{=u16:__internal_niche_option}"}
+non_zero! {num::NonZeroU32, "{=u32}", "{=u32:__internal_niche_option}"}
+non_zero! {num::NonZeroU64, "{=u64}", "{=u64:__internal_niche_option}"}
+non_zero! {num::NonZeroU128, "{=u128}", "{=u128:__internal_niche_option}"}
+non_zero! {num::NonZeroUsize, "{=usize}", "{=usize:__internal_niche_option}"}
 
 impl Format for num::TryFromIntError {
     fn format(&self, fmt: Formatter) {
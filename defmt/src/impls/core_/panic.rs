@@ -0,0 +1,27 @@
+use core::panic;
+
+use super::*;
+
+impl Format for panic::Location<'_> {
+    fn format(&self, fmt: Formatter) {
+        crate::write!(
+            fmt,
+            "{=str}:{=u32}:{=u32}",
+            self.file(),
+            self.line(),
+            self.column()
+        )
+    }
+}
+
+// the file name isn't interned because it is only known at runtime; the message is formatted
+// on-device, like `core::fmt::Arguments`
+impl Format for panic::PanicInfo<'_> {
+    fn format(&self, fmt: Formatter) {
+        let message = crate::Display2Format(&self.message());
+        match self.location() {
+            Some(location) => crate::write!(fmt, "panicked at {}:\n{}", location, message),
+            None => crate::write!(fmt, "panicked:\n{}", message),
+        }
+    }
+}
@@ -33,8 +33,10 @@ mod alloc_;
 mod arrays;
 mod core_;
 mod primitives;
+#[cfg(feature = "serde")]
+mod serde_;
 mod tuples;
 
 use defmt_macros::internp;
 
-use crate::{self as defmt, Format, Formatter, Str};
+use crate::{self as defmt, ErrorCode, Format, Formatter, Str};
@@ -3,7 +3,7 @@ use crate::export;
 use super::*;
 
 macro_rules! prim {
-    ($ty:ty, $fmt: literal, $self_:ident, $write:expr) => {
+    ($ty:ty, $fmt: literal, $self_:ident, $write:expr $(, $le_bytes:ident)?) => {
         impl Format for $ty {
             default_format!();
 
@@ -16,6 +16,13 @@ macro_rules! prim {
             fn _format_data(&$self_) {
                 $write
             }
+
+            $(
+                #[inline]
+                fn _format_slice_data(values: &[Self]) {
+                    export::$le_bytes(values)
+                }
+            )?
         }
     };
 }
@@ -26,19 +33,33 @@ prim!(i32, "{=i32}", self, export::i32(self));
 prim!(i64, "{=i64}", self, export::i64(self));
 prim!(i128, "{=i128}", self, export::i128(self));
 prim!(isize, "{=isize}", self, export::isize(self));
-prim!(u8, "{=u8}", self, export::u8(self));
-prim!(u16, "{=u16}", self, export::u16(self));
-prim!(u32, "{=u32}", self, export::u32(self));
-prim!(u64, "{=u64}", self, export::u64(self));
-prim!(u128, "{=u128}", self, export::u128(self));
+prim!(u8, "{=u8}", self, export::u8(self), le_bytes);
+prim!(u16, "{=u16}", self, export::u16(self), le_bytes);
+prim!(u32, "{=u32}", self, export::u32(self), le_bytes);
+prim!(u64, "{=u64}", self, export::u64(self), le_bytes);
+prim!(u128, "{=u128}", self, export::u128(self), le_bytes);
 prim!(usize, "{=usize}", self, export::usize(self));
-prim!(f32, "{=f32}", self, export::f32(self));
-prim!(f64, "{=f64}", self, export::f64(self));
+prim!(f32, "{=f32}", self, export::f32(self), le_bytes);
+prim!(f64, "{=f64}", self, export::f64(self), le_bytes);
+
+// safety: these types are written as their little-endian bytes
+unsafe impl export::LeBytes for u8 {}
+unsafe impl export::LeBytes for u16 {}
+unsafe impl export::LeBytes for u32 {}
+unsafe impl export::LeBytes for u64 {}
+unsafe impl export::LeBytes for u128 {}
+unsafe impl export::LeBytes for f32 {}
+unsafe impl export::LeBytes for f64 {}
 prim!(str, "{=str}", self, export::str(self));
 prim!(bool, "{=bool}", self, export::bool(self));
 prim!(Str, "{=istr}", self, export::istr(self));
 prim!(char, "{=char}", self, export::char(self));
 
+// written like the `Str` of its message, so that it's printed as the message
+impl Format for ErrorCode {
+    delegate_format!(Str, self, &self.message);
+}
+
 impl<T> Format for [T]
 where
     T: Format,
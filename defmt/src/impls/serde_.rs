@@ -0,0 +1,398 @@
+//! Rendering of `Serialize` values for [`Serde2Format`](crate::Serde2Format).
+//!
+//! The value is serialized into its `Debug`-like text representation, using the builders of
+//! `core::fmt::Formatter`, so it needs neither an allocator nor support in the decoder.
+
+use core::fmt;
+
+use serde::ser::{self, Serialize};
+
+/// Implements `Debug` by serializing the wrapped value.
+pub(crate) struct SerdeDebug<'a, T: Serialize + ?Sized>(pub &'a T);
+
+impl<T: Serialize + ?Sized> fmt::Debug for SerdeDebug<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.serialize(Serializer(f)).map_err(|_| fmt::Error)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Error;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("could not serialize the value")
+    }
+}
+
+impl ser::StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        Error
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Self {
+        Error
+    }
+}
+
+struct Serializer<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+/// Serializes compound values with one of the `Debug*` builders of `core::fmt`.
+struct Builder<B>(B);
+
+impl Serializer<'_, '_> {
+    fn debug(self, val: &dyn fmt::Debug) -> Result<(), Error> {
+        Ok(val.fmt(self.0)?)
+    }
+}
+
+impl<'a, 'b> ser::Serializer for Serializer<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Builder<fmt::DebugList<'a, 'b>>;
+    type SerializeTuple = Builder<fmt::DebugTuple<'a, 'b>>;
+    type SerializeTupleStruct = Builder<fmt::DebugTuple<'a, 'b>>;
+    type SerializeTupleVariant = Builder<fmt::DebugTuple<'a, 'b>>;
+    type SerializeMap = Builder<fmt::DebugMap<'a, 'b>>;
+    type SerializeStruct = Builder<fmt::DebugStruct<'a, 'b>>;
+    type SerializeStructVariant = Builder<fmt::DebugStruct<'a, 'b>>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.debug(&v)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(self.0.write_str("None")?)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        self.serialize_newtype_struct("Some", value)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(self.0.write_str("()")?)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Error> {
+        Ok(self.0.write_str(name)?)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        Ok(self.0.write_str(variant)?)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        Ok(self
+            .0
+            .debug_tuple(name)
+            .field(&SerdeDebug(value))
+            .finish()?)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.serialize_newtype_struct(variant, value)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Ok(Builder(self.0.debug_list()))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Ok(Builder(self.0.debug_tuple("")))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Ok(Builder(self.0.debug_tuple(name)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(Builder(self.0.debug_tuple(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(Builder(self.0.debug_map()))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Ok(Builder(self.0.debug_struct(name)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(Builder(self.0.debug_struct(variant)))
+    }
+
+    fn collect_str<T: fmt::Display + ?Sized>(self, value: &T) -> Result<(), Error> {
+        // NOTE can't be escaped without an allocator
+        Ok(write!(self.0, "\"{value}\"")?)
+    }
+}
+
+impl ser::SerializeSeq for Builder<fmt::DebugList<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.entry(&SerdeDebug(value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        Ok(self.0.finish()?)
+    }
+}
+
+impl ser::SerializeTuple for Builder<fmt::DebugTuple<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.field(&SerdeDebug(value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        Ok(self.0.finish()?)
+    }
+}
+
+impl ser::SerializeTupleStruct for Builder<fmt::DebugTuple<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Builder<fmt::DebugTuple<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+impl ser::SerializeMap for Builder<fmt::DebugMap<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.0.key(&SerdeDebug(key));
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.value(&SerdeDebug(value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        Ok(self.0.finish()?)
+    }
+}
+
+impl ser::SerializeStruct for Builder<fmt::DebugStruct<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.0.field(key, &SerdeDebug(value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        Ok(self.0.finish()?)
+    }
+}
+
+impl ser::SerializeStructVariant for Builder<fmt::DebugStruct<'_, '_>> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeStruct::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, format};
+
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Unit;
+
+    #[derive(Serialize)]
+    struct Point {
+        x: i32,
+        y: Option<f32>,
+    }
+
+    #[derive(Serialize)]
+    enum Message {
+        Quit,
+        Move(Point),
+        Write(&'static str, [u8; 2]),
+        Color { rgb: (u8, u8, u8) },
+    }
+
+    fn render<T: Serialize>(value: T) -> std::string::String {
+        format!("{:?}", SerdeDebug(&value))
+    }
+
+    #[test]
+    fn renders_like_debug() {
+        assert_eq!(render(Unit), "Unit");
+        assert_eq!(render(()), "()");
+        assert_eq!(
+            render(Point {
+                x: -1,
+                y: Some(0.5)
+            }),
+            "Point { x: -1, y: Some(0.5) }"
+        );
+        assert_eq!(render(Message::Quit), "Quit");
+        assert_eq!(
+            render(Message::Move(Point { x: 1, y: None })),
+            "Move(Point { x: 1, y: None })"
+        );
+        assert_eq!(
+            render(Message::Write("hi\n", [1, 2])),
+            r#"Write("hi\n", (1, 2))"#
+        );
+        assert_eq!(
+            render(Message::Color { rgb: (1, 2, 3) }),
+            "Color { rgb: (1, 2, 3) }"
+        );
+        assert_eq!(render([Some(1u128)]), "(Some(1),)");
+        assert_eq!(render(&[1, 2][..]), "[1, 2]");
+        assert_eq!(
+            render(BTreeMap::from([("a", 1), ("b", 2)])),
+            r#"{"a": 1, "b": 2}"#
+        );
+        assert_eq!(render(core::net::Ipv4Addr::LOCALHOST), r#""127.0.0.1""#);
+    }
+}
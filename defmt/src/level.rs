@@ -0,0 +1,126 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The log level of a [`log!`] invocation.
+///
+/// The level is transmitted as part of the log frame, so the host can colorize and filter these
+/// frames like the ones emitted by the level-specific logging macros.
+///
+/// [`log!`]: macro.log.html
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum Level {
+    /// Same level as [`trace!`](macro.trace.html).
+    Trace,
+    /// Same level as [`debug!`](macro.debug.html).
+    Debug,
+    /// Same level as [`info!`](macro.info.html).
+    Info,
+    /// Same level as [`warn!`](macro.warn.html).
+    Warn,
+    /// Same level as [`error!`](macro.error.html).
+    Error,
+}
+
+/// The most verbose level that is logged, as `u8`
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Trace as u8);
+
+/// Sets the most verbose level that is logged, e.g. `Level::Info` to skip the `trace!` and `debug!`
+/// statements.
+///
+/// This toggles verbose logging at runtime, e.g. in production builds that are compiled with
+/// `DEFMT_LOG=trace`; it can't enable the log statements that `DEFMT_LOG` disabled at compile time.
+/// Skipped statements don't evaluate their arguments, and cost a single atomic load. [`println!`]
+/// is never skipped.
+///
+/// [`println!`]: macro.println.html
+pub fn set_max_level(level: Level) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Returns the most verbose level that is logged, see [`set_max_level`]. `Level::Trace` unless it
+/// was changed.
+pub fn max_level() -> Level {
+    Level::from_u8(MAX_LEVEL.load(Ordering::Relaxed)).unwrap_or(Level::Trace)
+}
+
+/// Number of entries of the per-module level table, with the `module-filter` feature. Must match
+/// `MODULES` in `defmt-decoder`.
+pub(crate) const MODULES: usize = 64;
+
+/// Entry of the per-module level table of a module that follows [`max_level`]
+#[cfg(feature = "module-filter")]
+const UNSET: u8 = u8::MAX;
+
+/// The most verbose level that is logged per module index, as `u8`, see [`module_index`]
+#[cfg(feature = "module-filter")]
+static MODULE_LEVELS: [AtomicU8; MODULES] = [const { AtomicU8::new(UNSET) }; MODULES];
+
+/// Sets the most verbose level that is logged by the log statements of `module`, e.g.
+/// `"app::radio"`, with the `module-filter` feature. `None` makes the module follow
+/// [`max_level`] again.
+///
+/// Unlike `DEFMT_LOG`, this only affects the log statements of `module` itself, not the ones of
+/// its submodules. Module paths are hashed into a table of 64 entries, so modules whose paths
+/// collide share their level.
+///
+/// ```
+/// # #[cfg(feature = "module-filter")]
+/// defmt::set_module_max_level("app::radio", Some(defmt::Level::Trace));
+/// ```
+#[cfg(feature = "module-filter")]
+pub fn set_module_max_level(module: &str, level: Option<Level>) {
+    set_module_index_max_level(module_index(module), level);
+}
+
+/// Like [`set_module_max_level`], with the index of the module, see `Control` in `defmt-decoder`.
+#[cfg(feature = "module-filter")]
+pub(crate) fn set_module_index_max_level(index: u8, level: Option<Level>) {
+    let level = level.map_or(UNSET, |level| level as u8);
+    if let Some(entry) = MODULE_LEVELS.get(usize::from(index)) {
+        entry.store(level, Ordering::Relaxed);
+    }
+}
+
+/// Returns the index of `module` in the per-module level table: its FNV-1a hash, modulo the size of
+/// the table. Evaluated at compile time by the log statements. Must match `module_index` in
+/// `defmt-decoder`.
+pub const fn module_index(module: &str) -> u8 {
+    let bytes = module.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    (hash % MODULES as u32) as u8
+}
+
+/// Returns `true` if log statements of `level` are logged in the module with index `module`.
+#[inline(always)]
+pub(crate) fn enabled(level: Level, module: u8) -> bool {
+    #[cfg(feature = "module-filter")]
+    if let Some(entry) = MODULE_LEVELS.get(usize::from(module)) {
+        let max_level = entry.load(Ordering::Relaxed);
+        if max_level != UNSET {
+            return level as u8 >= max_level;
+        }
+    }
+    #[cfg(not(feature = "module-filter"))]
+    let _ = module;
+
+    level as u8 >= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+impl Level {
+    pub(crate) fn from_u8(level: u8) -> Option<Self> {
+        Some(match level {
+            0 => Level::Trace,
+            1 => Level::Debug,
+            2 => Level::Info,
+            3 => Level::Warn,
+            4 => Level::Error,
+            _ => return None,
+        })
+    }
+}
@@ -10,7 +10,14 @@
 //! with an incompatible version will result in an error. This means that you have to update both
 //! the host and target side if a breaking change in defmt is released.
 
-#![cfg_attr(not(feature = "unstable-test"), no_std)]
+#![cfg_attr(
+    not(any(
+        feature = "unstable-test",
+        feature = "host-println",
+        feature = "host-logger"
+    )),
+    no_std
+)]
 // NOTE if you change this URL you'll also need to update all other crates in this repo
 #![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
 #![warn(missing_docs)]
@@ -18,6 +25,8 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 // This must be in the root lib.rs, otherwise it doesn't appear in the final binary.
 
@@ -30,37 +39,136 @@ extern crate alloc;
 #[used]
 #[cfg_attr(target_os = "macos", link_section = ".defmt,end.VERSION")]
 #[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
-#[export_name = "_defmt_version_ = 4"]
+#[export_name = "_defmt_version_ = 5"]
 static DEFMT_VERSION: u8 = 0;
 
+/// The wire format version transmitted by [`announce_version`]; must match `_defmt_version_`.
+const WIRE_VERSION: u8 = 5;
+
 #[used]
 #[cfg_attr(target_os = "macos", link_section = ".defmt,end.ENCODING")]
 #[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
-#[cfg_attr(feature = "encoding-raw", export_name = "_defmt_encoding_ = raw")]
 #[cfg_attr(
-    not(feature = "encoding-raw"),
+    all(feature = "encoding-raw", not(defmt_sync)),
+    export_name = "_defmt_encoding_ = raw"
+)]
+#[cfg_attr(
+    all(feature = "encoding-raw", defmt_sync),
+    export_name = "_defmt_encoding_ = raw-sync"
+)]
+#[cfg_attr(feature = "encoding-lzss", export_name = "_defmt_encoding_ = lzss")]
+#[cfg_attr(
+    feature = "encoding-length-prefixed",
+    export_name = "_defmt_encoding_ = length-prefixed"
+)]
+#[cfg_attr(
+    not(any(
+        feature = "encoding-raw",
+        feature = "encoding-lzss",
+        feature = "encoding-length-prefixed"
+    )),
     export_name = "_defmt_encoding_ = rzcobs"
 )]
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub static DEFMT_ENCODING: u8 = 0;
 
-mod encoding;
+/// Tells the host that log frames carry a sequence number.
+#[cfg(feature = "sequence-numbers")]
+#[used]
+#[cfg_attr(target_os = "macos", link_section = ".defmt,end.SEQUENCE")]
+#[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+#[export_name = "_defmt_sequence_numbers_"]
+#[allow(missing_docs)]
+#[doc(hidden)]
+pub static DEFMT_SEQUENCE_NUMBERS: u8 = 0;
+
+/// Tells the host to acknowledge the log frames it receives.
+#[cfg(feature = "reliable")]
+#[used]
+#[cfg_attr(target_os = "macos", link_section = ".defmt,end.RELIABLE")]
+#[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+#[export_name = "_defmt_reliable_"]
+#[allow(missing_docs)]
+#[doc(hidden)]
+pub static DEFMT_RELIABLE: u8 = 0;
+
+/// Tells the host that the log frames are encrypted.
+#[cfg(feature = "encryption")]
+#[used]
+#[cfg_attr(target_os = "macos", link_section = ".defmt,end.ENCRYPTED")]
+#[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+#[export_name = "_defmt_encrypted_"]
+#[allow(missing_docs)]
+#[doc(hidden)]
+pub static DEFMT_ENCRYPTED: u8 = 0;
+
+/// Tells the host that the log frames carry a MAC.
+#[cfg(feature = "authentication")]
+#[used]
+#[cfg_attr(target_os = "macos", link_section = ".defmt,end.AUTHENTICATED")]
+#[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+#[export_name = "_defmt_authenticated_"]
+#[allow(missing_docs)]
+#[doc(hidden)]
+pub static DEFMT_AUTHENTICATED: u8 = 0;
+
+pub mod backpressure;
+#[cfg(feature = "control")]
+pub mod control;
+#[cfg(feature = "std")]
+pub mod decoder;
+pub mod encoding;
+mod error_code;
+pub mod event;
 #[doc(hidden)]
 pub mod export;
 mod formatter;
+pub mod harness;
 mod impls;
+mod level;
+#[cfg(feature = "std")]
+pub mod mock;
+#[cfg(feature = "reliable")]
+pub mod reliable;
+pub mod stack;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(any(feature = "testing", all(feature = "unstable-test", feature = "std")))]
+pub mod testing;
 #[cfg(all(test, feature = "unstable-test"))]
 mod tests;
 mod traits;
 
 pub use crate::{
     encoding::Encoder,
-    formatter::{Formatter, Str},
-    impls::adapter::{Debug2Format, Display2Format},
-    traits::{Format, Logger},
+    error_code::ErrorCode,
+    formatter::{FmtWriter, Formatter, Str},
+    impls::adapter::{join, Debug2Format, Display2Format, Join},
+    level::{max_level, set_max_level, Level},
+    traits::{FnWrite, Format, GuardedLogger, Logger, Write},
 };
 
+#[cfg(feature = "serde")]
+pub use crate::impls::adapter::Serde2Format;
+#[cfg(feature = "ufmt")]
+pub use crate::impls::adapter::{UDebug2Format, UDisplay2Format};
+#[cfg(feature = "module-filter")]
+pub use crate::level::set_module_max_level;
+#[cfg(feature = "stats")]
+pub use crate::stats::{stats, Stats};
+#[cfg(feature = "std")]
+pub use crate::traits::IoWrite;
+
+#[cfg(all(
+    feature = "host-logger",
+    any(feature = "unstable-test", feature = "host-println")
+))]
+compile_error!(
+    "the `host-logger` feature can't be combined with `host-println` or `unstable-test`, which \
+     replace the global logger"
+);
+
 #[cfg(all(test, not(feature = "unstable-test")))]
 compile_error!(
     "to run unit tests enable the `unstable-test` feature, e.g. `cargo t --features unstable-test`"
@@ -93,6 +201,26 @@ pub use defmt_macros::assert_eq_ as assert_eq;
 /// [the manual]: https://defmt.ferrous-systems.com/macros.html
 pub use defmt_macros::assert_ne_ as assert_ne;
 
+/// Like the unstable [`core::assert_matches::assert_matches!`] macro but `defmt` is used to log
+/// the panic message
+///
+/// The panic message includes the value that didn't match the pattern, which must implement
+/// `Format`.
+///
+/// [`core::assert_matches::assert_matches!`]: https://doc.rust-lang.org/core/assert_matches/macro.assert_matches.html
+///
+/// If used, the format string must follow the defmt syntax (documented in [the manual])
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html
+///
+/// # Examples
+///
+/// ```
+/// let reading: Option<u8> = Some(42);
+/// defmt::assert_matches!(reading, Some(value) if value > 0);
+/// ```
+pub use defmt_macros::assert_matches_ as assert_matches;
+
 /// Just like the [`core::debug_assert!`] macro but `defmt` is used to log the panic message
 ///
 /// [`core::debug_assert!`]: https://doc.rust-lang.org/core/macro.debug_assert.html
@@ -120,6 +248,16 @@ pub use defmt_macros::debug_assert_eq_ as debug_assert_eq;
 /// [the manual]: https://defmt.ferrous-systems.com/macros.html
 pub use defmt_macros::debug_assert_ne_ as debug_assert_ne;
 
+/// Like the unstable [`core::assert_matches::debug_assert_matches!`] macro but `defmt` is used to
+/// log the panic message
+///
+/// [`core::assert_matches::debug_assert_matches!`]: https://doc.rust-lang.org/core/assert_matches/macro.debug_assert_matches.html
+///
+/// If used, the format string must follow the defmt syntax (documented in [the manual])
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html
+pub use defmt_macros::debug_assert_matches_ as debug_assert_matches;
+
 /// Just like the [`core::unreachable!`] macro but `defmt` is used to log the panic message
 ///
 /// [`core::unreachable!`]: https://doc.rust-lang.org/core/macro.unreachable.html
@@ -159,9 +297,10 @@ pub use defmt_macros::panic_ as panic;
 /// Unwraps an `Option` or `Result`, panicking if it is `None` or `Err`.
 ///
 /// This macro is roughly equivalent to `{Option,Result}::{expect,unwrap}` but invocation looks
-/// a bit different because this is a macro and not a method. The other difference is that
-/// `unwrap!`-ing a `Result<T, E>` value requires that the error type `E` implements the `Format`
-/// trait
+/// a bit different because this is a macro and not a method. The other difference is that the
+/// error of a `Result<T, E>` value is logged with its `Format` implementation; if the error type
+/// `E` only implements `core::fmt::Debug`, like the errors of many foreign crates, it is logged
+/// through [`Debug2Format`] instead, which costs more flash and bandwidth than `Format`
 ///
 /// The following snippet shows the differences between core's unwrap method and defmt's unwrap
 /// macro:
@@ -216,9 +355,104 @@ pub use defmt_macros::panic_handler;
 /// let interned = defmt::intern!("long string literal taking up little space");
 /// ```
 ///
+/// The result is a constant, so `intern!` can also be used in `const` and `static` initializers:
+///
+/// ```
+/// use defmt::Str;
+///
+/// const MESSAGES: [Str; 2] = [defmt::intern!("ready"), defmt::intern!("not ready")];
+/// ```
+///
+/// Instead of a literal, the argument can be an invocation of `concat!`, `env!` or `stringify!`,
+/// which are evaluated when `intern!` is expanded, e.g. to intern build metadata:
+///
+/// ```
+/// let version = defmt::intern!(concat!("firmware v", env!("CARGO_PKG_VERSION")));
+/// ```
+///
 /// [`Str`]: struct.Str.html
 pub use defmt_macros::intern;
 
+/// Creates an [`ErrorCode`] from a string literal.
+///
+/// The message is interned like with [`intern!`], and its string index is the code. Host tooling
+/// can list all the codes of a firmware and their messages with `defmt-print -e <elf> error-codes`.
+///
+/// # Example
+///
+/// ```
+/// use defmt::ErrorCode;
+///
+/// const TIMEOUT: ErrorCode = defmt::error_code!("no response from the sensor");
+///
+/// fn read_sensor() -> Result<u16, ErrorCode> {
+///     Err(TIMEOUT)
+/// }
+///
+/// if let Err(e) = read_sensor() {
+///     // e.g. reported to the host in a protocol message
+///     let code: u32 = e.code();
+/// #   let _ = code;
+///     defmt::error!("sensor failed: {}", e);
+/// }
+/// ```
+///
+/// [`intern!`]: macro.intern.html
+pub use defmt_macros::error_code;
+
+/// Increments a counter metric.
+///
+/// `counter!("name")` increments the counter `name` by 1 and `counter!("name", delta)` by the
+/// `u32` `delta`. Each invocation emits a metric frame, irrespective of log level; the name of the
+/// metric is interned and an increment by 1 carries no data besides the timestamp. Host tooling
+/// gets the samples from `Frame::metric` to compute rates and plot them separately from the
+/// logs; printers display them like `println!` messages, e.g. `rx_packets += 1`.
+///
+/// Names may only contain ASCII letters, digits and `_.:/-`. Invocations with the same name count
+/// towards the same metric.
+///
+/// # Examples
+///
+/// ```
+/// # let burst = 3;
+/// defmt::counter!("rx_packets");
+/// defmt::counter!("rx_bytes", burst * 64);
+/// ```
+pub use defmt_macros::counter;
+
+/// Records a named timeline event, e.g. for profiling.
+///
+/// `event!(name)` emits an event frame that consists of the interned name and the timestamp only,
+/// which takes 1 to 3 bytes plus the timestamp, so it is cheap enough for interrupt handlers. Host
+/// timeline tools get it from `Frame::event` as a named marker and can measure the jitter and
+/// latency between markers; printers display it like a `println!` message, e.g. `event=adc_done`.
+///
+/// See the [`event`](mod@event) module for task switches, interrupts and numeric markers.
+///
+/// # Examples
+///
+/// ```
+/// defmt::event!(adc_start);
+/// // ..
+/// defmt::event!(adc_done);
+/// ```
+pub use defmt_macros::event;
+
+/// Sets a gauge metric.
+///
+/// `gauge!("name", value)` reports the current `i32` `value` of the gauge `name`, like
+/// [`counter!`] reports increments. Use fixed-point values, e.g. millidegrees, for fractional
+/// quantities; values close to zero take fewer bytes.
+///
+/// # Examples
+///
+/// ```
+/// # let millidegrees = 21_500;
+/// defmt::gauge!("temperature_mc", millidegrees);
+/// // -> temperature_mc = 21500
+/// ```
+pub use defmt_macros::gauge;
+
 /// Always logs data irrespective of log level.
 ///
 /// Please refer to [the manual] for documentation on the syntax.
@@ -238,12 +472,48 @@ pub use defmt_macros::debug;
 ///
 /// [the manual]: https://defmt.ferrous-systems.com/macros.html
 pub use defmt_macros::error;
+/// Logs several statements as one atomic group, whose frames are never interleaved with the output
+/// of other execution contexts.
+///
+/// The group holds the global logger while all of its statements are written, so keep it short: on
+/// most loggers, this disables interrupts. Only the logging macros and [`println!`] can be used in a
+/// group; each statement is still filtered by its level. The host marks the frames of a group with
+/// the same group ID, see `Frame::group` in `defmt-decoder`. The whole group counts as a single
+/// frame, e.g. for the `self-check` and `stats` features.
+///
+/// Unlike those of a standalone statement, the arguments of the statements are evaluated while
+/// the group holds the global logger. They must not log themselves, e.g. by calling a function
+/// that uses the logging macros: depending on the logger, that panics or deadlocks. Evaluate such
+/// arguments into variables before the group. In debug builds on the host, this panics with a
+/// message pointing at the group.
+///
+/// ```
+/// # let (voltage, current) = (3300u16, 120u16);
+/// defmt::group! {
+///     defmt::error!("brownout");
+///     defmt::error!("voltage: {=u16} mV", voltage);
+///     defmt::error!("current: {=u16} mA", current);
+/// }
+/// ```
+///
+/// [`println!`]: macro.println.html
+pub use defmt_macros::group;
 /// Logs data at *info* level.
 ///
 /// Please refer to [the manual] for documentation on the syntax.
 ///
 /// [the manual]: https://defmt.ferrous-systems.com/macros.html
 pub use defmt_macros::info;
+/// Logs data at a [`Level`] that is only known at runtime.
+///
+/// The first argument is an expression of type [`Level`], the remaining arguments work like the
+/// ones of the other logging macros. The level is transmitted as part of the log frame.
+///
+/// Please refer to [the manual] for documentation on the syntax.
+///
+/// [`Level`]: enum.Level.html
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html
+pub use defmt_macros::log;
 /// Logs data at *trace* level.
 ///
 /// Please refer to [the manual] for documentation on the syntax.
@@ -257,11 +527,60 @@ pub use defmt_macros::trace;
 /// [the manual]: https://defmt.ferrous-systems.com/macros.html
 pub use defmt_macros::warn;
 
+/// Logs an error message at *error* level and returns the error from the enclosing function.
+///
+/// `bail!(error, "message", args..)` is short for logging `"message"` with `error!` and then
+/// returning `Err(error.into())`, i.e. the error is converted with `From` like `?` does. Without a
+/// message, `bail!(error)` logs the error itself, which then must implement `Format`.
+///
+/// Please refer to [the manual] for documentation on the format string syntax.
+///
+/// [the manual]: https://defmt.ferrous-systems.com/macros.html
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(defmt::Format)]
+/// # enum Error { Timeout }
+/// # fn transfer(_: u8) -> bool { false }
+/// fn read(addr: u8) -> Result<u8, Error> {
+///     if !transfer(addr) {
+///         defmt::bail!(Error::Timeout, "i2c transfer timed out addr={=u8:#x}", addr);
+///     }
+///     Ok(0)
+/// }
+/// ```
+pub use defmt_macros::bail;
+
 /// Just like the [`std::dbg!`] macro but `defmt` is used to log the message at `TRACE` level.
 ///
 /// [`std::dbg!`]: https://doc.rust-lang.org/std/macro.dbg.html
 pub use defmt_macros::dbg;
 
+/// Logs a byte slice as a hexdump.
+///
+/// The host renders the bytes as one line per 16 bytes, each annotated with its offset and
+/// followed by an ASCII gutter, like `hexdump -C`.
+///
+/// `hexdump!(bytes)` logs irrespective of log level, like
+/// `defmt::println!("{=[u8]:hexdump}", bytes)`. `hexdump!(level, label, bytes)` logs at `level`
+/// with a string literal `label`, like `defmt::info!("label:{=[u8]:hexdump}", bytes)`. `level` is
+/// either one of `trace`, `debug`, `info`, `warn` and `error`, which is filtered at compile time
+/// like the logging macros, or a runtime [`Level`] value like the one of [`log!`].
+///
+/// # Example
+///
+/// ```
+/// let buf = [0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x00];
+/// defmt::hexdump!(&buf);
+/// // -> 00000000  48 65 6c 6c 6f 00                                 |Hello.|
+///
+/// defmt::hexdump!(info, "rx", &buf);
+/// // -> INFO rx:
+/// // 00000000  48 65 6c 6c 6f 00                                 |Hello.|
+/// ```
+pub use defmt_macros::hexdump;
+
 /// Writes formatted data to a [`Formatter`].
 ///
 /// [`Formatter`]: struct.Formatter.html
@@ -301,6 +620,29 @@ pub use defmt_macros::write;
 /// ```
 ///
 /// [`Logger`]: trait.Logger.html
+///
+/// It can also be put on a type alias of a logger defined in another crate, e.g. the
+/// `defmt::mock::MockLogger` (`std` feature) for unit tests on the host:
+///
+/// ``` ignore
+/// #[defmt::global_logger]
+/// type Logger = defmt::mock::MockLogger;
+/// ```
+///
+/// # Guarded loggers
+///
+/// `#[global_logger(guarded)]` uses a [`GuardedLogger`] implementation instead of a [`Logger`]
+/// one. See the [`GuardedLogger`] documentation for an example, and for the execution contexts it
+/// supports without the `std` feature.
+///
+/// [`GuardedLogger`]: trait.GuardedLogger.html
+///
+/// # Inlined writes
+///
+/// By default, the logger's `write` is called through a shim function that is never inlined, to
+/// keep the code size down. `#[global_logger(inline)]`, which can be combined with `guarded`,
+/// allows LTO to inline the logger's `write` into every place that writes log frame data instead,
+/// which speeds up logging at the cost of code size. This has no effect without LTO.
 pub use defmt_macros::global_logger;
 
 /// Defines the global timestamp provider for defmt.
@@ -311,7 +653,13 @@ pub use defmt_macros::global_logger;
 ///
 /// `timestamp!` must only be used once across the crate graph.
 ///
-/// If no crate defines a timestamp, no timestamp will be included in the logged messages.
+/// The timestamp expression is only evaluated once the logger has been acquired, and not at all
+/// for log frames that are discarded, e.g. when a guarded logger cannot be acquired. Expensive
+/// timer reads therefore only happen for frames that actually reach the transport.
+///
+/// If no crate defines a timestamp, no timestamp will be included in the logged messages. The
+/// `no-timestamp` Cargo feature also turns all `timestamp!` invocations into no-ops, which removes
+/// the timestamp from the log frames of applications that depend on a crate defining one.
 ///
 /// # Examples
 ///
@@ -321,6 +669,21 @@ pub use defmt_macros::global_logger;
 /// static COUNT: AtomicU32 = AtomicU32::new(0);
 /// defmt::timestamp!("{=u32:us}", COUNT.fetch_add(1, Ordering::Relaxed));
 /// ```
+///
+/// # Delta timestamps
+///
+/// Prefixing the format string with `delta,` transmits the timestamp as the LEB128-encoded
+/// difference to the timestamp of the previous log frame, which usually takes just 1 or 2 bytes
+/// instead of 8. The host reconstructs the absolute timestamp by adding up the deltas. The format
+/// string must contain exactly one `{=u64}` parameter.
+///
+/// ```
+/// # fn monotonic_us() -> u64 { 0 }
+/// defmt::timestamp!(delta, "{=u64:us}", monotonic_us());
+/// ```
+///
+/// The host can only reconstruct the correct time if it receives every frame, so delta timestamps
+/// should not be used with transports that drop data.
 pub use defmt_macros::timestamp;
 
 /// Generates a bitflags structure that can be formatted with defmt.
@@ -363,11 +726,214 @@ pub use defmt_macros::Format;
 #[export_name = "__defmt_default_timestamp"]
 fn default_timestamp(_f: Formatter<'_>) {}
 
+// Loggers that always get hold of the transport in `acquire` don't have to define `_defmt_acquired`.
+#[export_name = "__defmt_default_acquired"]
+fn default_acquired() -> bool {
+    true
+}
+
 #[export_name = "__defmt_default_panic"]
 fn default_panic() -> ! {
     core::panic!()
 }
 
+/// Emits a heartbeat frame.
+///
+/// A heartbeat frame carries the timestamp and nothing else. Call this function periodically, for
+/// example from a timer interrupt or the idle loop, so that host tooling can tell an idle firmware
+/// (heartbeats keep arriving) apart from a dead link (nothing arrives at all).
+///
+/// Printers do not display heartbeat frames as log messages.
+pub fn heartbeat() {
+    use crate as defmt;
+
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(&defmt_macros::intern_tagged!("heartbeat", "heartbeat"));
+}
+
+/// Emits a frame carrying the wire format version.
+///
+/// The version is also embedded in the ELF file, but the host may decode the stream with a stale
+/// or mismatched ELF file. Call this function once at boot so that the decoder refuses to decode
+/// the stream when the version in the stream differs from the one in the ELF file, instead of
+/// producing garbage output.
+///
+/// Printers do not display version frames as log messages.
+pub fn announce_version() {
+    use crate as defmt;
+
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(&defmt_macros::intern_tagged!(
+        "version",
+        "wire format version {=u8}"
+    ));
+    export::u8(&WIRE_VERSION);
+}
+
+/// Reports the wall-clock time, in milliseconds since the Unix epoch.
+///
+/// Call this once the wall-clock time is known, e.g. at boot from a battery-backed RTC or after
+/// synchronizing with a server. The frame emitted by this function carries the regular monotonic
+/// timestamp as well, so the host can derive the wall-clock time of all following frames from
+/// their monotonic timestamps and correlate the logs with server-side events. For this to work,
+/// the `timestamp!` must be a single integer in microseconds, milliseconds or ticks, e.g.
+/// `{=u64:us}`.
+///
+/// Printers display the frame as the reported time, in ISO 8601 format.
+pub fn wall_clock(unix_ms: u64) {
+    use crate as defmt;
+
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(&defmt_macros::intern_tagged!(
+        "wall_clock",
+        "wall clock: {=u64:iso8601ms}"
+    ));
+    export::u64(&unix_ms);
+}
+
+/// Reports the current time of another clock domain, in microseconds.
+///
+/// When several cores or channels log with their own time sources, each of them has to be
+/// related to a common time base on the host. Call this function periodically with the current
+/// time of clock domain `domain` (e.g. the other core's timer, read through shared memory); the
+/// frame emitted by this function also carries the regular timestamp, so the host can derive the
+/// offset between the two clocks and convert timestamps from one domain to the other. Calling it
+/// periodically compensates for drift between the clocks.
+///
+/// The statements select the clock domain of their timestamp with a `{=u16:domain}` parameter in
+/// `timestamp!` (any unsigned integer type works), e.g. the ID of the core they run on; timestamps
+/// without it are in domain `0`.
+/// Domain IDs are chosen by the application. Like [`wall_clock`], this requires the rest of the
+/// `timestamp!` to be a single integer in microseconds, milliseconds or ticks.
+pub fn clock_sync(domain: u16, micros: u64) {
+    use crate as defmt;
+
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(&defmt_macros::intern_tagged!(
+        "clock_sync",
+        "clock sync: domain {=u16} at {=u64:us}"
+    ));
+    export::u16(&domain);
+    export::u64(&micros);
+}
+
+/// Emits a time sync frame, which carries nothing but the regular timestamp.
+///
+/// The host pairs every time sync frame with the time it received the frame at, and derives its
+/// own wall-clock time for the timestamps of all frames, correcting for the drift between the
+/// target's clock and the host's. Unlike [`wall_clock`], this doesn't need the wall-clock time on
+/// the target. Call it periodically, e.g. once per second from a timer: the more time sync frames
+/// the host has received, the better the latency of the transport averages out. Like
+/// [`wall_clock`], this requires the `timestamp!` to be a single integer in microseconds,
+/// milliseconds or ticks.
+///
+/// Printers do not display time sync frames as log messages.
+pub fn time_sync() {
+    use crate as defmt;
+
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(&defmt_macros::intern_tagged!("time_sync", "time sync"));
+}
+
+/// Logs a large buffer in chunks of at most `chunk_size` bytes, one frame per chunk.
+///
+/// Unlike logging the whole buffer with `{=[u8]}`, this releases the logger between chunks, so a
+/// multi-kilobyte capture doesn't hold up other log statements, and no frame gets larger than the
+/// transport can handle. Every chunk frame carries `label`, a transfer ID, its offset in `data` and
+/// the length of `data`; the host reassembles the chunks of a transfer and exposes the whole buffer
+/// once its last chunk has been received. Returns the transfer ID.
+///
+/// Printers display each chunk as a message, e.g. `capture #3 @64/4096: [..]`.
+///
+/// ```
+/// let capture = [0u8; 1024];
+/// defmt::chunked(defmt::intern!("capture"), &capture, 64);
+/// ```
+pub fn chunked(label: Str, data: &[u8], chunk_size: usize) -> u16 {
+    use core::sync::atomic::{AtomicU16, Ordering};
+
+    use crate as defmt;
+
+    static NEXT_ID: AtomicU16 = AtomicU16::new(0);
+
+    let chunk_size = chunk_size.max(1);
+    let total = data.len() as u32;
+    let mut id = None;
+    let mut offset = 0;
+    loop {
+        let chunk = &data[offset..data.len().min(offset + chunk_size)];
+        {
+            // safety: released when the guard is dropped
+            let _guard = unsafe { export::acquire_guard() };
+            // NOTE no compare-and-swap needed, the logger is acquired
+            let id = *id.get_or_insert_with(|| {
+                let id = NEXT_ID.load(Ordering::Relaxed);
+                NEXT_ID.store(id.wrapping_add(1), Ordering::Relaxed);
+                id
+            });
+            export::header(&defmt_macros::intern_tagged!(
+                "chunk",
+                "{=istr} #{=u16} @{=u32}/{=u32}: {=[u8]:02x}"
+            ));
+            export::istr(&label);
+            export::u16(&id);
+            export::u32(&(offset as u32));
+            export::u32(&total);
+            export::slice(chunk);
+        }
+
+        offset += chunk.len();
+        if offset >= data.len() {
+            // an empty buffer is sent as a single empty chunk
+            return id.unwrap_or_default();
+        }
+    }
+}
+
+/// Logs a message whose format string is only known at runtime.
+///
+/// This is the expensive path and only meant for code that builds messages at runtime, like a
+/// scripting layer. Unlike the logging macros, it transmits the whole format string in every log
+/// frame instead of a string index, and the message can't be filtered by `DEFMT_LOG`. Use the
+/// logging macros (or [`log!`] if only the level is dynamic) whenever the format string is known
+/// at compile time.
+///
+/// The format string uses the same syntax as the logging macros. `args` are the arguments of
+/// `format`, already encoded the way the logging macros encode them; they are sent as-is. For
+/// example, `{=u8}` takes 1 byte, `{=u32}` takes 4 bytes in little-endian order, and `{=str}` takes
+/// the length as a little-endian `u32` followed by the UTF-8 bytes.
+///
+/// If `args` does not match `format`, the host can't decode this frame, and with encodings that
+/// don't support recovery it can't decode any of the following frames either.
+///
+/// Like the logging macros, this logs nothing if `level` is more verbose than [`max_level`]. The
+/// per-module levels of the `module-filter` feature don't apply, because the module of the caller
+/// isn't known.
+///
+/// ```
+/// # let value = 42u8;
+/// defmt::log_dynamic(defmt::Level::Info, "value from script: {=u8}", &[value]);
+/// ```
+pub fn log_dynamic(level: Level, format: &str, args: &[u8]) {
+    use crate as defmt;
+
+    if level < max_level() {
+        return;
+    }
+
+    // safety: released when the guard is dropped
+    let _guard = unsafe { export::acquire_guard() };
+    export::header(&defmt_macros::intern_tagged!("dynamic", "{=str}"));
+    export::u8(&(level as u8));
+    export::str(format);
+    export::write(args);
+}
+
 /// Block until host has read all pending data.
 ///
 /// The flush operation will not fail, but might not succeed in flushing _all_ pending data. It is
@@ -377,12 +943,12 @@ fn default_panic() -> ! {
 /// [`defmt-rtt`](https://crates.io/crates/defmt-rtt) or [`defmt-itm`](https://crates.io/crates/defmt-itm).
 pub fn flush() {
     match () {
-        #[cfg(feature = "unstable-test")]
+        #[cfg(any(feature = "unstable-test", feature = "host-println"))]
         () => {
             // no-op when run on host
         }
 
-        #[cfg(not(feature = "unstable-test"))]
+        #[cfg(not(any(feature = "unstable-test", feature = "host-println")))]
         () => {
             extern "Rust" {
                 fn _defmt_acquire();
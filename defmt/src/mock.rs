@@ -0,0 +1,114 @@
+//! A global logger for unit tests on the host, enabled by the `std` feature.
+//!
+//! [`MockLogger`] records the data of every log frame instead of sending it anywhere, so tests can
+//! inspect what was logged:
+//!
+//! ``` no_run
+//! #[defmt::global_logger]
+//! type Logger = defmt::mock::MockLogger;
+//!
+//! // the linker script isn't used on the host, so there's no default timestamp
+//! defmt::timestamp!("");
+//!
+//! // in a `#[test]` function
+//! defmt::error!("oh no");
+//! assert_eq!(defmt::mock::MockLogger::take_frames().len(), 1);
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    thread_local,
+    vec::Vec,
+};
+
+use crate::Logger;
+
+thread_local! {
+    static ACQUIRED: Cell<bool> = const { Cell::new(false) };
+    static FRAMES: RefCell<Vec<MockFrame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A global logger that records every log frame in a thread-local buffer.
+///
+/// Each thread is its own execution context, so tests running in parallel don't see each other's
+/// frames. The data is recorded *unencoded*, i.e. as the logging macros write it.
+pub struct MockLogger;
+
+impl MockLogger {
+    /// Returns the frames logged by the current thread since the last call, oldest first.
+    pub fn take_frames() -> Vec<MockFrame> {
+        FRAMES.with(|frames| core::mem::take(&mut *frames.borrow_mut()))
+    }
+}
+
+unsafe impl Logger for MockLogger {
+    fn acquire() {
+        if ACQUIRED.with(|acquired| acquired.replace(true)) {
+            panic!("`MockLogger` is already acquired on this thread");
+        }
+        FRAMES.with(|frames| frames.borrow_mut().push(MockFrame::default()));
+    }
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {
+        ACQUIRED.with(|acquired| acquired.set(false));
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        FRAMES.with(|frames| {
+            if let Some(frame) = frames.borrow_mut().last_mut() {
+                frame.writes.push(bytes.to_vec());
+            }
+        });
+    }
+}
+
+/// A log frame recorded by [`MockLogger`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MockFrame {
+    /// The data of each `write` call, in order.
+    pub writes: Vec<Vec<u8>>,
+}
+
+impl MockFrame {
+    /// Returns the data of the whole frame.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.writes.concat()
+    }
+}
+
+#[cfg(feature = "unstable-test")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(writes: &[&[u8]]) {
+        MockLogger::acquire();
+        for bytes in writes {
+            unsafe { MockLogger::write(bytes) }
+        }
+        unsafe { MockLogger::release() }
+    }
+
+    #[test]
+    fn records_frames() {
+        log(&[&[1], &[2, 3]]);
+        log(&[]);
+
+        let frames = MockLogger::take_frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].writes, [vec![1], vec![2, 3]]);
+        assert_eq!(frames[0].bytes(), [1, 2, 3]);
+        assert!(frames[1].writes.is_empty());
+
+        assert!(MockLogger::take_frames().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn double_acquire() {
+        MockLogger::acquire();
+        MockLogger::acquire();
+    }
+}
@@ -0,0 +1,199 @@
+//! Retransmission of log frames over bidirectional links, enabled by the `reliable` feature.
+//!
+//! Over a lossy link, like a radio, the log frames that matter most, e.g. the last error before a
+//! panic, are just as likely to be lost as any other. With the `reliable` feature, every log frame
+//! carries a sequence number (see the `sequence-numbers` feature), and the host acknowledges every
+//! frame it received by sending an ack message back to the target. A `Logger` implementation on a
+//! bidirectional link keeps the encoded frames that weren't acknowledged yet in a [`Window`], and
+//! sends them again until they are.
+//!
+//! ``` ignore
+//! static WINDOW: Mutex<RefCell<Window<8, 64>>> = Mutex::new(RefCell::new(Window::new()));
+//!
+//! // in the `Logger`, for every frame
+//! window.start_frame();
+//! // .. for every byte the encoder produces for the frame, including the bytes written by
+//! // `Encoder::end_frame`
+//! window.write(bytes);
+//! // .. once the frame is complete
+//! window.end_frame();
+//!
+//! // when data is received from the host, e.g. in the UART interrupt handler
+//! window.received(&data);
+//!
+//! // periodically, e.g. from a timer, and in a loop in `Logger::flush` until `pending()` is 0
+//! window.retransmit(|bytes| uart.write(bytes));
+//! ```
+//!
+//! The window holds `N` frames of up to `SIZE` encoded bytes. Frames that are longer are sent once
+//! but not retransmitted, and if all `N` frames are waiting for their acks, the oldest one is given
+//! up on. Printers discard the frames they received more than once.
+
+use crate::export;
+
+/// First byte of the ack messages the host sends, followed by the sequence number of the
+/// acknowledged frame and its complement. Must match `ack` in `defmt-decoder`.
+const ACK: u8 = 0xac;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Free,
+    /// The frame is being written
+    Writing,
+    /// The frame is being written, but doesn't fit into the slot
+    Overflowed,
+    /// The frame was written, but the host hasn't acknowledged it yet
+    Unacked,
+}
+
+#[derive(Clone, Copy)]
+struct Slot<const SIZE: usize> {
+    data: [u8; SIZE],
+    len: usize,
+    state: State,
+    sequence_number: u8,
+    /// Number of the frame, to give up on the oldest frame first
+    age: u32,
+}
+
+impl<const SIZE: usize> Slot<SIZE> {
+    const FREE: Self = Self {
+        data: [0; SIZE],
+        len: 0,
+        state: State::Free,
+        sequence_number: 0,
+        age: 0,
+    };
+}
+
+/// The encoded frames that haven't been acknowledged by the host yet.
+///
+/// The window does not synchronize access itself; like the encoder, it's meant to be kept in the
+/// `Logger`'s state, behind its critical section or lock.
+pub struct Window<const N: usize, const SIZE: usize> {
+    slots: [Slot<SIZE>; N],
+    /// The slot of the frame that is being written
+    current: Option<usize>,
+    /// Number of the next frame
+    age: u32,
+    /// The last bytes received from the host, to find the ack messages in
+    received: [u8; 3],
+}
+
+impl<const N: usize, const SIZE: usize> Window<N, SIZE> {
+    /// Creates an empty window.
+    pub const fn new() -> Self {
+        Self {
+            slots: [Slot::FREE; N],
+            current: None,
+            age: 0,
+            received: [0; 3],
+        }
+    }
+
+    /// Starts keeping a new frame. Call this when the frame is started, before it is written.
+    pub fn start_frame(&mut self) {
+        let age = self.age;
+        self.age = self.age.wrapping_add(1);
+
+        let free = self
+            .slots
+            .iter()
+            .position(|slot| matches!(slot.state, State::Free | State::Overflowed));
+        // if all frames are waiting for their acks, give up on the oldest one
+        let oldest = || {
+            (0..N).max_by_key(|&i| age.wrapping_sub(self.slots.get(i).map_or(0, |slot| slot.age)))
+        };
+        self.current = free.or_else(oldest);
+
+        if let Some(slot) = self.current_slot() {
+            *slot = Slot {
+                len: 0,
+                state: State::Writing,
+                age,
+                ..*slot
+            };
+        }
+    }
+
+    /// Keeps `bytes` of the encoded frame, which must also be sent to the host.
+    pub fn write(&mut self, bytes: &[u8]) {
+        let Some(slot) = self.current_slot() else {
+            return;
+        };
+        if slot.state != State::Writing {
+            return;
+        }
+        match slot.data.get_mut(slot.len..slot.len + bytes.len()) {
+            Some(data) => {
+                data.copy_from_slice(bytes);
+                slot.len += bytes.len();
+            }
+            None => slot.state = State::Overflowed,
+        }
+    }
+
+    /// Finishes keeping the frame. Call this once the frame has been written, before the global
+    /// logger is released.
+    pub fn end_frame(&mut self) {
+        let sequence_number = export::sequence_number();
+        if let Some(slot) = self.current_slot() {
+            if slot.state == State::Writing {
+                slot.state = State::Unacked;
+                slot.sequence_number = sequence_number;
+            } else {
+                slot.state = State::Free;
+            }
+        }
+        self.current = None;
+    }
+
+    /// Processes data received from the host, which contains its ack messages.
+    pub fn received(&mut self, data: &[u8]) {
+        for &byte in data {
+            let [_, sequence_number, complement] = self.received;
+            self.received = [sequence_number, complement, byte];
+            if let [ACK, sequence_number, complement] = self.received {
+                if complement == !sequence_number {
+                    self.ack(sequence_number);
+                    self.received = [0; 3];
+                }
+            }
+        }
+    }
+
+    /// Writes the frames that haven't been acknowledged yet with `write`, again.
+    pub fn retransmit(&mut self, mut write: impl FnMut(&[u8])) {
+        for slot in &self.slots {
+            if slot.state == State::Unacked {
+                write(slot.data.get(..slot.len).unwrap_or(&slot.data));
+            }
+        }
+    }
+
+    /// Returns the number of frames that haven't been acknowledged yet.
+    pub fn pending(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.state == State::Unacked)
+            .count()
+    }
+
+    fn ack(&mut self, sequence_number: u8) {
+        for slot in &mut self.slots {
+            if slot.state == State::Unacked && slot.sequence_number == sequence_number {
+                slot.state = State::Free;
+            }
+        }
+    }
+
+    fn current_slot(&mut self) -> Option<&mut Slot<SIZE>> {
+        self.slots.get_mut(self.current?)
+    }
+}
+
+impl<const N: usize, const SIZE: usize> Default for Window<N, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,122 @@
+//! Stack usage measurement, for tuning stack sizes from the logs.
+//!
+//! The stack region is *painted* with a known pattern once, early at boot, with [`Stack::paint`]
+//! (`defmt_cortex_m::paint_main_stack` on Cortex-M).
+//! Everything the stack has grown into since then no longer contains the pattern, so scanning the
+//! region from its far end gives the stack's high-water mark. [`Stack::report`] emits it as a
+//! *stack frame*, which printers display like a `println!` message, e.g.
+//! `stack: 1536 of 8192 bytes used`, and which the decoder also exposes as a structured
+//! [stack usage], so the high-water marks of a fleet of devices can be collected from their logs.
+//!
+//! This assumes a stack that grows downwards, like on Cortex-M and RISC-V.
+//!
+//! ``` ignore
+//! // the stack region, as defined by the linker script of `cortex-m-rt`
+//! extern "C" {
+//!     static mut _stack_end: u32;
+//!     static mut _stack_start: u32;
+//! }
+//!
+//! let stack = unsafe {
+//!     defmt::stack::Stack::new(
+//!         core::ptr::addr_of_mut!(_stack_end),
+//!         core::ptr::addr_of_mut!(_stack_start),
+//!     )
+//! };
+//! defmt_cortex_m::paint_main_stack(&stack);
+//! // ..
+//! stack.report();
+//! ```
+//!
+//! [stack usage]: https://docs.rs/defmt-decoder/*/defmt_decoder/struct.StackUsage.html
+
+use core::mem;
+
+use crate as defmt;
+use crate::export;
+
+/// The pattern the unused part of the stack is painted with.
+const PAINT: u32 = 0xCCCC_CCCC;
+
+/// Number of bytes below the stack pointer that [`Stack::paint`] leaves alone, so it doesn't
+/// overwrite its own stack frame.
+const MARGIN: usize = 256;
+
+/// The memory region of a stack that grows downwards.
+#[derive(Clone, Copy, Debug)]
+pub struct Stack {
+    bottom: *mut u32,
+    top: *mut u32,
+}
+
+// safety: the region is only accessed with volatile reads and writes of whole words
+unsafe impl Send for Stack {}
+unsafe impl Sync for Stack {}
+
+impl Stack {
+    /// Creates the stack that starts at `top` and can grow down to `bottom`.
+    ///
+    /// # Safety
+    ///
+    /// `bottom..top` must be valid for reads and writes for the rest of the program, and nothing
+    /// but the stack may write to it.
+    pub const unsafe fn new(bottom: *mut u32, top: *mut u32) -> Self {
+        Self { bottom, top }
+    }
+
+    /// Returns the size of the stack, in bytes.
+    pub fn size(&self) -> u32 {
+        (self.top as usize).saturating_sub(self.bottom as usize) as u32
+    }
+
+    /// Paints the part of the stack below the stack pointer `sp`, which isn't in use yet.
+    ///
+    /// Call this once, as early as possible, e.g. at the start of `main`. The part of the stack
+    /// above `sp`, and a margin of a few hundred bytes below it for the stack frame of this
+    /// function, is counted as used by [`high_water_mark`](Self::high_water_mark).
+    ///
+    /// # Safety
+    ///
+    /// `sp` must be the current stack pointer of this stack, read by the caller, e.g. with
+    /// `cortex_m::register::msp::read`. Nothing else may use the stack while it is painted: an
+    /// interrupt handler that runs on it would have its stack frame overwritten, so call this with
+    /// interrupts disabled.
+    pub unsafe fn paint(&self, sp: *const u32) {
+        let limit = (sp as usize).saturating_sub(MARGIN);
+        let mut word = self.bottom;
+        while word < self.top && (word as usize) + mem::size_of::<u32>() <= limit {
+            // safety: `word` is in the stack region, below the part that is in use
+            unsafe {
+                word.write_volatile(PAINT);
+                word = word.add(1);
+            }
+        }
+    }
+
+    /// Returns the largest number of bytes of the stack that were in use since it was painted.
+    ///
+    /// If the stack wasn't painted, this is its whole size.
+    pub fn high_water_mark(&self) -> u32 {
+        let mut word = self.bottom;
+        // safety: `word` is in the stack region
+        while word < self.top && unsafe { word.read_volatile() } == PAINT {
+            word = unsafe { word.add(1) };
+        }
+        (self.top as usize - word as usize) as u32
+    }
+
+    /// Logs the [`high_water_mark`](Self::high_water_mark) and the size of the stack.
+    pub fn report(&self) {
+        let used = self.high_water_mark();
+        let size = self.size();
+
+        // safety: released when the guard is dropped
+        let _guard = unsafe { export::acquire_guard() };
+        export::header(&defmt_macros::intern_tagged!(
+            "stack",
+            "stack: {=u32} of {=u32} bytes used"
+        ));
+        export::u32(&used);
+        export::u32(&size);
+    }
+}
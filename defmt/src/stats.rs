@@ -0,0 +1,81 @@
+//! Statistics of the log frames, enabled by the `stats` feature.
+//!
+//! The counters are maintained by `defmt` itself, so they work with every global logger. They
+//! wrap around on overflow.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate as defmt;
+use crate::{export, Format, Formatter};
+
+static BYTES_WRITTEN: AtomicU32 = AtomicU32::new(0);
+static FRAMES: AtomicU32 = AtomicU32::new(0);
+static FRAMES_DROPPED: AtomicU32 = AtomicU32::new(0);
+
+/// Snapshot of the log statistics, see [`stats`](crate::stats()).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Bytes of log frame data handed to the global logger, before encoding.
+    pub bytes_written: u32,
+    /// Log frames emitted, including the dropped ones.
+    pub frames: u32,
+    /// Log frames that didn't reach the transport, because a `#[global_logger(guarded)]` logger
+    /// could not be acquired or because the frame was too long for the `encoding-length-prefixed`
+    /// encoder.
+    pub frames_dropped: u32,
+}
+
+impl Format for Stats {
+    fn format(&self, fmt: Formatter) {
+        crate::write!(
+            fmt,
+            "Stats {{ bytes_written: {=u32}, frames: {=u32}, frames_dropped: {=u32} }}",
+            self.bytes_written,
+            self.frames,
+            self.frames_dropped,
+        )
+    }
+}
+
+/// Returns the statistics of the log frames emitted so far.
+///
+/// Requires the `stats` feature. Firmware can report them periodically to monitor the health of
+/// the log link, e.g. `defmt::info!("{}", defmt::stats())`.
+pub fn stats() -> Stats {
+    Stats {
+        bytes_written: BYTES_WRITTEN.load(Ordering::Relaxed),
+        frames: FRAMES.load(Ordering::Relaxed),
+        frames_dropped: FRAMES_DROPPED.load(Ordering::Relaxed),
+    }
+}
+
+fn add(counter: &AtomicU32, n: u32) {
+    #[cfg(not(no_cas))]
+    counter.fetch_add(n, Ordering::Relaxed);
+    // NOTE without compare-and-swap, a count of a preempting execution context can get lost
+    #[cfg(no_cas)]
+    counter.store(
+        counter.load(Ordering::Relaxed).wrapping_add(n),
+        Ordering::Relaxed,
+    );
+}
+
+/// Counts bytes written to the global logger. Must be called between `acquire()` and `release()`.
+pub(crate) fn write(len: usize) {
+    if export::acquired() {
+        add(&BYTES_WRITTEN, len as u32);
+    }
+}
+
+/// Counts a completed frame. Must be called between `acquire()` and `release()`.
+pub(crate) fn end_frame() {
+    add(&FRAMES, 1);
+    if !export::acquired() {
+        frame_dropped();
+    }
+}
+
+/// Counts a frame that was dropped by the encoder.
+pub(crate) fn frame_dropped() {
+    add(&FRAMES_DROPPED, 1);
+}
@@ -0,0 +1,289 @@
+//! Snapshot testing of [`Format`] implementations.
+//!
+//! Only available with the `testing` feature, which implies `host-println`: string interning is
+//! mocked on the host and the interned strings are recorded, so a value can be formatted, decoded
+//! and rendered without a firmware ELF file:
+//!
+//! ```
+//! use defmt::{testing::assert_snapshot, Format};
+//!
+//! #[derive(Format)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! assert_snapshot!(Point { x: 1, y: -2 }, "Point { x: 1, y: -2 }");
+//! ```
+//!
+//! Likewise, the log frames emitted by a piece of code can be checked with [`assert_logs!`]:
+//!
+//! ```
+//! use defmt::testing::assert_logs;
+//!
+//! assert_logs!(
+//!     {
+//!         defmt::println!("reset failed: {=u8}", 3);
+//!         defmt::println!("retrying");
+//!     },
+//!     ["reset failed: 3", "retrying"],
+//! );
+//! ```
+//!
+//! For property testing, [`roundtrip`] checks that a value is rendered like its `Debug`
+//! representation. It works with any source of values, and the [`Samples`] trait provides edge
+//! cases of the primitive types:
+//!
+//! ```
+//! use defmt::testing::{roundtrip, Samples};
+//!
+//! for x in <(i16, Option<bool>)>::samples() {
+//!     roundtrip(&x);
+//! }
+//! ```
+//!
+//! With the `proptest` feature, the [`strategies`] module provides `proptest` strategies for the
+//! values that `proptest::arbitrary::any` can't generate with a round trip in mind.
+
+use std::{
+    fmt::Debug,
+    format,
+    string::{String, ToString as _},
+    vec,
+    vec::Vec,
+};
+
+use crate as defmt;
+use crate::{export, Format, Str};
+
+/// Asserts that a value is rendered as the expected string after a round trip through the wire
+/// format.
+///
+/// See the [module documentation](self) for an example.
+#[doc(inline)]
+pub use crate::__assert_snapshot as assert_snapshot;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_snapshot {
+    ($value:expr, $expected:expr $(,)?) => {
+        ::core::assert_eq!($crate::testing::render(&$value), $expected)
+    };
+}
+
+/// Asserts that running a block emits log frames that are rendered as the expected lines.
+///
+/// Lines start with the log level, if any. See the [module documentation](self) for an example.
+#[doc(inline)]
+pub use crate::__assert_logs as assert_logs;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_logs {
+    ($code:block, [$($expected:expr),* $(,)?] $(,)?) => {{
+        let expected: &[&str] = &[$($expected),*];
+        ::core::assert_eq!($crate::testing::capture_logs(|| $code), expected)
+    }};
+}
+
+/// Runs `f` and returns the decoded log frames it emitted on the current thread, one line each.
+///
+/// The mocked string interning only has 128 distinct indices, so `f` must not intern more strings
+/// than that.
+///
+/// # Panics
+///
+/// Panics if the data can't be decoded.
+pub fn capture_logs(f: impl FnOnce()) -> Vec<String> {
+    // discard the data of earlier, unchecked writes
+    export::fetch_bytes();
+
+    export::capture(f);
+    let bytes = export::fetch_bytes();
+
+    let table = export::mocked_table();
+    let mut rest = &bytes[..];
+    let mut lines = Vec::new();
+    while !rest.is_empty() {
+        match table.decode(rest) {
+            Ok((frame, consumed)) => {
+                lines.push(frame.display(false).to_string());
+                rest = &rest[consumed..];
+            }
+            Err(e) => panic!("failed to decode {rest:?}: {e}"),
+        }
+    }
+    lines
+}
+
+/// Formats `value`, decodes the data and returns the rendered string.
+///
+/// # Panics
+///
+/// Panics if the data can't be decoded, e.g. because the `Format` implementation writes data that
+/// doesn't match its format string.
+pub fn render<T: Format + ?Sized>(value: &T) -> String {
+    // the value is nested in a frame, because the decoder only expands enums in nested formats
+    render_in(&defmt_macros::internp!("{=?}"), value)
+}
+
+/// Asserts that `value` is rendered like its `Debug` representation after a round trip through
+/// the wire format.
+///
+/// This holds for most primitives, the `core` types that have a `Debug`-like `Format` impl (e.g.
+/// `Option`, arrays and tuples of them) and derived `Format` impls. `usize` and `isize` values
+/// must fit into 32 bits, like on the target.
+///
+/// # Panics
+///
+/// Panics if the renderings differ, or if the data can't be decoded.
+pub fn roundtrip<T: Format + Debug + ?Sized>(value: &T) {
+    // the `Debug` hint quotes strings and `char`s, like `Debug`
+    let rendered = render_in(&defmt_macros::internp!("{=?:?}"), value);
+    assert_eq!(rendered, format!("{value:?}"));
+}
+
+/// Types with a fixed set of interesting values, for property testing with [`roundtrip`]
+pub trait Samples: Sized {
+    /// Returns the values, including the edge cases of the type.
+    fn samples() -> Vec<Self>;
+}
+
+macro_rules! samples_int {
+    ($($ty:ty),*) => {
+        $(impl Samples for $ty {
+            fn samples() -> Vec<Self> {
+                vec![0, 1, 42, <$ty>::MAX / 2, <$ty>::MAX / 2 + 1, <$ty>::MAX, <$ty>::MIN, <$ty>::MIN / 2]
+            }
+        })*
+    };
+}
+
+samples_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Samples for usize {
+    fn samples() -> Vec<Self> {
+        u32::samples().into_iter().map(|x| x as usize).collect()
+    }
+}
+
+impl Samples for isize {
+    fn samples() -> Vec<Self> {
+        i32::samples().into_iter().map(|x| x as isize).collect()
+    }
+}
+
+macro_rules! samples_float {
+    ($($ty:ident),*) => {
+        $(impl Samples for $ty {
+            fn samples() -> Vec<Self> {
+                vec![
+                    0.0,
+                    -0.0,
+                    1.0,
+                    -1.5,
+                    1e-7,
+                    1.5e20,
+                    $ty::EPSILON,
+                    $ty::MIN,
+                    $ty::MAX,
+                    $ty::MIN_POSITIVE,
+                    $ty::INFINITY,
+                    $ty::NEG_INFINITY,
+                    $ty::NAN,
+                ]
+            }
+        })*
+    };
+}
+
+samples_float!(f32, f64);
+
+impl Samples for bool {
+    fn samples() -> Vec<Self> {
+        vec![false, true]
+    }
+}
+
+impl Samples for char {
+    fn samples() -> Vec<Self> {
+        vec!['a', '\0', '\'', '"', '\\', '\n', 'é', '\u{10ffff}']
+    }
+}
+
+impl<T: Samples> Samples for Option<T> {
+    fn samples() -> Vec<Self> {
+        let mut samples = vec![None];
+        samples.extend(T::samples().into_iter().map(Some));
+        samples
+    }
+}
+
+impl<A: Samples + Clone, B: Samples> Samples for (A, B) {
+    fn samples() -> Vec<Self> {
+        let a = A::samples();
+        B::samples()
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| (a[i % a.len()].clone(), b))
+            .collect()
+    }
+}
+
+/// [`proptest`] strategies for [`roundtrip`], with the `proptest` feature.
+///
+/// Most types that take a round trip, like the fixed-size integers, `bool`, `Option`s and tuples of
+/// them, can be generated with `proptest::arbitrary::any`. Floats can't: the decoder renders some
+/// of them differently than `Debug`, e.g. in scientific notation, so use [`samples`] for them.
+///
+/// ```
+/// use defmt::testing::{roundtrip, strategies};
+/// use proptest::prelude::*;
+///
+/// proptest!(|(x in any::<(i16, Option<bool>)>(), len in strategies::usize())| {
+///     roundtrip(&x);
+///     roundtrip(&len);
+/// });
+/// ```
+///
+/// [`proptest`]: https://docs.rs/proptest
+#[cfg(feature = "proptest")]
+pub mod strategies {
+    use proptest::{prelude::*, sample};
+
+    use super::{Debug, Samples};
+
+    /// `usize` values that fit into 32 bits, like on the target
+    pub fn usize() -> impl Strategy<Value = usize> {
+        any::<u32>().prop_map(|x| x as usize)
+    }
+
+    /// `isize` values that fit into 32 bits, like on the target
+    pub fn isize() -> impl Strategy<Value = isize> {
+        any::<i32>().prop_map(|x| x as isize)
+    }
+
+    /// One of the [`Samples`] of `T`, i.e. one of its edge cases
+    pub fn samples<T: Samples + Clone + Debug + 'static>() -> impl Strategy<Value = T> {
+        sample::select(T::samples())
+    }
+}
+
+/// Formats `value` as the argument of the interned `frame` format and renders the decoded frame.
+fn render_in<T: Format + ?Sized>(frame: &Str, value: &T) -> String {
+    // discard the data of earlier, unchecked writes
+    export::fetch_bytes();
+
+    export::istr(frame);
+    export::fmt(value);
+    let bytes = export::fetch_bytes();
+
+    match export::mocked_table().decode(&bytes) {
+        Ok((frame, consumed)) if consumed == bytes.len() => frame.display_message().to_string(),
+        Ok((_, consumed)) => panic!(
+            "{} trailing bytes after decoding {bytes:?}",
+            bytes.len() - consumed
+        ),
+        Err(e) => panic!("failed to decode {bytes:?}: {e}"),
+    }
+}
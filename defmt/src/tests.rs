@@ -10,6 +10,26 @@ fn log_levels() {
     defmt::error!("test error");
 }
 
+#[test]
+fn max_level() {
+    use defmt::{export::module_index, Level};
+
+    let level_enabled = |level| defmt::export::level_enabled(level, module_index(module_path!()));
+    assert_eq!(defmt::max_level(), Level::Trace);
+    defmt::set_max_level(Level::Warn);
+    assert_eq!(defmt::max_level(), Level::Warn);
+    assert!(!level_enabled(Level::Info));
+    assert!(level_enabled(Level::Warn));
+    assert!(level_enabled(Level::Error));
+    defmt::log_dynamic(Level::Info, "skipped", &[]);
+    assert!(defmt::export::fetch_bytes().is_empty());
+    defmt::log_dynamic(Level::Warn, "logged", &[]);
+    assert!(!defmt::export::fetch_bytes().is_empty());
+
+    defmt::set_max_level(Level::Trace);
+    assert!(level_enabled(Level::Trace));
+}
+
 #[test]
 fn str() {
     defmt::info!("Hello, {=str}", "world");
@@ -32,3 +52,39 @@ fn trailing_comma() {
     defmt::warn!("test warn {=?}", 0,);
     defmt::error!("test error {=?}", 0,);
 }
+
+#[test]
+fn fn_write() {
+    use defmt::Write as _;
+
+    let mut bytes = Vec::new();
+    {
+        let mut writer = defmt::FnWrite(|chunk: &[u8]| bytes.extend_from_slice(chunk));
+        writer.write(&[1, 2]);
+        writer.write(&[3]);
+        writer.flush();
+    }
+
+    assert_eq!(bytes, [1, 2, 3]);
+}
+
+#[test]
+fn backpressure() {
+    use std::sync::Mutex;
+
+    use defmt::backpressure;
+
+    static TRANSITIONS: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+
+    backpressure::set_hook(|congested| TRANSITIONS.lock().unwrap().push(congested));
+    assert!(!backpressure::is_congested());
+
+    backpressure::set_congested(true);
+    backpressure::set_congested(true);
+    assert!(backpressure::is_congested());
+    backpressure::set_congested(false);
+    assert!(!backpressure::is_congested());
+
+    // the hook is only called when the state changes
+    assert_eq!(*TRANSITIONS.lock().unwrap(), [true, false]);
+}
@@ -55,7 +55,43 @@ pub trait Format {
     #[doc(hidden)]
     fn _format_data(&self) {
         self.format(export::make_formatter());
-        export::u16(&0); // terminator
+        export::u8(&0); // terminator
+    }
+
+    /// Writes the data of all `values`, e.g. the elements of a slice.
+    #[doc(hidden)]
+    fn _format_slice_data(values: &[Self])
+    where
+        Self: Sized,
+    {
+        for value in values {
+            value._format_data();
+        }
+    }
+
+    /// Returns the format string of `Option<Self>`.
+    #[doc(hidden)]
+    fn _option_format_tag() -> Str
+    where
+        Self: Sized,
+    {
+        internp!("None|Some({=?})")
+    }
+
+    /// Writes the data of `Option<Self>`.
+    #[doc(hidden)]
+    fn _format_option_data(value: &Option<Self>)
+    where
+        Self: Sized,
+    {
+        match value {
+            None => export::u8(&0),
+            Some(x) => {
+                export::u8(&1);
+                export::istr(&Self::_format_tag());
+                x._format_data()
+            }
+        }
     }
 }
 
@@ -78,6 +114,9 @@ pub trait Format {
 /// - one thread in std environments.
 /// - one interrupt priority level in embedded devices.
 ///
+/// Implementations that drop log frames or block when the transport can't keep up should report it
+/// with [`backpressure::set_congested`](crate::backpressure::set_congested).
+///
 /// # Safety
 ///
 /// - `acquire` logically acquires the global logger in the current execution context.
@@ -106,7 +145,9 @@ pub unsafe trait Logger {
 
     /// Releases the global logger in the current execution context.
     ///
-    /// This will be called by the defmt logging macros after writing each log frame.
+    /// This will be called by the defmt logging macros after writing each log frame. It is also
+    /// called if a `Format` impl or the timestamp function panics in the middle of a log frame and
+    /// the panic unwinds, so the frame may be incomplete.
     ///
     /// # Safety
     /// Must be called exactly once for each acquire(), in the same execution context.
@@ -129,3 +170,217 @@ pub unsafe trait Logger {
     /// (i.e. between `acquire()` and `release()`).
     unsafe fn write(bytes: &[u8]);
 }
+
+/// Destination for the bytes of a log frame.
+///
+/// This is the sink that [`GuardedLogger`] guards write into.
+pub trait Write {
+    /// Writes `bytes` to the destination.
+    ///
+    /// The write operation must not fail. This is a "best effort" operation, I/O errors should be
+    /// discarded.
+    fn write(&mut self, bytes: &[u8]);
+
+    /// Blocks until host has read all pending data.
+    ///
+    /// This is a "best effort" operation. The default implementation does nothing.
+    fn flush(&mut self) {}
+}
+
+/// An adapter that turns a closure into a [`Write`] implementor.
+///
+/// This is handy for quick transports that push the bytes somewhere that already exists, like an
+/// existing driver's transmit queue, without defining a new type.
+///
+/// # Examples
+///
+/// ```
+/// use defmt::{FnWrite, Write};
+///
+/// let mut sent = 0;
+/// let mut writer = FnWrite(|bytes: &[u8]| sent += bytes.len());
+/// writer.write(&[1, 2, 3]);
+/// ```
+pub struct FnWrite<F: FnMut(&[u8])>(pub F);
+
+impl<F: FnMut(&[u8])> Write for FnWrite<F> {
+    fn write(&mut self, bytes: &[u8]) {
+        (self.0)(bytes)
+    }
+}
+
+/// An adapter that turns a [`std::io::Write`] implementor into a [`Write`] implementor.
+///
+/// This lets pipes, sockets and files be used as the destination of log frames on hosted
+/// platforms.
+///
+/// Since [`Write::write`] must not fail, the first I/O error is stored instead and all the bytes
+/// written after it are discarded, as the stream would be corrupted anyway. The error can be
+/// inspected with [`IoWrite::error`]; [`IoWrite::take_error`] also resumes writing, e.g. after
+/// reconnecting. Interrupted writes are retried.
+///
+/// # Examples
+///
+/// ```
+/// use defmt::{IoWrite, Write};
+///
+/// let mut writer = IoWrite::new(Vec::new());
+/// writer.write(&[1, 2, 3]);
+/// assert!(writer.error().is_none());
+/// assert_eq!(writer.into_inner(), [1, 2, 3]);
+/// ```
+///
+/// The bytes after an error are discarded:
+///
+/// ```
+/// use std::io::{Cursor, ErrorKind};
+///
+/// use defmt::{IoWrite, Write};
+///
+/// let mut writer = IoWrite::new(Cursor::new([0; 2]));
+/// writer.write(&[1, 2, 3]);
+/// writer.write(&[4]);
+/// assert_eq!(writer.error().unwrap().kind(), ErrorKind::WriteZero);
+/// assert_eq!(writer.get_ref().position(), 2);
+///
+/// writer.get_mut().set_position(0);
+/// assert!(writer.take_error().is_some());
+/// writer.write(&[4]);
+/// assert_eq!(writer.into_inner().into_inner(), [4, 2]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoWrite<W: std::io::Write> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> IoWrite<W> {
+    /// Wraps `inner`.
+    pub const fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Returns the I/O error that stopped writing, if any.
+    pub fn error(&self) -> Option<&std::io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the I/O error that stopped writing, if any, and resumes writing.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    /// Returns a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps the wrapped writer, discarding any stored error.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for IoWrite<W> {
+    fn write(&mut self, bytes: &[u8]) {
+        if self.error.is_none() {
+            self.error = self.inner.write_all(bytes).err();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.error.is_none() {
+            self.error = self.inner.flush().err();
+        }
+    }
+}
+
+/// Global logger acquire mechanism based on an RAII guard
+///
+/// This is a safe alternative to implementing [`Logger`]. Instead of separate `acquire()` and
+/// `release()` calls that must be correctly paired, `acquire()` returns a guard that represents
+/// ownership of the global logger in the current execution context:
+/// - The guard is created by `acquire()` to start the log frame.
+/// - Fragments of the log frame data are passed to the guard's [`Write::write`].
+/// - Dropping the guard ends the log frame and releases the logger.
+///
+/// Like with [`Logger`], the data passed to `write()` is *unencoded*. Implementations MUST encode
+/// it with [`Encoder`](crate::Encoder) prior to sending it over the wire.
+///
+/// `acquire` returns `None` if the logger cannot be taken in the current execution context, for
+/// example because a lower priority context is in the middle of writing a frame. The log frame
+/// is then silently discarded. Implementations must never hand out two guards at the same time;
+/// doing so panics.
+///
+/// `GuardedLogger` is offered in addition to [`Logger`], which is unchanged; a global logger
+/// implements one or the other.
+///
+/// Without the `std` feature, `#[global_logger(guarded)]` keeps track of the execution contexts
+/// that could not acquire the logger in a single counter. It therefore only supports single-core
+/// targets on which execution contexts, such as interrupt handlers, preempt each other in a
+/// stack-like fashion. On multi-core targets implement [`Logger`] instead. With the `std`
+/// feature every thread is its own execution context.
+///
+/// To use a `GuardedLogger` as the global logger, pass the `guarded` argument to
+/// [`#[global_logger]`](crate::global_logger):
+///
+/// ```
+/// use core::sync::atomic::{AtomicBool, Ordering};
+///
+/// #[defmt::global_logger(guarded)]
+/// struct MyLogger;
+///
+/// static TAKEN: AtomicBool = AtomicBool::new(false);
+///
+/// struct MyGuard;
+///
+/// impl defmt::GuardedLogger for MyLogger {
+///     type Guard = MyGuard;
+///
+///     fn acquire() -> Option<MyGuard> {
+///         // NOTE a real implementation must also make this interrupt-safe
+///         if TAKEN.swap(true, Ordering::Acquire) {
+///             return None;
+///         }
+///         // start the frame here
+///         Some(MyGuard)
+///     }
+/// }
+///
+/// impl defmt::Write for MyGuard {
+///     fn write(&mut self, bytes: &[u8]) {
+/// # let _ = bytes;
+///         // encode and transmit `bytes`
+///     }
+/// }
+///
+/// impl Drop for MyGuard {
+///     fn drop(&mut self) {
+///         // end the frame here
+///         TAKEN.store(false, Ordering::Release);
+///     }
+/// }
+/// ```
+pub trait GuardedLogger {
+    /// Proof of ownership of the global logger in the current execution context.
+    ///
+    /// Dropping the guard ends the current log frame and releases the logger.
+    type Guard: Write;
+
+    /// Acquire the global logger in the current execution context.
+    ///
+    /// This will be called by the defmt logging macros before writing each log frame. Returns
+    /// `None` if the logger is not available, in which case the log frame is discarded.
+    ///
+    /// Returning `Some` again before the previous guard was dropped panics, because it would end
+    /// the log frame of the preempted execution context.
+    fn acquire() -> Option<Self::Guard>;
+}
@@ -0,0 +1,29 @@
+//! Control messages from the host, with the `control` feature
+
+use defmt::{control, Level};
+
+// NOTE a single test, since the state of the control channel is global
+#[test]
+fn commands() {
+    assert_eq!(defmt::max_level(), Level::Trace);
+
+    // messages may be split across calls, and be surrounded by other data
+    control::poll_control(&[0, 0xc1]);
+    control::poll_control(&[3, !3, 0xac]);
+    assert_eq!(defmt::max_level(), Level::Warn);
+
+    // not a message: wrong complement, unknown level
+    control::poll_control(&[0xc1, 4, 4, 0xc1, 5, !5]);
+    assert_eq!(defmt::max_level(), Level::Warn);
+
+    // statements of higher levels are still logged
+    defmt::error!("x");
+    assert!(!defmt::export::fetch_bytes().is_empty());
+
+    control::poll_control(&[0xc1, 0, 0xff]);
+    assert_eq!(defmt::max_level(), Level::Trace);
+
+    control::poll_control(&[0xc3, 0, 0xff]);
+    // the index of the time sync frame; the mocked indices fit into a single byte
+    assert_eq!(defmt::export::fetch_bytes().len(), 1);
+}
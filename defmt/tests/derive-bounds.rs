@@ -1,3 +1,5 @@
+#![allow(clippy::disallowed_names)]
+
 fn main() {
     let baz: Baz<Qux> = Default::default();
     defmt::info!("{}", baz);
@@ -35,12 +35,36 @@
 // Additional notes:
 //
 // - the mocked index is 7 bits so its LEB128 encoding is the input byte
+// - `fetch_tokens` splits the output into typed `Token`s, which is easier to read than raw bytes
+//   for nested or variable-length data
 
-use defmt::{export::fetch_string_index, write, Debug2Format, Display2Format, Format, Formatter};
+use defmt::{
+    export::{fetch_string_index, fetch_tokens, Token},
+    write, Debug2Format, Display2Format, ErrorCode, Format, Formatter, Str,
+};
 
 // Increase the 7-bit mocked interned index
-fn inc(index: u16, n: u16) -> u16 {
-    index.wrapping_add(n)
+fn inc(index: u8, n: u8) -> u8 {
+    index.wrapping_add(n) & 0x7f
+}
+
+// Header of a log frame: the index of its format string, followed by its sequence number with the
+// `sequence-numbers` feature. `frame` counts the frames logged since the last `fetch_bytes`.
+fn header(index: u8, frame: u8) -> Header {
+    let mut bytes = vec![index];
+    if cfg!(feature = "sequence-numbers") {
+        bytes.push(frame);
+    }
+    Header(bytes)
+}
+
+struct Header(Vec<u8>);
+
+impl Header {
+    // so that a header can be listed in `check!`
+    fn to_le_bytes(&self) -> Vec<u8> {
+        self.0.clone()
+    }
 }
 
 fn write_format<T: Format + ?Sized>(val: &T) {
@@ -103,10 +127,9 @@ fn bitfields_mixed() {
         0b1110_0101_1111_0000u16, 0b1111_0000u8
     );
     check!([
-        index, // bitfields {0=7..12}, {1=0..5}",
-        0b1111_0000u8,
-        0b1110_0101u8, // u16
-        0b1111_0000u8, // u8
+        index,      // bitfields {0=7..12}, {1=0..5}",
+        0b0_1011u8, // u16 bitfield, packed
+        0b1_0000u8, // u8 bitfield, doesn't fit in the first byte
     ]);
 }
 
@@ -236,6 +259,23 @@ fn display_attr_enum() {
     )
 }
 
+#[test]
+fn packed_args() {
+    let index = fetch_string_index();
+    let g = defmt::export::make_formatter();
+
+    write!(
+        g,
+        "{=bool} {=u8} {=bool} {=0..3} {=bool}",
+        true, 42u8, false, 0b1111_1110u8, true
+    );
+    check!([
+        index,         // "{=bool} {=u8} {=bool} {=0..3} {=bool}"
+        0b0011_1001u8, // the bools and the bitfield, packed
+        42u8,          // u8
+    ]);
+}
+
 #[test]
 fn boolean_struct() {
     #[derive(Format)]
@@ -248,9 +288,8 @@ fn boolean_struct() {
     check_format!(
         &X { y: false, z: true },
         [
-            index, // "X {{ y: {=bool}, z: {=bool} }}"
-            0b0u8, // y
-            0b1u8, // z
+            index,  // "X {{ y: {=bool}, z: {=bool} }}"
+            0b10u8, // y and z, packed
         ],
     )
 }
@@ -297,7 +336,69 @@ fn single_struct_manual() {
             inc(index, 1), // "X {{ y: {=u8}, z: {=u16} }}"
             1u8,           // y
             2u16,          // z
-            0u16,          // terminator
+            0u8,           // terminator
+        ],
+    )
+}
+
+#[test]
+fn join() {
+    let index = fetch_string_index();
+    check_format!(
+        &defmt::join(&[3u8, 7], ", "),
+        [
+            index,         // "{=__internal_FormatSequence}"
+            inc(index, 1), // "{}"
+            inc(index, 2), // "{=u8}"
+            3u8,           //
+            inc(index, 3), // "{=str}{}"
+            2u32,          // separator.len()
+            b',',          //
+            b' ',          //
+            inc(index, 4), // "{=u8}"
+            7u8,           //
+            0u8,           // terminator
+        ],
+    );
+
+    let empty: &[u8] = &[];
+    let index = fetch_string_index();
+    check_format!(
+        &defmt::join(empty, ", "),
+        [
+            index, // "{=__internal_FormatSequence}"
+            0u8,   // terminator
+        ],
+    );
+}
+
+#[test]
+fn fmt_writer() {
+    use core::fmt::Write as _;
+
+    struct X {
+        y: u8,
+    }
+
+    impl Format for X {
+        fn format(&self, f: Formatter) {
+            let mut writer = f.fmt_writer();
+            core::write!(writer, "y={}", self.y).ok();
+        }
+    }
+
+    let index = fetch_string_index();
+    check_format!(
+        &X { y: 42 },
+        [
+            index,         // "{=__internal_FormatSequence}"
+            inc(index, 1), // "{=__internal_Display}"
+            b'y',
+            b'=',
+            b'4',
+            b'2',
+            0xffu8, // terminator
+            0u8,    // terminator
         ],
     )
 }
@@ -326,7 +427,7 @@ fn single_struct_manual_multiwrite() {
             1u8,           // y
             inc(index, 2), // "z={=u16}"
             2u16,          // z
-            0u16,          // terminator
+            0u8,           // terminator
         ],
     )
 }
@@ -358,13 +459,13 @@ fn slice_struct_manual_multiwrite() {
             1u8,           // y
             inc(index, 3), // "z={=u16}"
             2u16,          // z
-            0u16,          // terminator
+            0u8,           // terminator
             // second element
             inc(index, 4), // "y={=u8}"
             3u8,           // y
             inc(index, 5), // "z={=u16}"
             4u16,          // z
-            0u16,          // terminator
+            0u8,           // terminator
         ],
     )
 }
@@ -409,6 +510,34 @@ fn tuple_struct() {
     );
 }
 
+#[test]
+fn native_fields() {
+    #[derive(Format)]
+    struct Struct<'a> {
+        a: u64,
+        b: i128,
+        c: char,
+        d: [u8; 2],
+        e: &'a [u8],
+    }
+
+    let index = fetch_string_index();
+    check_format!(
+        &Struct {
+            a: 1,
+            b: -1,
+            c: 'x',
+            d: [2, 3],
+            e: &[4],
+        },
+        [
+            // "Struct {{ a: {=u64:?}, b: {=i128:?}, c: {=char:?}, d: {=[u8; 2]:?}, e: {=[u8]:?} }}"
+            index, 1u64, -1i128, 'x' as u32, 2u8, 3u8, 1u32, // length of `e`
+            4u8,
+        ],
+    );
+}
+
 #[test]
 fn c_like_enum() {
     #[derive(Format)]
@@ -439,6 +568,7 @@ fn c_like_enum() {
 
 #[test]
 fn uninhabited_enum() {
+    #[allow(dead_code)]
     #[derive(Format)]
     enum Void {}
 }
@@ -647,14 +777,14 @@ fn format_primitives() {
         &42i8,
         [
             inc(index, 6), // "{=i8}"
-            42u8,
+            84u8,          // zigzag(42)
         ],
     );
     check_format!(
         &-42i8,
         [
             inc(index, 7), // "{=i8}"
-            -42i8 as u8,
+            83u8,          // zigzag(-42)
         ],
     );
 
@@ -676,8 +806,8 @@ fn format_primitives() {
         ],
     );
 
-    check_format!(&-1isize, [inc(index, 11), -1i32]);
-    check_format!(&-128isize, [inc(index, 12), -128i32]);
+    check_format!(&-1isize, [inc(index, 11), 1u8]);
+    check_format!(&-128isize, [inc(index, 12), 0xffu8, 0x01u8]);
 
     check_format!(
         &true,
@@ -699,7 +829,7 @@ fn format_primitives() {
         &-2i64,
         [
             inc(index, 15), // "{=i64}"
-            -2i64,
+            3u8,            // zigzag(-2)
         ],
     );
 
@@ -715,6 +845,67 @@ fn format_primitives() {
     );
 }
 
+#[test]
+fn leb128_boundaries() {
+    let index = fetch_string_index();
+    check_format!(
+        &-64i32,
+        [
+            index, // "{=i32}"
+            127u8, // zigzag(-64)
+        ],
+    );
+    check_format!(
+        &64i32,
+        [
+            inc(index, 1), // "{=i32}"
+            0x80u8,        // zigzag(64) = 128
+            1u8,
+        ],
+    );
+    check_format!(
+        &i32::MIN,
+        [
+            inc(index, 2), // "{=i32}"
+            0xffu8,        // zigzag(i32::MIN) = u32::MAX
+            0xffu8,
+            0xffu8,
+            0xffu8,
+            0x0fu8,
+        ],
+    );
+}
+
+#[test]
+fn option_non_zero() {
+    use core::num::{NonZeroI16, NonZeroU32};
+
+    let index = fetch_string_index();
+    check_format!(
+        &None::<NonZeroU32>,
+        [
+            index, // "{=u32:__internal_niche_option}"
+            0u32,  // None
+        ],
+    );
+
+    check_format!(
+        &NonZeroU32::new(42),
+        [
+            inc(index, 1), // "{=u32:__internal_niche_option}"
+            42u32,         // Some.0
+        ],
+    );
+
+    check_format!(
+        &NonZeroI16::new(-1),
+        [
+            inc(index, 2), // "{=i16:__internal_niche_option}"
+            1u8,           // Some.0, zigzag(-1)
+        ],
+    );
+}
+
 #[test]
 fn istr() {
     let index = fetch_string_index();
@@ -722,8 +913,61 @@ fn istr() {
     check_format!(
         &interned,
         [
-            inc(index, 1), // "{=istr}"
-            index,
+            index,         // "{=istr}"
+            inc(index, 1), // "interned string contents"
+        ],
+    );
+}
+
+#[test]
+fn istr_const() {
+    const ERRORS: [Str; 2] = [defmt::intern!("timeout"), defmt::intern!("overrun")];
+    static DEFAULT: Str = defmt::intern!("unknown");
+
+    let index = fetch_string_index();
+    check_format!(
+        &ERRORS[1],
+        [
+            index,         // "{=istr}"
+            inc(index, 1), // "overrun"
+        ],
+    );
+
+    let index = fetch_string_index();
+    check_format!(
+        &DEFAULT,
+        [
+            index,         // "{=istr}"
+            inc(index, 1), // "unknown"
+        ],
+    );
+}
+
+#[test]
+fn istr_introspection() {
+    let (timeout, overrun) = (defmt::intern!("timeout"), defmt::intern!("overrun"));
+    let copy = timeout;
+    assert!(timeout == copy);
+    assert!(timeout != overrun);
+    assert_eq!(timeout.cmp(&overrun), overrun.cmp(&timeout).reverse());
+
+    // the mocked interning gives every use of a string a new index
+    let index = fetch_string_index();
+    assert_eq!(timeout.as_index(), u32::from(index));
+    defmt::export::istr(&timeout);
+    check!([inc(index, 1)]);
+}
+
+#[test]
+fn error_code() {
+    const TIMEOUT: ErrorCode = defmt::error_code!("timeout");
+
+    let index = fetch_string_index();
+    check_format!(
+        &TIMEOUT,
+        [
+            index,         // "{=istr}"
+            inc(index, 1), // "timeout"
         ],
     );
 }
@@ -771,6 +1015,22 @@ fn format_slice_of_primitives() {
     );
 }
 
+#[test]
+fn format_slice_of_floats() {
+    let index = fetch_string_index();
+    let slice: &[f32] = &[1.5, -2.0];
+    check_format!(
+        slice,
+        [
+            index,              // "{=[?]}"
+            slice.len() as u32, //
+            inc(index, 1),      // "{=f32}"
+            1.5f32,             // [0]
+            -2.0f32,            // [1]
+        ],
+    );
+}
+
 #[test]
 fn format_slice_of_structs() {
     #[derive(Format)]
@@ -899,7 +1159,7 @@ fn derive_with_bounds() {
         [
             index,         // "S {{ val: {=?} }}"
             inc(index, 1), // "{=i32}"
-            0u32,
+            0u8,
         ],
     );
 
@@ -913,6 +1173,42 @@ fn derive_with_bounds() {
     );
 }
 
+#[test]
+fn derive_with_explicit_bounds() {
+    // the inferred `Option<&List<T>>: Format` bound would be recursive
+    #[derive(Format)]
+    #[defmt(bound = "T: Format")]
+    struct List<'a, T> {
+        head: T,
+        tail: Option<&'a List<'a, T>>,
+    }
+
+    let list = List {
+        head: 1u8,
+        tail: Some(&List {
+            head: 2u8,
+            tail: None,
+        }),
+    };
+
+    let index = fetch_string_index();
+    check_format!(
+        &list,
+        [
+            index,         // "List {{ head: {=?}, tail: {=?} }}"
+            inc(index, 1), // "{=u8}"
+            1u8,           // list.head
+            inc(index, 2), // "None|Some({=?})"
+            1u8,           // Some discriminant
+            inc(index, 3), // "List {{ head: {=?}, tail: {=?} }}"
+            inc(index, 4), // "{=u8}"
+            2u8,           // list.tail.head
+            inc(index, 5), // "None|Some({=?})"
+            0u8,           // None discriminant
+        ],
+    );
+}
+
 #[test]
 fn format_bools() {
     #[derive(Format)]
@@ -979,6 +1275,23 @@ fn enum_variants() {
     check_format!(&e, [index, 269u16]);
 }
 
+#[test]
+fn enum_explicit_discriminants() {
+    #[allow(dead_code)]
+    #[derive(Format)]
+    enum Status {
+        Ok = 0,
+        Error = 0x42,
+        Busy,
+    }
+
+    // the discriminants are part of the format string; the variant is encoded as its position
+    let index = fetch_string_index();
+    check_format!(&Status::Error, [index, 1u8]);
+    let index = fetch_string_index();
+    check_format!(&Status::Busy, [index, 2u8]);
+}
+
 #[test]
 fn derive_str() {
     #[derive(Format)]
@@ -1007,4 +1320,399 @@ fn core_fmt_adapters() {
     check_format!(&Debug2Format(&123u8), [index, b'1', b'2', b'3', 0xffu8]);
     let index = fetch_string_index();
     check_format!(&Display2Format(&123u8), [index, b'1', b'2', b'3', 0xffu8]);
+    let index = fetch_string_index();
+    check_format!(
+        &format_args!("{}-{}", 1, 'a'),
+        [index, b'1', b'-', b'a', 0xffu8]
+    );
+}
+
+#[test]
+fn tokens() {
+    #[derive(Format)]
+    struct X<'a> {
+        name: &'a str,
+        offset: i32,
+        #[defmt(Debug2Format)]
+        id: u16,
+    }
+
+    let index = fetch_string_index();
+    write!(
+        defmt::export::make_formatter(),
+        "{=?} {=[u8;2]}",
+        X {
+            name: "x",
+            offset: -300,
+            id: 7,
+        },
+        [1, 2]
+    );
+    let expected = [
+        Token::Index(index.into()),         // "{=?} {=[u8;2]}"
+        Token::Index(inc(index, 1).into()), // "X {{ name: {=str:?}, offset: {=i32:?}, id: {=u16:?} }}"
+        Token::Str("x".into()),
+        Token::Signed(-300),
+        Token::Index(inc(index, 2).into()), // "{=__internal_Debug}"
+        Token::Fmt("7".into()),
+        Token::Bytes(vec![1, 2]),
+    ];
+    assert_eq!(fetch_tokens(&expected), expected);
+
+    // data that doesn't match the expected kinds is returned as raw bytes
+    let index = fetch_string_index();
+    write!(defmt::export::make_formatter(), "{=u8}", 42);
+    assert_eq!(
+        fetch_tokens(&[Token::Index(0), Token::Str(String::new())]),
+        [Token::Index(index.into()), Token::Bytes(vec![42])],
+    );
+}
+
+#[test]
+fn heartbeat() {
+    let index = fetch_string_index();
+    defmt::heartbeat();
+    check!([
+        header(index, 0), // "heartbeat"
+    ]);
+}
+
+#[test]
+fn runtime_level() {
+    // `DEFMT_LOG` is not set so only the *error* level is enabled
+    let index = fetch_string_index();
+    defmt::log!(defmt::Level::Error, "The answer is {=u8}", 42);
+    check!([
+        header(index, 0), // "The answer is {=u8}"
+        4u8,              // level
+        42u8,             // u8 value
+    ]);
+
+    defmt::log!(defmt::Level::Info, "The answer is {=u8}", 42);
+    assert!(defmt::export::fetch_bytes().is_empty());
+}
+
+#[test]
+fn disabled_statements() {
+    // `DEFMT_LOG` is not set so only the *error* level is enabled
+    let mut evaluated = 0;
+    let mut eval = |x: u8| {
+        evaluated += 1;
+        x
+    };
+
+    let index = fetch_string_index();
+    defmt::trace!("x={=u8}", eval(1));
+    defmt::info!("x={=u8} y={}", eval(2), [eval(3), eval(4)]);
+    defmt::log!(defmt::Level::Warn, "x={=u8}", eval(5));
+    assert!(defmt::export::fetch_bytes().is_empty());
+    // no format string was interned
+    assert_eq!(fetch_string_index(), index);
+
+    defmt::error!("x={=u8}", eval(6));
+    check!([
+        header(index, 0), // "x={=u8}"
+        6u8,              // x
+    ]);
+    assert_eq!(evaluated, 1);
+}
+
+#[test]
+fn log_dynamic() {
+    let index = fetch_string_index();
+    defmt::log_dynamic(defmt::Level::Warn, "x={=u8}", &[42]);
+    check!([
+        header(index, 0), // "{=str}"
+        3u8,              // level
+        7u32,             // length of the format string
+        b'x',             // format string
+        b'=',
+        b'{',
+        b'=',
+        b'u',
+        b'8',
+        b'}',
+        42u8, // u8 value
+    ]);
+}
+
+#[test]
+fn bail() {
+    fn timeout(addr: u8) -> Result<(), u16> {
+        defmt::bail!(7u8, "timed out addr={=u8}", addr);
+    }
+
+    fn nack() -> Result<(), u16> {
+        defmt::bail!(8u8);
+    }
+
+    let index = fetch_string_index();
+    // the error is converted into the return type
+    assert_eq!(timeout(42), Err(7));
+    check!([
+        header(index, 0), // "timed out addr={=u8}"
+        42u8,             // addr
+    ]);
+
+    let index = fetch_string_index();
+    assert_eq!(nack(), Err(8));
+    check!([
+        header(index, 0), // "{:?}"
+        inc(index, 1),    // "{=u8}"
+        8u8,              // error
+    ]);
+}
+
+#[test]
+fn odd_width_integers() {
+    let index = fetch_string_index();
+    let g = defmt::export::make_formatter();
+    write!(
+        g,
+        "{=u24} {=u48} {=u56} {=i24} {=i48} {=i56}",
+        0x0012_3456u32,
+        0xffff_0000_0000_0001u64,
+        u64::MAX,
+        -2i32,
+        -1i64,
+        i64::MIN >> 8,
+    );
+    check!([
+        index, // "{=u24} {=u48} {=u56} {=i24} {=i48} {=i56}"
+        0x56u8, 0x34u8, 0x12u8, // u24
+        1u8, 0u8, 0u8, 0u8, 0u8, 0u8, // u48, upper bits truncated
+        0xffu8, 0xffu8, 0xffu8, 0xffu8, 0xffu8, 0xffu8, 0xffu8, // u56, upper bits truncated
+        0xfeu8, 0xffu8, 0xffu8, // i24
+        0xffu8, 0xffu8, 0xffu8, 0xffu8, 0xffu8, 0xffu8, // i48
+        0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0x80u8, // i56
+    ]);
+}
+
+#[test]
+fn announce_version() {
+    let index = fetch_string_index();
+    defmt::announce_version();
+    check!([
+        header(index, 0), // "wire format version {=u8}"
+        5u8,              // version
+    ]);
+}
+
+#[test]
+fn hexdump() {
+    let index = fetch_string_index();
+    defmt::hexdump!(&[1, 2, 3]);
+    check!([
+        header(index, 0), // "{=[u8]:hexdump}"
+        3u32,             // length
+        1u8,              // bytes
+        2u8,
+        3u8,
+    ]);
+
+    // `DEFMT_LOG` is not set so only the *error* level is enabled
+    let index = fetch_string_index();
+    defmt::hexdump!(error, "rx {frame}", &[1, 2]);
+    check!([
+        header(index, 0), // "rx {{frame}}:{=[u8]:hexdump}"
+        2u32,             // length
+        1u8,              // bytes
+        2u8,
+    ]);
+
+    defmt::hexdump!(info, "rx", &[1, 2]);
+    assert!(defmt::export::fetch_bytes().is_empty());
+
+    let index = fetch_string_index();
+    defmt::hexdump!(defmt::Level::Error, "tx", &[3]);
+    check!([
+        header(index, 0), // "tx:{=[u8]:hexdump}"
+        4u8,              // level
+        1u32,             // length
+        3u8,              // bytes
+    ]);
+}
+
+#[test]
+fn metrics() {
+    let index = fetch_string_index();
+    defmt::counter!("rx_packets");
+    check!([
+        header(index, 0), // "rx_packets += 1"
+    ]);
+
+    let index = fetch_string_index();
+    defmt::counter!("rx_bytes", 2 * 32);
+    check!([
+        header(index, 0), // "rx_bytes += {=u32}"
+        64u32,            // delta
+    ]);
+
+    let index = fetch_string_index();
+    defmt::gauge!("temperature", -3);
+    check!([
+        header(index, 0), // "temperature = {=i32}"
+        5u8,              // value, zigzag and LEB128 encoded
+    ]);
+}
+
+#[test]
+fn stack_usage() {
+    let region = Vec::leak(vec![0u32; 16]).as_mut_ptr_range();
+    let stack = unsafe { defmt::stack::Stack::new(region.start, region.end) };
+    assert_eq!(stack.high_water_mark(), 64);
+
+    // the region is on the heap, below the stack of the test
+    let sp = 0u32;
+    unsafe { stack.paint(&sp) };
+    assert_eq!(stack.high_water_mark(), 0);
+    // the stack grows down from the end of the region
+    unsafe { region.end.sub(3).write_volatile(0) };
+
+    let index = fetch_string_index();
+    stack.report();
+    check!([
+        header(index, 0), // "stack: {=u32} of {=u32} bytes used"
+        12u32,            // used
+        64u32,            // size
+    ]);
+}
+
+#[test]
+fn event() {
+    let index = fetch_string_index();
+    defmt::event::task_switch(2);
+    check!([
+        header(index, 0), // "task_switch={=u16}"
+        2u16,             // task
+    ]);
+
+    let index = fetch_string_index();
+    defmt::event::isr_exit();
+    check!([
+        header(index, 0), // "isr_exit"
+    ]);
+
+    let index = fetch_string_index();
+    {
+        let _isr = defmt::event::isr(5);
+    }
+    check!([
+        header(index, 0),         // "isr_enter={=u16}"
+        5u16,                     // irq
+        header(inc(index, 1), 1), // "isr_exit"
+    ]);
+
+    let index = fetch_string_index();
+    defmt::event!(adc_done);
+    check!([
+        header(index, 0), // "event=adc_done"
+    ]);
+}
+
+#[test]
+fn harness() {
+    let name = defmt::intern!("assert_true");
+    let index = fetch_string_index();
+    defmt::harness::test_started(1, 8, name);
+    check!([
+        header(index, 0), // "({=u16}/{=u16}) running `{=istr}`..."
+        1u16,             // number
+        8u16,             // count
+        inc(index, 1),    // "assert_true"
+    ]);
+
+    let index = fetch_string_index();
+    defmt::harness::finished(7, 0, 1);
+    check!([
+        header(index, 0), // "test result: {=u16} passed; {=u16} failed; {=u16} ignored"
+        7u16,             // passed
+        0u16,             // failed
+        1u16,             // ignored
+    ]);
+}
+
+#[test]
+fn timestamp_delta() {
+    defmt::export::timestamp_delta(1000);
+    check!([0xe8u8, 0x07u8]);
+
+    defmt::export::timestamp_delta(1005);
+    check!([5u8]);
+
+    defmt::export::timestamp_delta(1004);
+    // wraps around
+    let mut expected = vec![0xff; 9];
+    expected.push(0x01);
+    assert_eq!(defmt::export::fetch_bytes(), expected);
+}
+
+#[test]
+fn wall_clock() {
+    let index = fetch_string_index();
+    defmt::wall_clock(1618910624804);
+    check!([
+        header(index, 0), // "wall clock: {=u64:iso8601ms}"
+        1618910624804u64, // unix time in ms
+    ]);
+}
+
+#[test]
+fn clock_sync() {
+    let index = fetch_string_index();
+    defmt::clock_sync(1, 10_000);
+    check!([
+        header(index, 0), // "clock sync: domain {=u16} at {=u64:us}"
+        1u16,             // domain
+        10_000u64,        // domain time
+    ]);
+}
+
+#[test]
+fn chunked() {
+    let index = fetch_string_index();
+    let id = defmt::chunked(defmt::intern!("capture"), &[1, 2, 3], 2);
+    check!([
+        header(index, 0),         // "{=istr} #{=u16} @{=u32}/{=u32}: {=[u8]:02x}"
+        inc(index, 1),            // "capture"
+        id,                       // transfer ID
+        0u32,                     // offset
+        3u32,                     // total
+        2u32,                     // chunk.len()
+        1u8,                      //
+        2u8,                      //
+        header(inc(index, 2), 1), // "{=istr} #{=u16} @{=u32}/{=u32}: {=[u8]:02x}"
+        inc(index, 3),            // "capture"
+        id,                       // transfer ID
+        2u32,                     // offset
+        3u32,                     // total
+        1u32,                     // chunk.len()
+        3u8,                      //
+    ]);
+
+    let index = fetch_string_index();
+    let next = defmt::chunked(defmt::intern!("empty"), &[], 2);
+    assert_eq!(next, id.wrapping_add(1));
+    check!([
+        header(index, 0), // "{=istr} #{=u16} @{=u32}/{=u32}: {=[u8]:02x}"
+        inc(index, 1),    // "empty"
+        next,             // transfer ID
+        0u32,             // offset
+        0u32,             // total
+        0u32,             // chunk.len()
+    ]);
+}
+
+#[test]
+#[should_panic = "defmt log statement used while writing another log frame"]
+fn log_in_format_impl() {
+    struct Reentrant;
+
+    impl Format for Reentrant {
+        fn format(&self, _: Formatter) {
+            defmt::error!("nested");
+        }
+    }
+
+    defmt::error!("{}", Reentrant);
 }
@@ -0,0 +1,32 @@
+//! Atomic groups of log statements
+
+use defmt::testing::assert_logs;
+
+#[test]
+fn frames() {
+    let value = 42u8;
+    assert_logs!(
+        {
+            defmt::group! {
+                defmt::println!("first");
+                defmt::error!("second: {=u8}", value);
+                // filtered out by `DEFMT_LOG`
+                defmt::info!("third");
+            }
+        },
+        ["group start", "first", "ERROR second: 42", "group end"],
+    );
+}
+
+#[test]
+#[should_panic = "defmt log statement used while writing a `group!`"]
+fn log_in_argument() {
+    fn measure() -> u8 {
+        defmt::error!("measuring");
+        42
+    }
+
+    defmt::group! {
+        defmt::error!("value: {=u8}", measure());
+    }
+}
@@ -0,0 +1,20 @@
+fn main() {
+    defmt::info!("hello");
+}
+
+#[defmt::global_logger(guarded)]
+struct Logger;
+
+struct Guard;
+
+impl defmt::GuardedLogger for Logger {
+    type Guard = Guard;
+
+    fn acquire() -> Option<Guard> {
+        Some(Guard)
+    }
+}
+
+impl defmt::Write for Guard {
+    fn write(&mut self, _bytes: &[u8]) {}
+}
@@ -0,0 +1,203 @@
+//! `#[global_logger]` in a host program, with the `host-logger` feature
+
+use std::{
+    cell::{Cell, RefCell},
+    panic,
+    sync::Mutex,
+    thread,
+};
+
+use defmt::decoder::host_table;
+
+static FRAMES: Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static ACQUIRED: Cell<bool> = const { Cell::new(false) };
+    static FRAME: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        assert!(!ACQUIRED.replace(true), "logger acquired twice");
+    }
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {
+        ACQUIRED.set(false);
+        let frame = FRAME.take();
+        if !thread::panicking() {
+            FRAMES.lock().unwrap().push(frame);
+        }
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        FRAME.with_borrow_mut(|frame| frame.extend(bytes));
+    }
+}
+
+#[derive(defmt::Format)]
+#[defmt(debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[allow(dead_code)]
+#[derive(defmt::Format)]
+#[defmt(debug)]
+enum Status {
+    Ok = 0,
+    Error = 0x42,
+    Busy,
+}
+
+#[allow(dead_code)]
+#[derive(defmt::Format)]
+#[defmt(debug, discriminant = hex)]
+enum Register {
+    Status = 0x10,
+    Control,
+}
+
+#[derive(defmt::Format)]
+#[defmt(debug)]
+enum Message<'a> {
+    Text(&'a str, char),
+    Reading {
+        id: &'a str,
+        point: Point,
+        valid: bool,
+        value: f32,
+        #[defmt(Display2Format)]
+        address: std::net::Ipv4Addr,
+    },
+}
+
+fn log(i: u8) {
+    defmt::println!("iteration {=u8}", i);
+}
+
+#[test]
+fn decodes_frames() {
+    defmt::error!("{}", Point { x: 1, y: -2 });
+    defmt::error!("{} {}", Status::Busy, Register::Control);
+    let messages = [
+        Message::Text("hi", 'x'),
+        Message::Reading {
+            id: "a\"b",
+            point: Point { x: 3, y: 4 },
+            valid: true,
+            value: 1.0,
+            address: std::net::Ipv4Addr::LOCALHOST,
+        },
+    ];
+    for message in &messages {
+        defmt::error!("{}", message);
+    }
+    defmt::error!("[{}]", defmt::join(&[1u8, 2, 3], "; "));
+    defmt::error!(
+        "{=istr}",
+        defmt::intern!(concat!(
+            "v",
+            env!("CARGO_PKG_VERSION"),
+            '-',
+            1,
+            stringify!(rc)
+        ))
+    );
+    let location = std::panic::Location::caller();
+    defmt::error!("{}", location);
+    // `ParseIntError` implements `Debug` but not `Format`
+    let parsed = "x".parse::<u8>();
+    assert!(panic::catch_unwind(move || defmt::unwrap!(parsed)).is_err());
+    for i in 0..2 {
+        log(i);
+    }
+    thread::spawn(|| log(2)).join().unwrap();
+
+    let table = host_table();
+    let frames = FRAMES.lock().unwrap();
+    let lines = frames
+        .iter()
+        .map(|bytes| {
+            let (frame, consumed) = table.decode(bytes).unwrap();
+            assert_eq!(consumed, bytes.len());
+            frame.display(false).to_string()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        [
+            "ERROR Point { x: 1, y: -2 }",
+            "ERROR Busy = 67 Control = 0x11",
+            "ERROR Text(hi, x)",
+            "ERROR Reading { id: \"a\\\"b\", point: Point { x: 3, y: 4 }, valid: true, value: 1.0, address: 127.0.0.1 }",
+            "ERROR [1; 2; 3]",
+            concat!("ERROR v", env!("CARGO_PKG_VERSION"), "-1rc"),
+            &format!("ERROR {location}"),
+            "ERROR panicked at 'unwrap failed: parsed'\nerror: `ParseIntError { kind: InvalidDigit }`",
+            "iteration 0",
+            "iteration 1",
+            "iteration 2"
+        ]
+    );
+
+    // another process can build the same table from the interned strings
+    let entries = (1..=defmt::decoder::host_string_count())
+        .map(|index| {
+            let (string, tag) = defmt::decoder::host_string(index).unwrap();
+            (index, defmt::decoder::host_table_entry(string, tag))
+        })
+        .collect();
+    let sent_table = defmt::decoder::Table::new(entries, defmt::decoder::Encoding::Rzcobs);
+    for (bytes, line) in frames.iter().zip(&lines) {
+        let (frame, _) = sent_table.decode(bytes).unwrap();
+        assert_eq!(frame.display(false).to_string(), *line);
+    }
+
+    // the table uses the selected encoding, the default one here
+    let mut encoder = defmt::Encoder::new();
+    let mut encoded = Vec::new();
+    for frame in frames.iter() {
+        encoder.start_frame(|bytes| encoded.extend(bytes));
+        encoder.write(frame, |bytes| encoded.extend(bytes));
+        encoder.end_frame(|bytes| encoded.extend(bytes));
+    }
+    let mut stream_decoder = table.new_stream_decoder();
+    stream_decoder.received(&encoded);
+    for line in &lines {
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.display(false).to_string(), *line);
+    }
+
+    // `#[defmt(debug)]` renders like the decoder
+    for (message, line) in messages.iter().zip(&lines[2..4]) {
+        assert_eq!(format!("ERROR {message:?}"), *line);
+    }
+    assert_eq!(
+        format!("{:?} {:?}", Status::Busy, Register::Control),
+        lines[1]["ERROR ".len()..]
+    );
+}
+
+struct Panics;
+
+impl defmt::Format for Panics {
+    fn format(&self, _: defmt::Formatter) {
+        panic!("oops");
+    }
+}
+
+#[test]
+fn releases_on_panic() {
+    let result = panic::catch_unwind(|| defmt::error!("{}", Panics));
+    assert!(result.is_err());
+
+    // the logger was released, so it can be acquired again
+    assert!(!ACQUIRED.get());
+}
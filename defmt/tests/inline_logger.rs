@@ -0,0 +1,16 @@
+fn main() {
+    defmt::info!("hello {=u8}", 42);
+}
+
+#[defmt::global_logger(inline)]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {}
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {}
+
+    unsafe fn write(_bytes: &[u8]) {}
+}
@@ -0,0 +1,27 @@
+//! `defmt::mock::MockLogger` as the global logger of a test binary, with the `std` feature
+// `unstable-test` replaces the global logger
+#![cfg(not(feature = "unstable-test"))]
+
+#[defmt::global_logger]
+type Logger = defmt::mock::MockLogger;
+
+// the linker script isn't used on the host, so there's no default timestamp
+defmt::timestamp!("");
+
+#[test]
+fn logs_once() {
+    defmt::error!("oh no");
+    assert_eq!(defmt::mock::MockLogger::take_frames().len(), 1);
+}
+
+#[test]
+fn records_the_data_of_every_frame() {
+    defmt::error!("{=u8}", 42);
+    defmt::error!("{=u8}", 43);
+
+    let frames = defmt::mock::MockLogger::take_frames();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].bytes().last(), Some(&42));
+    assert_eq!(frames[1].bytes().last(), Some(&43));
+    assert!(defmt::mock::MockLogger::take_frames().is_empty());
+}
@@ -0,0 +1,46 @@
+//! Per-module levels, with the `module-filter` feature
+
+use defmt::{control, export::level_enabled, export::module_index, Level};
+
+mod radio {
+    pub fn log() {
+        defmt::error!("x");
+    }
+}
+
+// NOTE the levels are global, so this is the only test that changes them
+#[test]
+fn module_levels() {
+    let radio = module_index("module_filter::radio");
+    let other = module_index(module_path!());
+    assert_ne!(radio, other);
+
+    defmt::set_module_max_level("module_filter::radio", Some(Level::Error));
+    assert!(!level_enabled(Level::Warn, radio));
+    assert!(level_enabled(Level::Warn, other));
+    radio::log();
+    assert!(!defmt::export::fetch_bytes().is_empty());
+
+    // the module follows the global level again
+    defmt::set_module_max_level("module_filter::radio", None);
+    defmt::set_max_level(Level::Error);
+    assert!(!level_enabled(Level::Warn, radio));
+    defmt::set_max_level(Level::Trace);
+    assert!(level_enabled(Level::Warn, radio));
+
+    // with the control channel; the module's level overrides the global one in both directions
+    defmt::set_max_level(Level::Error);
+    control::poll_control(&[0xd0, radio, !radio]);
+    assert!(level_enabled(Level::Trace, radio));
+    assert!(!level_enabled(Level::Warn, other));
+    control::poll_control(&[0xd5, radio, !radio]);
+    assert!(!level_enabled(Level::Warn, radio));
+    defmt::set_max_level(Level::Trace);
+}
+
+#[test]
+fn module_index_matches_decoder() {
+    for module in ["app", "app::radio", "module_filter::radio"] {
+        assert_eq!(module_index(module), defmt_decoder::module_index(module));
+    }
+}
@@ -0,0 +1,61 @@
+//! Retransmission of log frames, with the `reliable` feature
+
+use defmt::reliable::Window;
+
+/// Logs a frame and keeps its data in `window`, like a `Logger` would
+fn log(window: &mut Window<2, 8>, data: &[u8]) {
+    window.start_frame();
+    defmt::println!("x");
+    window.write(data);
+    window.end_frame();
+}
+
+fn retransmitted(window: &mut Window<2, 8>) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    window.retransmit(|bytes| frames.push(bytes.to_vec()));
+    frames
+}
+
+#[test]
+fn retransmits_until_acked() {
+    let mut window = Window::<2, 8>::new();
+
+    // sequence numbers 0 and 1
+    log(&mut window, &[1, 2]);
+    log(&mut window, &[3]);
+    assert_eq!(window.pending(), 2);
+    assert_eq!(retransmitted(&mut window), [vec![1, 2], vec![3]]);
+
+    // acks may be split across reads, and be surrounded by other data
+    window.received(&[0, 0xac, 0]);
+    window.received(&[0xff, 0xac]);
+    assert_eq!(window.pending(), 1);
+    assert_eq!(retransmitted(&mut window), [vec![3]]);
+
+    // an ack with the wrong complement is ignored
+    window.received(&[0xac, 1, 0xff]);
+    assert_eq!(window.pending(), 1);
+
+    // frames that don't fit are not kept, and the oldest frame is given up on
+    log(&mut window, &[0; 9]);
+    log(&mut window, &[4]);
+    log(&mut window, &[5]);
+    assert_eq!(window.pending(), 2);
+    assert_eq!(retransmitted(&mut window), [vec![4], vec![5]]);
+
+    window.received(&[0xac, 3, !3, 0xac, 4, !4]);
+    assert_eq!(window.pending(), 0);
+}
+
+#[test]
+fn acks_the_sequence_number_of_the_frame() {
+    let mut window = Window::<2, 8>::new();
+    defmt::export::fetch_bytes();
+
+    log(&mut window, &[1]);
+    // index and sequence number; the mocked index fits into a single byte
+    let sequence_number = defmt::export::fetch_bytes()[1];
+
+    window.received(&[0xac, sequence_number, !sequence_number]);
+    assert_eq!(window.pending(), 0);
+}
@@ -0,0 +1,14 @@
+//! Sequence numbers of the log frames, with the `sequence-numbers` feature
+
+#[test]
+fn increments_per_frame() {
+    for _ in 0..3 {
+        defmt::println!("x");
+    }
+
+    // index and sequence number of each frame; the mocked indices fit into a single byte
+    let bytes = defmt::export::fetch_bytes();
+    assert_eq!(bytes.len(), 6);
+    let sequence_numbers = bytes.chunks(2).map(|frame| frame[1]).collect::<Vec<_>>();
+    assert_eq!(sequence_numbers, [0, 1, 2]);
+}
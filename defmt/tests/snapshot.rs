@@ -0,0 +1,119 @@
+use defmt::{
+    testing::{assert_logs, assert_snapshot, roundtrip, Samples},
+    Debug2Format, Format,
+};
+
+#[test]
+fn primitives() {
+    assert_snapshot!(42u8, "42");
+    assert_snapshot!(-300i32, "-300");
+    assert_snapshot!("hi", "hi");
+    assert_snapshot!([1u16, 2, 3], "[1, 2, 3]");
+    assert_snapshot!(Some(true), "Some(true)");
+}
+
+#[test]
+fn derived() {
+    #[derive(Format)]
+    struct Flags {
+        a: bool,
+        b: bool,
+        #[defmt(Debug2Format)]
+        id: u16,
+    }
+
+    #[derive(Format)]
+    enum E {
+        A,
+        B(Flags),
+    }
+
+    assert_snapshot!(E::A, "A");
+    assert_snapshot!(
+        E::B(Flags {
+            a: true,
+            b: false,
+            id: 7,
+        }),
+        "B(Flags { a: true, b: false, id: 7 })",
+    );
+
+    #[derive(Format)]
+    struct Native<'a> {
+        a: u64,
+        c: char,
+        d: [u8; 2],
+        e: &'a [u8],
+    }
+
+    assert_snapshot!(
+        Native {
+            a: 1,
+            c: 'x',
+            d: [2, 3],
+            e: &[4],
+        },
+        "Native { a: 1, c: 'x', d: [2, 3], e: [4] }",
+    );
+}
+
+#[test]
+fn adapters() {
+    assert_snapshot!(Debug2Format(&[1, 2]), "[1, 2]");
+}
+
+#[test]
+fn logs() {
+    assert_logs!({}, []);
+    assert_logs!(
+        {
+            for i in 0..2u8 {
+                defmt::error!("attempt {=u8} failed", i);
+            }
+            defmt::println!("giving up on {=str}", "x");
+        },
+        [
+            "ERROR attempt 0 failed",
+            "ERROR attempt 1 failed",
+            "giving up on x"
+        ],
+    );
+}
+
+#[test]
+fn roundtrips() {
+    fn check<T: Samples + Format + core::fmt::Debug>() {
+        for x in T::samples() {
+            roundtrip(&x);
+        }
+    }
+
+    check::<u8>();
+    check::<u16>();
+    check::<u32>();
+    check::<u64>();
+    check::<u128>();
+    check::<usize>();
+    check::<i8>();
+    check::<i16>();
+    check::<i32>();
+    check::<i64>();
+    check::<i128>();
+    check::<isize>();
+    check::<f32>();
+    check::<f64>();
+    check::<bool>();
+    check::<char>();
+    check::<Option<u32>>();
+    check::<(i8, Option<char>)>();
+
+    #[derive(Debug, Format)]
+    struct Point {
+        x: i32,
+        y: Option<bool>,
+    }
+    for (x, y) in <(i32, Option<bool>)>::samples() {
+        roundtrip(&Point { x, y });
+    }
+    roundtrip(&[1u8, 2, 3]);
+}
@@ -0,0 +1,20 @@
+//! Statistics of the log frames, with the `stats` feature
+
+use defmt::Stats;
+
+#[test]
+fn counts_frames_and_bytes() {
+    assert_eq!(defmt::stats(), Stats::default());
+
+    defmt::error!("x={=u8}", 42);
+    defmt::println!("{=[u8]}", [1, 2, 3]);
+    assert_eq!(
+        defmt::stats(),
+        Stats {
+            // index and `u8`, index and length and bytes
+            bytes_written: 2 + 1 + 4 + 3,
+            frames: 2,
+            frames_dropped: 0,
+        }
+    );
+}
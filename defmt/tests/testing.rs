@@ -0,0 +1,35 @@
+//! The `testing` module in a crate that uses `defmt`, with the `testing` and `proptest` features
+
+use defmt::testing::{assert_logs, assert_snapshot, roundtrip, strategies};
+use proptest::prelude::*;
+
+#[test]
+fn snapshot() {
+    assert_snapshot!([Some(1u8), None], "[Some(1), None]");
+}
+
+#[test]
+fn logs() {
+    // the frames are captured instead of being printed, like `host-println` does otherwise
+    assert_logs!(
+        {
+            defmt::println!("reset failed: {=u8}", 3);
+            defmt::println!("retrying");
+        },
+        ["reset failed: 3", "retrying"],
+    );
+}
+
+proptest! {
+    #[test]
+    fn integers(x in any::<(u64, i128, Option<i8>)>(), len in strategies::usize()) {
+        roundtrip(&x);
+        roundtrip(&len);
+    }
+
+    #[test]
+    fn floats(x in strategies::samples::<f32>(), y in strategies::samples::<f64>()) {
+        roundtrip(&x);
+        roundtrip(&y);
+    }
+}
@@ -10,5 +10,7 @@ fn ui() {
 
         t.pass("tests/basic_usage.rs");
         t.pass("tests/derive-bounds.rs");
+        t.pass("tests/guarded_logger.rs");
+        t.pass("tests/inline_logger.rs");
     }
 }
@@ -0,0 +1,10 @@
+const BASE: isize = 0x40;
+
+#[derive(defmt::Format)]
+#[defmt(discriminant = hex)]
+enum E {
+    A = BASE,
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[derive(defmt::Format)]
+#[defmt(bound = "T Format")]
+struct S<T> {
+    f: T,
+}
+
+fn main() {}
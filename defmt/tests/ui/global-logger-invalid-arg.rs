@@ -0,0 +1,4 @@
+#[defmt::global_logger(unguarded)]
+struct Logger;
+
+fn main() {}
@@ -0,0 +1,6 @@
+fn main() {
+    defmt::group! {
+        defmt::info!("x");
+        defmt::hexdump!(defmt::Level::Info, &[0u8; 4]);
+    }
+}
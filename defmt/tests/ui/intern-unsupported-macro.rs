@@ -0,0 +1,3 @@
+fn main() {
+    defmt::intern!(concat!("version ", include_str!("version.txt")));
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let x = 1u8;
+    let name = "x";
+    defmt::info!("{=str} is {=u16}", name, x);
+}
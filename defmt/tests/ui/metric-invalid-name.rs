@@ -0,0 +1,3 @@
+fn main() {
+    defmt::counter!("rx packets");
+}
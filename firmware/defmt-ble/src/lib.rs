@@ -0,0 +1,168 @@
+//! [`defmt`](https://github.com/knurling-rs/defmt) global logger sending log data as Bluetooth Low
+//! Energy GATT notifications.
+//!
+//! This crate is independent of the BLE stack. Log frames are buffered in RAM; call [`drain`]
+//! whenever a client has subscribed to the log characteristic and the stack can queue more
+//! notifications, passing a closure that sends one notification.
+//!
+//! ``` ignore
+//! // src/main.rs or src/bin/my-app.rs
+//! use defmt_ble as _;
+//!
+//! // once the client has enabled notifications, and whenever the stack has room again
+//! defmt_ble::drain(connection.att_mtu(), |chunk| server.notify(&connection, log_handle, chunk));
+//! ```
+//!
+//! # Wire format
+//!
+//! The first byte of every notification is a sequence number that increments by one (wrapping)
+//! with every notification; the remaining bytes are the defmt byte stream.
+//!
+//! To reassemble the stream on the client, append the payloads of consecutive notifications. If
+//! the sequence number of a notification is not the expected one, notifications were lost:
+//! discard the data up to the next frame boundary. This only works with the `rzcobs` encoding
+//! (the default), whose frames are delimited by zero bytes.
+//!
+//! When the buffer is full, new log frames are dropped entirely. The buffer size defaults to 1024
+//! bytes and can be changed by setting the `DEFMT_BLE_BUFFER_SIZE` environment variable at
+//! compile time.
+//!
+//! # Critical section implementation
+//!
+//! This crate uses [`critical-section`](https://github.com/rust-embedded/critical-section) to
+//! synchronize the logger with [`drain`]. You must import a crate that provides a
+//! `critical-section` implementation suitable for the current target.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use defmt_buffer::Buffer;
+
+/// Maximum size of a notification sent by [`drain`], including the sequence number.
+///
+/// This is the largest attribute value that fits into a single link-layer packet with data length
+/// extension.
+pub const MAX_NOTIFICATION_LEN: usize = 244;
+
+/// Size of the ATT notification header (opcode and attribute handle).
+const ATT_HEADER_LEN: usize = 3;
+
+/// Log buffer size (default: 1024).
+///
+/// Can be customized by setting the `DEFMT_BLE_BUFFER_SIZE` environment variable.
+const BUF_SIZE: usize = defmt_buffer::env_usize!("DEFMT_BLE_BUFFER_SIZE", 1024);
+
+#[defmt::global_logger]
+struct Logger;
+
+/// Global logger lock.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+static mut CS_RESTORE: critical_section::RestoreState = critical_section::RestoreState::invalid();
+static mut ENCODER: defmt::Encoder = defmt::Encoder::new();
+static mut BUFFER: Buffer<BUF_SIZE> = Buffer::new();
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        // safety: Must be paired with corresponding call to release(), see below
+        let restore = unsafe { critical_section::acquire() };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if TAKEN.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly")
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(true, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe { CS_RESTORE = restore };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe {
+            BUFFER.start_frame();
+            ENCODER.start_frame(do_write)
+        }
+    }
+
+    unsafe fn flush() {
+        // Do nothing.
+        //
+        // The data is sent by `drain`, which cannot run while we hold the critical
+        // section. Waiting here would never finish.
+    }
+
+    unsafe fn release() {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.end_frame(do_write);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if BUFFER.end_frame() {
+            ENCODER.frame_dropped();
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(false, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        let restore = CS_RESTORE;
+
+        // safety: Must be paired with corresponding call to acquire(), see above
+        critical_section::release(restore);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.write(bytes, do_write);
+    }
+}
+
+fn do_write(bytes: &[u8]) {
+    // safety: only called from the logger, which holds the critical section
+    unsafe { BUFFER.write(bytes) }
+}
+
+/// Sequence number of the next notification.
+static SEQUENCE: AtomicU8 = AtomicU8::new(0);
+
+/// Sends buffered log data as notifications.
+///
+/// `att_mtu` is the ATT MTU negotiated for the connection; each notification is at most
+/// `att_mtu - 3` (and at most [`MAX_NOTIFICATION_LEN`]) bytes long. `notify` is called once per
+/// notification and must return `false` when the stack cannot queue more of them; the remaining
+/// data is kept for the next `drain`.
+///
+/// The critical section is not held while `notify` runs.
+pub fn drain(att_mtu: usize, mut notify: impl FnMut(&[u8]) -> bool) {
+    let max_len = usize::min(att_mtu.saturating_sub(ATT_HEADER_LEN), MAX_NOTIFICATION_LEN);
+    if max_len < 2 {
+        return;
+    }
+
+    let mut chunk = [0; MAX_NOTIFICATION_LEN];
+    loop {
+        let len = critical_section::with(|_| {
+            // safety: accessing the `static mut` is OK because we have acquired a critical section.
+            let buffer = unsafe { &BUFFER };
+            buffer.peek(&mut chunk[1..max_len]);
+            usize::min(buffer.len(), max_len - 1)
+        });
+        if len == 0 {
+            break;
+        }
+
+        let sequence = SEQUENCE.load(Ordering::Relaxed);
+        chunk[0] = sequence;
+        if !notify(&chunk[..=len]) {
+            break;
+        }
+        SEQUENCE.store(sequence.wrapping_add(1), Ordering::Relaxed);
+
+        // the logger only ever appends, so the peeked bytes are still the oldest ones
+        critical_section::with(|_| {
+            // safety: accessing the `static mut` is OK because we have acquired a critical section.
+            unsafe { BUFFER.consume(len) }
+        });
+    }
+}
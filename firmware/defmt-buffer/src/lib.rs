@@ -0,0 +1,201 @@
+//! Log frame buffer shared by the buffering [`defmt`](https://github.com/knurling-rs/defmt)
+//! transports.
+//!
+//! The transports encode log frames into a [`Buffer`] from the global logger and move the
+//! buffered data to the device later, outside of the logger. Their buffer size is configured at
+//! compile time with [`env_usize!`].
+//!
+//! This crate is an implementation detail of `defmt-ble`, `defmt-can`, `defmt-sdmmc` and
+//! `defmt-usb-cdc`; its API is not stable.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+/// Ring buffer holding encoded log frames until they are moved to the device.
+///
+/// Frames are stored whole or not at all: if a frame does not fit into the remaining space, the
+/// part of it that was already stored is discarded and the rest of the frame is ignored.
+/// [`Buffer::end_frame`] reports a dropped frame, which must be passed on to
+/// `defmt::Encoder::frame_dropped`: encodings that keep state across frames have to start over.
+pub struct Buffer<const N: usize> {
+    data: [u8; N],
+    /// Index of the oldest byte that has not been sent yet.
+    read: usize,
+    /// Number of bytes stored.
+    len: usize,
+    /// Number of bytes of the frame currently being written.
+    frame_len: usize,
+    /// Whether the frame currently being written is being discarded.
+    discarding: bool,
+}
+
+impl<const N: usize> Buffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            data: [0; N],
+            read: 0,
+            len: 0,
+            frame_len: 0,
+            discarding: false,
+        }
+    }
+
+    pub fn start_frame(&mut self) {
+        self.frame_len = 0;
+        self.discarding = false;
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        if self.discarding {
+            return;
+        }
+
+        if bytes.len() > N - self.len {
+            // drop what we've got of this frame so the host never sees a partial frame
+            self.len -= self.frame_len;
+            self.discarding = true;
+            return;
+        }
+
+        for &byte in bytes {
+            let write = (self.read + self.len) % N;
+            self.data[write] = byte;
+            self.len += 1;
+        }
+        self.frame_len += bytes.len();
+    }
+
+    /// Ends the frame started by [`Buffer::start_frame`], and returns whether it was dropped.
+    pub fn end_frame(&mut self) -> bool {
+        self.discarding
+    }
+
+    /// Number of bytes stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the longest contiguous run of bytes that's ready to be sent.
+    pub fn readable(&self) -> &[u8] {
+        let end = usize::min(self.read + self.len, N);
+        &self.data[self.read..end]
+    }
+
+    /// Copies the oldest stored bytes into `out`, without removing them.
+    pub fn peek(&self, out: &mut [u8]) {
+        for (i, byte) in out.iter_mut().take(self.len).enumerate() {
+            *byte = self.data[(self.read + i) % N];
+        }
+    }
+
+    /// Marks the `n` oldest bytes, as returned by [`Buffer::readable`] or [`Buffer::peek`], as
+    /// sent.
+    pub fn consume(&mut self, n: usize) {
+        self.read = (self.read + n) % N;
+        self.len -= n;
+    }
+}
+
+impl<const N: usize> Default for Buffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads a `usize` from the environment variable `$name` at compile time, or returns `$default`
+/// if it is not set.
+///
+/// The value is decimal, or hexadecimal if prefixed with `0x`. Building fails if it cannot be
+/// parsed.
+#[macro_export]
+macro_rules! env_usize {
+    ($name:literal, $default:expr) => {
+        match ::core::option_env!($name) {
+            Some(value) => $crate::parse_usize(
+                value,
+                ::core::concat!("could not parse ", $name, " as usize"),
+            ),
+            None => $default,
+        }
+    };
+}
+
+#[doc(hidden)]
+pub const fn parse_usize(value: &str, error: &str) -> usize {
+    let mut digits = value.as_bytes();
+    let mut radix = 10;
+    if let [b'0', b'x', rest @ ..] = digits {
+        digits = rest;
+        radix = 16;
+    }
+    if digits.is_empty() {
+        panic!("{}", error);
+    }
+
+    let mut result: usize = 0;
+    while let [byte, rest @ ..] = digits {
+        let digit = match *byte {
+            byte @ b'0'..=b'9' => byte - b'0',
+            byte @ b'a'..=b'f' => byte - b'a' + 10,
+            byte @ b'A'..=b'F' => byte - b'A' + 10,
+            _ => panic!("{}", error),
+        } as usize;
+        if digit >= radix {
+            panic!("{}", error);
+        }
+        result = match result.checked_mul(radix) {
+            Some(result) => match result.checked_add(digit) {
+                Some(result) => result,
+                None => panic!("{}", error),
+            },
+            None => panic!("{}", error),
+        };
+        digits = rest;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_frames_that_do_not_fit() {
+        let mut buffer = Buffer::<4>::new();
+        buffer.start_frame();
+        buffer.write(&[1, 2]);
+        assert!(!buffer.end_frame());
+        buffer.start_frame();
+        buffer.write(&[3]);
+        buffer.write(&[4, 5]);
+        // the rest of a dropped frame is skipped, even if it would fit
+        buffer.write(&[6]);
+        assert!(buffer.end_frame());
+        assert_eq!(buffer.readable(), &[1, 2]);
+
+        buffer.consume(1);
+        buffer.start_frame();
+        buffer.write(&[6, 7, 8]);
+        assert_eq!(buffer.readable(), &[2, 6, 7]);
+
+        let mut out = [0; 4];
+        buffer.peek(&mut out);
+        assert_eq!(out, [2, 6, 7, 8]);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_usize("1024", ""), 1024);
+        assert_eq!(parse_usize("0x7f0", ""), 0x7f0);
+    }
+
+    #[test]
+    #[should_panic = "invalid"]
+    fn parse_invalid() {
+        parse_usize("0x", "invalid");
+    }
+}
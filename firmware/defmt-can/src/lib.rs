@@ -0,0 +1,175 @@
+//! [`defmt`](https://github.com/knurling-rs/defmt) global logger over a CAN bus.
+//!
+//! To use this crate, link to it by importing it somewhere in your project and periodically call
+//! [`drain`] with a CAN peripheral that implements [`embedded_can::nb::Can`], e.g. from the
+//! transmit-mailbox-empty interrupt handler or the main loop.
+//!
+//! ``` ignore
+//! // src/main.rs or src/bin/my-app.rs
+//! use defmt_can as _;
+//!
+//! // in the CAN TX interrupt handler or the main loop
+//! defmt_can::drain(&mut can);
+//! ```
+//!
+//! # Wire format
+//!
+//! Log data is sent in classic CAN data frames with a standard identifier (`0x7f0` by default).
+//! The first data byte of every message is a sequence number that increments by one (wrapping)
+//! with every message; the remaining 1 to 7 bytes are the defmt byte stream.
+//!
+//! To reassemble the stream on the host, append the payloads of consecutive messages. If the
+//! sequence number of a message is not the expected one, messages were lost: discard the data
+//! up to the next frame boundary. This only works with the `rzcobs` encoding (the default), whose
+//! frames are delimited by zero bytes.
+//!
+//! # Configuration
+//!
+//! - `DEFMT_CAN_ID`: standard identifier of the log messages, in decimal or `0x`-prefixed
+//!   hexadecimal (default: `0x7f0`).
+//! - `DEFMT_CAN_BUFFER_SIZE`: size of the buffer holding log frames until they are sent
+//!   (default: 1024). When the buffer is full, new log frames are dropped entirely.
+//!
+//! # Critical section implementation
+//!
+//! This crate uses [`critical-section`](https://github.com/rust-embedded/critical-section) to
+//! synchronize the logger with [`drain`]. You must import a crate that provides a
+//! `critical-section` implementation suitable for the current target.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use defmt_buffer::Buffer;
+use embedded_can::{nb::Can, Frame, StandardId};
+
+/// Number of defmt bytes carried by each CAN message; the first data byte is the sequence number.
+const PAYLOAD_SIZE: usize = 7;
+
+/// Log buffer size (default: 1024).
+///
+/// Can be customized by setting the `DEFMT_CAN_BUFFER_SIZE` environment variable.
+const BUF_SIZE: usize = defmt_buffer::env_usize!("DEFMT_CAN_BUFFER_SIZE", 1024);
+
+/// Standard identifier of the log messages (default: 0x7f0).
+///
+/// Can be customized by setting the `DEFMT_CAN_ID` environment variable.
+const CAN_ID: u16 = {
+    let id = defmt_buffer::env_usize!("DEFMT_CAN_ID", 0x7f0);
+    assert!(
+        id <= 0x7ff,
+        "DEFMT_CAN_ID must be a standard (11-bit) identifier"
+    );
+    id as u16
+};
+
+#[defmt::global_logger]
+struct Logger;
+
+/// Global logger lock.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+static mut CS_RESTORE: critical_section::RestoreState = critical_section::RestoreState::invalid();
+static mut ENCODER: defmt::Encoder = defmt::Encoder::new();
+static mut BUFFER: Buffer<BUF_SIZE> = Buffer::new();
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        // safety: Must be paired with corresponding call to release(), see below
+        let restore = unsafe { critical_section::acquire() };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if TAKEN.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly")
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(true, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe { CS_RESTORE = restore };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe {
+            BUFFER.start_frame();
+            ENCODER.start_frame(do_write)
+        }
+    }
+
+    unsafe fn flush() {
+        // Do nothing.
+        //
+        // The data is sent over the bus by `drain`, which cannot run while we hold the critical
+        // section. Waiting here would never finish.
+    }
+
+    unsafe fn release() {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.end_frame(do_write);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if BUFFER.end_frame() {
+            ENCODER.frame_dropped();
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(false, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        let restore = CS_RESTORE;
+
+        // safety: Must be paired with corresponding call to acquire(), see above
+        critical_section::release(restore);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.write(bytes, do_write);
+    }
+}
+
+fn do_write(bytes: &[u8]) {
+    // safety: only called from the logger, which holds the critical section
+    unsafe { BUFFER.write(bytes) }
+}
+
+/// Sequence number of the next CAN message.
+static SEQUENCE: AtomicU8 = AtomicU8::new(0);
+
+/// Moves buffered log data onto the CAN bus.
+///
+/// Sends messages until the buffer is empty or the peripheral has no free transmit mailbox.
+pub fn drain<C: Can>(can: &mut C) {
+    critical_section::with(|_| {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        let buffer = unsafe { &mut BUFFER };
+
+        loop {
+            let readable = buffer.readable();
+            if readable.is_empty() {
+                break;
+            }
+
+            let len = usize::min(readable.len(), PAYLOAD_SIZE);
+            let mut data = [0; PAYLOAD_SIZE + 1];
+            data[0] = SEQUENCE.load(Ordering::Relaxed);
+            data[1..=len].copy_from_slice(&readable[..len]);
+
+            // `CAN_ID` is checked to be a standard identifier at compile time
+            let id = StandardId::new(CAN_ID).unwrap();
+            let Some(frame) = C::Frame::new(id, &data[..=len]) else {
+                break;
+            };
+
+            match can.transmit(&frame) {
+                // a displaced lower-priority frame is not ours to retry
+                Ok(_) => {
+                    buffer.consume(len);
+                    SEQUENCE.store(data[0].wrapping_add(1), Ordering::Relaxed);
+                }
+                // no free mailbox (`WouldBlock`) or a bus error; try again on the next `drain`
+                Err(_) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,16 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(has_basepri)");
+    println!("cargo:rustc-check-cfg=cfg(has_cyccnt)");
+    let target = env::var("TARGET").unwrap();
+
+    // ARMv6-M and ARMv8-M Baseline cores have neither a `BASEPRI` register nor a DWT cycle counter
+    if target.starts_with("thumbv7m")
+        || target.starts_with("thumbv7em")
+        || target.starts_with("thumbv8m.main")
+    {
+        println!("cargo:rustc-cfg=has_basepri");
+        println!("cargo:rustc-cfg=has_cyccnt");
+    }
+}
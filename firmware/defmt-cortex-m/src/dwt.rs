@@ -0,0 +1,71 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use cortex_m::peripheral::{DCB, DWT};
+
+/// A 64-bit cycle count based on the 32-bit DWT cycle counter (`CYCCNT`).
+///
+/// `CYCCNT` has no overflow interrupt, so wrap-arounds are detected when the count is read: a
+/// count lower than the previous one means the counter wrapped. This only works if the count is
+/// read at least once per wrap-around period (`2^32` cycles, about 67 seconds at 64 MHz), for
+/// example because something is logged at least that often, or from a periodic timer interrupt.
+///
+/// [`now`](Self::now) is meant to be called from `defmt::timestamp!`, which only runs while the
+/// global logger is acquired. Calling it from other, preempting execution contexts can lose a
+/// wrap-around.
+///
+/// Use the `ticks@HZ` display hint with the CPU frequency to display the cycle count as seconds.
+///
+/// # Example
+///
+/// ``` ignore
+/// use defmt_cortex_m::CycleCounter;
+///
+/// static CYCLES: CycleCounter = CycleCounter::new();
+///
+/// // the core runs at 64 MHz
+/// defmt::timestamp!("{=u64:ticks@64000000}", CYCLES.now());
+///
+/// #[cortex_m_rt::entry]
+/// fn main() -> ! {
+///     let mut core = cortex_m::Peripherals::take().unwrap();
+///     CycleCounter::enable(&mut core.DCB, &mut core.DWT);
+///     // ..
+/// }
+/// ```
+pub struct CycleCounter {
+    wraps: AtomicU32,
+    last: AtomicU32,
+}
+
+#[allow(clippy::new_without_default)]
+impl CycleCounter {
+    /// Creates a new cycle counter.
+    pub const fn new() -> Self {
+        Self {
+            wraps: AtomicU32::new(0),
+            last: AtomicU32::new(0),
+        }
+    }
+
+    /// Enables the DWT cycle counter.
+    ///
+    /// Until this is called, [`now`](Self::now) returns `0`.
+    pub fn enable(dcb: &mut DCB, dwt: &mut DWT) {
+        dcb.enable_trace();
+        DWT::unlock();
+        dwt.enable_cycle_counter();
+    }
+
+    /// Returns the number of CPU cycles since the cycle counter was enabled.
+    pub fn now(&self) -> u64 {
+        let count = DWT::cycle_count();
+        let mut wraps = self.wraps.load(Ordering::Relaxed);
+        if count < self.last.load(Ordering::Relaxed) {
+            wraps = wraps.wrapping_add(1);
+            self.wraps.store(wraps, Ordering::Relaxed);
+        }
+        self.last.store(count, Ordering::Relaxed);
+
+        u64::from(wraps) << 32 | u64::from(count)
+    }
+}
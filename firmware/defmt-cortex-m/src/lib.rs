@@ -0,0 +1,72 @@
+//! Cortex-M building blocks for implementing [`defmt`](https://github.com/knurling-rs/defmt)
+//! global loggers.
+//!
+//! The transport crates (`defmt-rtt`, `defmt-itm`, ...) each need to solve the same problems:
+//! making `acquire`/`release` interrupt-safe, and so on. This crate collects Cortex-M specific
+//! solutions to those problems so that custom loggers don't have to reimplement them.
+//!
+//! # Priority-aware locking
+//!
+//! [`BasepriLock`] implements the `Logger` lock by raising `BASEPRI` up to a configurable
+//! priority ceiling instead of disabling all interrupts. Interrupts more urgent than the ceiling
+//! keep running while a log frame is being written.
+//!
+//! ``` ignore
+//! use defmt_cortex_m::BasepriLock;
+//!
+//! // mask every interrupt with a hardware priority of 0x80 or lower urgency
+//! static LOCK: BasepriLock = BasepriLock::new(0x80);
+//! static mut ENCODER: defmt::Encoder = defmt::Encoder::new();
+//!
+//! #[defmt::global_logger]
+//! struct Logger;
+//!
+//! unsafe impl defmt::Logger for Logger {
+//!     fn acquire() {
+//!         LOCK.acquire();
+//!         // safety: accessing the `static mut` is OK because we hold the lock
+//!         unsafe { ENCODER.start_frame(do_write) }
+//!     }
+//!
+//!     unsafe fn flush() {}
+//!
+//!     unsafe fn release() {
+//!         ENCODER.end_frame(do_write);
+//!         LOCK.release();
+//!     }
+//!
+//!     unsafe fn write(bytes: &[u8]) {
+//!         ENCODER.write(bytes, do_write);
+//!     }
+//! }
+//! ```
+
+//!
+//! # 64-bit timestamps
+//!
+//! [`ExtendedCounter`] turns a wrapping 24- or 32-bit hardware counter into a monotonic 64-bit
+//! count that can be used in `defmt::timestamp!`. On cores with a DWT cycle counter,
+//! [`CycleCounter`] provides a cycle-accurate 64-bit timestamp for profiling.
+//!
+//! # Stack usage
+//!
+//! [`paint_main_stack`] paints the main stack for `defmt::stack::Stack`, reading the stack pointer
+//! from `MSP` with interrupts disabled, so no interrupt handler can have its stack frame
+//! overwritten.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+#[cfg(has_cyccnt)]
+mod dwt;
+#[cfg(has_basepri)]
+mod lock;
+mod stack;
+mod timestamp;
+
+#[cfg(has_cyccnt)]
+pub use crate::dwt::CycleCounter;
+#[cfg(has_basepri)]
+pub use crate::lock::BasepriLock;
+pub use crate::stack::paint_main_stack;
+pub use crate::timestamp::ExtendedCounter;
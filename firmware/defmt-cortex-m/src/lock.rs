@@ -0,0 +1,95 @@
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use cortex_m::register::{basepri, basepri_max};
+
+/// A `Logger` lock that masks interrupts up to a priority ceiling using the `BASEPRI` register.
+///
+/// While the lock is held, all interrupts with a priority at or below the ceiling are masked.
+/// Interrupts that are *more* urgent than the ceiling are still serviced, so their latency is not
+/// affected by logging.
+///
+/// The flip side is that those interrupts must not log: acquiring the lock from an execution
+/// context above the ceiling while it is already taken panics with "defmt logger taken
+/// reentrantly", just like the other defmt loggers do on re-entrant use.
+pub struct BasepriLock {
+    ceiling: u8,
+    taken: AtomicBool,
+    restore: AtomicU8,
+}
+
+impl BasepriLock {
+    /// Creates a lock that masks interrupts with a hardware priority of `ceiling` or lower
+    /// urgency.
+    ///
+    /// `ceiling` is the raw priority byte, i.e. the same value you would pass to
+    /// `NVIC::set_priority`. Lower values are more urgent. Only the upper, implemented priority
+    /// bits are taken into account by the hardware.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ceiling` is `0`, because writing `0` to `BASEPRI` disables masking altogether.
+    pub const fn new(ceiling: u8) -> Self {
+        assert!(ceiling != 0, "a `BASEPRI` ceiling of 0 masks nothing");
+
+        Self {
+            ceiling,
+            taken: AtomicBool::new(false),
+            restore: AtomicU8::new(0),
+        }
+    }
+
+    /// Creates a lock from a *logical* priority, as used by RTIC.
+    ///
+    /// Logical priority `1` is the least urgent interrupt priority and `1 << nvic_prio_bits` is
+    /// the most urgent one. `nvic_prio_bits` is the number of priority bits implemented by the
+    /// device (`NVIC_PRIO_BITS` in most PACs).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `priority` is not in the range `1..(1 << nvic_prio_bits)` or if
+    /// `nvic_prio_bits` is not in the range `1..=8`. The most urgent logical priority cannot be
+    /// expressed through `BASEPRI`.
+    pub const fn from_logical(priority: u8, nvic_prio_bits: u8) -> Self {
+        assert!(nvic_prio_bits >= 1 && nvic_prio_bits <= 8);
+        let levels = 1u16 << nvic_prio_bits;
+        assert!(priority >= 1 && (priority as u16) < levels);
+
+        Self::new(((levels - priority as u16) as u8) << (8 - nvic_prio_bits))
+    }
+
+    /// Acquires the lock in the current execution context.
+    ///
+    /// Call this from `Logger::acquire`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is already taken.
+    pub fn acquire(&self) {
+        let previous = basepri::read();
+        // `BASEPRI_MAX` only ever *raises* the masking level, so acquiring from a context that
+        // already runs with a stricter `BASEPRI` leaves that level in place
+        basepri_max::write(self.ceiling);
+
+        // no need for CAS because every context that can log is now masked
+        if self.taken.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly")
+        }
+        self.taken.store(true, Ordering::Relaxed);
+        self.restore.store(previous, Ordering::Relaxed);
+    }
+
+    /// Releases the lock and restores the previous `BASEPRI` value.
+    ///
+    /// Call this from `Logger::release`.
+    ///
+    /// # Safety
+    ///
+    /// Must be called exactly once for each `acquire()`, in the same execution context.
+    pub unsafe fn release(&self) {
+        let previous = self.restore.load(Ordering::Relaxed);
+        self.taken.store(false, Ordering::Relaxed);
+
+        // safety: restores the value that was active before `acquire`
+        basepri::write(previous);
+    }
+}
@@ -0,0 +1,15 @@
+use cortex_m::{interrupt, register::msp};
+use defmt::stack::Stack;
+
+/// Paints the part of the main stack that isn't in use yet, see `defmt::stack::Stack::paint`.
+///
+/// `stack` must be the main stack, e.g. `_stack_end.._stack_start` of `cortex-m-rt`. Call this
+/// once, as early as possible, from thread mode, e.g. at the start of `main`. Interrupts are
+/// disabled while painting.
+pub fn paint_main_stack(stack: &Stack) {
+    interrupt::free(|_| {
+        // safety: `MSP` is the stack pointer of the main stack, and no interrupt handler can use the
+        // stack while interrupts are disabled
+        unsafe { stack.paint(msp::read() as *const u32) }
+    });
+}
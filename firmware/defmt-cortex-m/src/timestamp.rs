@@ -0,0 +1,183 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Extends a wrapping hardware counter (e.g. the 24-bit SysTick or a 32-bit RTC) into a
+/// monotonic 64-bit count.
+///
+/// The number of times the hardware counter wrapped around is tracked in software: call
+/// [`on_wrap`](Self::on_wrap) from the interrupt that fires when the counter wraps, and
+/// [`now`](Self::now) wherever the 64-bit count is needed, e.g. in `defmt::timestamp!`.
+///
+/// `now` may run while the wrap-around interrupt is pending but cannot run yet, for example in an
+/// execution context that preempts it or with interrupts disabled, like the timestamp of a log
+/// frame written inside a critical section. The wrap-around is not counted yet at that point, so
+/// `now` also takes the interrupt's pending flag and accounts for the wrap-around itself.
+///
+/// # Example
+///
+/// ``` ignore
+/// use cortex_m::peripheral::{SCB, SYST};
+/// use defmt_cortex_m::ExtendedCounter;
+///
+/// // SysTick counts down from `RELOAD` to 0, so it has a period of `RELOAD + 1` ticks
+/// const RELOAD: u32 = 0x00ff_ffff;
+/// static SYSTICK: ExtendedCounter = ExtendedCounter::new(RELOAD as u64 + 1);
+///
+/// defmt::timestamp!(
+///     "{=u64:ticks@64000000}",
+///     SYSTICK.now(|| RELOAD - SYST::get_current(), SCB::is_pendst_pending)
+/// );
+///
+/// #[cortex_m_rt::exception]
+/// fn SysTick() {
+///     SYSTICK.on_wrap();
+/// }
+/// ```
+pub struct ExtendedCounter {
+    period: u64,
+    wraps: AtomicU32,
+}
+
+impl ExtendedCounter {
+    /// Creates an extender for a hardware counter that counts from `0` to `period - 1` before
+    /// wrapping around.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is `0`.
+    pub const fn new(period: u64) -> Self {
+        assert!(period != 0, "a counter period of 0 is not possible");
+
+        Self {
+            period,
+            wraps: AtomicU32::new(0),
+        }
+    }
+
+    /// Records that the hardware counter wrapped around.
+    ///
+    /// Call this exactly once per wrap-around, from the counter's interrupt handler. This uses
+    /// only atomic loads and stores, so it also works on ARMv6-M.
+    pub fn on_wrap(&self) {
+        let wraps = self.wraps.load(Ordering::Relaxed);
+        self.wraps.store(wraps.wrapping_add(1), Ordering::Relaxed);
+    }
+
+    /// Returns the extended 64-bit count.
+    ///
+    /// `read` must return the current value of the hardware counter, counting upwards. It may be
+    /// called more than once if the counter wraps around while it is being read.
+    ///
+    /// `pending` must return whether the wrap-around interrupt is pending, i.e. the counter
+    /// wrapped around but [`on_wrap`](Self::on_wrap) has not been called for it yet.
+    pub fn now(&self, read: impl Fn() -> u32, pending: impl Fn() -> bool) -> u64 {
+        loop {
+            let before = self.wraps.load(Ordering::Relaxed);
+            let mut wraps = before;
+            let mut count = read();
+            if pending() {
+                // `count` may be from before or after the wrap-around; read it again so that it
+                // is from after it
+                count = read();
+                wraps = wraps.wrapping_add(1);
+            }
+            let after = self.wraps.load(Ordering::Relaxed);
+
+            if before == after {
+                return u64::from(wraps) * self.period + u64::from(count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    const PERIOD: u64 = 100;
+
+    /// Returns the values of `reads` one after the other, then the last one again.
+    fn reads<'a>(reads: &'a [u32], i: &'a Cell<usize>) -> impl Fn() -> u32 + 'a {
+        move || {
+            let read = reads[i.get().min(reads.len() - 1)];
+            i.set(i.get() + 1);
+            read
+        }
+    }
+
+    #[test]
+    fn wrap() {
+        let counter = ExtendedCounter::new(PERIOD);
+        assert_eq!(counter.now(|| 90, || false), 90);
+
+        counter.on_wrap();
+        assert_eq!(counter.now(|| 5, || false), 105);
+    }
+
+    #[test]
+    fn pending_wrap_read_low() {
+        let counter = ExtendedCounter::new(PERIOD);
+        counter.on_wrap();
+
+        // the counter wrapped around before `now` read it
+        let i = Cell::new(0);
+        assert_eq!(counter.now(reads(&[5, 6], &i), || true), 206);
+    }
+
+    #[test]
+    fn pending_wrap_read_high() {
+        let counter = ExtendedCounter::new(PERIOD);
+        counter.on_wrap();
+
+        // the counter wrapped around between reading it and checking the pending flag
+        let i = Cell::new(0);
+        assert_eq!(counter.now(reads(&[99, 1], &i), || true), 201);
+    }
+
+    #[test]
+    fn wrap_while_reading() {
+        let counter = ExtendedCounter::new(PERIOD);
+
+        // the wrap-around interrupt preempts the first read
+        let i = Cell::new(0);
+        let read = reads(&[99, 3], &i);
+        let now = counter.now(
+            || {
+                if i.get() == 0 {
+                    counter.on_wrap();
+                }
+                read()
+            },
+            || false,
+        );
+        assert_eq!(now, 103);
+    }
+
+    #[test]
+    fn several_wraps() {
+        let counter = ExtendedCounter::new(PERIOD);
+        let ticks = Cell::new(0u64);
+        let handled = Cell::new(0u64);
+
+        let mut last = 0;
+        for step in 0..100 {
+            ticks.set(ticks.get() + 37);
+            // the interrupt only runs every third step, e.g. because of critical sections
+            if step % 3 == 0 {
+                while handled.get() < ticks.get() / PERIOD {
+                    counter.on_wrap();
+                    handled.set(handled.get() + 1);
+                }
+            }
+
+            let now = counter.now(
+                || (ticks.get() % PERIOD) as u32,
+                || handled.get() < ticks.get() / PERIOD,
+            );
+            assert_eq!(now, ticks.get());
+            assert!(now > last);
+            last = now;
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! [`defmt`](https://github.com/knurling-rs/defmt) global logger writing to an SD card or eMMC
+//! device.
+//!
+//! Log frames are buffered in RAM and written to a range of raw blocks of an
+//! [`embedded_sdmmc::BlockDevice`], one 512-byte block at a time. No filesystem is involved; the
+//! blocks hold the plain defmt byte stream, which can be read back with e.g.
+//! `dd if=/dev/sdX skip=<first block> | defmt-print -e <elf>`.
+//!
+//! To use this crate, link to it by importing it somewhere in your project, create a
+//! [`BlockLog`] covering the blocks reserved for logging and periodically call
+//! [`BlockLog::drain`] from a context where blocking on the card is acceptable.
+//!
+//! ``` ignore
+//! // src/main.rs or src/bin/my-app.rs
+//! use defmt_sdmmc::BlockLog;
+//!
+//! // blocks 0x1000 up to (but not including) 0x8000 are reserved for logs
+//! let mut log = BlockLog::new(0x1000, 0x8000);
+//!
+//! loop {
+//!     // ..
+//!     log.drain(&sd_card).unwrap();
+//! }
+//! ```
+//!
+//! Only complete blocks are written by [`BlockLog::drain`]. Call [`BlockLog::sync`] before
+//! powering down to also write the last, partially filled block.
+//!
+//! When the buffer is full, new log frames are dropped entirely. The buffer size defaults to 4096
+//! bytes and can be changed by setting the `DEFMT_SDMMC_BUFFER_SIZE` environment variable at
+//! compile time.
+//!
+//! # Encoding
+//!
+//! This crate enables the `encoding-rzcobs` feature of `defmt`, because the zero padding
+//! written by [`BlockLog::sync`] is skipped by the rzCOBS decoder only: in rzCOBS zero bytes
+//! separate frames and runs of them are ignored. Enabling any other `encoding-*` feature fails to
+//! build.
+//!
+//! # Critical section implementation
+//!
+//! This crate uses [`critical-section`](https://github.com/rust-embedded/critical-section) to
+//! synchronize the logger with [`BlockLog`]. You must import a crate that provides a
+//! `critical-section` implementation suitable for the current target.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt_buffer::Buffer;
+use embedded_sdmmc::{Block, BlockDevice, BlockIdx};
+
+/// Log buffer size (default: 4096).
+///
+/// Can be customized by setting the `DEFMT_SDMMC_BUFFER_SIZE` environment variable.
+const BUF_SIZE: usize = defmt_buffer::env_usize!("DEFMT_SDMMC_BUFFER_SIZE", 4096);
+
+#[defmt::global_logger]
+struct Logger;
+
+/// Global logger lock.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+static mut CS_RESTORE: critical_section::RestoreState = critical_section::RestoreState::invalid();
+static mut ENCODER: defmt::Encoder = defmt::Encoder::new();
+static mut BUFFER: Buffer<BUF_SIZE> = Buffer::new();
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        // safety: Must be paired with corresponding call to release(), see below
+        let restore = unsafe { critical_section::acquire() };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if TAKEN.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly")
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(true, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe { CS_RESTORE = restore };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe {
+            BUFFER.start_frame();
+            ENCODER.start_frame(do_write)
+        }
+    }
+
+    unsafe fn flush() {
+        // Do nothing.
+        //
+        // The data is written to the card by `BlockLog`, which cannot run while we hold the critical
+        // section. Waiting here would never finish.
+    }
+
+    unsafe fn release() {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.end_frame(do_write);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if BUFFER.end_frame() {
+            ENCODER.frame_dropped();
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(false, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        let restore = CS_RESTORE;
+
+        // safety: Must be paired with corresponding call to acquire(), see above
+        critical_section::release(restore);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.write(bytes, do_write);
+    }
+}
+
+fn do_write(bytes: &[u8]) {
+    // safety: only called from the logger, which holds the critical section
+    unsafe { BUFFER.write(bytes) }
+}
+
+/// Error returned by [`BlockLog`].
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The block device reported an error.
+    Device(E),
+    /// All blocks reserved for logging have been written.
+    Full,
+}
+
+/// Writes buffered log data to a range of blocks of a block device.
+///
+/// There must only be one `BlockLog` at a time.
+pub struct BlockLog {
+    next: u32,
+    end: u32,
+}
+
+impl BlockLog {
+    /// Creates a log that writes the blocks `start..end` of the device, in order.
+    pub const fn new(start: u32, end: u32) -> Self {
+        Self { next: start, end }
+    }
+
+    /// Returns the index of the block that will be written next.
+    pub fn next_block(&self) -> u32 {
+        self.next
+    }
+
+    /// Writes all complete blocks of buffered log data to `device`.
+    ///
+    /// The critical section is only held while copying data out of the buffer, not while the
+    /// device is busy, so logging from interrupts keeps working during the write.
+    pub fn drain<D: BlockDevice>(&mut self, device: &D) -> Result<(), Error<D::Error>> {
+        while buffered() >= Block::LEN {
+            self.write_block(device)?;
+        }
+        Ok(())
+    }
+
+    /// Writes all buffered log data to `device`, padding the last block with zeros.
+    ///
+    /// The padding is skipped by the rzCOBS decoder; data logged afterwards starts in the next
+    /// block.
+    pub fn sync<D: BlockDevice>(&mut self, device: &D) -> Result<(), Error<D::Error>> {
+        while buffered() != 0 {
+            self.write_block(device)?;
+        }
+        Ok(())
+    }
+
+    fn write_block<D: BlockDevice>(&mut self, device: &D) -> Result<(), Error<D::Error>> {
+        if self.next >= self.end {
+            return Err(Error::Full);
+        }
+
+        let mut block = Block::new();
+        let len = critical_section::with(|_| {
+            // safety: accessing the `static mut` is OK because we have acquired a critical section.
+            let buffer = unsafe { &BUFFER };
+            buffer.peek(&mut block.contents);
+            usize::min(buffer.len(), Block::LEN)
+        });
+
+        device
+            .write(core::slice::from_ref(&block), BlockIdx(self.next))
+            .map_err(Error::Device)?;
+        self.next += 1;
+
+        // the logger only ever appends, so the peeked bytes are still the oldest ones
+        critical_section::with(|_| {
+            // safety: accessing the `static mut` is OK because we have acquired a critical section.
+            unsafe { BUFFER.consume(len) }
+        });
+
+        Ok(())
+    }
+}
+
+fn buffered() -> usize {
+    critical_section::with(|_| {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe { BUFFER.len() }
+    })
+}
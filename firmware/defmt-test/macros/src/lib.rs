@@ -369,13 +369,18 @@ fn tests_impl(args: TokenStream, input: TokenStream) -> parse::Result<TokenStrea
         } else {
             quote!(#ident())
         };
+        let name = ident.to_string();
         if ignore {
-            unit_test_calls.push(quote!(let _ = #call;));
+            unit_test_calls.push(quote!(
+                let _ = #call;
+                __defmt_test_ignored += 1;
+            ));
         } else {
             unit_test_calls.push(quote!(
                 #before_each_call;
-                #krate::export::check_outcome(#call, #should_error);
+                #krate::export::check_outcome(defmt::intern!(#name), #call, #should_error);
                 #after_each_call;
+                __defmt_test_passed += 1;
             ));
         }
     }
@@ -402,13 +407,15 @@ fn tests_impl(args: TokenStream, input: TokenStream) -> parse::Result<TokenStrea
     let unit_test_progress = tests
         .iter()
         .map(|test| {
-            let message = format!(
-                "({{=usize}}/{{=usize}}) {} `{}`...",
-                if test.ignore { "ignoring" } else { "running" },
-                test.func.sig.ident
-            );
+            let name = test.func.sig.ident.to_string();
+            let ignore = test.ignore;
             quote_spanned! {
-                test.func.sig.ident.span() => defmt::println!(#message, __defmt_test_number, DEFMT_TEST_COUNT);
+                test.func.sig.ident.span() => #krate::export::test_started(
+                    __defmt_test_number,
+                    DEFMT_TEST_COUNT,
+                    defmt::intern!(#name),
+                    #ignore,
+                );
             }
         })
         .collect::<Vec<_>>();
@@ -423,6 +430,8 @@ fn tests_impl(args: TokenStream, input: TokenStream) -> parse::Result<TokenStrea
             #init_expr
 
             let mut __defmt_test_number: usize = 1;
+            let mut __defmt_test_passed: usize = 0;
+            let mut __defmt_test_ignored: usize = 0;
             #(
                 #(#test_cfgs)*
                 {
@@ -432,8 +441,7 @@ fn tests_impl(args: TokenStream, input: TokenStream) -> parse::Result<TokenStrea
                 }
             )*
 
-            defmt::println!("all tests passed!");
-            #krate::export::exit()
+            #krate::export::finished(__defmt_test_passed, __defmt_test_ignored)
         }
 
         #init_fn
@@ -1,6 +1,7 @@
 use cortex_m_rt as _;
 use cortex_m_semihosting::debug;
 pub use defmt::info;
+use defmt::{harness, Str};
 
 use crate::TestOutcome;
 
@@ -12,8 +13,18 @@ pub fn exit() -> ! {
     }
 }
 
-pub fn check_outcome<T: TestOutcome>(outcome: T, should_error: bool) {
+/// Reports that the test `name`, the `number`th of `count` tests, is about to run or is skipped.
+pub fn test_started(number: usize, count: usize, name: Str, ignore: bool) {
+    if ignore {
+        harness::test_ignored(number as u16, count as u16, name);
+    } else {
+        harness::test_started(number as u16, count as u16, name);
+    }
+}
+
+pub fn check_outcome<T: TestOutcome>(name: Str, outcome: T, should_error: bool) {
     if outcome.is_success() == should_error {
+        harness::test_failed(name);
         let note = if should_error {
             defmt::intern!("`#[should_error]` ")
         } else {
@@ -21,4 +32,13 @@ pub fn check_outcome<T: TestOutcome>(outcome: T, should_error: bool) {
         };
         defmt::panic!("{}test failed with outcome: {}", note, outcome);
     }
+    harness::test_passed(name);
+}
+
+/// Reports that all tests ran, then exits.
+///
+/// A failing test panics, so there are no failed tests at this point.
+pub fn finished(passed: usize, ignored: usize) -> ! {
+    harness::finished(passed as u16, 0, ignored as u16);
+    exit()
 }
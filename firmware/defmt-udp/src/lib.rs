@@ -0,0 +1,407 @@
+//! [`defmt`](https://github.com/knurling-rs/defmt) global logger for std targets, sending every log
+//! frame as a UDP datagram.
+//!
+//! This is meant for Linux-class devices that share code with microcontroller firmware: the code
+//! keeps using the `defmt` logging macros, and the frames are sent to a collector on the network
+//! instead of through a debug probe.
+//!
+//! To use this crate, link to it by importing it somewhere in your project and call [`init`] (or
+//! [`init_syslog`]) once at start-up. Frames logged before that are dropped.
+//!
+//! ``` ignore
+//! // src/main.rs
+//! fn main() -> std::io::Result<()> {
+//!     defmt_udp::init("192.168.1.10:5140")?;
+//!
+//!     defmt::info!("up and running");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Datagrams
+//!
+//! This crate enables the `host-logger` feature of `defmt`, so no linker script is needed. The
+//! strings are interned at runtime instead of being stored in the ELF file, in the order the
+//! program first uses them, so the collector can't read the string table from the ELF file.
+//! Instead, the strings are sent along with the frames. Every datagram starts with its kind:
+//!
+//! - `0`, followed by one frame, encoded with the encoding selected with the `encoding-*`
+//!   features of `defmt`
+//! - `1`, followed by the index of a string (`u32`, little endian), its tag, a `0` byte and the
+//!   string itself. It is sent before the first frame that uses the string.
+//!
+//! A collector that restarted, or that lost a datagram, can send the single byte
+//! [`TABLE_REQUEST`] back to the address the datagrams come from, and all strings are sent again.
+//! The request must be sent from the collector's address, since others are ignored.
+//!
+//! [`Datagram::parse`] splits the datagrams, and the collector builds the string table from the
+//! strings with `defmt::decoder::host_table_entry`:
+//!
+//! ``` ignore
+//! use std::collections::BTreeMap;
+//!
+//! use defmt::decoder::{host_table_entry, Encoding, Table};
+//! use defmt_udp::Datagram;
+//!
+//! let mut strings = BTreeMap::new();
+//! for datagram in datagrams {
+//!     match Datagram::parse(&datagram) {
+//!         Some(Datagram::String { index, string, tag }) => {
+//!             strings.insert(index, (string.to_string(), tag.to_string()));
+//!         }
+//!         Some(Datagram::Frame(frame)) => {
+//!             let entries = strings
+//!                 .iter()
+//!                 .map(|(&index, (string, tag))| (index, host_table_entry(string, tag)))
+//!                 .collect();
+//!             // the encoding selected on the sending side
+//!             let table = Table::new(entries, Encoding::Rzcobs);
+//!             let mut decoder = table.new_stream_decoder();
+//!             decoder.received(frame);
+//!             println!("{}", decoder.decode()?.display(false));
+//!         }
+//!         None => {} // not from `defmt-udp`
+//!     }
+//! }
+//! ```
+//!
+//! # syslog
+//!
+//! With [`init_syslog`], each datagram is prefixed with an RFC 5424 syslog header so the datagrams
+//! can be routed by an existing syslog collector. The datagram itself is the message body; use
+//! [`Datagram::parse_syslog`] to skip the header. The severity of each frame is derived from its
+//! log level, so syslog filters work on the defmt log levels.
+
+use std::{
+    cell::Cell,
+    io,
+    net::{ToSocketAddrs, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+};
+
+#[defmt::global_logger]
+struct Logger;
+
+/// Global logger lock.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+// these are only ever locked by the holder of the logger lock, so they never block
+static ENCODER: Mutex<defmt::Encoder> = Mutex::new(defmt::Encoder::new());
+/// The frame currently being logged.
+static FRAME: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+/// The string index the frame currently being logged starts with.
+static INDEX: Mutex<Leb128> = Mutex::new(Leb128::new());
+/// Number of interned strings that have been sent to the collector.
+static SENT_STRINGS: AtomicUsize = AtomicUsize::new(0);
+static SINK: OnceLock<Sink> = OnceLock::new();
+
+thread_local! {
+    /// Whether the current thread holds the logger lock.
+    static HOLDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Kind of a datagram that holds a frame.
+const FRAME_KIND: u8 = 0;
+/// Kind of a datagram that holds an interned string.
+const STRING_KIND: u8 = 1;
+
+/// The datagram a collector sends to request all interned strings.
+pub const TABLE_REQUEST: [u8; 1] = [2];
+
+struct Sink {
+    socket: UdpSocket,
+    syslog: Option<SyslogHeader>,
+}
+
+/// syslog settings used by [`init_syslog`].
+#[derive(Clone, Debug)]
+pub struct Syslog {
+    /// Facility code, 0 to 23 (e.g. 1 for user-level messages, 16 to 23 for local0 to local7).
+    pub facility: u8,
+    /// Severity code, 0 (emergency) to 7 (debug), of the datagrams that don't hold a frame with a
+    /// log level, like `println!` frames and strings.
+    ///
+    /// Frames with a log level have the severity of their level: 3 (error) for `error!`, 4
+    /// (warning) for `warn!`, 6 (informational) for `info!`, and 7 (debug) for `debug!` and
+    /// `trace!`.
+    pub severity: u8,
+    /// The `HOSTNAME` field.
+    pub hostname: String,
+    /// The `APP-NAME` field.
+    pub app_name: String,
+}
+
+struct SyslogHeader {
+    facility: u8,
+    severity: u8,
+    /// The header after the `PRI` field.
+    rest: String,
+}
+
+impl SyslogHeader {
+    fn write(&self, tag: Option<&str>, datagram: &mut Vec<u8>) {
+        let severity = match tag {
+            Some("error") => 3,
+            Some("warn") => 4,
+            Some("info") => 6,
+            Some("debug" | "trace") => 7,
+            _ => self.severity,
+        };
+        let priority = u16::from(self.facility) * 8 + u16::from(severity);
+        datagram.extend_from_slice(format!("<{}>{}", priority, self.rest).as_bytes());
+    }
+}
+
+/// A datagram sent by this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Datagram<'a> {
+    /// An encoded frame.
+    Frame(&'a [u8]),
+    /// An interned string, for the string table.
+    String {
+        /// The index of the string.
+        index: usize,
+        /// The string.
+        string: &'a str,
+        /// The tag of the string, e.g. `"info"`.
+        tag: &'a str,
+    },
+}
+
+impl<'a> Datagram<'a> {
+    /// Parses a datagram sent after [`init`].
+    ///
+    /// Returns `None` if `datagram` was not sent by this crate.
+    pub fn parse(datagram: &'a [u8]) -> Option<Self> {
+        match datagram.split_first()? {
+            (&FRAME_KIND, frame) => Some(Datagram::Frame(frame)),
+            (&STRING_KIND, data) => {
+                let (index, data) = data.split_first_chunk::<4>()?;
+                let separator = data.iter().position(|&byte| byte == 0)?;
+                Some(Datagram::String {
+                    index: u32::from_le_bytes(*index) as usize,
+                    tag: std::str::from_utf8(&data[..separator]).ok()?,
+                    string: std::str::from_utf8(&data[separator + 1..]).ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses a datagram sent after [`init_syslog`], skipping its syslog header.
+    ///
+    /// Returns `None` if `datagram` was not sent by this crate.
+    pub fn parse_syslog(datagram: &'a [u8]) -> Option<Self> {
+        // PRI and VERSION, TIMESTAMP, HOSTNAME, APP-NAME, PROCID, MSGID and STRUCTURED-DATA
+        let mut body = datagram;
+        for _ in 0..7 {
+            let space = body.iter().position(|&byte| byte == b' ')?;
+            body = &body[space + 1..];
+        }
+        Self::parse(body)
+    }
+}
+
+/// Sends all log frames to `collector`.
+///
+/// Returns an error if the socket cannot be set up, or if the logger was already initialized.
+pub fn init(collector: impl ToSocketAddrs) -> io::Result<()> {
+    init_sink(collector, None)
+}
+
+/// Sends all log frames to `collector`, each wrapped in an RFC 5424 syslog message.
+///
+/// Returns an error if the socket cannot be set up, or if the logger was already initialized.
+pub fn init_syslog(collector: impl ToSocketAddrs, syslog: Syslog) -> io::Result<()> {
+    if syslog.facility > 23 || syslog.severity > 7 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "syslog facility must be at most 23 and severity at most 7",
+        ));
+    }
+
+    let rest = format!(
+        "1 - {} {} {} - - ",
+        nil_if_empty(&syslog.hostname),
+        nil_if_empty(&syslog.app_name),
+        std::process::id(),
+    );
+    let header = SyslogHeader {
+        facility: syslog.facility,
+        severity: syslog.severity,
+        rest,
+    };
+    init_sink(collector, Some(header))
+}
+
+fn nil_if_empty(field: &str) -> &str {
+    if field.is_empty() {
+        "-"
+    } else {
+        field
+    }
+}
+
+fn init_sink(collector: impl ToSocketAddrs, syslog: Option<SyslogHeader>) -> io::Result<()> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.connect(collector)?;
+    let requests = socket.try_clone()?;
+
+    SINK.set(Sink { socket, syslog }).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "defmt-udp logger already initialized",
+        )
+    })?;
+
+    thread::Builder::new()
+        .name("defmt-udp".to_string())
+        .spawn(move || serve_table_requests(requests))?;
+    Ok(())
+}
+
+/// Sends all interned strings whenever the collector requests them.
+fn serve_table_requests(socket: UdpSocket) {
+    let mut request = [0; TABLE_REQUEST.len() + 1];
+    loop {
+        // e.g. `ConnectionRefused` while no collector is listening
+        let Ok(len) = socket.recv(&mut request) else {
+            continue;
+        };
+        if let (Some(sink), true) = (SINK.get(), request[..len] == TABLE_REQUEST) {
+            sink.send_strings(1..=defmt::decoder::host_string_count());
+        }
+    }
+}
+
+impl Sink {
+    /// Sends the interned strings with the indices in `indices`.
+    fn send_strings(&self, indices: impl Iterator<Item = usize>) {
+        for index in indices {
+            let Some((string, tag)) = defmt::decoder::host_string(index) else {
+                continue;
+            };
+            let mut datagram = Vec::new();
+            if let Some(syslog) = &self.syslog {
+                syslog.write(None, &mut datagram);
+            }
+            datagram.push(STRING_KIND);
+            datagram.extend_from_slice(&(index as u32).to_le_bytes());
+            datagram.extend_from_slice(tag.as_bytes());
+            datagram.push(0);
+            datagram.extend_from_slice(string.as_bytes());
+            // logging must not fail; a lost string can be requested again
+            let _ = self.socket.send(&datagram);
+        }
+    }
+
+    /// Sends `frame`, which starts with the string `index`.
+    fn send_frame(&self, index: Option<usize>, frame: &[u8]) {
+        let mut datagram = Vec::new();
+        if let Some(syslog) = &self.syslog {
+            let tag = index
+                .and_then(defmt::decoder::host_string)
+                .map(|(_, tag)| tag);
+            syslog.write(tag, &mut datagram);
+        }
+        datagram.push(FRAME_KIND);
+        datagram.extend_from_slice(frame);
+        // logging must not fail; a lost datagram is a lost frame
+        let _ = self.socket.send(&datagram);
+    }
+}
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        if HOLDING.with(Cell::get) {
+            panic!("defmt logger taken reentrantly")
+        }
+
+        while TAKEN
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            std::hint::spin_loop();
+        }
+        HOLDING.with(|holding| holding.set(true));
+
+        lock(&FRAME).clear();
+        *lock(&INDEX) = Leb128::new();
+        lock(&ENCODER).start_frame(do_write);
+    }
+
+    unsafe fn flush() {
+        // Do nothing.
+        //
+        // Every frame is sent by `release`, and UDP has no buffers the process could flush.
+    }
+
+    unsafe fn release() {
+        lock(&ENCODER).end_frame(do_write);
+
+        if let Some(sink) = SINK.get() {
+            // the strings of the frame have been interned while it was written
+            let count = defmt::decoder::host_string_count();
+            let sent = SENT_STRINGS.swap(count, Ordering::Relaxed);
+            sink.send_strings(sent + 1..=count);
+
+            sink.send_frame(lock(&INDEX).value(), &lock(&FRAME));
+        }
+
+        HOLDING.with(|holding| holding.set(false));
+        TAKEN.store(false, Ordering::Release);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        lock(&INDEX).push(bytes);
+        lock(&ENCODER).write(bytes, do_write);
+    }
+}
+
+fn do_write(bytes: &[u8]) {
+    lock(&FRAME).extend_from_slice(bytes)
+}
+
+/// Locks `mutex`, ignoring poisoning: a panic while logging must not disable the logger.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Decodes the LEB128 string index a frame starts with, from the data written to the frame.
+struct Leb128 {
+    value: u64,
+    shift: u32,
+    done: bool,
+}
+
+impl Leb128 {
+    const fn new() -> Self {
+        Self {
+            value: 0,
+            shift: 0,
+            done: false,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if self.done {
+                return;
+            }
+            if self.shift < u64::BITS {
+                self.value |= u64::from(byte & 0x7f) << self.shift;
+            }
+            self.shift += 7;
+            self.done = byte & 0x80 == 0;
+        }
+    }
+
+    fn value(&self) -> Option<usize> {
+        self.done.then_some(self.value as usize)
+    }
+}
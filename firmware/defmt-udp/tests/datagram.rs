@@ -0,0 +1,94 @@
+//! Log frames sent as datagrams can be decoded by a collector with the strings sent along with them
+
+use std::{
+    collections::BTreeMap,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use defmt::decoder::{Encoding, Table};
+use defmt_udp::{Datagram, TABLE_REQUEST};
+
+/// A collector in another process: it only knows what it receives
+struct Collector {
+    socket: UdpSocket,
+    strings: BTreeMap<usize, (String, String)>,
+}
+
+impl Collector {
+    fn new() -> Self {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        Self {
+            socket,
+            strings: BTreeMap::new(),
+        }
+    }
+
+    fn table(&self) -> Table {
+        let entries = self
+            .strings
+            .iter()
+            .map(|(&index, (string, tag))| (index, defmt::decoder::host_table_entry(string, tag)))
+            .collect();
+        Table::new(entries, Encoding::Rzcobs)
+    }
+
+    fn add_string(&mut self, datagram: &[u8]) {
+        match Datagram::parse(datagram).unwrap() {
+            Datagram::String { index, string, tag } => {
+                self.strings
+                    .insert(index, (string.to_string(), tag.to_string()));
+            }
+            Datagram::Frame(_) => panic!("frame sent instead of a string"),
+        }
+    }
+
+    /// Receives datagrams up to the next frame, and returns it displayed, and the sender
+    fn next_frame(&mut self) -> (String, SocketAddr) {
+        let mut datagram = [0; 1500];
+        loop {
+            let (len, sender) = self.socket.recv_from(&mut datagram).unwrap();
+            match Datagram::parse(&datagram[..len]).unwrap() {
+                Datagram::String { .. } => self.add_string(&datagram[..len]),
+                Datagram::Frame(frame) => {
+                    let table = self.table();
+                    let mut decoder = table.new_stream_decoder();
+                    decoder.received(frame);
+                    let frame = decoder.decode().unwrap();
+                    return (frame.display(false).to_string(), sender);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn sends_frames_and_strings() {
+    let mut collector = Collector::new();
+    // interned before `init`, so it's sent with the first frame
+    defmt::error!("early");
+    defmt_udp::init(collector.socket.local_addr().unwrap()).unwrap();
+
+    defmt::error!("early");
+    defmt::error!("hello {=u8}", 42);
+    defmt::error!("hello {=u8}", 43);
+    assert_eq!(collector.next_frame().0, "ERROR early");
+    assert_eq!(collector.next_frame().0, "ERROR hello 42");
+    let (frame, device) = collector.next_frame();
+    assert_eq!(frame, "ERROR hello 43");
+
+    // a restarted collector lost the strings, and requests them again
+    collector.strings.clear();
+    collector.socket.send_to(&TABLE_REQUEST, device).unwrap();
+    let mut datagram = [0; 1500];
+    for _ in 0..defmt::decoder::host_string_count() {
+        let len = collector.socket.recv(&mut datagram).unwrap();
+        collector.add_string(&datagram[..len]);
+    }
+
+    defmt::error!("hello {=u8}", 44);
+    assert_eq!(collector.next_frame().0, "ERROR hello 44");
+}
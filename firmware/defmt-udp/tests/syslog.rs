@@ -0,0 +1,69 @@
+//! Log frames sent as syslog messages have the severity of their log level
+
+use std::{collections::BTreeMap, net::UdpSocket, time::Duration};
+
+use defmt::decoder::{Encoding, Table};
+use defmt_udp::{Datagram, Syslog};
+
+#[test]
+fn sends_syslog_messages() {
+    let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+    collector
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let syslog = Syslog {
+        facility: 16,
+        severity: 5,
+        hostname: "device".to_string(),
+        app_name: String::new(),
+    };
+    defmt_udp::init_syslog(collector.local_addr().unwrap(), syslog).unwrap();
+
+    defmt::error!("hello {=u8}", 42);
+    defmt::warn!("careful");
+    defmt::info!("fine");
+    defmt::debug!("details");
+    defmt::trace!("more details");
+    defmt::println!("plain");
+
+    let mut strings = BTreeMap::new();
+    let mut datagram = [0; 1500];
+    let mut frames = Vec::new();
+    while frames.len() < 6 {
+        let len = collector.recv(&mut datagram).unwrap();
+        let datagram = &datagram[..len];
+        let header_len = datagram.len()
+            - match Datagram::parse_syslog(datagram).unwrap() {
+                Datagram::String { index, string, tag } => {
+                    strings.insert(index, (string.to_string(), tag.to_string()));
+                    continue;
+                }
+                Datagram::Frame(frame) => frame.len() + 1,
+            };
+        let header = String::from_utf8(datagram[..header_len].to_vec()).unwrap();
+        let entries = strings
+            .iter()
+            .map(|(&index, (string, tag))| (index, defmt::decoder::host_table_entry(string, tag)))
+            .collect();
+        let table = Table::new(entries, Encoding::Rzcobs);
+        let mut decoder = table.new_stream_decoder();
+        decoder.received(&datagram[header_len + 1..]);
+        let frame = decoder.decode().unwrap();
+        frames.push((header, frame.display(false).to_string()));
+    }
+
+    // local0 (16 * 8) plus the severity of the level, or the configured one without a level
+    let pid = std::process::id();
+    let expected = [
+        (131, "ERROR hello 42"),
+        (132, "WARN careful"),
+        (134, "INFO fine"),
+        (135, "DEBUG details"),
+        (135, "TRACE more details"),
+        (133, "plain"),
+    ];
+    for ((header, frame), (priority, expected)) in frames.iter().zip(expected) {
+        assert_eq!(*header, format!("<{priority}>1 - device - {pid} - - "));
+        assert_eq!(frame, expected);
+    }
+}
@@ -0,0 +1,145 @@
+//! [`defmt`](https://github.com/knurling-rs/defmt) global logger over a USB CDC-ACM serial port.
+//!
+//! To use this crate, link to it by importing it somewhere in your project and periodically call
+//! [`drain`] with your USB device and serial port, e.g. from the USB interrupt handler right after
+//! polling the device.
+//!
+//! ``` ignore
+//! // src/main.rs or src/bin/my-app.rs
+//! use defmt_usb_cdc as _;
+//!
+//! // in the USB interrupt handler or the main loop
+//! usb_dev.poll(&mut [&mut serial]);
+//! defmt_usb_cdc::drain(&usb_dev, &mut serial);
+//! ```
+//!
+//! # Enumeration
+//!
+//! Log frames are buffered until the host has configured the USB device. Once it has, [`drain`]
+//! moves as much buffered data into the serial port as it accepts. This means messages logged
+//! during start-up, before enumeration completes, are not lost as long as they fit into the
+//! buffer.
+//!
+//! When the buffer is full, new log frames are dropped entirely; the host never receives partial
+//! frames. The buffer size defaults to 1024 bytes and can be changed by setting the
+//! `DEFMT_USB_CDC_BUFFER_SIZE` environment variable at compile time.
+//!
+//! # Critical section implementation
+//!
+//! This crate uses [`critical-section`](https://github.com/rust-embedded/critical-section) to
+//! synchronize the logger with [`drain`]. You must import a crate that provides a
+//! `critical-section` implementation suitable for the current target.
+
+#![doc(html_logo_url = "https://knurling.ferrous-systems.com/knurling_logo_light_text.svg")]
+#![no_std]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt_buffer::Buffer;
+use usb_device::{
+    bus::UsbBus,
+    device::{UsbDevice, UsbDeviceState},
+};
+use usbd_serial::SerialPort;
+
+/// Log buffer size (default: 1024).
+///
+/// Can be customized by setting the `DEFMT_USB_CDC_BUFFER_SIZE` environment variable.
+const BUF_SIZE: usize = defmt_buffer::env_usize!("DEFMT_USB_CDC_BUFFER_SIZE", 1024);
+
+#[defmt::global_logger]
+struct Logger;
+
+/// Global logger lock.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+static mut CS_RESTORE: critical_section::RestoreState = critical_section::RestoreState::invalid();
+static mut ENCODER: defmt::Encoder = defmt::Encoder::new();
+static mut BUFFER: Buffer<BUF_SIZE> = Buffer::new();
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        // safety: Must be paired with corresponding call to release(), see below
+        let restore = unsafe { critical_section::acquire() };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if TAKEN.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly")
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(true, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe { CS_RESTORE = restore };
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        unsafe {
+            BUFFER.start_frame();
+            ENCODER.start_frame(do_write)
+        }
+    }
+
+    unsafe fn flush() {
+        // Do nothing.
+        //
+        // The data is moved to the host by `drain`, which cannot run while we hold the critical
+        // section. Waiting here would never finish.
+    }
+
+    unsafe fn release() {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.end_frame(do_write);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        if BUFFER.end_frame() {
+            ENCODER.frame_dropped();
+        }
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        TAKEN.store(false, Ordering::Relaxed);
+
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        let restore = CS_RESTORE;
+
+        // safety: Must be paired with corresponding call to acquire(), see above
+        critical_section::release(restore);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        ENCODER.write(bytes, do_write);
+    }
+}
+
+fn do_write(bytes: &[u8]) {
+    // safety: only called from the logger, which holds the critical section
+    unsafe { BUFFER.write(bytes) }
+}
+
+/// Moves buffered log data into the USB serial port.
+///
+/// Call this after every `UsbDevice::poll`. Nothing is sent until the host has configured the
+/// device; data is kept in the buffer in the meantime.
+pub fn drain<B: UsbBus>(device: &UsbDevice<'_, B>, serial: &mut SerialPort<'_, B>) {
+    if device.state() != UsbDeviceState::Configured {
+        return;
+    }
+
+    critical_section::with(|_| {
+        // safety: accessing the `static mut` is OK because we have acquired a critical section.
+        let buffer = unsafe { &mut BUFFER };
+
+        loop {
+            let readable = buffer.readable();
+            if readable.is_empty() {
+                break;
+            }
+
+            match serial.write(readable) {
+                Ok(n) => buffer.consume(n),
+                // the endpoint is busy (`WouldBlock`) or gone; try again on the next `drain`
+                Err(_) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,29 @@
+#![no_std]
+#![no_main]
+
+use cortex_m_rt::entry;
+
+use defmt_semihosting as _; // global logger
+
+#[entry]
+fn main() -> ! {
+    defmt::assert_matches!(Some(1 + 1), Some(2));
+    defmt::assert_matches!(1 + 1, 1 | 2);
+
+    let x: Option<u8> = Some(42);
+    defmt::assert_matches!(x, Some(y) if y > 0);
+    defmt::debug_assert_matches!(x, None, "dev");
+    defmt::assert_matches!(x, None, "release");
+    defmt::unreachable!();
+}
+
+// like `panic-semihosting` but doesn't print to stdout (that would corrupt the defmt stream)
+#[cfg(target_os = "none")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    use cortex_m_semihosting::debug;
+
+    loop {
+        debug::exit(debug::EXIT_SUCCESS)
+    }
+}
@@ -1,66 +1,154 @@
 use proc_macro::TokenStream;
-use proc_macro_error::{abort, abort_call_site};
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
 use quote::quote;
-use syn::{parse_macro_input, Fields, ItemStruct};
+use syn::{parse_macro_input, punctuated::Punctuated, Fields, Ident, Item, Token};
 
 pub(crate) fn expand(args: TokenStream, item: TokenStream) -> TokenStream {
-    if !args.is_empty() {
-        abort_call_site!("`#[global_logger]` attribute takes no arguments")
+    let args = parse_macro_input!(args with Punctuated::<Ident, Token![,]>::parse_terminated);
+    let (mut guarded, mut inline) = (false, false);
+    for arg in &args {
+        let flag = if arg == "guarded" {
+            &mut guarded
+        } else if arg == "inline" {
+            &mut inline
+        } else {
+            abort!(
+                arg,
+                "`#[global_logger]` attribute only accepts the `guarded` and `inline` arguments"
+            )
+        };
+        if *flag {
+            abort!(arg, "duplicate `{}` argument", arg)
+        }
+        *flag = true;
     }
 
-    let strukt = parse_macro_input!(item as ItemStruct);
+    let item = parse_macro_input!(item as Item);
 
-    validate(&strukt);
+    let ident = validate(&item);
 
-    codegen(&strukt)
+    codegen(&item, ident, guarded, inline)
 }
 
-fn validate(strukt: &ItemStruct) {
-    let is_unit_struct = matches!(strukt.fields, Fields::Unit);
-
-    if !strukt.generics.params.is_empty()
-        || strukt.generics.where_clause.is_some()
-        || !is_unit_struct
-    {
-        abort!(
-            strukt,
-            "struct must be a non-generic unit struct (e.g. `struct S;`)"
-        );
+/// Returns the name of the logger.
+fn validate(item: &Item) -> &Ident {
+    match item {
+        Item::Struct(strukt) => {
+            let is_unit_struct = matches!(strukt.fields, Fields::Unit);
+
+            if !strukt.generics.params.is_empty()
+                || strukt.generics.where_clause.is_some()
+                || !is_unit_struct
+            {
+                abort!(
+                    strukt,
+                    "struct must be a non-generic unit struct (e.g. `struct S;`)"
+                );
+            }
+            &strukt.ident
+        }
+        // a logger defined in another crate, e.g. `type Logger = defmt::mock::MockLogger;`
+        Item::Type(alias) => {
+            if !alias.generics.params.is_empty() || alias.generics.where_clause.is_some() {
+                abort!(alias, "type alias must not be generic");
+            }
+            &alias.ident
+        }
+        _ => abort!(
+            item,
+            "`#[global_logger]` must be put on a unit struct or a type alias"
+        ),
     }
 }
 
-fn codegen(strukt: &ItemStruct) -> TokenStream {
-    let attrs = &strukt.attrs;
-    let ident = &strukt.ident;
-    let vis = &strukt.vis;
+fn codegen(item: &Item, ident: &Ident, guarded: bool, inline: bool) -> TokenStream {
+    // `_defmt_write` is called for every primitive that is logged; with `inline`, LTO can inline
+    // the logger's `write` into the callers instead of calling through the shim
+    let write_inline = if inline {
+        quote!(#[inline])
+    } else {
+        quote!(#[inline(never)])
+    };
+
+    let functions = if guarded {
+        codegen_guarded(ident, write_inline)
+    } else {
+        quote!(
+            #[inline(never)]
+            #[no_mangle]
+            unsafe fn _defmt_acquire()  {
+                <#ident as defmt::Logger>::acquire()
+            }
+
+            #[inline(never)]
+            #[no_mangle]
+            unsafe fn _defmt_flush()  {
+                <#ident as defmt::Logger>::flush()
+            }
+
+            #[inline(never)]
+            #[no_mangle]
+            unsafe fn _defmt_release()  {
+                <#ident as defmt::Logger>::release()
+            }
+
+            #write_inline
+            #[no_mangle]
+            unsafe fn _defmt_write(bytes: &[u8])  {
+                <#ident as defmt::Logger>::write(bytes)
+            }
+
+            // NOTE the linker script provides the same default, but it isn't used on the host
+            #[inline(never)]
+            #[no_mangle]
+            unsafe fn _defmt_acquired() -> bool {
+                true
+            }
+        )
+    };
 
     quote!(
-        #(#attrs)*
-        #vis struct #ident;
+        #item
+
+        #functions
+    )
+    .into()
+}
+
+fn codegen_guarded(ident: &Ident, write_inline: TokenStream2) -> TokenStream2 {
+    quote!(
+        static __DEFMT_GUARD_SLOT: defmt::export::GuardSlot<#ident> =
+            defmt::export::GuardSlot::new();
 
         #[inline(never)]
         #[no_mangle]
         unsafe fn _defmt_acquire()  {
-            <#ident as defmt::Logger>::acquire()
+            __DEFMT_GUARD_SLOT.acquire()
         }
 
         #[inline(never)]
         #[no_mangle]
         unsafe fn _defmt_flush()  {
-            <#ident as defmt::Logger>::flush()
+            __DEFMT_GUARD_SLOT.flush()
         }
 
         #[inline(never)]
         #[no_mangle]
         unsafe fn _defmt_release()  {
-            <#ident as defmt::Logger>::release()
+            __DEFMT_GUARD_SLOT.release()
         }
 
-        #[inline(never)]
+        #write_inline
         #[no_mangle]
         unsafe fn _defmt_write(bytes: &[u8])  {
-            <#ident as defmt::Logger>::write(bytes)
+            __DEFMT_GUARD_SLOT.write(bytes)
+        }
+
+        #[inline(never)]
+        #[no_mangle]
+        unsafe fn _defmt_acquired() -> bool {
+            __DEFMT_GUARD_SLOT.acquired()
         }
     )
-    .into()
 }
@@ -36,19 +36,17 @@ pub(crate) fn interned_string(string: &str, tag: &str, is_log_statement: bool) -
         format_ident!("S")
     };
 
-    let var_addr = if cfg!(feature = "unstable-test") {
-        quote!({ defmt::export::fetch_add_string_index() })
+    if cfg!(any(feature = "unstable-test", feature = "host-println")) {
+        quote!({
+            defmt::export::make_istr(#string, #tag)
+        })
     } else {
         let var_item = static_variable(&var_name, string, tag);
         quote!({
             #var_item
-            &#var_name as *const u8 as u16
+            defmt::export::make_istr(&#var_name)
         })
-    };
-
-    quote!({
-        defmt::export::make_istr(#var_addr)
-    })
+    }
 }
 
 /// work around restrictions on length and allowed characters imposed by macos linker
@@ -21,6 +21,8 @@ struct Symbol<'a> {
     /// * `defmt_println` for logging messages that are always displayed.
     /// * `defmt_trace`, `defmt_debug`, `defmt_info`, `defmt_warn`, `defmt_error` for logging
     ///   messages used at the different log levels.
+    /// * `defmt_log` for logging messages whose level is only known at runtime and transmitted as
+    ///   part of the log frame, see `defmt::log!`.
     /// * `defmt_bitflags` indicates that a format string was generated by a `defmt::bitflags!`
     ///   invocation, and that the decoder should look up possible flags in the binary.
     ///   The data string is of the format `NAME@REPR#NUM`, where `NAME` is the name of the bitflags
@@ -28,6 +30,28 @@ struct Symbol<'a> {
     ///   wire format), and `NUM` is the number of defined bitflag values.
     /// * `defmt_bitflags_value` marks a `static` that holds the value of a bitflags `const`, its
     ///   data field is `STRUCT_NAME::FLAG_NAME`.
+    /// * `defmt_timestamp` for the format string of the global timestamp, and
+    ///   `defmt_timestamp_delta` for a timestamp that is transmitted as the LEB128-encoded
+    ///   difference to the timestamp of the previous frame.
+    /// * `defmt_heartbeat` for the format string of heartbeat frames emitted by
+    ///   `defmt::heartbeat`.
+    /// * `defmt_event` for the format strings of timeline event frames emitted by the functions in
+    ///   `defmt::event`.
+    /// * `defmt_test` for the format strings of test frames emitted by the functions in
+    ///   `defmt::harness`.
+    /// * `defmt_version` for the format string of the frames emitted by `defmt::announce_version`.
+    /// * `defmt_wall_clock` for the format string of the frames emitted by `defmt::wall_clock`.
+    /// * `defmt_clock_sync` for the format string of the frames emitted by `defmt::clock_sync`.
+    /// * `defmt_time_sync` for the format string of the frames emitted by `defmt::time_sync`.
+    /// * `defmt_chunk` for the format string of the chunk frames emitted by `defmt::chunked`.
+    /// * `defmt_counter` and `defmt_gauge` for the format strings of the metric frames emitted by
+    ///   `defmt::counter!` and `defmt::gauge!`, which start with the name of the metric.
+    /// * `defmt_stack` for the format string of the frames emitted by `defmt::stack::Stack::report`.
+    /// * `defmt_continuation` for the format string of the continuation frames of long frames.
+    /// * `defmt_self_check` for the format string of the check frames emitted with the
+    ///   `self-check` feature.
+    /// * `defmt_dynamic` for the format string of the frames emitted by `defmt::log_dynamic`,
+    ///   which carry the log level and their actual format string.
     /// * Anything starting with `defmt_` is reserved for use by defmt, other prefixes are free for
     ///   use by third-party apps (but they all should use a prefix!).
     tag: String,
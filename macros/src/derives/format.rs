@@ -9,8 +9,15 @@ pub(crate) fn expand(input: TokenStream) -> TokenStream {
     let mut input = parse_macro_input!(input as DeriveInput);
 
     let ident = &input.ident;
+    let attributes = match codegen::ContainerAttributes::parse(&input.attrs) {
+        Ok(attributes) => attributes,
+        Err(e) => return e.into_compile_error().into(),
+    };
     let encode_data = match &input.data {
-        Data::Enum(data) => codegen::encode_enum_data(ident, data),
+        Data::Enum(data) => codegen::encode_enum_data(ident, data, attributes.discriminant),
+        Data::Struct(_) if attributes.discriminant.is_some() => {
+            abort_call_site!("`#[defmt(discriminant = ..)]` is only supported on enums")
+        }
         Data::Struct(data) => codegen::encode_struct_data(ident, data),
         Data::Union(_) => abort_call_site!("`#[derive(Format)]` does not support unions"),
     };
@@ -19,10 +26,39 @@ pub(crate) fn expand(input: TokenStream) -> TokenStream {
         format_tag,
         stmts,
         where_predicates,
+        debug_stmts,
+        debug_where_predicates,
     } = match encode_data {
         Ok(data) => data,
         Err(e) => return e.into_compile_error().into(),
     };
+    // explicit bounds replace the inferred ones, like `#[serde(bound = "..")]`
+    let (where_predicates, debug_where_predicates) = match attributes.bound {
+        Some(bound) => (bound.clone(), bound),
+        None => (where_predicates, debug_where_predicates),
+    };
+
+    let debug_impl = if attributes.debug {
+        let mut generics = input.generics.clone();
+        let codegen::Generics {
+            impl_generics,
+            type_generics,
+            where_clause,
+        } = codegen::Generics::codegen(&mut generics, debug_where_predicates);
+
+        // renders like the decoder renders the `Format` impl; only on the host, where the
+        // formatting machinery of `core::fmt` doesn't take up space on the device
+        quote!(
+            #[cfg(not(target_os = "none"))]
+            impl #impl_generics ::core::fmt::Debug for #ident #type_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #(#debug_stmts)*
+                }
+            }
+        )
+    } else {
+        quote!()
+    };
 
     let codegen::Generics {
         impl_generics,
@@ -44,6 +80,8 @@ pub(crate) fn expand(input: TokenStream) -> TokenStream {
                 #(#stmts)*
             }
         }
+
+        #debug_impl
     )
     .into()
 }
@@ -2,10 +2,12 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::{DataStruct, Ident, ImplGenerics, TypeGenerics, WhereClause, WherePredicate};
 
+pub(crate) use container::{ContainerAttributes, DiscriminantFormat};
 pub(crate) use enum_data::encode as encode_enum_data;
 
 use crate::construct;
 
+mod container;
 mod enum_data;
 mod fields;
 
@@ -13,27 +15,42 @@ pub(crate) struct EncodeData {
     pub(crate) format_tag: TokenStream2,
     pub(crate) stmts: Vec<TokenStream2>,
     pub(crate) where_predicates: Vec<WherePredicate>,
+    /// Body of the `core::fmt::Debug` impl, for `#[defmt(debug)]`
+    pub(crate) debug_stmts: Vec<TokenStream2>,
+    pub(crate) debug_where_predicates: Vec<WherePredicate>,
 }
 
 pub(crate) fn encode_struct_data(ident: &Ident, data: &DataStruct) -> syn::Result<EncodeData> {
     let mut format_string = ident.to_string();
     let mut stmts = vec![];
     let mut field_patterns = vec![];
+    let mut debug = fields::DebugFields {
+        format_string: ident.to_string(),
+        ..Default::default()
+    };
 
-    let (encode_fields_stmts, where_predicates) =
-        fields::codegen(&data.fields, &mut format_string, &mut field_patterns)?;
+    let (encode_fields_stmts, where_predicates) = fields::codegen(
+        &data.fields,
+        &mut format_string,
+        &mut field_patterns,
+        &mut debug,
+    )?;
 
     stmts.push(quote!(match self {
         Self { #(#field_patterns),* } => {
             #(#encode_fields_stmts;)*
         }
     }));
+    let debug_arm = debug.match_arm(quote!(Self { #(#field_patterns),* }));
+    let debug_stmts = vec![quote!(match self { #debug_arm })];
 
     let format_tag = construct::interned_string(&format_string, "derived", false);
     Ok(EncodeData {
         format_tag,
         stmts,
         where_predicates,
+        debug_stmts,
+        debug_where_predicates: debug.where_predicates,
     })
 }
 
@@ -0,0 +1,74 @@
+use syn::{punctuated::Punctuated, Attribute, Ident, LitStr, Token, WherePredicate};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum DiscriminantFormat {
+    Decimal,
+    Hex,
+}
+
+/// The `#[defmt(..)]` attributes of the type that `Format` is derived for.
+#[derive(Default)]
+pub(crate) struct ContainerAttributes {
+    /// `#[defmt(bound = "T: Format")]`: replaces the `Format` bounds that are inferred from the
+    /// field types.
+    pub(crate) bound: Option<Vec<WherePredicate>>,
+    /// `#[defmt(debug)]`: also implement `core::fmt::Debug`, on the host.
+    pub(crate) debug: bool,
+    /// `#[defmt(discriminant = hex)]`: how to show the discriminants of an enum.
+    pub(crate) discriminant: Option<DiscriminantFormat>,
+}
+
+impl ContainerAttributes {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("defmt") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                // #[defmt(bound = "T: Format")]
+                if meta.path.is_ident("bound") {
+                    if attributes.bound.is_some() {
+                        return Err(meta.error("duplicate `bound` attribute"));
+                    }
+                    let bound: LitStr = meta.value()?.parse()?;
+                    let predicates = bound
+                        .parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+                    attributes.bound = Some(predicates.into_iter().collect());
+                    return Ok(());
+                }
+
+                // #[defmt(debug)]
+                if meta.path.is_ident("debug") {
+                    attributes.debug = true;
+                    return Ok(());
+                }
+
+                // #[defmt(discriminant = hex)]
+                if meta.path.is_ident("discriminant") {
+                    if attributes.discriminant.is_some() {
+                        return Err(meta.error("duplicate `discriminant` attribute"));
+                    }
+                    let format: Ident = meta.value()?.parse()?;
+                    attributes.discriminant = Some(if format == "hex" {
+                        DiscriminantFormat::Hex
+                    } else if format == "decimal" {
+                        DiscriminantFormat::Decimal
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            format,
+                            "expected `hex` or `decimal`",
+                        ));
+                    });
+                    return Ok(());
+                }
+
+                Err(meta.error("expected `bound`, `debug` or `discriminant`"))
+            })?;
+        }
+
+        Ok(attributes)
+    }
+}
@@ -1,25 +1,35 @@
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::abort_call_site;
 use quote::quote;
-use syn::{DataEnum, Ident};
+use syn::{DataEnum, Expr, ExprLit, ExprUnary, Ident, Lit, UnOp};
 
 use crate::construct;
 
-use super::EncodeData;
+use super::{DiscriminantFormat, EncodeData};
 
-pub(crate) fn encode(ident: &Ident, data: &DataEnum) -> syn::Result<EncodeData> {
+pub(crate) fn encode(
+    ident: &Ident,
+    data: &DataEnum,
+    discriminant_format: Option<DiscriminantFormat>,
+) -> syn::Result<EncodeData> {
     if data.variants.is_empty() {
         return Ok(EncodeData {
             stmts: vec![quote!(match *self {})],
             format_tag: construct::interned_string("!", "derived", false),
             where_predicates: vec![],
+            debug_stmts: vec![quote!(match *self {})],
+            debug_where_predicates: vec![],
         });
     }
 
+    let discriminants = discriminants(data, discriminant_format)?;
+
     let mut format_string = String::new();
     let mut where_predicates = vec![];
+    let mut debug_where_predicates = vec![];
 
     let mut match_arms = vec![];
+    let mut debug_match_arms = vec![];
     let mut is_first_variant = true;
     let discriminant_encoder = DiscriminantEncoder::new(data.variants.len());
     let enum_ident = ident;
@@ -31,13 +41,28 @@ pub(crate) fn encode(ident: &Ident, data: &DataEnum) -> syn::Result<EncodeData>
         } else {
             format_string.push('|');
         }
-        format_string.push_str(&variant_ident.to_string());
+        let mut variant_string = variant_ident.to_string();
+        if let Some(discriminants) = &discriminants {
+            variant_string.push_str(" = ");
+            variant_string.push_str(&discriminants[index]);
+        }
+        format_string.push_str(&variant_string);
 
         let mut field_patterns = vec![];
-        let (encode_fields_stmts, encode_field_where_predicates) =
-            super::fields::codegen(&variant.fields, &mut format_string, &mut field_patterns)?;
-        where_predicates.extend(encode_field_where_predicates.into_iter());
+        let mut debug = super::fields::DebugFields {
+            format_string: variant_string,
+            ..Default::default()
+        };
+        let (encode_fields_stmts, encode_field_where_predicates) = super::fields::codegen(
+            &variant.fields,
+            &mut format_string,
+            &mut field_patterns,
+            &mut debug,
+        )?;
+        where_predicates.extend(encode_field_where_predicates);
+        debug_where_predicates.append(&mut debug.where_predicates);
         let pattern = quote!( { #(#field_patterns),* } );
+        debug_match_arms.push(debug.match_arm(quote!(#enum_ident::#variant_ident #pattern)));
 
         let encode_discriminant_stmt = discriminant_encoder.encode(index);
 
@@ -54,14 +79,84 @@ pub(crate) fn encode(ident: &Ident, data: &DataEnum) -> syn::Result<EncodeData>
         #(#match_arms)*
     })];
     where_predicates.dedup_by(|a, b| a == b);
+    let debug_stmts = vec![quote!(match self {
+        #(#debug_match_arms)*
+    })];
+    debug_where_predicates.dedup_by(|a, b| a == b);
 
     Ok(EncodeData {
         format_tag,
         stmts,
         where_predicates,
+        debug_stmts,
+        debug_where_predicates,
     })
 }
 
+/// Returns the discriminants of the variants of an enum, as they appear in its format string.
+///
+/// The discriminants are only shown if the enum has explicit discriminants (`Error = 0x42`), or
+/// with `#[defmt(discriminant = ..)]`. They are part of the interned format string, so they cost
+/// nothing on the wire; the variant is still encoded as its position.
+fn discriminants(
+    data: &DataEnum,
+    format: Option<DiscriminantFormat>,
+) -> syn::Result<Option<Vec<String>>> {
+    let is_explicit = data
+        .variants
+        .iter()
+        .any(|variant| variant.discriminant.is_some());
+    if !is_explicit && format.is_none() {
+        return Ok(None);
+    }
+
+    let mut discriminants = vec![];
+    let mut next = 0;
+    for variant in &data.variants {
+        let value = match &variant.discriminant {
+            Some((_, expr)) => match as_integer(expr) {
+                Some(Ok(value)) => value,
+                // e.g. `A = 1 << 3` or `B = CONST`, whose value isn't known to the macro
+                _ if format.is_none() => return Ok(None),
+                Some(Err(e)) => return Err(e),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        expr,
+                        "`#[defmt(discriminant = ..)]` requires integer literal discriminants",
+                    ))
+                }
+            },
+            None => next,
+        };
+        next = value.wrapping_add(1);
+
+        discriminants.push(match format {
+            Some(DiscriminantFormat::Hex) if value < 0 => format!("-{:#x}", value.unsigned_abs()),
+            Some(DiscriminantFormat::Hex) => format!("{value:#x}"),
+            Some(DiscriminantFormat::Decimal) | None => value.to_string(),
+        });
+    }
+
+    Ok(Some(discriminants))
+}
+
+/// Returns the value of `expr` if it is an integer literal, e.g. `0x42` or `-1`.
+fn as_integer(expr: &Expr) -> Option<syn::Result<i128>> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => Some(int.base10_parse()),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => as_integer(expr).map(|value| value.map(|value: i128| -value)),
+        Expr::Paren(paren) => as_integer(&paren.expr),
+        Expr::Group(group) => as_integer(&group.expr),
+        _ => None,
+    }
+}
+
 enum DiscriminantEncoder {
     Nop,
     U8,
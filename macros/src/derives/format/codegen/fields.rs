@@ -2,14 +2,33 @@ use std::fmt::Write as _;
 
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use syn::{parse_quote, Field, Fields, Index, Type, WherePredicate};
+use syn::{parse_quote, Expr, ExprLit, Field, Fields, Ident, Index, Lit, Type, WherePredicate};
 
-use crate::consts;
+use crate::{consts, function_like::log};
+
+/// The `core::fmt::Debug` rendering of fields, for `#[defmt(debug)]`.
+#[derive(Default)]
+pub(crate) struct DebugFields {
+    /// `core::fmt` format string that renders like the `defmt` format string
+    pub(crate) format_string: String,
+    pub(crate) args: Vec<TokenStream2>,
+    pub(crate) where_predicates: Vec<WherePredicate>,
+}
+
+impl DebugFields {
+    /// Returns the match arm that writes the fields bound by `pattern`.
+    pub(crate) fn match_arm(&self, pattern: TokenStream2) -> TokenStream2 {
+        let format_string = &self.format_string;
+        let args = &self.args;
+        quote!(#pattern => ::core::write!(f, #format_string #(, #args)*),)
+    }
+}
 
 pub(crate) fn codegen(
     fields: &Fields,
     format_string: &mut String,
     patterns: &mut Vec<TokenStream2>,
+    debug: &mut DebugFields,
 ) -> syn::Result<(Vec<TokenStream2>, Vec<WherePredicate>)> {
     let (fields, fields_are_named) = match fields {
         Fields::Named(named) => (&named.named, true),
@@ -21,25 +40,30 @@ pub(crate) fn codegen(
         return Ok((vec![], vec![]));
     }
 
-    if fields_are_named {
-        format_string.push_str(" {{ ");
-    } else {
-        format_string.push('(');
-    }
+    let open = if fields_are_named { " {{ " } else { "(" };
+    format_string.push_str(open);
+    debug.format_string.push_str(open);
 
     let mut stmts = vec![];
     let mut where_predicates = vec![];
+    let mut params = vec![];
+    let mut idents = vec![];
     let mut is_first = true;
     for (index, field) in fields.iter().enumerate() {
         if is_first {
             is_first = false;
         } else {
             format_string.push_str(", ");
+            debug.format_string.push_str(", ");
         }
 
         let format_opt = get_defmt_format_option(field)?;
-        // Find out if the field type is natively supported by defmt. `ty` will be None if not.
-        let ty = as_native_type(&field.ty);
+        // Find out if the field type is natively supported by defmt. `ty` will be None if not, or
+        // if the field is formatted with `core::fmt`.
+        let ty = match format_opt {
+            Some(_) => None,
+            None => as_native_type(&field.ty),
+        };
         // `field_ty` will be the field's type if it is not natively supported by defmt
         let field_ty = if ty.is_none() { Some(&field.ty) } else { None };
         // Get the field format specifier. Either the native specifier or '?'.
@@ -48,6 +72,25 @@ pub(crate) fn codegen(
             .ident
             .clone()
             .unwrap_or_else(|| format_ident!("arg{}", index));
+        // The decoder shows strings and characters in tuple fields, and `Display2Format` fields,
+        // without quotes, like `Display`; everything else is shown like `Debug`.
+        let debug_is_display = match format_opt {
+            Some(FormatOption::Display2Format) => true,
+            Some(FormatOption::Debug2Format) => false,
+            None => field.ident.is_none() && (ty == "str" || ty == "char"),
+        };
+        let debug_bound: syn::Path = if debug_is_display {
+            parse_quote!(::core::fmt::Display)
+        } else {
+            parse_quote!(::core::fmt::Debug)
+        };
+        debug.args.push(quote!(#ident));
+        if let Some(field_ty) = field_ty {
+            debug
+                .where_predicates
+                .push(parse_quote!(#field_ty: #debug_bound));
+        }
+
         // Find the required trait bounds for the field and add the formatting statement depending on the field type and the formatting options
         let bound: Option<syn::Path> = if let Some(FormatOption::Debug2Format) = format_opt {
             stmts.push(quote!(::defmt::export::fmt(&defmt::Debug2Format(&#ident))));
@@ -59,7 +102,7 @@ pub(crate) fn codegen(
             stmts.push(quote!(::defmt::export::fmt(#ident)));
             field_ty.map(|_| parse_quote!(::defmt::Format))
         } else {
-            let method = format_ident!("{}", ty);
+            let method = native_method(&ty);
             stmts.push(quote!(::defmt::export::#method(#ident)));
             field_ty.map(|_| parse_quote!(::defmt::Format))
         };
@@ -67,9 +110,22 @@ pub(crate) fn codegen(
             where_predicates.push(parse_quote!(#field_ty: #bound));
         }
 
+        params.push(defmt_parser::Parameter {
+            index,
+            ty: if ty == "bool" {
+                defmt_parser::Type::Bool
+            } else {
+                // only the `bool`s are relevant for packing
+                defmt_parser::Type::Format
+            },
+            hint: None,
+        });
+        idents.push(ident.clone());
+
         if field.ident.is_some() {
             // Named field.
             write!(format_string, "{ident}: {{={ty}:?}}").ok();
+            write!(debug.format_string, "{ident}: ").ok();
 
             patterns.push(quote!( #ident ));
         } else {
@@ -79,12 +135,22 @@ pub(crate) fn codegen(
             let index = Index::from(index);
             patterns.push(quote!( #index: #ident ));
         }
+        debug
+            .format_string
+            .push_str(if debug_is_display { "{}" } else { "{:?}" });
     }
 
-    if fields_are_named {
-        format_string.push_str(" }}");
-    } else {
-        format_string.push(')');
+    let close = if fields_are_named { " }}" } else { ")" };
+    format_string.push_str(close);
+    debug.format_string.push_str(close);
+
+    // bools are packed into shared bytes, like the arguments of the logging macros
+    let (packed, packed_len) = defmt_parser::packed_args(&params);
+    if !packed.is_empty() {
+        for packed in &packed {
+            stmts[packed.index] = quote!();
+        }
+        stmts.insert(0, log::encode_packed(&packed, packed_len, &params, &idents));
     }
 
     Ok((stmts, where_predicates))
@@ -146,7 +212,9 @@ fn get_defmt_format_option(field: &Field) -> syn::Result<Option<FormatOption>> {
 /// Returns `Some` if `ty` refers to a builtin Rust type that has native support from defmt and does
 /// not have to go through the `Format` trait.
 ///
-/// This should return `Some` for all types that can be used as `{=TYPE}`.
+/// This should return `Some` for all types that can be used as `{=TYPE}`. Encoding such fields
+/// directly, instead of as nested `Format` values, saves the string index of the nested format
+/// string on the wire.
 ///
 /// Note: This is technically incorrect, since builtin types can be shadowed. However the efficiency
 /// gains are too big to pass up, so we expect user code to not do that.
@@ -157,12 +225,34 @@ fn as_native_type(ty: &Type) -> Option<String> {
             let ty_name = ident.to_string();
 
             match &*ty_name {
-                "u8" | "u16" | "u32" | "usize" | "i8" | "i16" | "i32" | "isize" | "f32" | "f64"
-                | "bool" | "str" => Some(ty_name),
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "isize" | "f32" | "f64" | "bool" | "char" | "str" => Some(ty_name),
                 _ => None,
             }
         }
+        Type::Array(array) if is_u8(&array.elem) => match &array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => Some(format!("[u8; {}]", len.base10_digits())),
+            _ => None,
+        },
+        Type::Slice(slice) if is_u8(&slice.elem) => Some("[u8]".to_string()),
         Type::Reference(ty_ref) => as_native_type(&ty_ref.elem),
         _ => None,
     }
 }
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8"))
+}
+
+/// Returns the `defmt::export` function that encodes values of the native type `ty`.
+fn native_method(ty: &str) -> Ident {
+    if ty == "[u8]" {
+        format_ident!("slice")
+    } else if ty.starts_with("[u8;") {
+        format_ident!("u8_array")
+    } else {
+        format_ident!("{}", ty)
+    }
+}
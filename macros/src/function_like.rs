@@ -1,9 +1,17 @@
 pub(crate) mod assert_binop;
 pub(crate) mod assert_like;
+pub(crate) mod assert_matches;
+pub(crate) mod bail;
 pub(crate) mod dbg;
+pub(crate) mod error_code;
+pub(crate) mod event;
+pub(crate) mod group;
+pub(crate) mod hexdump;
 pub(crate) mod intern;
+pub(crate) mod intern_tagged;
 pub(crate) mod internp;
 pub(crate) mod log;
+pub(crate) mod metric;
 pub(crate) mod panic_like;
 pub(crate) mod println;
 pub(crate) mod write;
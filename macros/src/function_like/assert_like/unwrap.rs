@@ -9,6 +9,7 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as super::Args);
 
     let condition = args.condition;
+    let log_error = args.log_args.is_none();
     let (format_string, formatting_args) = if let Some(log_args) = args.log_args {
         let format_string = format!("panicked at '{}'", log_args.format_string.value());
         (format_string, log_args.formatting_args)
@@ -33,10 +34,21 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
         },
     );
 
+    // errors that don't implement `Format` are logged with their `Debug` impl
+    let format_error = log_error.then(|| {
+        quote!(
+            #[allow(unused_imports)]
+            use defmt::export::{UnwrapErrorDebug as _, UnwrapErrorFormat as _};
+            let _unwrap_err =
+                (&&defmt::export::UnwrapError(&_unwrap_err)).format_unwrap_error();
+        )
+    });
+
     quote!(
         match defmt::export::into_result(#condition) {
             ::core::result::Result::Ok(res) => res,
             ::core::result::Result::Err(_unwrap_err) => {
+                #format_error
                 #log_stmt;
                 defmt::export::panic()
             }
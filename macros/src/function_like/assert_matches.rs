@@ -0,0 +1,59 @@
+use defmt_parser::Level;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, punctuated::Punctuated};
+
+use crate::{construct, function_like::log};
+
+use self::args::Args;
+
+mod args;
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as Args);
+
+    let expr = args.expr;
+    let pat = args.pat;
+    let guard = args.guard.map(|guard| quote!(if #guard));
+
+    let mut formatting_args = Punctuated::new();
+
+    let extra_string = if let Some(log_args) = args.log_args {
+        if let Some(args) = log_args.formatting_args {
+            formatting_args.extend(args);
+        }
+        format!(": {}", log_args.format_string.value())
+    } else {
+        String::new()
+    };
+
+    formatting_args.push(construct::variable("left_val"));
+
+    let pattern = quote!(#pat #guard)
+        .to_string()
+        .replace('{', "{{")
+        .replace('}', "}}");
+    let panic_msg = format!(
+        "panicked at 'assertion failed: `(left matches right)`{extra_string}'
+ left: `{{:?}}`
+right: `{pattern}`"
+    );
+
+    let log_args = log::Args {
+        format_string: construct::string_literal(&panic_msg),
+        formatting_args: Some(formatting_args),
+    };
+    let log_stmt = log::expand_parsed(Level::Error, log_args);
+
+    quote!(
+        // following `core::assert_matches!`
+        match #expr {
+            #pat #guard => {}
+            ref left_val => {
+                #log_stmt;
+                defmt::export::panic()
+            }
+        }
+    )
+    .into()
+}
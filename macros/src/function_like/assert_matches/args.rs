@@ -0,0 +1,57 @@
+use syn::{
+    parse::{self, Parse, ParseStream},
+    Expr, Pat, Token,
+};
+
+use crate::function_like::log;
+
+pub(crate) struct Args {
+    pub(crate) expr: Expr,
+    pub(crate) pat: Pat,
+    pub(crate) guard: Option<Expr>,
+    pub(crate) log_args: Option<log::Args>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let expr = input.parse()?;
+        let _comma: Token![,] = input.parse()?;
+        let pat = Pat::parse_multi_with_leading_vert(input)?;
+        let guard = if input.peek(Token![if]) {
+            let _if: Token![if] = input.parse()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        if input.is_empty() {
+            // assert_matches!(a, B)
+            return Ok(Args {
+                expr,
+                pat,
+                guard,
+                log_args: None,
+            });
+        }
+
+        let _comma: Token![,] = input.parse()?;
+
+        if input.is_empty() {
+            // assert_matches!(a, B,)
+            Ok(Args {
+                expr,
+                pat,
+                guard,
+                log_args: None,
+            })
+        } else {
+            // assert_matches!(a, B, "c", d)
+            Ok(Args {
+                expr,
+                pat,
+                guard,
+                log_args: Some(input.parse()?),
+            })
+        }
+    }
+}
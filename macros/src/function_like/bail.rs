@@ -0,0 +1,70 @@
+use defmt_parser::Level;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Expr, Token,
+};
+
+use crate::{construct, function_like::log};
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let Args { error, log_args } = parse_macro_input!(args as Args);
+
+    // without a message, the error itself is logged
+    let log_args = log_args.unwrap_or_else(|| {
+        let mut formatting_args = Punctuated::new();
+        formatting_args.push(construct::variable("_defmt_bail_err"));
+        log::Args {
+            format_string: construct::string_literal("{:?}"),
+            formatting_args: Some(formatting_args),
+        }
+    });
+    let log_stmt = log::expand_parsed(Level::Error, log_args);
+
+    quote!({
+        // evaluate the error first, like `return Err(..)` would
+        let _defmt_bail_err = #error;
+        #log_stmt;
+        // convert the error like `?` does
+        return ::core::result::Result::Err(::core::convert::From::from(_defmt_bail_err));
+    })
+    .into()
+}
+
+/// `error` or `error, "format string", formatting_args..`
+struct Args {
+    error: Expr,
+    log_args: Option<log::Args>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let error = input.parse()?;
+        if input.is_empty() {
+            // bail!(a)
+            return Ok(Args {
+                error,
+                log_args: None,
+            });
+        }
+
+        let _comma: Token![,] = input.parse()?;
+
+        if input.is_empty() {
+            // bail!(a,)
+            Ok(Args {
+                error,
+                log_args: None,
+            })
+        } else {
+            // bail!(a, "b", c)
+            Ok(Args {
+                error,
+                log_args: Some(input.parse()?),
+            })
+        }
+    }
+}
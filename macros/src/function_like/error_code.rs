@@ -0,0 +1,11 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+use crate::construct;
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(args as LitStr);
+    let message = construct::interned_string(&literal.value(), "error_code", false);
+    quote!(defmt::export::make_error_code(#message)).into()
+}
@@ -0,0 +1,12 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Ident};
+
+use crate::construct;
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let name = parse_macro_input!(args as Ident);
+    // the name is part of the format string, so the frame carries no data
+    let format = construct::interned_string(&format!("event={name}"), "event", false);
+    quote!(defmt::export::event(&#format)).into()
+}
@@ -0,0 +1,77 @@
+use defmt_parser::Level;
+use proc_macro::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_macro_input, Macro, Token,
+};
+
+use crate::function_like::{log, println};
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let Group { statements } = parse_macro_input!(args as Group);
+
+    // the statements write their frames without acquiring the logger themselves
+    let statements = statements.iter().map(|mac| {
+        let level = level(mac);
+        let args = match mac.parse_body() {
+            Ok(args) => args,
+            Err(e) => abort!(e.span(), "{}", e),
+        };
+        match level {
+            Some(level) => log::expand_statement(level, args, quote!()),
+            None => println::expand_statement(args, quote!()),
+        }
+    });
+    let acquire = log::acquire();
+
+    quote!({
+        #acquire
+        defmt::export::group_start();
+        #(#statements;)*
+        defmt::export::group_end();
+    })
+    .into()
+}
+
+/// Returns the level of a logging macro, or `None` for `println!`.
+fn level(mac: &Macro) -> Option<Level> {
+    let name = mac
+        .path
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string());
+    match name.as_deref() {
+        Some("trace") => Some(Level::Trace),
+        Some("debug") => Some(Level::Debug),
+        Some("info") => Some(Level::Info),
+        Some("warn") => Some(Level::Warn),
+        Some("error") => Some(Level::Error),
+        Some("println") => None,
+        _ => abort!(
+            mac.path,
+            "only the logging macros `trace!`, `debug!`, `info!`, `warn!`, `error!` and `println!` \
+             can be used in `group!`"
+        ),
+    }
+}
+
+/// `info!(..); debug!(..); ..`
+struct Group {
+    statements: Vec<Macro>,
+}
+
+impl Parse for Group {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let mut statements = Vec::new();
+        while !input.is_empty() {
+            statements.push(input.parse()?);
+            // the semicolon after the last statement is optional
+            if !input.is_empty() {
+                let _semi: Token![;] = input.parse()?;
+            }
+        }
+        Ok(Self { statements })
+    }
+}
@@ -0,0 +1,71 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_macro_input, Expr, LitStr, Token,
+};
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let Args { level, bytes } = parse_macro_input!(args as Args);
+
+    let Some((level, label)) = level else {
+        return quote!({
+            let bytes: &[u8] = #bytes;
+            defmt::println!("{=[u8]:hexdump}", bytes)
+        })
+        .into();
+    };
+
+    let label = label.value().replace('{', "{{").replace('}', "}}");
+    let format_string = format!("{label}:{{=[u8]:hexdump}}");
+    // a level name is filtered at compile time, like the logging macros; anything else is a
+    // runtime `defmt::Level`, like the argument of `log!`
+    let log = match &level {
+        Expr::Path(path) if is_level_name(path) => quote!(defmt::#path!(#format_string, bytes)),
+        _ => quote!(defmt::log!(#level, #format_string, bytes)),
+    };
+
+    quote!({
+        let bytes: &[u8] = #bytes;
+        #log
+    })
+    .into()
+}
+
+fn is_level_name(path: &syn::ExprPath) -> bool {
+    path.qself.is_none()
+        && path.path.get_ident().is_some_and(|ident| {
+            ["trace", "debug", "info", "warn", "error"]
+                .iter()
+                .any(|level| ident == level)
+        })
+}
+
+/// `bytes` or `level, label, bytes`
+struct Args {
+    level: Option<(Expr, LitStr)>,
+    bytes: Expr,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let first: Expr = input.parse()?;
+        if input.is_empty() || (input.peek(Token![,]) && input.peek2(parse::End)) {
+            let _: Option<Token![,]> = input.parse()?;
+            return Ok(Self {
+                level: None,
+                bytes: first,
+            });
+        }
+
+        let _: Token![,] = input.parse()?;
+        let label: LitStr = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let bytes = input.parse()?;
+        let _: Option<Token![,]> = input.parse()?;
+        Ok(Self {
+            level: Some((first, label)),
+            bytes,
+        })
+    }
+}
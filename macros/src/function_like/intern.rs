@@ -1,9 +1,13 @@
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, LitStr};
+use syn::parse_macro_input;
 
 use crate::construct;
 
+use self::args::Args;
+
+mod args;
+
 pub(crate) fn expand(args: TokenStream) -> TokenStream {
-    let literal = parse_macro_input!(args as LitStr);
-    construct::interned_string(&literal.value(), "str", false).into()
+    let args = parse_macro_input!(args as Args);
+    construct::interned_string(&args.value, "str", false).into()
 }
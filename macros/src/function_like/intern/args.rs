@@ -0,0 +1,76 @@
+use std::env;
+
+use syn::{
+    parse::{self, Parse, ParseStream},
+    punctuated::Punctuated,
+    Expr, Lit, Token,
+};
+
+/// The argument of `intern!`: a string literal, or a `concat!`, `env!` or `stringify!` invocation
+///
+/// Proc macros see other macro invocations unexpanded, so these built-in macros are evaluated here,
+/// the way the compiler would.
+pub(crate) struct Args {
+    pub(crate) value: String,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let expr: Expr = input.parse()?;
+        let _comma: Option<Token![,]> = input.parse()?;
+        Ok(Self {
+            value: evaluate(&expr)?,
+        })
+    }
+}
+
+fn evaluate(expr: &Expr) -> parse::Result<String> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Str(lit) => Ok(lit.value()),
+            Lit::Char(lit) => Ok(lit.value().to_string()),
+            Lit::Int(lit) => Ok(lit.base10_digits().to_string()),
+            Lit::Float(lit) => Ok(lit.base10_digits().to_string()),
+            Lit::Bool(lit) => Ok(lit.value.to_string()),
+            _ => Err(unsupported(expr)),
+        },
+        // a literal passed through a `macro_rules!` macro
+        Expr::Group(group) => evaluate(&group.expr),
+        Expr::Macro(mac) => {
+            let mac = &mac.mac;
+            if mac.path.is_ident("concat") {
+                let parts = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+                parts.iter().map(evaluate).collect()
+            } else if mac.path.is_ident("env") {
+                let args = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+                let mut args = args.iter();
+                let (Some(name), message, None) = (args.next(), args.next(), args.next()) else {
+                    return Err(syn::Error::new_spanned(
+                        mac,
+                        "`env!` takes 1 or 2 arguments",
+                    ));
+                };
+                let name = evaluate(name)?;
+                env::var(&name).map_err(|_| {
+                    let message = match message.map(evaluate).transpose() {
+                        Ok(Some(message)) => message,
+                        _ => format!("environment variable `{name}` not defined at compile time"),
+                    };
+                    syn::Error::new_spanned(mac, message)
+                })
+            } else if mac.path.is_ident("stringify") {
+                Ok(mac.tokens.to_string())
+            } else {
+                Err(unsupported(expr))
+            }
+        }
+        _ => Err(unsupported(expr)),
+    }
+}
+
+fn unsupported(expr: &Expr) -> syn::Error {
+    syn::Error::new_spanned(
+        expr,
+        "expected a string literal, or a `concat!`, `env!` or `stringify!` invocation",
+    )
+}
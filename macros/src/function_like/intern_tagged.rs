@@ -0,0 +1,27 @@
+use proc_macro::TokenStream;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_macro_input, LitStr, Token,
+};
+
+use crate::construct;
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    let Args { tag, literal } = parse_macro_input!(args as Args);
+    construct::interned_string(&literal.value(), &tag.value(), false).into()
+}
+
+struct Args {
+    tag: LitStr,
+    literal: LitStr,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let tag = input.parse()?;
+        let _comma: Token![,] = input.parse()?;
+        let literal = input.parse()?;
+
+        Ok(Self { tag, literal })
+    }
+}
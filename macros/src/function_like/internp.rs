@@ -12,20 +12,18 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
     let section = construct::linker_section(false, prefix, &sym_name);
     let section_for_macos = construct::linker_section(true, prefix, &sym_name);
 
-    let var_addr = if cfg!(feature = "unstable-test") {
-        quote!({ defmt::export::fetch_add_string_index() as u16 })
+    if cfg!(any(feature = "unstable-test", feature = "host-println")) {
+        quote!({
+            defmt::export::make_istr(#literal, "prim")
+        })
     } else {
         quote!({
             #[cfg_attr(target_os = "macos", link_section = #section_for_macos)]
             #[cfg_attr(not(target_os = "macos"), link_section = #section)]
             #[export_name = #sym_name]
             static S: u8 = 0;
-            &S as *const u8 as u16
+            defmt::export::make_istr(&S)
         })
-    };
-
-    quote!({
-        defmt::export::make_istr(#var_addr)
-    })
+    }
     .into()
 }
@@ -8,7 +8,10 @@ use syn::parse_macro_input;
 use crate::construct;
 
 use self::env_filter::EnvFilter;
-pub(crate) use self::{args::Args, codegen::Codegen};
+pub(crate) use self::{
+    args::{Args, RuntimeLevelArgs},
+    codegen::{encode_packed, Codegen},
+};
 
 mod args;
 mod codegen;
@@ -19,6 +22,20 @@ pub(crate) fn expand(level: Level, args: TokenStream) -> TokenStream {
 }
 
 pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
+    expand_statement(level, args, acquire())
+}
+
+/// Acquires the global logger for a single log statement. The statements of a `group!` don't, the
+/// group acquires it once for all of them.
+pub(crate) fn acquire() -> TokenStream2 {
+    quote!(
+        // safety: released when the guard is dropped
+        let _guard = unsafe { defmt::export::acquire_guard() };
+    )
+}
+
+/// Expands a log statement that runs `acquire` before writing its frame.
+pub(crate) fn expand_statement(level: Level, args: Args, acquire: TokenStream2) -> TokenStream2 {
     let format_string = args.format_string.value();
     let fragments = match defmt_parser::parse(&format_string, ParserMode::Strict) {
         Ok(args) => args,
@@ -30,36 +47,116 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
         .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())
         .unwrap_or_default();
 
-    let Codegen { patterns, exprs } = Codegen::new(
-        &fragments,
-        formatting_exprs.len(),
-        args.format_string.span(),
-    );
+    let Codegen { patterns, exprs } =
+        Codegen::new(&fragments, &formatting_exprs, args.format_string.span());
 
     let header = construct::interned_string(&format_string, level.as_str(), true);
     let env_filter = EnvFilter::from_env_var();
 
+    // NOTE the arguments are only evaluated if the statement is enabled, so that disabled
+    // statements cost nothing at runtime; the filter check is a constant
     if let Some(filter_check) = env_filter.path_check(level) {
+        let variant = level_variant(level);
+        let module = module_index();
         quote!(
-            match (#(&(#formatting_exprs)),*) {
-                (#(#patterns),*) => {
-                    if #filter_check {
-                        // safety: will be released a few lines further down
-                        unsafe { defmt::export::acquire() };
+            if #filter_check && defmt::export::level_enabled(defmt::Level::#variant, #module) {
+                match (#(&(#formatting_exprs)),*) {
+                    (#(#patterns),*) => {
+                        #acquire
                         defmt::export::header(&#header);
                         #(#exprs;)*
-                        // safety: acquire() was called a few lines above
-                        unsafe { defmt::export::release() }
                     }
                 }
             }
         )
     } else {
-        // if logging is disabled match args, so they are not considered "unused"
+        // if logging is disabled still type check the args against the format string, so they are
+        // not considered "unused" and type errors don't depend on `DEFMT_LOG`, but don't evaluate
+        // them nor intern the format string
         quote!(
-            match (#(&(#formatting_exprs)),*) {
-                _ => {}
+            if false {
+                match (#(&(#formatting_exprs)),*) {
+                    (#(#patterns),*) => {
+                        #(#exprs;)*
+                    }
+                }
             }
         )
     }
 }
+
+pub(crate) fn expand_runtime_level(args: TokenStream) -> TokenStream {
+    let RuntimeLevelArgs { level, args } = parse_macro_input!(args as RuntimeLevelArgs);
+
+    let format_string = args.format_string.value();
+    let fragments = match defmt_parser::parse(&format_string, ParserMode::Strict) {
+        Ok(args) => args,
+        Err(e) => abort!(args.format_string, "{}", e),
+    };
+
+    let formatting_exprs = args
+        .formatting_args
+        .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let Codegen { patterns, exprs } =
+        Codegen::new(&fragments, &formatting_exprs, args.format_string.span());
+
+    let header = construct::interned_string(&format_string, "log", true);
+    let env_filter = EnvFilter::from_env_var();
+    let module = module_index();
+
+    let levels = [
+        Level::Trace,
+        Level::Debug,
+        Level::Info,
+        Level::Warn,
+        Level::Error,
+    ];
+    let (variants, filter_checks): (Vec<_>, Vec<_>) = levels
+        .into_iter()
+        .map(|level| {
+            let filter_check = env_filter.path_check(level).unwrap_or(quote!(false));
+            (level_variant(level), filter_check)
+        })
+        .unzip();
+
+    quote!({
+        let level: defmt::Level = #level;
+        let enabled = match level {
+            #(defmt::Level::#variants => #filter_checks,)*
+        } && defmt::export::level_enabled(level, #module);
+        if enabled {
+            match (#(&(#formatting_exprs)),*) {
+                (#(#patterns),*) => {
+                    // safety: released when the guard is dropped
+                    let _guard = unsafe { defmt::export::acquire_guard() };
+                    defmt::export::header(&#header);
+                    defmt::export::u8(&(level as u8));
+                    #(#exprs;)*
+                }
+            }
+        }
+    })
+    .into()
+}
+
+/// Returns the `defmt::Level` variant of `level`.
+fn level_variant(level: Level) -> TokenStream2 {
+    match level {
+        Level::Trace => quote!(Trace),
+        Level::Debug => quote!(Debug),
+        Level::Info => quote!(Info),
+        Level::Warn => quote!(Warn),
+        Level::Error => quote!(Error),
+    }
+}
+
+/// Returns the index of the module of the log statement in the per-module level table, evaluated at
+/// compile time.
+fn module_index() -> TokenStream2 {
+    quote!({
+        const MODULE: u8 = defmt::export::module_index(::core::module_path!());
+        MODULE
+    })
+}
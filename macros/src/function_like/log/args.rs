@@ -9,6 +9,12 @@ pub(crate) struct Args {
     pub(crate) formatting_args: Option<Punctuated<Expr, Token![,]>>,
 }
 
+/// Arguments of `log!`, whose level is an expression evaluated at runtime
+pub(crate) struct RuntimeLevelArgs {
+    pub(crate) level: Expr,
+    pub(crate) args: Args,
+}
+
 impl Parse for Args {
     fn parse(input: ParseStream) -> parse::Result<Self> {
         Ok(Self {
@@ -22,3 +28,14 @@ impl Parse for Args {
         })
     }
 }
+
+impl Parse for RuntimeLevelArgs {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let level = input.parse()?;
+        let _comma: Token![,] = input.parse()?;
+        Ok(Self {
+            level,
+            args: input.parse()?,
+        })
+    }
+}
@@ -1,7 +1,8 @@
-use defmt_parser::{Fragment, Parameter, Type};
+use defmt_parser::{Fragment, PackedArg, Parameter, Type};
 use proc_macro2::{Ident as Ident2, Span as Span2, TokenStream as TokenStream2};
 use proc_macro_error::abort;
 use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Expr};
 
 pub(crate) struct Codegen {
     pub(crate) exprs: Vec<TokenStream2>,
@@ -9,7 +10,9 @@ pub(crate) struct Codegen {
 }
 
 impl Codegen {
-    pub(crate) fn new(fragments: &[Fragment<'_>], given_arg_count: usize, span: Span2) -> Self {
+    /// `args` are the formatting arguments; the errors about their types point at them.
+    pub(crate) fn new(fragments: &[Fragment<'_>], args: &[Expr], span: Span2) -> Self {
+        let given_arg_count = args.len();
         let params = fragments
             .iter()
             .filter_map(|frag| match frag {
@@ -42,14 +45,33 @@ impl Codegen {
         let mut exprs = vec![];
         let mut patterns = vec![];
 
-        for arg_index in 0..expected_arg_count {
-            let arg_ident = format_ident!("arg{}", arg_index);
+        // the identifiers keep the hygiene of the macro but are located at the arguments, so that
+        // type mismatches, e.g. a `u8` passed to `{=u16}`, are reported at the argument
+        let arg_idents = args
+            .iter()
+            .enumerate()
+            .map(|(arg_index, arg)| {
+                let span = Span2::call_site().located_at(arg.span());
+                format_ident!("arg{}", arg_index, span = span)
+            })
+            .collect::<Vec<_>>();
+
+        let (packed, packed_len) = defmt_parser::packed_args(&params);
+        if !packed.is_empty() {
+            exprs.push(encode_packed(&packed, packed_len, &params, &arg_idents));
+        }
+
+        for (arg_index, arg_ident) in arg_idents.into_iter().enumerate() {
             let matching_param = params
                 .iter()
                 .find(|param| param.index == arg_index)
                 .unwrap();
 
-            let expr = encode_arg(&matching_param.ty, &params, arg_index, &arg_ident);
+            let expr = if packed.iter().any(|packed| packed.index == arg_index) {
+                quote!() // already written by `encode_packed`
+            } else {
+                encode_arg(&matching_param.ty, &params, arg_index, &arg_ident)
+            };
 
             exprs.push(expr);
             patterns.push(arg_ident);
@@ -59,18 +81,65 @@ impl Codegen {
     }
 }
 
+/// Writes the sub-byte arguments, packed into shared bytes.
+///
+/// `args` are the identifiers of the arguments, which are references, in the order of their indices.
+pub(crate) fn encode_packed(
+    packed: &[PackedArg],
+    len: usize,
+    params: &[Parameter],
+    args: &[Ident2],
+) -> TokenStream2 {
+    let bits = packed.iter().map(|packed| {
+        let arg = &args[packed.index];
+        let PackedArg {
+            byte, shift, start, ..
+        } = packed;
+        let mask = packed.mask();
+
+        let is_bool = params
+            .iter()
+            .any(|param| param.index == packed.index && param.ty == Type::Bool);
+        let value = if is_bool {
+            quote!(u8::from(*{
+                let arg: &bool = #arg;
+                arg
+            }))
+        } else {
+            quote!({
+                let bits: u8 = defmt::export::truncate((*#arg) >> #start);
+                bits & #mask
+            })
+        };
+
+        quote!(packed[#byte] |= #value << #shift;)
+    });
+
+    quote!({
+        let mut packed = [0u8; #len];
+        #(#bits)*
+        defmt::export::u8_array(&packed);
+    })
+}
+
 fn encode_arg(ty: &Type, params: &[Parameter], arg_index: usize, arg: &Ident2) -> TokenStream2 {
     match ty {
         Type::I8 => quote!(defmt::export::i8(#arg)),
         Type::I16 => quote!(defmt::export::i16(#arg)),
+        Type::I24 => quote!(defmt::export::i24(#arg)),
         Type::I32 => quote!(defmt::export::i32(#arg)),
+        Type::I48 => quote!(defmt::export::i48(#arg)),
+        Type::I56 => quote!(defmt::export::i56(#arg)),
         Type::I64 => quote!(defmt::export::i64(#arg)),
         Type::I128 => quote!(defmt::export::i128(#arg)),
         Type::Isize => quote!(defmt::export::isize(#arg)),
 
         Type::U8 => quote!(defmt::export::u8(#arg)),
         Type::U16 => quote!(defmt::export::u16(#arg)),
+        Type::U24 => quote!(defmt::export::u24(#arg)),
         Type::U32 => quote!(defmt::export::u32(#arg)),
+        Type::U48 => quote!(defmt::export::u48(#arg)),
+        Type::U56 => quote!(defmt::export::u56(#arg)),
         Type::U64 => quote!(defmt::export::u64(#arg)),
         Type::U128 => quote!(defmt::export::u128(#arg)),
         Type::Usize => quote!(defmt::export::usize(#arg)),
@@ -0,0 +1,83 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_macro_input, Expr, LitStr, Token,
+};
+
+use crate::construct;
+
+/// `counter!("name")` or `counter!("name", delta)`
+pub(crate) fn expand_counter(args: TokenStream) -> TokenStream {
+    let Args { name, value } = parse_macro_input!(args as Args);
+    let name = metric_name(&name);
+
+    match value {
+        // the increment is part of the format string, so the frame carries no data
+        None => {
+            let format = construct::interned_string(&format!("{name} += 1"), "counter", false);
+            quote!(defmt::export::metric(&#format, || {}))
+        }
+        Some(delta) => {
+            let format =
+                construct::interned_string(&format!("{name} += {{=u32}}"), "counter", false);
+            quote!({
+                let delta: u32 = #delta;
+                defmt::export::metric(&#format, || defmt::export::u32(&delta))
+            })
+        }
+    }
+    .into()
+}
+
+/// `gauge!("name", value)`
+pub(crate) fn expand_gauge(args: TokenStream) -> TokenStream {
+    let Args { name, value } = parse_macro_input!(args as Args);
+    let Some(value) = value else {
+        abort!(name, "expected a value: `gauge!(\"name\", value)`");
+    };
+    let name = metric_name(&name);
+
+    let format = construct::interned_string(&format!("{name} = {{=i32}}"), "gauge", false);
+    quote!({
+        let value: i32 = #value;
+        defmt::export::metric(&#format, || defmt::export::i32(&value))
+    })
+    .into()
+}
+
+/// Returns the name of the metric, which becomes part of the format string.
+fn metric_name(name: &LitStr) -> String {
+    let value = name.value();
+    let is_valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '/' | '-'));
+    if !is_valid {
+        abort!(
+            name,
+            "metric names must be non-empty and only contain ASCII letters, digits and `_.:/-`"
+        );
+    }
+    value
+}
+
+struct Args {
+    name: LitStr,
+    value: Option<TokenStream2>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let name = input.parse()?;
+        let mut value = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let expr: Expr = input.parse()?;
+            value = Some(quote!(#expr));
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(Self { name, value })
+    }
+}
@@ -6,13 +6,18 @@ use quote::quote;
 use syn::parse_macro_input;
 
 use crate::construct;
-use crate::function_like::log::{Args, Codegen};
+use crate::function_like::log::{self, Args, Codegen};
 
 pub(crate) fn expand(args: TokenStream) -> TokenStream {
     expand_parsed(parse_macro_input!(args as Args)).into()
 }
 
 pub(crate) fn expand_parsed(args: Args) -> TokenStream2 {
+    expand_statement(args, log::acquire())
+}
+
+/// Expands a `println!` that runs `acquire` before writing its frame, see `log::expand_statement`.
+pub(crate) fn expand_statement(args: Args, acquire: TokenStream2) -> TokenStream2 {
     let format_string = args.format_string.value();
     let fragments = match defmt_parser::parse(&format_string, ParserMode::Strict) {
         Ok(args) => args,
@@ -28,22 +33,16 @@ pub(crate) fn expand_parsed(args: Args) -> TokenStream2 {
         .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())
         .unwrap_or_default();
 
-    let Codegen { patterns, exprs } = Codegen::new(
-        &fragments,
-        formatting_exprs.len(),
-        args.format_string.span(),
-    );
+    let Codegen { patterns, exprs } =
+        Codegen::new(&fragments, &formatting_exprs, args.format_string.span());
 
     let header = construct::interned_string(&format_string, "println", true);
     quote!({
         match (#(&(#formatting_exprs)),*) {
             (#(#patterns),*) => {
-                // safety: will be released a few lines further down
-                unsafe { defmt::export::acquire(); }
+                #acquire
                 defmt::export::header(&#header);
                 #(#exprs;)*
-                // safety: acquire() was called a few lines above
-                unsafe { defmt::export::release() }
             }
         }
     })
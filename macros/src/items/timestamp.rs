@@ -1,14 +1,17 @@
-use defmt_parser::ParserMode;
+use defmt_parser::{Fragment, ParserMode, Type};
 use proc_macro::TokenStream;
 use proc_macro_error::abort;
 use quote::format_ident;
 use quote::quote;
-use syn::parse_macro_input;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_macro_input, Ident, Token,
+};
 
 use crate::{construct, function_like::log};
 
 pub(crate) fn expand(args: TokenStream) -> TokenStream {
-    let args = parse_macro_input!(args as log::Args);
+    let Args { delta, args } = parse_macro_input!(args as Args);
 
     let format_string = args.format_string.value();
 
@@ -22,26 +25,65 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
         .map(|punctuated| punctuated.into_iter().collect())
         .unwrap_or_default();
 
-    let log::Codegen { patterns, exprs } = log::Codegen::new(
-        &fragments,
-        formatting_exprs.len(),
-        args.format_string.span(),
-    );
+    let body = if delta {
+        let mut params = fragments.iter().filter_map(|fragment| match fragment {
+            Fragment::Parameter(param) => Some(param),
+            Fragment::Literal(_) => None,
+        });
+        match (params.next(), params.next(), &*formatting_exprs) {
+            (Some(param), None, [expr]) if param.ty == Type::U64 => {
+                quote!(
+                    let _ = fmt;
+                    ::defmt::export::timestamp_delta(#expr)
+                )
+            }
+            _ => abort!(
+                args.format_string,
+                "delta timestamps must have exactly one `{=u64}` parameter"
+            ),
+        }
+    } else {
+        let log::Codegen { patterns, exprs } =
+            log::Codegen::new(&fragments, &formatting_exprs, args.format_string.span());
+
+        quote!(
+            match (#(&(#formatting_exprs)),*) {
+                (#(#patterns),*) => {
+                // NOTE: No format string index, and no finalize call.
+                    #(#exprs;)*
+                }
+            }
+        )
+    };
+
+    if cfg!(feature = "no-timestamp") {
+        // Still type-check the arguments, but don't define the timestamp symbols so neither target
+        // nor host expect timestamp data in the log frames.
+        return quote!(
+            const _: () = {
+                #[allow(dead_code)]
+                fn defmt_timestamp(fmt: ::defmt::Formatter<'_>) {
+                    #body
+                }
+            };
+        )
+        .into();
+    }
 
     let var_name = format_ident!("S");
-    let var_item = construct::static_variable(&var_name, &format_string, "timestamp");
+    let tag = if delta {
+        "timestamp_delta"
+    } else {
+        "timestamp"
+    };
+    let var_item = construct::static_variable(&var_name, &format_string, tag);
 
     quote!(
         const _: () = {
             #[export_name = "_defmt_timestamp"]
             #[inline(never)]
             fn defmt_timestamp(fmt: ::defmt::Formatter<'_>) {
-                match (#(&(#formatting_exprs)),*) {
-                    (#(#patterns),*) => {
-                    // NOTE: No format string index, and no finalize call.
-                        #(#exprs;)*
-                    }
-                }
+                #body
             }
 
             #var_item;
@@ -57,3 +99,32 @@ pub(crate) fn expand(args: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+struct Args {
+    /// `true` if the timestamp is prefixed with `delta,`
+    delta: bool,
+    args: log::Args,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let delta = if input.peek(Ident) {
+            let ident: Ident = input.parse()?;
+            if ident != "delta" {
+                return Err(parse::Error::new(
+                    ident.span(),
+                    "expected `delta` or a format string",
+                ));
+            }
+            let _comma: Token![,] = input.parse()?;
+            true
+        } else {
+            false
+        };
+
+        Ok(Self {
+            delta,
+            args: input.parse()?,
+        })
+    }
+}
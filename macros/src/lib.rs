@@ -59,6 +59,12 @@ pub fn assert_ne_(args: TokenStream) -> TokenStream {
     function_like::assert_binop::ne(args)
 }
 
+#[proc_macro]
+#[proc_macro_error]
+pub fn assert_matches_(args: TokenStream) -> TokenStream {
+    function_like::assert_matches::expand(args)
+}
+
 /* ## `debug_` variants */
 // NOTE these `debug_*` macros can be written using `macro_rules!` (that'd be simpler) but that
 // results in an incorrect source code location being reported: the location of the `macro_rules!`
@@ -93,14 +99,60 @@ pub fn debug_assert_ne_(input: TokenStream) -> TokenStream {
     })
     .into()
 }
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn debug_assert_matches_(input: TokenStream) -> TokenStream {
+    let assert = TokenStream2::from(assert_matches_(input));
+    quote!(if cfg!(debug_assertions) {
+        #assert
+    })
+    .into()
+}
 /* ## end of `debug_` variants */
 
+#[proc_macro]
+#[proc_macro_error]
+pub fn bail(args: TokenStream) -> TokenStream {
+    function_like::bail::expand(args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn counter(args: TokenStream) -> TokenStream {
+    function_like::metric::expand_counter(args)
+}
+
 #[proc_macro]
 #[proc_macro_error]
 pub fn dbg(args: TokenStream) -> TokenStream {
     function_like::dbg::expand(args)
 }
 
+#[proc_macro]
+#[proc_macro_error]
+pub fn error_code(args: TokenStream) -> TokenStream {
+    function_like::error_code::expand(args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn event(args: TokenStream) -> TokenStream {
+    function_like::event::expand(args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn gauge(args: TokenStream) -> TokenStream {
+    function_like::metric::expand_gauge(args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn hexdump(args: TokenStream) -> TokenStream {
+    function_like::hexdump::expand(args)
+}
+
 #[proc_macro]
 #[proc_macro_error]
 pub fn intern(args: TokenStream) -> TokenStream {
@@ -113,6 +165,13 @@ pub fn internp(args: TokenStream) -> TokenStream {
     function_like::internp::expand(args)
 }
 
+/// Interns a string with a defmt-internal symbol tag, e.g. `intern_tagged!("heartbeat", "...")`
+#[proc_macro]
+#[proc_macro_error]
+pub fn intern_tagged(args: TokenStream) -> TokenStream {
+    function_like::intern_tagged::expand(args)
+}
+
 #[proc_macro]
 #[proc_macro_error]
 pub fn println(args: TokenStream) -> TokenStream {
@@ -150,6 +209,18 @@ pub fn warn(args: TokenStream) -> TokenStream {
 pub fn error(args: TokenStream) -> TokenStream {
     function_like::log::expand(Level::Error, args)
 }
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn log(args: TokenStream) -> TokenStream {
+    function_like::log::expand_runtime_level(args)
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn group(args: TokenStream) -> TokenStream {
+    function_like::group::expand(args)
+}
 /* ## end of logging macros */
 
 #[proc_macro]
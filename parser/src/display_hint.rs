@@ -19,14 +19,21 @@ pub enum DisplayHint {
     },
     /// `:a`
     Ascii,
+    /// `:hexdump`, formats byte slices as an offset-annotated hexdump with ASCII gutter
+    Hexdump,
     /// `:?`
     Debug,
     /// `:us` `:ms`, formats integers as timestamps in seconds
     Seconds(TimePrecision),
     /// `:tus` `:tms` `:ts`, formats integers as human-readable time
     Time(TimePrecision),
+    /// `:ticks@HZ`, formats integers counting the ticks of a `HZ` clock as seconds
+    Ticks(u64),
     /// `:iso8601{ms,s}`, formats integers as timestamp in ISO8601 date time format
     ISO8601(TimePrecision),
+    /// `:domain`, marks the clock domain of a timestamp, see `defmt::clock_sync`; formatted like an
+    /// integer without hint
+    ClockDomain,
     /// `__internal_bitflags_NAME` instructs the decoder to print the flags that are set, instead of
     /// the raw value.
     Bitflags {
@@ -35,6 +42,9 @@ pub enum DisplayHint {
         disambiguator: String,
         crate_name: Option<String>,
     },
+    /// `__internal_niche_option` formats an `Option` of a non-zero integer, which is transmitted as
+    /// a single integer with `0` meaning `None`.
+    NicheOption,
     /// Display hints currently not supported / understood
     Unknown(String),
 }
@@ -75,6 +85,13 @@ impl DisplayHint {
             });
         }
 
+        if let Some(rest) = s.strip_prefix("ticks@") {
+            return match parse_integer::<u64>(rest)? {
+                ("", hz) if hz != 0 => Some(DisplayHint::Ticks(hz)),
+                _ => None,
+            };
+        }
+
         Some(match s {
             "" => DisplayHint::NoHint { zero_pad },
             "us" => DisplayHint::Seconds(TimePrecision::Micros),
@@ -83,6 +100,7 @@ impl DisplayHint {
             "tms" => DisplayHint::Time(TimePrecision::Millis),
             "ts" => DisplayHint::Time(TimePrecision::Seconds),
             "a" => DisplayHint::Ascii,
+            "hexdump" => DisplayHint::Hexdump,
             "b" => DisplayHint::Binary {
                 alternate,
                 zero_pad,
@@ -99,7 +117,9 @@ impl DisplayHint {
             },
             "iso8601ms" => DisplayHint::ISO8601(TimePrecision::Millis),
             "iso8601s" => DisplayHint::ISO8601(TimePrecision::Seconds),
+            "domain" => DisplayHint::ClockDomain,
             "?" => DisplayHint::Debug,
+            "__internal_niche_option" => DisplayHint::NicheOption,
             _ => return None,
         })
     }
@@ -131,7 +131,7 @@ fn parse_range(mut s: &str) -> Option<(Range<u8>, usize /* consumed */)> {
     let start = s[..start_digits].parse().ok()?;
 
     // next two `char`s should be `..`
-    if &s[start_digits..start_digits + 2] != ".." {
+    if s.get(start_digits..start_digits + 2) != Some("..") {
         return None;
     }
     s = &s[start_digits + 2..];
@@ -289,6 +289,82 @@ fn push_literal<'f>(frag: &mut Vec<Fragment<'f>>, unescaped_literal: &'f str) ->
     Ok(())
 }
 
+/// An argument that is packed into bytes shared with the other sub-byte arguments of its format
+/// string, see [`packed_args`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackedArg {
+    /// The index of the argument.
+    pub index: usize,
+    /// The packed byte containing the argument.
+    pub byte: usize,
+    /// The position of the argument's lowest bit within `byte`.
+    pub shift: u8,
+    /// The number of bits of the argument.
+    pub width: u8,
+    /// The lowest bit of the argument that is transmitted; the start of the range of bitfields, `0`
+    /// for bools.
+    pub start: u8,
+}
+
+impl PackedArg {
+    /// Returns the mask of the argument's bits, before shifting them to their position.
+    pub fn mask(&self) -> u8 {
+        ((1u16 << self.width) - 1) as u8
+    }
+}
+
+/// Returns the layout of the sub-byte arguments of a format string, and the number of bytes they
+/// are packed into.
+///
+/// If a format string has at least two `{=bool}` arguments or bitfield arguments spanning less than
+/// 8 bits, they are packed into shared bytes in the order of their indices, starting at the lowest
+/// bit. An argument never spans two bytes. The packed bytes are transmitted before all other
+/// arguments of the format string.
+pub fn packed_args(params: &[Parameter]) -> (Vec<PackedArg>, usize) {
+    let mut indices = params.iter().map(|param| param.index).collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices.dedup();
+
+    let mut packable = vec![];
+    for index in indices {
+        let with_index = params.iter().filter(|param| param.index == index);
+        if with_index.clone().all(|param| param.ty == Type::Bool) {
+            packable.push((index, 1, 0));
+        } else if with_index
+            .clone()
+            .all(|param| matches!(param.ty, Type::BitField(_)))
+        {
+            let (start, end) = get_max_bitfield_range(with_index).unwrap();
+            if end - start < 8 {
+                packable.push((index, end - start, start));
+            }
+        }
+    }
+
+    if packable.len() < 2 {
+        return (vec![], 0);
+    }
+
+    let mut packed = Vec::with_capacity(packable.len());
+    let (mut byte, mut shift) = (0, 0);
+    for (index, width, start) in packable {
+        if shift + width > 8 {
+            byte += 1;
+            shift = 0;
+        }
+        packed.push(PackedArg {
+            index,
+            byte,
+            shift,
+            width,
+            start,
+        });
+        shift += width;
+    }
+
+    (packed, byte + 1)
+}
+
 /// Returns `Some(smallest_bit_index, largest_bit_index)` contained in `params` if
 /// `params` contains any bitfields. Otherwise `None`.
 pub fn get_max_bitfield_range<'a, I>(params: I) -> Option<(u8, u8)>
@@ -36,9 +36,13 @@ fn all_parse_param_cases(
 #[case(":ts", DisplayHint::Time(TimePrecision::Seconds))]
 #[case(":tms", DisplayHint::Time(TimePrecision::Millis))]
 #[case(":tus", DisplayHint::Time(TimePrecision::Micros))]
+#[case(":ticks@32768", DisplayHint::Ticks(32768))]
 #[case(":iso8601ms", DisplayHint::ISO8601(TimePrecision::Millis))]
 #[case(":iso8601s", DisplayHint::ISO8601(TimePrecision::Seconds))]
+#[case(":domain", DisplayHint::ClockDomain)]
 #[case(":?", DisplayHint::Debug)]
+#[case(":hexdump", DisplayHint::Hexdump)]
+#[case(":__internal_niche_option", DisplayHint::NicheOption)]
 #[case(":02", DisplayHint::NoHint { zero_pad: 2 })]
 fn all_display_hints(#[case] input: &str, #[case] hint: DisplayHint) {
     assert_eq!(
@@ -67,13 +71,19 @@ fn display_hint_unknown() {
 #[rstest]
 #[case("=i8", Type::I8)]
 #[case("=i16", Type::I16)]
+#[case("=i24", Type::I24)]
 #[case("=i32", Type::I32)]
+#[case("=i48", Type::I48)]
+#[case("=i56", Type::I56)]
 #[case("=i64", Type::I64)]
 #[case("=i128", Type::I128)]
 #[case("=isize", Type::Isize)]
 #[case("=u8", Type::U8)]
 #[case("=u16", Type::U16)]
+#[case("=u24", Type::U24)]
 #[case("=u32", Type::U32)]
+#[case("=u48", Type::U48)]
+#[case("=u56", Type::U56)]
 #[case("=u64", Type::U64)]
 #[case("=u128", Type::U128)]
 #[case("=usize", Type::Usize)]
@@ -199,6 +209,7 @@ fn arrays_err(#[case] input: &str) {
 #[case::range_missing_parts_3("{=..4}", Error::InvalidTypeSpecifier("..4".to_string()))]
 #[case::range_missing_parts_4("{=0.4}", Error::InvalidTypeSpecifier("0.4".to_string()))]
 #[case::range_missing_parts_5("{=0...4}", Error::InvalidTypeSpecifier("0...4".to_string()))]
+#[case::range_missing_parts_6("{=1}", Error::InvalidTypeSpecifier("1".to_string()))]
 #[case::index_with_different_types(
     "{0=u8}{0=u16}",
     Error::ConflictingTypes(0, Type::U8, Type::U16)
@@ -227,3 +238,32 @@ fn escaped_braces(#[case] input: &str, #[case] literal: &str) {
         Ok(vec![Fragment::Literal(literal.into())])
     );
 }
+
+#[rstest]
+#[case::single_bool("{=bool} {=u8}", &[])]
+#[case::bools("{=bool} {=u8} {=bool}", &[(0, 0, 0, 1, 0), (2, 0, 1, 1, 0)])]
+#[case::bitfields("{0=2..5} {1=bool} {0=3..7}", &[(0, 0, 0, 5, 2), (1, 0, 5, 1, 0)])]
+#[case::next_byte("{0=bool} {1=0..8} {2=1..8} {3=bool}", &[(0, 0, 0, 1, 0), (2, 0, 1, 7, 1), (3, 1, 0, 1, 0)])]
+fn packed(#[case] input: &str, #[case] expected: &[(usize, usize, u8, u8, u8)]) {
+    let params = parse(input, ParserMode::Strict)
+        .unwrap()
+        .into_iter()
+        .filter_map(|fragment| match fragment {
+            Fragment::Parameter(param) => Some(param),
+            Fragment::Literal(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let expected = expected
+        .iter()
+        .map(|&(index, byte, shift, width, start)| PackedArg {
+            index,
+            byte,
+            shift,
+            width,
+            start,
+        })
+        .collect::<Vec<_>>();
+    let bytes = expected.last().map_or(0, |last| last.byte + 1);
+
+    assert_eq!(packed_args(&params), (expected, bytes));
+}
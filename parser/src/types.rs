@@ -23,7 +23,13 @@ pub enum Type {
 
     I8,
     I16,
+    /// 24-bit signed integer, passed as `i32`
+    I24,
     I32,
+    /// 48-bit signed integer, passed as `i64`
+    I48,
+    /// 56-bit signed integer, passed as `i64`
+    I56,
     I64,
     I128,
     Isize,
@@ -35,7 +41,13 @@ pub enum Type {
 
     U8,
     U16,
+    /// 24-bit unsigned integer, passed as `u32`
+    U24,
     U32,
+    /// 48-bit unsigned integer, passed as `u64`
+    U48,
+    /// 56-bit unsigned integer, passed as `u64`
+    U56,
     U64,
     U128,
     Usize,
@@ -53,13 +65,19 @@ impl FromStr for Type {
         Ok(match s {
             "u8" => Type::U8,
             "u16" => Type::U16,
+            "u24" => Type::U24,
             "u32" => Type::U32,
+            "u48" => Type::U48,
+            "u56" => Type::U56,
             "u64" => Type::U64,
             "u128" => Type::U128,
             "usize" => Type::Usize,
             "i8" => Type::I8,
             "i16" => Type::I16,
+            "i24" => Type::I24,
             "i32" => Type::I32,
+            "i48" => Type::I48,
+            "i56" => Type::I56,
             "i64" => Type::I64,
             "i128" => Type::I128,
             "isize" => Type::Isize,
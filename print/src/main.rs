@@ -1,8 +1,10 @@
 use std::{
+    cmp::Reverse,
     env, fs,
-    io::{self, Read, StdinLock},
+    io::{self, Read, StdinLock, Write},
     net::TcpStream,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::anyhow;
@@ -12,7 +14,7 @@ use defmt_decoder::{
         format::{Formatter, FormatterConfig, HostFormatter},
         DefmtLoggerType,
     },
-    DecodeError, Frame, Locations, Table, DEFMT_VERSIONS,
+    DecodeError, Frame, Locations, SideTable, Table, DEFMT_VERSIONS,
 };
 
 /// Prints defmt-encoded logs to stdout
@@ -25,6 +27,10 @@ struct Opts {
     #[arg(long)]
     json: bool,
 
+    /// Side table of an ELF file that was obfuscated with the `obfuscate` command
+    #[arg(long)]
+    side_table: Option<PathBuf>,
+
     #[arg(long)]
     log_format: Option<String>,
 
@@ -56,6 +62,27 @@ enum Command {
         #[arg(long, env = "RTT_PORT", default_value_t = 19021)]
         port: u16,
     },
+    /// Print the encoded size of each log statement, in bytes, instead of decoding frames
+    Sizes,
+    /// List the log statements and interned strings that have the same text, which could share a
+    /// string index
+    Duplicates,
+    /// List the error codes created by `defmt::error_code!` and their messages
+    ErrorCodes,
+    /// Print the number of interned strings, i.e. of string indices in use
+    Strings {
+        /// Exit with an error if there are more interned strings than this
+        #[arg(long)]
+        max: Option<u64>,
+    },
+    /// Write a copy of the ELF file without the plaintext of the interned strings, and the side
+    /// table that is needed to decode its logs
+    Obfuscate {
+        /// Path of the obfuscated ELF file
+        output: PathBuf,
+        /// Path of the side table, which must be kept private
+        side_table: PathBuf,
+    },
 }
 
 enum Source {
@@ -84,6 +111,14 @@ impl Source {
             Source::Tcp(tcpstream) => Ok((tcpstream.read(buf)?, false)),
         }
     }
+
+    /// Sends `data` back to the target, if the source is bidirectional.
+    fn send(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        match self {
+            Source::Stdin(_) => Ok(()),
+            Source::Tcp(tcpstream) => Ok(tcpstream.write_all(data)?),
+        }
+    }
 }
 
 const READ_BUFFER_SIZE: usize = 1024;
@@ -92,6 +127,7 @@ fn main() -> anyhow::Result<()> {
     let Opts {
         elf,
         json,
+        side_table,
         log_format,
         host_log_format,
         show_skipped_frames,
@@ -106,7 +142,23 @@ fn main() -> anyhow::Result<()> {
 
     // read and parse elf file
     let bytes = fs::read(elf.unwrap())?;
-    let table = Table::parse(&bytes)?.ok_or_else(|| anyhow!(".defmt data not found"))?;
+    if let Some(Command::Obfuscate { output, side_table }) = &command {
+        let (obfuscated, table) = defmt_decoder::obfuscate(&bytes)?;
+        fs::write(output, obfuscated)?;
+        fs::write(side_table, serde_json::to_vec(&table)?)?;
+        return Ok(());
+    }
+    if let Some(Command::Strings { max }) = &command {
+        return check_string_count(&bytes, *max);
+    }
+    let table = match side_table {
+        Some(path) => {
+            let side_table: SideTable = serde_json::from_slice(&fs::read(path)?)?;
+            Table::parse_with_side_table(&bytes, &side_table)?
+        }
+        None => Table::parse(&bytes)?,
+    };
+    let table = table.ok_or_else(|| anyhow!(".defmt data not found"))?;
     let locs = table.get_locations(&bytes)?;
 
     // check if the locations info contains all the indicies
@@ -160,8 +212,31 @@ fn main() -> anyhow::Result<()> {
     let mut source = match command {
         None | Some(Command::Stdin) => Source::stdin(),
         Some(Command::Tcp { host, port }) => Source::tcp(host, port)?,
+        Some(Command::Sizes) => {
+            print_sizes(&table, &locs, &current_dir);
+            return Ok(());
+        }
+        Some(Command::ErrorCodes) => {
+            for (code, message) in table.error_codes() {
+                println!("{code:>8}  {message}");
+            }
+            return Ok(());
+        }
+        Some(Command::Duplicates) => {
+            print_duplicates(&table, &locs, &current_dir);
+            return Ok(());
+        }
+        Some(Command::Obfuscate { .. } | Command::Strings { .. }) => unreachable!(),
     };
 
+    // the key and the AEAD are only known to the application
+    if table.encrypted() {
+        return Err(anyhow!(
+            "the log frames are encrypted (`encryption` feature); decode them with \
+             `Table::new_decrypting_stream_decoder` of `defmt-decoder`"
+        ));
+    }
+
     loop {
         // read from stdin or tcpstream and push it to the decoder
         let (n, eof) = source.read(&mut buf)?;
@@ -171,11 +246,50 @@ fn main() -> anyhow::Result<()> {
             break Ok(());
         }
 
-        stream_decoder.received(&buf[..n]);
+        // the receive time is paired with the time sync frames of the target
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(now) => stream_decoder.received_at(&buf[..n], now.as_micros()),
+            Err(_) => stream_decoder.received(&buf[..n]),
+        }
 
         // decode the received data
         loop {
-            match stream_decoder.decode() {
+            let frame = stream_decoder.decode();
+            // firmware with the `reliable` feature retransmits every frame until it's acknowledged
+            if let Some(sequence_number) = frame
+                .as_ref()
+                .ok()
+                .filter(|_| table.reliable())
+                .and_then(Frame::sequence_number)
+            {
+                source.send(&defmt_decoder::ack(sequence_number))?;
+            }
+            if let Some(lost) = frame
+                .as_ref()
+                .ok()
+                .and_then(Frame::lost_frames)
+                .filter(|&lost| lost != 0)
+            {
+                log::warn!("{lost} frame(s) lost");
+            }
+            match frame {
+                // retransmitted frames that were already displayed
+                Ok(frame) if frame.is_duplicate() => continue,
+                // heartbeat frames only signal that the link is alive; there's nothing to print
+                Ok(frame) if frame.is_heartbeat() => continue,
+                // version frames were already checked against the ELF file by the decoder
+                Ok(frame) if frame.is_version() => continue,
+                // time sync frames only relate the target's timestamps to the host's time
+                Ok(frame) if frame.is_time_sync() => continue,
+                // the statements of a group are printed like any other
+                Ok(frame) if frame.is_group_start() || frame.is_group_end() => continue,
+                // check frames of the `self-check` feature are only reported if they fail
+                Ok(frame) if frame.is_self_check() => {
+                    if let Some(Err(e)) = frame.self_check() {
+                        log::warn!("{e}");
+                    }
+                    continue;
+                }
                 Ok(frame) => forward_to_logger(&frame, location_info(&locs, &frame, &current_dir)),
                 Err(DecodeError::UnexpectedEof) => break,
                 Err(DecodeError::Malformed) => match table.encoding().can_recover() {
@@ -192,6 +306,7 @@ fn main() -> anyhow::Result<()> {
                         continue;
                     }
                 },
+                Err(e) => return Err(e.into()),
             }
         }
     }
@@ -221,6 +336,61 @@ fn location_info(locs: &Option<Locations>, frame: &Frame, current_dir: &Path) ->
     (file, line, mod_path)
 }
 
+/// Prints the typical and worst-case encoded size of each log statement, largest first.
+fn print_sizes(table: &Table, locs: &Option<Locations>, current_dir: &Path) {
+    let mut sizes = table.encoded_sizes().collect::<Vec<_>>();
+    sizes.sort_by_key(|(_, size)| (size.worst_case.is_some(), Reverse(size.worst_case)));
+
+    println!("{:>8} {:>10}  location", "typical", "worst case");
+    for (index, size) in sizes {
+        let worst_case = size
+            .worst_case
+            .map_or("unbounded".to_string(), |size| size.to_string());
+        let location = location(locs, index, current_dir);
+        println!("{:>8} {worst_case:>10}  {location}", size.typical);
+    }
+}
+
+fn print_duplicates(table: &Table, locs: &Option<Locations>, current_dir: &Path) {
+    let duplicates = table.duplicates();
+    let saved = duplicates
+        .iter()
+        .map(|duplicates| duplicates.indices.len() - 1)
+        .sum::<usize>();
+
+    for duplicates in &duplicates {
+        let kind = if duplicates.exact { "same" } else { "similar" };
+        println!("{} strings with {kind} text:", duplicates.indices.len());
+        for &index in &duplicates.indices {
+            println!("  {}", location(locs, index, current_dir));
+        }
+    }
+    println!("{saved} string indices could be saved");
+}
+
+fn location(locs: &Option<Locations>, index: usize, current_dir: &Path) -> String {
+    match locs.as_ref().and_then(|locs| locs.get(&(index as u64))) {
+        Some(loc) => {
+            let path = loc.file.strip_prefix(current_dir).unwrap_or(&loc.file);
+            format!("{}:{}", path.display(), loc.line)
+        }
+        None => format!("(string index {index})"),
+    }
+}
+
+fn check_string_count(elf: &[u8], max: Option<u64>) -> anyhow::Result<()> {
+    let count = defmt_decoder::string_count(elf)?.ok_or_else(|| {
+        anyhow!("string count not found; the ELF file was linked with an older `defmt.x`")
+    })?;
+    println!("{count}");
+    match max {
+        Some(max) if count > max => Err(anyhow!(
+            "{count} interned strings exceed the maximum of {max}"
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Report version from Cargo.toml _(e.g. "0.1.4")_ and supported `defmt`-versions.
 ///
 /// Used by `--version` flag.
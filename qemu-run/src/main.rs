@@ -91,14 +91,20 @@ fn notmain() -> Result<Option<i32>, anyhow::Error> {
 fn decode(decoder: &mut dyn StreamDecoder) -> Result<(), DecodeError> {
     loop {
         match decoder.decode() {
-            Ok(frame) => {
-                println!("{}", frame.display(true))
-            }
+            Ok(frame) => match frame.self_check() {
+                Some(Err(e)) => eprintln!("{e}"),
+                Some(Ok(())) => {}
+                None => println!("{}", frame.display(true)),
+            },
             Err(DecodeError::UnexpectedEof) => return Ok(()),
             Err(DecodeError::Malformed) => {
                 eprintln!("failed to decode defmt data");
                 return Err(DecodeError::Malformed);
             }
+            Err(e) => {
+                eprintln!("{e}");
+                return Err(e);
+            }
         }
     }
 }
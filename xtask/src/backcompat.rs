@@ -106,7 +106,7 @@ impl QemuRun {
                 .args(["-q", command, name])
                 .current_dir(SNAPSHOT_TESTS_DIRECTORY)
                 .env(RUNNER_ENV_VAR, self.path()),
-            || anyhow!("{}", name.to_string()),
+            || anyhow!("{}", name),
         )?;
 
         Ok(())
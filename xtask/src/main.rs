@@ -108,19 +108,112 @@ fn test_host(deny_warnings: bool) {
         false => vec![],
     };
 
-    for feat in ["", "unstable-test", "alloc"] {
+    for feat in [
+        "",
+        "unstable-test",
+        "std",
+        "alloc",
+        "host-println",
+        "host-logger",
+        "testing",
+        "proptest",
+        "self-check",
+        "stats",
+        "sequence-numbers",
+        "reliable",
+        "encryption",
+        "authentication",
+        "control",
+        "module-filter",
+        "outline-encoders",
+        "serde",
+        "ufmt",
+    ] {
         do_test(
             || run_command("cargo", &["check", "--features", feat], None, &env),
             "host",
         );
     }
 
-    for feat in ["unstable-test", "unstable-test,alloc"] {
+    for feat in [
+        "unstable-test",
+        "unstable-test,std",
+        "unstable-test,alloc",
+        "unstable-test,serde",
+        "unstable-test,stats",
+        "unstable-test,sequence-numbers",
+        "unstable-test,reliable",
+        "unstable-test,encryption",
+        "unstable-test,authentication",
+        "unstable-test,control",
+        "unstable-test,std,control,module-filter",
+    ] {
         do_test(
             || run_command("cargo", &["test", "--features", feat], None, &env),
             "host",
         );
     }
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["test", "--features", "host-logger", "--test", "host_logger"],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["test", "--features", "std", "--test", "mock_logger"],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
+
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["test", "--features", "proptest", "--test", "testing"],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
+
+    // the counter logic of `ExtendedCounter` is target independent
+    do_test(
+        || run_command("cargo", &["test"], Some("firmware/defmt-cortex-m"), &env),
+        "host",
+    );
+
+    // not part of the firmware workspace, whose crates would all get `std` through it
+    do_test(
+        || run_command("cargo", &["test"], Some("firmware/defmt-udp"), &env),
+        "host",
+    );
+
+    // the example fails to link if its log statement contains a panic branch
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &["build", "--release", "--example", "no_panic"],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
 }
 
 fn test_cross(deny_warnings: bool) {
@@ -323,7 +416,7 @@ fn test_lint() {
     println!("🧪 lint");
 
     // rustfmt
-    for cwd in [None, Some("firmware/")] {
+    for cwd in [None, Some("firmware/"), Some("firmware/defmt-udp")] {
         do_test(
             || run_command("cargo", &["fmt", "--", "--check"], cwd, &[]),
             "lint",
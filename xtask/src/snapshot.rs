@@ -10,7 +10,7 @@ use crate::{
 };
 
 pub const SNAPSHOT_TESTS_DIRECTORY: &str = "firmware/qemu";
-pub const ALL_SNAPSHOT_TESTS: [&str; 12] = [
+pub const ALL_SNAPSHOT_TESTS: [&str; 13] = [
     "log",
     "bitflags",
     "timestamp",
@@ -18,6 +18,7 @@ pub const ALL_SNAPSHOT_TESTS: [&str; 12] = [
     "assert",
     "assert-eq",
     "assert-ne",
+    "assert-matches",
     "unwrap",
     "defmt-test",
     "hints",